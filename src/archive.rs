@@ -0,0 +1,69 @@
+use std::{fs::{self, File, ReadDir}, io::{self, ErrorKind}, path::{Path, PathBuf}};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use tar::{Archive, Builder};
+
+use crate::{error::DotulousError, format::ManifestFormat, profile::DotfileProfile};
+
+/// Bundles the whole folder of `profile`, including it's manifest, into a `.tar.gz` archive at
+/// `output`, so it can be moved to another machine with [`import`]. The folder is stored under
+/// it's own name, and symlinks inside it are kept as symlinks.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+pub fn export(profile: &DotfileProfile, output: &Path) -> Result<(), DotulousError> {
+    let Some(folder_name) = profile.repo_path.file_name() else {
+        return Err(DotulousError::FailedCreateArchive(io::Error::new(ErrorKind::InvalidInput, "Profile folder has no name")))
+    };
+    let file: File = File::create(output).map_err(DotulousError::FailedCreateArchive)?;
+    let mut builder: Builder<GzEncoder<File>> = Builder::new(GzEncoder::new(file, Compression::default()));
+    builder.follow_symlinks(false);
+    builder.append_dir_all(folder_name, &profile.repo_path).map_err(DotulousError::FailedCreateArchive)?;
+    let encoder: GzEncoder<File> = builder.into_inner().map_err(DotulousError::FailedCreateArchive)?;
+    encoder.finish().map_err(DotulousError::FailedCreateArchive)?;
+    Ok(())
+}
+
+/// Unpacks the profile inside the `.tar.gz` archive at `archive` (made by [`export`]) into
+/// `dotulous_path`, the user's dotulous folder, returning the imported profile.
+///
+/// The profile's folder is named after the sanitized name in it's manifest, rather than whatever
+/// the folder was called inside the archive. If that folder already exists, [`Err`] with
+/// [`DotulousError::ProfileAlreadyExists`] is returned and nothing is changed. If the archive has
+/// no profile with a manifest inside, [`Err`] with [`DotulousError::NoManifestInProfile`] is
+/// returned.
+///
+/// The archive is first unpacked into the cache inside `dotulous_path`, so a broken archive never
+/// leaves half a profile behind. The profile is **not** trusted, this is left to the user.
+pub fn import(dotulous_path: &Path, archive: &Path) -> Result<DotfileProfile, DotulousError> {
+    let staging_path: PathBuf = dotulous_path.join("cache").join("import");
+    if staging_path.exists() {
+        fs::remove_dir_all(&staging_path).map_err(DotulousError::FailedReadArchive)?;
+    }
+    fs::create_dir_all(&staging_path).map_err(DotulousError::FailedReadArchive)?;
+
+    let result: Result<DotfileProfile, DotulousError> = unpack(dotulous_path, archive, &staging_path);
+    let _ = fs::remove_dir_all(&staging_path);
+    result
+}
+
+/// Unpacks `archive` into `staging_path`, then moves the profile inside it into `dotulous_path`.
+/// See [`import`].
+fn unpack(dotulous_path: &Path, archive: &Path, staging_path: &Path) -> Result<DotfileProfile, DotulousError> {
+    let file: File = File::open(archive).map_err(DotulousError::FailedReadArchive)?;
+    // Entries trying to escape `staging_path` (e.g. with `..`) are skipped by tar itself
+    Archive::new(GzDecoder::new(file)).unpack(staging_path).map_err(DotulousError::FailedReadArchive)?;
+
+    let entries: ReadDir = fs::read_dir(staging_path).map_err(DotulousError::FailedReadArchive)?;
+    let Some(unpacked_path) = entries.filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| ManifestFormat::find_manifest(path).is_some()) else { return Err(DotulousError::NoManifestInProfile) };
+    let unpacked: DotfileProfile = DotfileProfile::from_manifest(&unpacked_path)?;
+
+    let profile_path: PathBuf = dotulous_path.join(sanitize_filename::sanitize(&unpacked.name));
+    if profile_path.exists() {
+        return Err(DotulousError::ProfileAlreadyExists)
+    }
+    fs::rename(&unpacked_path, &profile_path).map_err(DotulousError::FailedReadArchive)?;
+    DotfileProfile::from_manifest(&profile_path)
+}
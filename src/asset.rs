@@ -0,0 +1,122 @@
+use std::{fs, path::{Path, PathBuf}, process::Command};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{error::DotulousError, platform};
+
+/// An external asset declared by a profile, such as a font or wallpaper, that is downloaded at
+/// load time rather than being committed to the profile's repo.
+///
+/// Downloaded assets are kept in the asset cache (`cache/assets/` inside the dotulous folder, see
+/// [`cache_path`]), named after their `sha256` digest, so the same asset is only ever downloaded
+/// once across loads and profiles. The cached file is then symlinked to `dest`, in the same way as
+/// the profile's `files`.
+///
+/// Once a profile is unloaded, the assets only it used are removed from the cache again, see
+/// [`remove_cached`].
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Asset {
+    /// The URL to download the asset from.
+    pub url: String,
+    /// The expected SHA-256 digest of the asset, as 64 lowercase hex characters.
+    pub sha256: String,
+    /// Where the asset should be symlinked to on the system, relative to the home folder.
+    pub dest: PathBuf
+}
+impl Asset {
+    /// Returns the path this asset is (or would be) stored at inside the given `cache_path`.
+    ///
+    /// As the digest becomes the file's name, [`Err`] with [`DotulousError::InvalidAssetChecksum`]
+    /// is returned unless it's a valid one, see [`is_valid_sha256`].
+    pub fn cached_path(&self, cache_path: &Path) -> Result<PathBuf, DotulousError> {
+        if !is_valid_sha256(&self.sha256) {
+            return Err(DotulousError::InvalidAssetChecksum)
+        }
+        Ok(cache_path.join(&self.sha256))
+    }
+
+    /// Fetches the asset into `cache_path`, returning the path of the cached file.
+    ///
+    /// If the asset is already cached and its checksum still matches, it is not downloaded again.
+    /// Downloading is done using `curl`, into a temporary `.part` file that is only moved into
+    /// place once its checksum has been verified.
+    ///
+    /// If the downloaded file does not match `sha256`, it is deleted and [`Err`] with
+    /// [`DotulousError::AssetChecksumMismatch`] is returned. Nothing is downloaded if `sha256`
+    /// isn't valid, see [`Asset::cached_path`].
+    pub fn fetch(&self, cache_path: &Path) -> Result<PathBuf, DotulousError> {
+        let cached: PathBuf = self.cached_path(cache_path)?;
+        if cached.exists() && self.verify(&cached).is_ok() {
+            return Ok(cached)
+        }
+
+        fs::create_dir_all(cache_path).map_err(DotulousError::FailedCreateAssetCache)?;
+        let partial: PathBuf = cached.with_extension("part");
+        let status = Command::new("curl")
+            .arg("--fail")
+            .arg("--silent")
+            .arg("--show-error")
+            .arg("--location")
+            .arg("--output")
+            .arg(&partial)
+            .arg(&self.url)
+            .status();
+        if let Err(e) = platform::check_status(status) {
+            let _ = fs::remove_file(&partial);
+            return Err(DotulousError::FailedDownloadAsset(e))
+        }
+
+        if let Err(e) = self.verify(&partial) {
+            let _ = fs::remove_file(&partial);
+            return Err(e)
+        }
+        fs::rename(&partial, &cached).map_err(DotulousError::FailedDownloadAsset)?;
+        Ok(cached)
+    }
+
+    /// Verifies the file at `path` matches this asset's `sha256` digest.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn verify(&self, path: &Path) -> Result<(), DotulousError> {
+        let contents: Vec<u8> = fs::read(path).map_err(DotulousError::FailedReadAsset)?;
+        let digest: String = format!("{:x}", Sha256::digest(contents));
+        if digest != self.sha256 {
+            return Err(DotulousError::AssetChecksumMismatch)
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path to the asset cache inside the given `dotulous_path`.
+pub fn cache_path(dotulous_path: &Path) -> PathBuf {
+    dotulous_path.join("cache").join("assets")
+}
+
+/// Removes the cached copy of each of `unused` from `cache_path`, other than those with the same
+/// digest as one of `in_use`, returning the paths that were removed. Assets that aren't cached
+/// (or have an invalid digest) are skipped.
+///
+/// If a cached copy can't be removed, [`Err`] with [`DotulousError::FailedRemoveAsset`] is
+/// returned, leaving the rest where they are.
+pub fn remove_cached(cache_path: &Path, unused: &[Asset], in_use: &[Asset]) -> Result<Vec<PathBuf>, DotulousError> {
+    let mut removed: Vec<PathBuf> = Vec::new();
+    for asset in unused {
+        if in_use.iter().any(|other| other.sha256 == asset.sha256) {
+            continue;
+        }
+        let Ok(cached) = asset.cached_path(cache_path) else { continue };
+        if cached.symlink_metadata().is_err() || removed.contains(&cached) {
+            continue;
+        }
+        fs::remove_file(&cached).map_err(DotulousError::FailedRemoveAsset)?;
+        removed.push(cached);
+    }
+    Ok(removed)
+}
+
+/// Returns if `sha256` is a SHA-256 digest written as exactly 64 lowercase hex characters.
+pub fn is_valid_sha256(sha256: &str) -> bool {
+    sha256.len() == 64 && sha256.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
@@ -0,0 +1,107 @@
+use std::{fmt::Display, fs::{self, OpenOptions}, io::{ErrorKind, Write}, path::{Path, PathBuf}};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DotulousError, profile::DotfileProfile};
+
+/// The log of every command a profile has ran on this machine, stored in the user's dotulous
+/// folder as `audit.log`, so there's a record of what profiles did with their access.
+///
+/// Each line of the log is one [`AuditEntry`] as JSON, appended as soon as the command finishes,
+/// so the log is never rewritten and survives dotulous being killed part way through.
+pub struct AuditLog {
+    /// The *absolute* path to `audit.log`.
+    path: PathBuf,
+    /// The name of the profile commands are being ran for.
+    profile_name: String,
+    /// The hash of the profile's manifest, see [`DotfileProfile::manifest_hash`].
+    manifest_hash: String
+}
+impl AuditLog {
+    /// Opens the log inside the given `dotulous_path` for recording the commands of `profile`. If
+    /// the manifest couldn't be hashed, the hash is left empty rather than not recording anything.
+    pub fn for_profile(profile: &DotfileProfile, dotulous_path: &Path) -> AuditLog {
+        AuditLog {
+            path: audit_path(dotulous_path),
+            profile_name: profile.name.clone(),
+            manifest_hash: profile.manifest_hash().unwrap_or_default()
+        }
+    }
+
+    /// Appends an entry for `command`, which finished with `exit_code`, or [`None`] if it didn't
+    /// start, timed out or was killed by a signal.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn record(&self, command: &str, exit_code: Option<i32>) -> Result<(), DotulousError> {
+        let entry: AuditEntry = AuditEntry {
+            timestamp: Utc::now(),
+            profile_name: self.profile_name.clone(),
+            command: command.to_string(),
+            exit_code,
+            manifest_hash: self.manifest_hash.clone()
+        };
+        let mut line: String = serde_json::to_string(&entry).map_err(DotulousError::FailedSerializeAudit)?;
+        line.push('\n');
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(DotulousError::FailedWriteAudit)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).map_err(DotulousError::FailedWriteAudit)?;
+        file.write_all(line.as_bytes()).map_err(DotulousError::FailedWriteAudit)?;
+        Ok(())
+    }
+}
+
+/// Reads every entry in `audit.log` inside the given `dotulous_path`, oldest first. If no command
+/// has been recorded yet, nothing is returned.
+///
+/// If any line isn't a valid entry, [`Err`] with [`DotulousError::FailedDeserializeAudit`] is
+/// returned.
+pub fn read(dotulous_path: &Path) -> Result<Vec<AuditEntry>, DotulousError> {
+    let contents: String = match fs::read_to_string(audit_path(dotulous_path)) {
+        Ok(r) => r,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(DotulousError::FailedReadAudit(e))
+    };
+    contents.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(DotulousError::FailedDeserializeAudit))
+        .collect()
+}
+
+/// Returns the path of `audit.log` inside `dotulous_path`.
+fn audit_path(dotulous_path: &Path) -> PathBuf {
+    dotulous_path.join("audit.log")
+}
+
+/// A single command ran by a profile, see [`AuditLog`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEntry {
+    /// When the command finished.
+    pub timestamp: DateTime<Utc>,
+    /// The name of the profile the command came from.
+    pub profile_name: String,
+    /// The command that was ran, with any `{{variables}}` in the manifest filled in.
+    pub command: String,
+    /// The code the command exited with, or [`None`] if it didn't start, timed out or was killed
+    /// by a signal.
+    pub exit_code: Option<i32>,
+    /// The hash of the profile's manifest when the command was ran, the same as the one it is
+    /// trusted with.
+    pub manifest_hash: String
+}
+impl AuditEntry {
+    /// Returns if the command exited successfully.
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+impl Display for AuditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timestamp: DateTime<Local> = self.timestamp.into();
+        let exit_code: String = self.exit_code.map_or("none".to_string(), |code| code.to_string());
+        let short_hash: &str = self.manifest_hash.get(..12).unwrap_or(&self.manifest_hash);
+        write!(f, "{} - {} (manifest {short_hash}) - exit {exit_code} - {}", timestamp.format("%Y-%m-%d %H:%M:%S"), self.profile_name, self.command)
+    }
+}
@@ -0,0 +1,308 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DotulousError, profile::ApplyMode, say};
+
+/// A backup of files that were in the way of a profile being loaded.
+///
+/// Backups are stored in the user's dotulous folder, under `backups/<profile>/<timestamp>/`.
+/// Each backup folder contains a `backup.json` manifest (this struct) and a `files` folder holding
+/// the backed up files themselves, which are moved there rather than copied.
+///
+/// Create a backup with [`Backup::new`], and move files into it with [`Backup::store`]. The backup
+/// is only written to disk once the first file is stored. Moving the files back is done with
+/// [`Backup::restore`].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Backup {
+    /// The *absolute* path to the backup's folder. Not stored in the JSON, as it can be moved.
+    #[serde(skip)]
+    pub path: PathBuf,
+    /// The name of the profile the backup was made while loading.
+    pub profile_name: String,
+    /// When the backup was made.
+    pub created: DateTime<Utc>,
+    /// Every file that was backed up.
+    pub entries: Vec<BackupEntry>,
+    /// If the backup has already been restored.
+    #[serde(default)]
+    pub restored: bool
+}
+impl Backup {
+    /// Creates a new, empty backup for `profile_name` inside `dotulous_path`.
+    ///
+    /// Note that this function does **not** create the backup on disk, this is done by
+    /// [`Backup::store`] when the first file is backed up.
+    pub fn new(dotulous_path: &Path, profile_name: &str) -> Self {
+        let created: DateTime<Utc> = Utc::now();
+        let folder_name: String = created.format("%Y%m%d-%H%M%S-%3f").to_string();
+        Self {
+            path: backups_path(dotulous_path).join(sanitize_filename::sanitize(profile_name)).join(folder_name),
+            profile_name: profile_name.to_string(),
+            created,
+            entries: Vec::new(),
+            restored: false
+        }
+    }
+
+    /// Loads the backup stored in the folder at `path`.
+    pub fn load(path: &Path) -> Result<Backup, DotulousError> {
+        let manifest_path: PathBuf = path.join("backup.json");
+        if !manifest_path.exists() {
+            return Err(DotulousError::BackupNotFound)
+        }
+
+        let contents: String = fs::read_to_string(&manifest_path).map_err(DotulousError::FailedReadBackup)?;
+        let mut deserialized: Backup = serde_json::from_str(&contents).map_err(DotulousError::FailedDeserializeBackup)?;
+        deserialized.path = path.to_path_buf();
+        Ok(deserialized)
+    }
+
+    /// Returns every backup inside `dotulous_path`, oldest first. If `profile_name` is given, only
+    /// backups of that profile are returned. Any backups that fail to load are skipped.
+    pub fn list(dotulous_path: &Path, profile_name: Option<&str>) -> Vec<Backup> {
+        let backups_path: PathBuf = backups_path(dotulous_path);
+        let profile_folders: Vec<PathBuf> = match profile_name {
+            Some(name) => vec![backups_path.join(sanitize_filename::sanitize(name))],
+            None => read_dirs(&backups_path)
+        };
+
+        let mut backups: Vec<Backup> = profile_folders.iter()
+            .flat_map(|folder| read_dirs(folder))
+            .filter_map(|folder| Backup::load(&folder).ok())
+            .collect();
+        backups.sort_by_key(|b| b.created);
+        backups
+    }
+
+    /// Deletes the restored backups inside `dotulous_path` that `retention` doesn't keep, oldest
+    /// first. Backups that haven't been restored still hold files that aren't back on the system,
+    /// so they're never deleted unless `unrestored` is set, which should only be done when the
+    /// user has asked for it (such as with `dotulous backups prune`). They always count towards
+    /// [`Retention::max_size_mb`].
+    ///
+    /// The backup at `in_use`, being the loaded profile's backup (see
+    /// [`crate::meta::Meta::current_backup`]), is never deleted as it's restored once the profile
+    /// is unloaded, even if `unrestored` is set. If `mode` is [`ApplyMode::DryRun`], nothing is
+    /// deleted.
+    ///
+    /// Returns the backups that were (or would be) deleted, oldest first.
+    pub fn prune(dotulous_path: &Path, retention: &Retention, in_use: Option<&Path>, unrestored: bool, mode: ApplyMode) -> Result<Vec<Backup>, DotulousError> {
+        let backups: Vec<Backup> = Backup::list(dotulous_path, None);
+        let prunable: Vec<bool> = backups.iter()
+            .map(|backup| in_use != Some(backup.path.as_path()) && (backup.restored || unrestored))
+            .collect();
+        let mut remove: Vec<bool> = vec![false; backups.len()];
+
+        if let Some(keep) = retention.keep {
+            let mut kept: HashMap<&Path, usize> = HashMap::new();
+            // Backups kept for not being restored don't push out restored ones, but the one in use does
+            for (i, backup) in backups.iter().enumerate().rev().filter(|(i, backup)| prunable[*i] || in_use == Some(backup.path.as_path())) {
+                let count: &mut usize = kept.entry(backup.path.parent().unwrap_or(&backup.path)).or_default();
+                *count += 1;
+                remove[i] |= prunable[i] && *count > keep;
+            }
+        }
+        // An age too large to count back from keeps everything
+        let oldest: Option<DateTime<Utc>> = retention.max_age_days
+            .and_then(|days| Duration::try_days(i64::try_from(days).ok()?))
+            .and_then(|age| Utc::now().checked_sub_signed(age));
+        if let Some(oldest) = oldest {
+            for (i, backup) in backups.iter().enumerate() {
+                remove[i] |= prunable[i] && backup.created < oldest;
+            }
+        }
+        if let Some(megabytes) = retention.max_size_mb {
+            let sizes: Vec<u64> = backups.iter().map(Backup::size).collect();
+            let mut total: u64 = sizes.iter().zip(&remove).filter(|(_, removed)| !**removed).map(|(size, _)| size).sum();
+            for i in 0..backups.len() {
+                if total <= megabytes.saturating_mul(1024 * 1024) {
+                    break;
+                }
+                if prunable[i] && !remove[i] {
+                    remove[i] = true;
+                    total -= sizes[i];
+                }
+            }
+        }
+
+        let pruned: Vec<Backup> = backups.into_iter().zip(remove).filter(|(_, removed)| *removed).map(|(backup, _)| backup).collect();
+        if mode == ApplyMode::Apply {
+            for backup in &pruned {
+                fs::remove_dir_all(&backup.path).map_err(DotulousError::FailedSaveBackup)?;
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Returns how many bytes the files in the backup take up. Anything that can't be read is
+    /// counted as empty.
+    pub fn size(&self) -> u64 {
+        folder_size(&self.path.join("files"))
+    }
+
+    /// Returns the id of the backup, being `<profile>/<timestamp>`. This is the path of the backup
+    /// relative to the backups folder, and can be found again with [`Backup::find`].
+    pub fn id(&self) -> String {
+        let timestamp = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let profile = self.path.parent().and_then(Path::file_name).unwrap_or_default().to_string_lossy();
+        format!("{profile}/{timestamp}")
+    }
+
+    /// Finds the backup with the given `id` (see [`Backup::id`]) inside `dotulous_path`.
+    pub fn find(dotulous_path: &Path, id: &str) -> Result<Backup, DotulousError> {
+        let Some((profile, timestamp)) = id.split_once('/') else { return Err(DotulousError::BackupNotFound) };
+        let path: PathBuf = backups_path(dotulous_path)
+            .join(sanitize_filename::sanitize(profile))
+            .join(sanitize_filename::sanitize(timestamp));
+        Backup::load(&path)
+    }
+
+    /// Moves the file or directory at `destination` into the backup, recording where it came from,
+    /// and saves the backup to disk.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn store(&mut self, destination: &Path) -> Result<(), DotulousError> {
+        let files_path: PathBuf = self.path.join("files");
+        fs::create_dir_all(&files_path).map_err(DotulousError::FailedCreateBackup)?;
+
+        let stored: PathBuf = PathBuf::from(self.entries.len().to_string());
+        fs::rename(destination, files_path.join(&stored)).map_err(DotulousError::FailedCreateBackup)?;
+        self.entries.push(BackupEntry { original: destination.to_path_buf(), stored });
+        self.save()
+    }
+
+    /// Moves every file in the backup back to where it came from, marking the backup as restored.
+    /// Files whose original location is now taken are left in the backup, with a warning printed.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    /// Upon any errors, the function will simply print to stdout and continue.
+    pub fn restore(&mut self) -> Result<(), DotulousError> {
+        say!("Restoring backup: {}", self.id());
+        self.restored = self.restore_entries(|_| true);
+        self.save()
+    }
+
+    /// Moves only the files in the backup that were originally at one of `originals` back, such
+    /// as when only part of a profile is unloaded. The backup isn't marked as restored, and the
+    /// files that were moved back are skipped once the rest of it is restored.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    /// Upon any errors, the function will simply print to stdout and continue.
+    pub fn restore_some(&mut self, originals: &[PathBuf]) {
+        if self.entries.iter().any(|entry| originals.contains(&entry.original)) {
+            say!("Restoring from backup: {}", self.id());
+            self.restore_entries(|original| originals.contains(&original.to_path_buf()));
+        }
+    }
+
+    /// Moves every file in the backup that was originally at a path `matching` back, returning if
+    /// all of them were. See [`Backup::restore`].
+    fn restore_entries(&self, matching: impl Fn(&Path) -> bool) -> bool {
+        let mut all_restored: bool = true;
+        for entry in self.entries.iter().filter(|entry| matching(&entry.original)) {
+            let stored: PathBuf = self.path.join("files").join(&entry.stored);
+            let original: &Path = &entry.original;
+            // Nothing to move back, such as when it was already restored on it's own
+            if stored.symlink_metadata().is_err() {
+                continue;
+            }
+            say!("  {stored:?} => {original:?}");
+            if original.symlink_metadata().is_ok() {
+                say!("  WARNING: {original:?} already exists! Leaving it in the backup.");
+                all_restored = false;
+                continue;
+            }
+            if let Err(e) = fs::rename(&stored, original) {
+                say!("  ERROR: Failed to restore {original:?}: {e:#}");
+                all_restored = false;
+            }
+        }
+        all_restored
+    }
+
+    /// Moves every file stored in the `other` backup into this one, then deletes `other`. Used
+    /// when more files are backed up while the profile this backup is for is still loaded.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn absorb(&mut self, other: Backup) -> Result<(), DotulousError> {
+        let files_path: PathBuf = self.path.join("files");
+        fs::create_dir_all(&files_path).map_err(DotulousError::FailedCreateBackup)?;
+        for entry in other.entries {
+            let stored: PathBuf = PathBuf::from(self.entries.len().to_string());
+            fs::rename(other.path.join("files").join(&entry.stored), files_path.join(&stored)).map_err(DotulousError::FailedCreateBackup)?;
+            self.entries.push(BackupEntry { original: entry.original, stored });
+        }
+        self.save()?;
+        fs::remove_dir_all(&other.path).map_err(DotulousError::FailedSaveBackup)?;
+        Ok(())
+    }
+
+    /// Saves the backup's manifest to `backup.json` inside it's folder.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn save(&self) -> Result<(), DotulousError> {
+        let serialized: String = serde_json::to_string_pretty(self).map_err(DotulousError::FailedSerializeBackup)?;
+        fs::write(self.path.join("backup.json"), serialized).map_err(DotulousError::FailedSaveBackup)?;
+        Ok(())
+    }
+}
+
+/// Which restored backups [`Backup::prune`] keeps, from the user's `backup_retention`,
+/// `backup_max_age_days` & `backup_max_size_mb` settings (see
+/// [`crate::settings::Settings::retention`]). A backup is deleted if any of them says so, and
+/// leaving them all unset keeps every backup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// How many restored backups to keep for each profile, newest first.
+    pub keep: Option<usize>,
+    /// How many days to keep restored backups for.
+    pub max_age_days: Option<u64>,
+    /// How many mebibytes every backup may take up together. The oldest restored backups are
+    /// deleted until they fit.
+    pub max_size_mb: Option<u64>
+}
+impl Retention {
+    /// Returns if every backup is kept, as nothing limits them.
+    pub fn is_unlimited(&self) -> bool {
+        self.keep.is_none() && self.max_age_days.is_none() && self.max_size_mb.is_none()
+    }
+}
+
+/// A single file or directory stored inside a [`Backup`].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BackupEntry {
+    /// The *absolute* path the file was originally at.
+    pub original: PathBuf,
+    /// The path the file is stored at, relative to the backup's `files` folder.
+    pub stored: PathBuf
+}
+
+/// Returns the path of the folder all backups are stored in.
+fn backups_path(dotulous_path: &Path) -> PathBuf {
+    dotulous_path.join("backups")
+}
+
+/// Returns how many bytes the file or folder at `path` takes up, including everything inside it.
+/// Symlinks aren't followed, and anything that can't be read is counted as empty.
+fn folder_size(path: &Path) -> u64 {
+    let Ok(metadata) = path.symlink_metadata() else { return 0 };
+    if !metadata.is_dir() {
+        return metadata.len()
+    }
+    let Ok(entries) = fs::read_dir(path) else { return 0 };
+    entries.filter_map(Result::ok).map(|entry| folder_size(&entry.path())).sum()
+}
+
+/// Returns every directory directly inside `path`, or nothing if it can't be read.
+fn read_dirs(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(path) else { return Vec::new() };
+    entries.filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect()
+}
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{packages::Distro, platform};
+
+/// A condition on a manifest entry, written as it's `when` field. The entry is only applied when
+/// every field given matches the current [`Host`], so an empty condition always matches.
+/// ```json
+/// "when": { "hostname": "laptop" }
+/// "when": { "os_release_id": "arch", "command_exists": "pacman" }
+/// ```
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Condition {
+    /// The hostname the machine must have. Compared case-insensitively.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// The distro the machine must be running, as an `ID` from `/etc/os-release` (e.g. `arch` or
+    /// `debian`). Distros based on it also match, through their `ID_LIKE`, so `debian` matches
+    /// Ubuntu too. Compared case-insensitively.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os_release_id: Option<String>,
+    /// A program that must be installed, found on the `PATH` (e.g. `pacman`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command_exists: Option<String>
+}
+impl Condition {
+    /// Returns if this condition matches the given `host`.
+    pub fn matches(&self, host: &Host) -> bool {
+        if let Some(hostname) = &self.hostname {
+            if !host.hostname.as_ref().is_some_and(|h| h.eq_ignore_ascii_case(hostname)) {
+                return false
+            }
+        }
+        if let Some(os_release_id) = &self.os_release_id {
+            if !host.distro_ids.iter().any(|id| id.eq_ignore_ascii_case(os_release_id)) {
+                return false
+            }
+        }
+        if let Some(command) = &self.command_exists {
+            if !host.has_command(command) {
+                return false
+            }
+        }
+        true
+    }
+}
+
+/// Facts about the current machine that a [`Condition`] is checked against. Create it with
+/// [`Host::detect`].
+#[derive(Debug)]
+pub struct Host {
+    /// The machine's hostname, if it could be found.
+    pub hostname: Option<String>,
+    /// The ids of the machine's distro, most specific first (see [`Distro`]). Empty if it could
+    /// not be found, such as when not running Linux.
+    pub distro_ids: Vec<String>,
+    /// The folders searched for programs, from `PATH`.
+    pub path: Vec<PathBuf>
+}
+impl Host {
+    /// Detects the facts about the current machine.
+    pub fn detect() -> Self {
+        Self {
+            hostname: platform::hostname(),
+            distro_ids: Distro::detect().map(|distro| distro.ids).unwrap_or_default(),
+            path: platform::search_path()
+        }
+    }
+
+    /// Returns if the program `command` is inside one of the folders in [`Host::path`].
+    pub fn has_command(&self, command: &str) -> bool {
+        platform::find_program(command, &self.path).is_some()
+    }
+}
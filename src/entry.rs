@@ -0,0 +1,504 @@
+use std::{collections::BTreeMap, fmt::Display, fs, io, path::{Path, PathBuf}, process::Command};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{condition::{Condition, Host}, platform::{self, symlink}, secret::SecretStore, template::Renderer};
+
+/// A single entry of a profile's `files`, describing where and how a file from the profile's
+/// directory is put onto the system.
+///
+/// In the manifest, an entry can either be written as just the destination path, which uses the
+/// default [`Strategy::Symlink`];
+/// ```json
+/// "files": { "nvim": ".config/nvim" }
+/// ```
+/// or as an object with extra options;
+/// ```json
+/// "files": { "nvim": { "destination": ".config/nvim", "strategy": "copy", "when": { "hostname": "laptop" } } }
+/// ```
+/// The same file can be put in several places with a list of entries, see [`FileTargetsRepr`].
+/// A directory can be linked file by file with `"link_mode": "contents"`, see [`LinkMode`],
+/// entries can be grouped with `"tags": ["gui"]`, see [`crate::filter::TagFilter`], given
+/// permissions with `"mode": "0600"`, see [`FileMode`], and marked `"optional": true` for files
+/// that only exist on some machines.
+/// Entries using only the defaults are saved back in the short form.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(from = "FileEntryRepr", into = "FileEntryRepr")]
+pub struct FileEntry {
+    /// Where the file should be put on the system, relative to the profile's target path.
+    pub destination: PathBuf,
+    /// How the file should be put onto the system.
+    pub strategy: Strategy,
+    /// The [`Condition`] the machine must match for the file to be loaded, if any.
+    pub when: Option<Condition>,
+    /// Whether a directory is put onto the system as a whole, or file by file.
+    pub link_mode: LinkMode,
+    /// The groups the file is in, picked with `--tags` & `--skip-tags` when loading (see
+    /// [`crate::filter::TagFilter`]).
+    pub tags: Vec<String>,
+    /// The permissions the file is given once it's put onto the system, if any.
+    pub mode: Option<FileMode>,
+    /// Whether the source may not exist, such as a file generated locally and kept out of git. If
+    /// it doesn't, the entry is quietly skipped when loading rather than failing.
+    pub optional: bool
+}
+impl FileEntry {
+    /// Creates a new `FileEntry` to `destination`, using the default [`Strategy::Symlink`].
+    pub fn new(destination: PathBuf) -> Self {
+        Self {
+            destination,
+            strategy: Strategy::default(),
+            when: None,
+            link_mode: LinkMode::default(),
+            tags: Vec::new(),
+            mode: None,
+            optional: false
+        }
+    }
+
+    /// Returns if the entry should be loaded on the given `host`.
+    pub fn applies_to(&self, host: &Host) -> bool {
+        self.when.as_ref().is_none_or(|c| c.matches(host))
+    }
+
+    /// Returns if the file looks like it holds secrets, such as anything encrypted, inside `.ssh`
+    /// or `.gnupg`, or a private key. Used to warn when one is readable by every user.
+    pub fn is_sensitive(&self) -> bool {
+        if self.strategy == Strategy::Encrypted {
+            return true
+        }
+        if self.destination.components().any(|c| matches!(c.as_os_str().to_str(), Some(".ssh" | ".gnupg" | ".aws" | ".kube"))) {
+            return true
+        }
+        let Some(name) = self.destination.file_name().and_then(|name| name.to_str()) else { return false };
+        let name: String = name.to_lowercase();
+        (name.starts_with("id_") && !name.ends_with(".pub"))
+            || [".pem", ".key", ".p12", ".pfx"].iter().any(|extension| name.ends_with(extension))
+            || [".netrc", ".pgpass", ".git-credentials", "credentials"].contains(&name.as_str())
+            || name.contains("secret") || name.contains("token")
+    }
+}
+
+/// The manifest representation of a [`FileEntry`], allowing the short form of just a destination.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum FileEntryRepr {
+    /// Just the destination, using the defaults for everything else.
+    Destination(PathBuf),
+    /// The full entry.
+    Full {
+        destination: PathBuf,
+        #[serde(default)]
+        strategy: Strategy,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        when: Option<Condition>,
+        #[serde(default, skip_serializing_if = "LinkMode::is_default")]
+        link_mode: LinkMode,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<FileMode>,
+        #[serde(default, skip_serializing_if = "crate::profile::is_false")]
+        optional: bool
+    }
+}
+impl From<FileEntryRepr> for FileEntry {
+    fn from(value: FileEntryRepr) -> Self {
+        match value {
+            FileEntryRepr::Destination(destination) => FileEntry::new(destination),
+            FileEntryRepr::Full { destination, strategy, when, link_mode, tags, mode, optional } => FileEntry { destination, strategy, when, link_mode, tags, mode, optional }
+        }
+    }
+}
+impl From<FileEntry> for FileEntryRepr {
+    fn from(value: FileEntry) -> Self {
+        if value.strategy == Strategy::default() && value.when.is_none() && value.link_mode.is_default() && value.tags.is_empty() && value.mode.is_none() && !value.optional {
+            return FileEntryRepr::Destination(value.destination)
+        }
+        FileEntryRepr::Full { destination: value.destination, strategy: value.strategy, when: value.when, link_mode: value.link_mode, tags: value.tags, mode: value.mode, optional: value.optional }
+    }
+}
+
+/// The manifest representation of every [`FileEntry`] of a single source in a profile's `files`,
+/// allowing a list of entries to put the same file in several places;
+/// ```json
+/// "files": { "starship.toml": [".config/starship.toml", ".config/other/starship.toml"] }
+/// ```
+/// Sources with a single entry are saved back without the list.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum FileTargetsRepr {
+    /// Several entries, each putting the file somewhere else. Tried first, as an entry on it's own
+    /// could otherwise be read from a list.
+    Many(Vec<FileEntry>),
+    /// A single entry.
+    One(FileEntry)
+}
+
+/// Serializes a profile's `files`, writing sources with a single entry without the list. See
+/// [`FileTargetsRepr`].
+pub(crate) fn serialize_targets<S: Serializer>(files: &BTreeMap<PathBuf, Vec<FileEntry>>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(files.iter().map(|(source, entries)| {
+        let targets: FileTargetsRepr = match entries.as_slice() {
+            [entry] => FileTargetsRepr::One(entry.clone()),
+            entries => FileTargetsRepr::Many(entries.to_vec())
+        };
+        (source, targets)
+    }))
+}
+
+/// Deserializes a profile's `files`, where each source has either one entry or a list of them.
+/// Sources with an empty list are left out. See [`FileTargetsRepr`].
+pub(crate) fn deserialize_targets<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<PathBuf, Vec<FileEntry>>, D::Error> {
+    let files: BTreeMap<PathBuf, FileTargetsRepr> = BTreeMap::deserialize(deserializer)?;
+    let files = files.into_iter()
+        .map(|(source, targets)| match targets {
+            FileTargetsRepr::Many(entries) => (source, entries),
+            FileTargetsRepr::One(entry) => (source, vec![entry])
+        })
+        .filter(|(_, entries)| !entries.is_empty());
+    Ok(files.collect())
+}
+
+/// How a [`FileEntry`] whose source is a directory is put onto the system. Entries for single
+/// files are always put there as they are.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMode {
+    /// Put the whole directory there at once, such as a single symlink to it. Anything a program
+    /// writes into the directory ends up inside the profile.
+    #[default]
+    Dir,
+    /// Create the directory (and any inside it) for real, and put each file inside it there on it's
+    /// own, like stow's tree unfolding. Programs can then write their own files next to the
+    /// profile's, without them ending up inside the profile. See
+    /// [`crate::profile::DotfileProfile::resolve_link_modes`].
+    Contents
+}
+impl LinkMode {
+    /// Returns if this is the default [`LinkMode::Dir`], so it can be left out of the manifest.
+    fn is_default(&self) -> bool {
+        *self == LinkMode::default()
+    }
+}
+
+/// A single command of a profile's `pre_commands`, `post_commands` or `removal_commands`.
+///
+/// In the manifest, a command can either be written as just the command itself, or as an object
+/// with a [`Condition`], an [`OnError`] policy and/or a [`Shell`];
+/// ```json
+/// "post_commands": ["echo hi", { "command": "xrandr --auto", "when": { "hostname": "desktop" }, "on_error": "continue" }]
+/// ```
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(from = "CommandEntryRepr", into = "CommandEntryRepr")]
+pub struct CommandEntry {
+    /// The command to run, in a `sh` shell.
+    pub command: String,
+    /// The [`Condition`] the machine must match for the command to be ran, if any.
+    pub when: Option<Condition>,
+    /// What to do if the command fails. If [`None`], the profile's `on_error` is used.
+    pub on_error: Option<OnError>,
+    /// The shell to run the command with. If [`None`], the profile's `shell` is used.
+    pub shell: Option<Shell>,
+    /// How many seconds the command can run for before it's killed. If [`None`], the profile's
+    /// `timeout` is used.
+    pub timeout: Option<u64>,
+    /// The groups the command is in, picked with `--tags` & `--skip-tags` when loading (see
+    /// [`crate::filter::TagFilter`]).
+    pub tags: Vec<String>
+}
+impl CommandEntry {
+    /// Creates a new `CommandEntry` running `command`, with no condition and the profile's own
+    /// `on_error`, `shell` & `timeout`.
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            when: None,
+            on_error: None,
+            shell: None,
+            timeout: None,
+            tags: Vec::new()
+        }
+    }
+
+    /// Returns if the command should be ran on the given `host`.
+    pub fn applies_to(&self, host: &Host) -> bool {
+        self.when.as_ref().is_none_or(|c| c.matches(host))
+    }
+}
+
+/// The manifest representation of a [`CommandEntry`], allowing the short form of just a command.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CommandEntryRepr {
+    /// Just the command, with no condition.
+    Command(String),
+    /// The full entry.
+    Full {
+        command: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        when: Option<Condition>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        on_error: Option<OnError>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        shell: Option<Shell>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout: Option<u64>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>
+    }
+}
+impl From<CommandEntryRepr> for CommandEntry {
+    fn from(value: CommandEntryRepr) -> Self {
+        match value {
+            CommandEntryRepr::Command(command) => CommandEntry::new(&command),
+            CommandEntryRepr::Full { command, when, on_error, shell, timeout, tags } => CommandEntry { command, when, on_error, shell, timeout, tags }
+        }
+    }
+}
+impl From<CommandEntry> for CommandEntryRepr {
+    fn from(value: CommandEntry) -> Self {
+        if value.when.is_none() && value.on_error.is_none() && value.shell.is_none() && value.timeout.is_none() && value.tags.is_empty() {
+            return CommandEntryRepr::Command(value.command)
+        }
+        CommandEntryRepr::Full { command: value.command, when: value.when, on_error: value.on_error, shell: value.shell, timeout: value.timeout, tags: value.tags }
+    }
+}
+
+/// The program a profile's commands are ran with, followed by any arguments to give it before the
+/// command itself. Set for the whole profile with it's `shell`, or for a single command (see
+/// [`CommandEntry`]). Commands are ran with `sh -c` if neither sets one.
+/// ```json
+/// "shell": ["bash", "-euo", "pipefail", "-c"]
+/// ```
+/// The program may start with `~` or be an environment variable, so `["$SHELL", "-c"]` uses the
+/// user's own shell.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct Shell {
+    /// The program to run.
+    program: String,
+    /// The arguments given to the program before the command.
+    args: Vec<String>
+}
+impl Shell {
+    /// Returns a [`Command`] running `command` with this shell.
+    pub fn command(&self, command: &str) -> Command {
+        let program = shellexpand::full(&self.program).unwrap_or(self.program.as_str().into());
+        let mut process: Command = Command::new(program.as_ref());
+        process.args(&self.args).arg(command);
+        process
+    }
+}
+impl TryFrom<Vec<String>> for Shell {
+    type Error = String;
+
+    fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
+        let mut value = value.into_iter();
+        match value.next() {
+            Some(program) if !program.is_empty() => Ok(Shell { program, args: value.collect() }),
+            _ => Err("shell must start with the program to run".to_string())
+        }
+    }
+}
+impl From<Shell> for Vec<String> {
+    fn from(value: Shell) -> Self {
+        [value.program].into_iter().chain(value.args).collect()
+    }
+}
+
+/// The unix permissions given to a [`FileEntry`] once it's put onto the system, written in the
+/// manifest as an octal string.
+/// ```json
+/// "ssh_config": { "destination": ".ssh/config", "strategy": "copy", "mode": "0600" }
+/// ```
+/// The mode is given to whatever the destination resolves to, so for symlinks & hardlinks it's the
+/// file inside the profile, and for templates & encrypted files it's the rendered output. Copies,
+/// rendered templates & decrypted files are created with it, see [`Strategy::apply`]. For a folder,
+/// only the folder itself is changed. Modes are ignored on Windows.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct FileMode(u32);
+impl FileMode {
+    /// Returns the mode as it's permission bits, such as `0o600`.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+}
+impl TryFrom<String> for FileMode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let digits: &str = value.strip_prefix("0o").unwrap_or(&value);
+        match u32::from_str_radix(digits, 8) {
+            Ok(bits) if bits <= 0o7777 => Ok(FileMode(bits)),
+            _ => Err(format!("mode \"{value}\" must be an octal mode like \"0600\""))
+        }
+    }
+}
+impl From<FileMode> for String {
+    fn from(value: FileMode) -> Self {
+        value.to_string()
+    }
+}
+impl Display for FileMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(&format!("{:04o}", self.0))
+    }
+}
+
+/// What to do when one of a profile's pre or post commands fails. Set for the whole profile with
+/// it's `on_error`, or for a single command (see [`CommandEntry`]).
+///
+/// Removal commands always carry on, as the profile's files are already gone by then.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    /// Stop loading, rolling back everything already done.
+    #[default]
+    Abort,
+    /// Ignore the failure and carry on loading.
+    Continue,
+    /// Ask the user whether to carry on or abort. Aborts if dotulous isn't interactive, see
+    /// [`crate::platform::is_interactive`].
+    Prompt
+}
+
+/// How a [`FileEntry`] is put onto the system.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// Symlink the destination to the file in the profile's directory.
+    #[default]
+    Symlink,
+    /// Copy the file (or directory, recursively) to the destination. Useful for programs that
+    /// break when their config is a symlink.
+    Copy,
+    /// Hardlink the destination to the file in the profile's directory. Only works for files on
+    /// the same filesystem as the profile.
+    Hardlink,
+    /// Render the file as a template (see [`crate::template::render`]) into the template cache, and
+    /// symlink the destination to the rendered output.
+    Template,
+    /// Decrypt the file with the profile's `secrets` backend into the secrets cache, and symlink
+    /// the destination to the decrypted output. See [`crate::secret::SecretStore`].
+    Encrypted
+}
+impl Strategy {
+    /// Puts the file at `source` onto the system at `destination` using this strategy. Templates
+    /// are rendered with the given `renderer`, and encrypted files decrypted with `secrets`.
+    ///
+    /// Copies, rendered templates & decrypted files are created with `mode` if it's given, so are
+    /// never readable by anyone else while being written. Symlinks & hardlinks share the profile's
+    /// own file, so their `mode` has to be set separately with [`platform::set_mode`].
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn apply(&self, source: &Path, destination: &Path, mode: Option<FileMode>, renderer: &Renderer, secrets: &SecretStore) -> io::Result<()> {
+        match self {
+            Strategy::Symlink => symlink(source, destination),
+            Strategy::Copy => copy_recursive(source, destination, mode),
+            Strategy::Hardlink => fs::hard_link(source, destination),
+            Strategy::Template => symlink(renderer.render_file(source, mode)?, destination),
+            Strategy::Encrypted => symlink(secrets.decrypt_file(source, mode)?, destination)
+        }
+    }
+
+    /// Returns if `destination` looks like it was put there by [`Strategy::apply`] for the file at
+    /// `source`, used to make sure only what dotulous made is ever removed. Symlinks count if they
+    /// resolve to the same file, even if the path they point to has since moved.
+    ///
+    /// Copies count if they still have the same contents as `source` (see
+    /// [`platform::same_contents`]), so a copy that was changed after loading, or anything else
+    /// that happens to be there, isn't counted.
+    pub fn is_applied(&self, source: &Path, destination: &Path, renderer: &Renderer, secrets: &SecretStore) -> bool {
+        match self {
+            Strategy::Symlink => destination.is_symlink() && same_target(destination, source),
+            Strategy::Template => destination.is_symlink() && same_target(destination, &renderer.rendered_path(source)),
+            Strategy::Encrypted => destination.is_symlink() && same_target(destination, &secrets.decrypted_path(source)),
+            Strategy::Hardlink => platform::same_file(destination, source),
+            Strategy::Copy => platform::same_contents(source, destination)
+        }
+    }
+
+    /// Returns if this strategy puts a symlink onto the system, rather than a real file.
+    pub fn is_link(&self) -> bool {
+        matches!(self, Strategy::Symlink | Strategy::Template | Strategy::Encrypted)
+    }
+
+    /// Removes what [`Strategy::apply`] put at `destination`. Symlinks are removed without
+    /// following them, and copied directories are removed recursively. Strategies that make a
+    /// symlink (see [`Strategy::is_link`]) never remove anything else, returning [`Err`] instead.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn remove(&self, destination: &Path) -> io::Result<()> {
+        match self {
+            _ if self.is_link() && !destination.is_symlink() => Err(io::Error::other("destination isn't a symlink")),
+            Strategy::Copy if destination.is_dir() && !destination.is_symlink() => fs::remove_dir_all(destination),
+            Strategy::Symlink | Strategy::Copy | Strategy::Hardlink | Strategy::Template | Strategy::Encrypted => fs::remove_file(destination)
+        }
+    }
+}
+impl Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strategy::Symlink => f.pad("symlink"),
+            Strategy::Copy => f.pad("copy"),
+            Strategy::Hardlink => f.pad("hardlink"),
+            Strategy::Template => f.pad("template"),
+            Strategy::Encrypted => f.pad("encrypted")
+        }
+    }
+}
+
+/// Returns if `destination` is a symlink pointing inside `dotulous_path`, the user's dotulous
+/// folder, without following it. Every symlink dotulous makes points there, at a profile's file or
+/// into the template, secret or asset caches, so this marks it as one dotulous made even if what it
+/// points at has since gone. Unloading never removes a symlink without it.
+pub fn is_owned_link(destination: &Path, dotulous_path: &Path) -> bool {
+    let Ok(target) = fs::read_link(destination) else { return false };
+    let target: PathBuf = match destination.parent() {
+        Some(parent) => parent.join(target),
+        None => target
+    };
+    platform::normalize_path(&target).starts_with(platform::normalize_path(dotulous_path))
+}
+
+/// Returns if `a` & `b` both resolve to the same existing path.
+fn same_target(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false
+    }
+}
+
+/// Copies `source` to `destination`, recursively if `source` is a directory. If `mode` is given,
+/// the file (or only the top folder) is created with it rather than keeping the mode of `source`.
+fn copy_recursive(source: &Path, destination: &Path, mode: Option<FileMode>) -> io::Result<()> {
+    if !source.is_dir() {
+        let Some(mode) = mode else {
+            return fs::copy(source, destination).map(|_| ())
+        };
+        let mut file: fs::File = platform::create_with_mode(destination, mode.bits())?;
+        return io::copy(&mut fs::File::open(source)?, &mut file).map(|_| ()).inspect_err(|_| {
+            // Never recorded, so would otherwise be left behind
+            let _ = fs::remove_file(destination);
+        })
+    }
+
+    match mode {
+        // The user can always fill the folder, it's only given exactly `mode` once it's full
+        Some(mode) => platform::create_dir_with_mode(destination, mode.bits() | 0o700)?,
+        None => fs::create_dir(destination)?
+    }
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target: PathBuf = destination.join(entry.file_name());
+        copy_recursive(&entry.path(), &target, None)?;
+    }
+    match mode {
+        Some(mode) => platform::set_mode(destination, mode.bits()),
+        None => Ok(())
+    }
+}
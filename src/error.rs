@@ -1,6 +1,11 @@
-use std::fmt::Display;
+use std::{error::Error, fmt::Display, io};
 
 /// A generic error for any Dotulous operation, including Profile and Meta operations.
+///
+/// Variants for something that failed (such as reading a file) carry the error that caused it,
+/// which is returned by [`Error::source`]. Displaying the error only shows dotulous's own message,
+/// use the alternate form (`{:#}`) to also show every cause.
+#[derive(Debug)]
 pub enum DotulousError {
     // Profiles
     /// Profile was not found.
@@ -8,50 +13,649 @@ pub enum DotulousError {
     /// No manifest was found inside the profile.
     NoManifestInProfile,
     /// Failed to read profile manifest.
-    FailedReadManifest,
-    /// Failed to deserialize profile manifest from JSON.
-    FailedDeserializeManifest,
-    /// Failed to serialize profile manifest to JSON.
-    FailedSerializeManifest,
+    FailedReadManifest(io::Error),
+    /// Failed to deserialize profile manifest, in whichever format it's in (see
+    /// [`crate::format::ManifestFormat`]).
+    FailedDeserializeManifest(Box<dyn Error + Send + Sync>),
+    /// Failed to serialize profile manifest, in whichever format it's in.
+    FailedSerializeManifest(Box<dyn Error + Send + Sync>),
     /// Failed to save profile manifest to disk.
-    FailedSaveManifest,
+    FailedSaveManifest(io::Error),
     /// Manifest files array is already populated.
     FillManifestArrayNotEmpty,
     /// Failed to read from profile directory.
-    FailedReadProfileDirectory,
+    FailedReadProfileDirectory(io::Error),
+    /// Profile has not been marked as trusted.
+    ProfileNotTrusted,
+    /// No profile is currently loaded.
+    NoProfileLoaded,
+    /// A profile is already loaded.
+    ProfileAlreadyLoaded,
+    /// No ephemeral profile is currently loaded.
+    NoEphemeralLoad,
+    /// Failed to read from the dotulous folder.
+    FailedReadDotulousDirectory(io::Error),
+    /// Failed to read the profile's ignore patterns.
+    FailedReadIgnoreFile(ignore::Error),
+    /// Extended profile was not found.
+    ExtendedProfileNotFound,
+    /// Profile extends itself.
+    ExtendsCycle,
+    /// Included folder has no manifest.
+    IncludeNotFound,
+    /// Included folder is not inside the profile's folder.
+    IncludeOutsideProfile,
+    /// Several of the profile's includes put something in the same place.
+    IncludeConflict(IncludeConflicts),
+    /// A profile with the same folder name already exists.
+    ProfileAlreadyExists,
+    /// Failed to rename profile.
+    FailedRenameProfile(io::Error),
+    /// Profile manifest was made by a newer version of dotulous.
+    ManifestTooNew,
+    /// Profile has no hook with the given name.
+    HookNotFound,
+    /// File is already in the profile's manifest.
+    FileAlreadyMapped,
+    /// Another file in the profile already goes to the same destination.
+    DestinationAlreadyMapped,
+    /// File is not in the profile's manifest.
+    FileNotMapped,
+    /// File was not found inside the profile's folder.
+    SourceNotFound,
 
     /// Meta was not found.
     MetaNotFound,
     /// Failed to serialize meta to JSON.
-    FailedSerializeMeta,
+    FailedSerializeMeta(serde_json::Error),
     /// Failed to deserialize meta from JSON.
-    FailedDeserializeMeta,
+    FailedDeserializeMeta(serde_json::Error),
+    /// Failed to read meta from disk.
+    FailedReadMeta(io::Error),
     /// Failed to save meta to disk.
-    FailedSaveMeta,
+    FailedSaveMeta(io::Error),
+    /// Failed to move the legacy `~/.dotulous` folder.
+    FailedMigrateLegacyFolder(io::Error),
+    /// Another dotulous instance is running.
+    AlreadyRunning,
+    /// Failed to lock the dotulous folder.
+    FailedLockDotulousDirectory(io::Error),
+
+    // Assets
+    /// Failed to create the asset cache directory.
+    FailedCreateAssetCache(io::Error),
+    /// Failed to download asset.
+    FailedDownloadAsset(io::Error),
+    /// Failed to read asset from disk.
+    FailedReadAsset(io::Error),
+    /// Failed to remove asset from the cache.
+    FailedRemoveAsset(io::Error),
+    /// Asset checksum did not match the manifest.
+    AssetChecksumMismatch,
+    /// Asset checksum is not 64 lowercase hex characters.
+    InvalidAssetChecksum,
+
+    // Packages
+    /// Failed to install packages with the native package manager.
+    FailedInstallPackages(io::Error),
+
+    // Overlays
+    /// Failed to create the temporary overlay home folder.
+    FailedCreateOverlay(io::Error),
+    /// Failed to remove the temporary overlay home folder.
+    FailedRemoveOverlay(io::Error),
+
+    // Generations
+    /// Failed to read the generation log.
+    FailedReadGenerations(io::Error),
+    /// Failed to deserialize the generation log from JSON.
+    FailedDeserializeGenerations(serde_json::Error),
+    /// Failed to serialize the generation log to JSON.
+    FailedSerializeGenerations(serde_json::Error),
+    /// Failed to save the generation log to disk.
+    FailedSaveGenerations(io::Error),
+    /// No generation has been recorded to undo.
+    NothingToUndo,
+    /// Generation was recorded by an older version of dotulous, and can't be undone.
+    GenerationNotUndoable,
+
+    // Audit
+    /// Failed to read the audit log.
+    FailedReadAudit(io::Error),
+    /// Failed to deserialize the audit log from JSON.
+    FailedDeserializeAudit(serde_json::Error),
+    /// Failed to serialize an audit log entry to JSON.
+    FailedSerializeAudit(serde_json::Error),
+    /// Failed to write to the audit log.
+    FailedWriteAudit(io::Error),
+    /// Failed to read the command logs.
+    FailedReadLogs(io::Error),
+
+    // Pins
+    /// Profile has no `dotulous.lock`.
+    PinsNotFound,
+    /// Failed to read the profile's `dotulous.lock`.
+    FailedReadPins(io::Error),
+    /// Failed to deserialize the profile's `dotulous.lock` from JSON.
+    FailedDeserializePins(serde_json::Error),
+    /// Failed to serialize the profile's pins to JSON.
+    FailedSerializePins(serde_json::Error),
+    /// Failed to save the profile's `dotulous.lock` to disk.
+    FailedSavePins(io::Error),
+
+    // Sources
+    /// Git is not installed.
+    GitNotFound,
+    /// Failed to clone git repository.
+    FailedGitClone(io::Error),
+    /// Failed to update profile from it's source.
+    FailedUpdateProfile(io::Error),
+    /// Failed to create profile archive.
+    FailedCreateArchive(io::Error),
+    /// Failed to read profile archive.
+    FailedReadArchive(io::Error),
+    /// Profile source is not a valid shorthand or URL.
+    InvalidRemote,
+    /// Failed to download profile.
+    FailedDownloadProfile(io::Error),
+    /// Stow directory has no packages.
+    NoStowPackages,
+    /// Failed to import stow directory.
+    FailedImportStow(io::Error),
+
+    // Dashboard
+    /// Failed to run the dashboard.
+    FailedRunDashboard(io::Error),
+
+    // Service
+    /// Systemd's systemctl is not installed.
+    SystemctlNotFound,
+    /// Failed to install the systemd user service.
+    FailedInstallService(io::Error),
+    /// Failed to remove the systemd user service.
+    FailedRemoveService(io::Error),
+    /// Failed to read the systemd user service.
+    FailedReadService(io::Error),
+
+    // State
+    /// Failed to read the load state.
+    FailedReadState(io::Error),
+    /// Failed to deserialize the load state from JSON.
+    FailedDeserializeState(serde_json::Error),
+    /// Failed to serialize the load state to JSON.
+    FailedSerializeState(serde_json::Error),
+    /// Failed to save the load state to disk.
+    FailedSaveState(io::Error),
+
+    // Backups
+    /// Backup was not found.
+    BackupNotFound,
+    /// Failed to create backup.
+    FailedCreateBackup(io::Error),
+    /// Failed to read backup manifest.
+    FailedReadBackup(io::Error),
+    /// Failed to deserialize backup manifest from JSON.
+    FailedDeserializeBackup(serde_json::Error),
+    /// Failed to serialize backup manifest to JSON.
+    FailedSerializeBackup(serde_json::Error),
+    /// Failed to save backup manifest to disk.
+    FailedSaveBackup(io::Error),
+
+    // Templates
+    /// Failed to read host variables.
+    FailedReadVariables(io::Error),
+    /// Failed to deserialize host variables from JSON.
+    FailedDeserializeVariables(serde_json::Error),
+
+    // Settings
+    /// Failed to read the user's settings.
+    FailedReadSettings(io::Error),
+    /// Failed to deserialize the user's settings from TOML.
+    FailedDeserializeSettings(toml::de::Error),
+    /// Failed to serialize the user's settings to TOML.
+    FailedSerializeSettings(toml::ser::Error),
+    /// Failed to save the user's settings to disk.
+    FailedSaveSettings(io::Error),
+    /// No setting has the given name.
+    UnknownSetting,
+
+    // Loading
+    /// Failed to create directory.
+    FailedCreateDirectory(io::Error),
+    /// Failed to put file onto the system.
+    FailedPlaceFile,
+    /// Command failed to run.
+    FailedRunCommand(io::Error),
+    /// Destination is outside of the home folder.
+    DestinationOutsideHome,
+    /// Destination is inside the dotulous folder or the profile's own folder.
+    DestinationInsideDotulous,
+    /// Destination already exists, and loading was aborted.
+    DestinationConflict,
+    /// A glob given to pick which entries to load is invalid.
+    InvalidFilter(globset::Error),
+    /// Profile can't be loaded onto the system as it is.
+    PreflightFailed,
+    /// Dotulous was interrupted, such as with Ctrl-C.
+    Interrupted,
+
+    // Adopting
+    /// File to adopt was not found.
+    AdoptFileNotFound,
+    /// File to adopt is a symlink.
+    AdoptFileIsSymlink,
+    /// File to adopt is not inside the profile's target folder.
+    AdoptFileOutsideTarget,
+    /// File to adopt already exists in the profile.
+    AdoptFileAlreadyInProfile,
+    /// Failed to move file into the profile.
+    FailedAdoptFile(io::Error),
+
+    // Secrets
+    /// Profile has no secrets configuration.
+    NoSecretsConfig,
+    /// Profile's secrets configuration has no recipients.
+    NoSecretRecipients,
+    /// Secrets backend is not installed.
+    SecretBackendNotFound,
+    /// Failed to decrypt secret.
+    FailedDecryptSecret(io::Error),
+    /// Failed to encrypt secret.
+    FailedEncryptSecret(io::Error),
+
+    // System files
+    /// System files can only be applied as root.
+    NotRoot,
+    /// System file's destination is not an absolute path.
+    SystemDestinationNotAbsolute,
+    /// System file's source is outside of the profile's folder.
+    SystemSourceOutsideProfile,
+    /// Failed to apply system file.
+    FailedApplySystemFile(io::Error),
+
+    // Updates
+    /// Failed to check for a newer version of dotulous.
+    FailedCheckUpdate(io::Error),
+    /// Failed to deserialize the latest release from JSON.
+    FailedDeserializeRelease(serde_json::Error),
+    /// Latest release has no build for this platform.
+    NoReleaseArtifact,
+    /// Failed to download the latest release.
+    FailedDownloadUpdate(io::Error),
+    /// Downloaded release's checksum did not match.
+    UpdateChecksumMismatch,
+    /// Failed to replace the dotulous executable.
+    FailedReplaceExecutable(io::Error),
 }
 impl DotulousError {
+    /// Returns the broad [`ErrorCategory`] of the error, such as something not being found.
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            DotulousError::ProfileNotFound
+            | DotulousError::NoManifestInProfile
+            | DotulousError::NoProfileLoaded
+            | DotulousError::NoEphemeralLoad
+            | DotulousError::ExtendedProfileNotFound
+            | DotulousError::IncludeNotFound
+            | DotulousError::HookNotFound
+            | DotulousError::NothingToUndo
+            | DotulousError::PinsNotFound
+            | DotulousError::FileNotMapped
+            | DotulousError::SourceNotFound
+            | DotulousError::MetaNotFound
+            | DotulousError::GitNotFound
+            | DotulousError::SystemctlNotFound
+            | DotulousError::BackupNotFound
+            | DotulousError::UnknownSetting
+            | DotulousError::AdoptFileNotFound
+            | DotulousError::NoSecretsConfig
+            | DotulousError::SecretBackendNotFound
+            | DotulousError::NoReleaseArtifact
+            | DotulousError::NoStowPackages => ErrorCategory::NotFound,
+
+            DotulousError::ProfileNotTrusted => ErrorCategory::TrustDeclined,
+
+            DotulousError::FailedDeserializeManifest(_)
+            | DotulousError::ExtendsCycle
+            | DotulousError::IncludeOutsideProfile
+            | DotulousError::IncludeConflict(_)
+            | DotulousError::ManifestTooNew
+            | DotulousError::DestinationOutsideHome
+            | DotulousError::DestinationInsideDotulous
+            | DotulousError::NoSecretRecipients
+            | DotulousError::SystemDestinationNotAbsolute
+            | DotulousError::SystemSourceOutsideProfile
+            | DotulousError::InvalidAssetChecksum => ErrorCategory::InvalidManifest,
+
+            DotulousError::FailedPlaceFile
+            | DotulousError::FailedCreateDirectory(_)
+            | DotulousError::FailedRunCommand(_)
+            | DotulousError::FailedDownloadAsset(_)
+            | DotulousError::AssetChecksumMismatch => ErrorCategory::PartialApply,
+
+            DotulousError::Interrupted => ErrorCategory::Interrupted,
+
+            DotulousError::FillManifestArrayNotEmpty
+            | DotulousError::ProfileAlreadyLoaded
+            | DotulousError::ProfileAlreadyExists
+            | DotulousError::DestinationConflict
+            | DotulousError::PreflightFailed
+            | DotulousError::FileAlreadyMapped
+            | DotulousError::DestinationAlreadyMapped
+            | DotulousError::InvalidFilter(_)
+            | DotulousError::InvalidRemote
+            | DotulousError::AlreadyRunning
+            | DotulousError::FailedDeserializeSettings(_)
+            | DotulousError::GenerationNotUndoable
+            | DotulousError::AdoptFileIsSymlink
+            | DotulousError::AdoptFileOutsideTarget
+            | DotulousError::AdoptFileAlreadyInProfile
+            | DotulousError::UpdateChecksumMismatch
+            | DotulousError::NotRoot => ErrorCategory::Other,
+
+            _ => ErrorCategory::Io
+        }
+    }
+
     /// Returns a string slice description of the error, for displaying it.
     fn as_str(&self) -> &str {
         match self {
             DotulousError::ProfileNotFound => "Profile was not found.",
             DotulousError::NoManifestInProfile => "No manifest was found inside the profile.",
-            DotulousError::FailedReadManifest => "Failed to read profile manifest.",
-            DotulousError::FailedDeserializeManifest => "Failed to deserialize profile manifest from JSON.",
-            DotulousError::FailedSerializeManifest => "Failed to serialize profile manifest to JSON.",
-            DotulousError::FailedSaveManifest => "Failed to save profile manifest to disk.",
+            DotulousError::FailedReadManifest(_) => "Failed to read profile manifest.",
+            DotulousError::FailedDeserializeManifest(_) => "Failed to deserialize profile manifest. Run `dotulous validate` for details.",
+            DotulousError::FailedSerializeManifest(_) => "Failed to serialize profile manifest.",
+            DotulousError::FailedSaveManifest(_) => "Failed to save profile manifest to disk.",
             DotulousError::FillManifestArrayNotEmpty => "Manifest files array is already populated.",
-            DotulousError::FailedReadProfileDirectory => "Failed to read from profile directory.",
+            DotulousError::FailedReadProfileDirectory(_) => "Failed to read from profile directory.",
+            DotulousError::ProfileNotTrusted => "Profile has not been marked as trusted.",
+            DotulousError::NoProfileLoaded => "No profile is currently loaded.",
+            DotulousError::ProfileAlreadyLoaded => "A profile is already loaded.",
+            DotulousError::NoEphemeralLoad => "No ephemeral profile is currently loaded.",
+            DotulousError::FailedReadDotulousDirectory(_) => "Failed to read from the dotulous folder.",
+            DotulousError::FailedReadIgnoreFile(_) => "Failed to read the profile's ignore patterns.",
+            DotulousError::ExtendedProfileNotFound => "Extended profile was not found.",
+            DotulousError::ExtendsCycle => "Profile extends itself.",
+            DotulousError::IncludeNotFound => "Included folder has no manifest.",
+            DotulousError::IncludeOutsideProfile => "Included folder is not inside the profile's folder.",
+            DotulousError::IncludeConflict(_) => "Several of the profile's includes put something in the same place.",
+            DotulousError::ProfileAlreadyExists => "A profile with the same folder name already exists.",
+            DotulousError::FailedRenameProfile(_) => "Failed to rename profile.",
+            DotulousError::ManifestTooNew => "Profile manifest was made by a newer version of dotulous, update dotulous to use it.",
+            DotulousError::HookNotFound => "Profile has no hook with the given name.",
+            DotulousError::FileAlreadyMapped => "File is already in the profile's manifest.",
+            DotulousError::DestinationAlreadyMapped => "Another file in the profile already goes to the same destination.",
+            DotulousError::FileNotMapped => "File is not in the profile's manifest.",
+            DotulousError::SourceNotFound => "File was not found inside the profile's folder.",
 
 
             DotulousError::MetaNotFound => "Meta was not found.",
-            DotulousError::FailedSerializeMeta => "Failed to serialize meta to JSON.",
-            DotulousError::FailedDeserializeMeta => "Failed to deserialize meta from JSON.",
-            DotulousError::FailedSaveMeta => "Failed to save meta to disk.",
+            DotulousError::FailedSerializeMeta(_) => "Failed to serialize meta to JSON.",
+            DotulousError::FailedDeserializeMeta(_) => "Failed to deserialize meta from JSON.",
+            DotulousError::FailedReadMeta(_) => "Failed to read meta from disk.",
+            DotulousError::FailedSaveMeta(_) => "Failed to save meta to disk.",
+            DotulousError::AlreadyRunning => "Another dotulous instance is running, wait for it to finish and try again.",
+            DotulousError::FailedLockDotulousDirectory(_) => "Failed to lock the dotulous folder.",
+            DotulousError::FailedMigrateLegacyFolder(_) => "Failed to move the legacy ~/.dotulous folder.",
+
+            DotulousError::FailedCreateAssetCache(_) => "Failed to create the asset cache directory.",
+            DotulousError::FailedDownloadAsset(_) => "Failed to download asset.",
+            DotulousError::FailedReadAsset(_) => "Failed to read asset from disk.",
+            DotulousError::FailedRemoveAsset(_) => "Failed to remove asset from the cache.",
+            DotulousError::AssetChecksumMismatch => "Asset checksum did not match the manifest.",
+            DotulousError::InvalidAssetChecksum => "Asset checksum is not 64 lowercase hex characters.",
+
+            DotulousError::FailedInstallPackages(_) => "Failed to install packages with the native package manager.",
+
+            DotulousError::FailedCreateOverlay(_) => "Failed to create the temporary overlay home folder.",
+            DotulousError::FailedRemoveOverlay(_) => "Failed to remove the temporary overlay home folder.",
+
+            DotulousError::FailedReadGenerations(_) => "Failed to read the generation log.",
+            DotulousError::FailedDeserializeGenerations(_) => "Failed to deserialize the generation log from JSON.",
+            DotulousError::FailedSerializeGenerations(_) => "Failed to serialize the generation log to JSON.",
+            DotulousError::FailedSaveGenerations(_) => "Failed to save the generation log to disk.",
+            DotulousError::NothingToUndo => "No generation has been recorded to undo.",
+            DotulousError::GenerationNotUndoable => "Generation was recorded by an older version of dotulous, and can't be undone.",
+
+            DotulousError::FailedReadAudit(_) => "Failed to read the audit log.",
+            DotulousError::FailedDeserializeAudit(_) => "Failed to deserialize the audit log from JSON.",
+            DotulousError::FailedSerializeAudit(_) => "Failed to serialize an audit log entry to JSON.",
+            DotulousError::FailedWriteAudit(_) => "Failed to write to the audit log.",
+            DotulousError::FailedReadLogs(_) => "Failed to read the command logs.",
+            DotulousError::PinsNotFound => "Profile has no dotulous.lock.",
+            DotulousError::FailedReadPins(_) => "Failed to read the profile's dotulous.lock.",
+            DotulousError::FailedDeserializePins(_) => "Failed to deserialize the profile's dotulous.lock from JSON.",
+            DotulousError::FailedSerializePins(_) => "Failed to serialize the profile's pins to JSON.",
+            DotulousError::FailedSavePins(_) => "Failed to save the profile's dotulous.lock to disk.",
+
+            DotulousError::GitNotFound => "Git is not installed.",
+            DotulousError::FailedGitClone(_) => "Failed to clone git repository.",
+            DotulousError::FailedUpdateProfile(_) => "Failed to update profile from it's source.",
+            DotulousError::FailedCreateArchive(_) => "Failed to create profile archive.",
+            DotulousError::FailedReadArchive(_) => "Failed to read profile archive.",
+            DotulousError::InvalidRemote => "Profile source is not a valid shorthand or URL.",
+            DotulousError::FailedDownloadProfile(_) => "Failed to download profile.",
+            DotulousError::NoStowPackages => "Stow directory has no packages.",
+            DotulousError::FailedImportStow(_) => "Failed to import stow directory.",
+            DotulousError::FailedRunDashboard(_) => "Failed to run the dashboard.",
+
+            DotulousError::SystemctlNotFound => "Systemd's systemctl is not installed.",
+            DotulousError::FailedInstallService(_) => "Failed to install the systemd user service.",
+            DotulousError::FailedRemoveService(_) => "Failed to remove the systemd user service.",
+            DotulousError::FailedReadService(_) => "Failed to read the systemd user service.",
+
+            DotulousError::FailedReadState(_) => "Failed to read the load state.",
+            DotulousError::FailedDeserializeState(_) => "Failed to deserialize the load state from JSON.",
+            DotulousError::FailedSerializeState(_) => "Failed to serialize the load state to JSON.",
+            DotulousError::FailedSaveState(_) => "Failed to save the load state to disk.",
+
+            DotulousError::BackupNotFound => "Backup was not found.",
+            DotulousError::FailedCreateBackup(_) => "Failed to create backup.",
+            DotulousError::FailedReadBackup(_) => "Failed to read backup manifest.",
+            DotulousError::FailedDeserializeBackup(_) => "Failed to deserialize backup manifest from JSON.",
+            DotulousError::FailedSerializeBackup(_) => "Failed to serialize backup manifest to JSON.",
+            DotulousError::FailedSaveBackup(_) => "Failed to save backup manifest to disk.",
+
+            DotulousError::FailedReadVariables(_) => "Failed to read host variables.",
+            DotulousError::FailedDeserializeVariables(_) => "Failed to deserialize host variables from JSON.",
+
+            DotulousError::FailedReadSettings(_) => "Failed to read the user's settings.",
+            DotulousError::FailedDeserializeSettings(_) => "Failed to deserialize the user's settings from TOML.",
+            DotulousError::FailedSerializeSettings(_) => "Failed to serialize the user's settings to TOML.",
+            DotulousError::FailedSaveSettings(_) => "Failed to save the user's settings to disk.",
+            DotulousError::UnknownSetting => "No setting has the given name.",
+
+            DotulousError::FailedCreateDirectory(_) => "Failed to create directory.",
+            DotulousError::FailedPlaceFile => "Failed to put file onto the system.",
+            DotulousError::FailedRunCommand(_) => "Command failed to run.",
+            DotulousError::DestinationOutsideHome => "Destination is outside of the home folder.",
+            DotulousError::DestinationInsideDotulous => "Destination is inside the dotulous folder or the profile's own folder.",
+            DotulousError::DestinationConflict => "Destination already exists, and loading was aborted.",
+            DotulousError::InvalidFilter(_) => "A glob given to pick which entries to load is invalid.",
+            DotulousError::PreflightFailed => "Profile can't be loaded onto the system as it is.",
+            DotulousError::Interrupted => "Dotulous was interrupted.",
+
+            DotulousError::AdoptFileNotFound => "File to adopt was not found.",
+            DotulousError::AdoptFileIsSymlink => "File to adopt is a symlink.",
+            DotulousError::AdoptFileOutsideTarget => "File to adopt is not inside the profile's target folder.",
+            DotulousError::AdoptFileAlreadyInProfile => "File to adopt already exists in the profile.",
+            DotulousError::FailedAdoptFile(_) => "Failed to move file into the profile.",
+
+            DotulousError::NoSecretsConfig => "Profile has no secrets configuration.",
+            DotulousError::NoSecretRecipients => "Profile's secrets configuration has no recipients.",
+            DotulousError::SecretBackendNotFound => "Secrets backend is not installed.",
+            DotulousError::FailedDecryptSecret(_) => "Failed to decrypt secret.",
+            DotulousError::FailedEncryptSecret(_) => "Failed to encrypt secret.",
+
+            DotulousError::NotRoot => "System files can only be applied as root.",
+            DotulousError::SystemDestinationNotAbsolute => "System file's destination is not an absolute path.",
+            DotulousError::SystemSourceOutsideProfile => "System file's source is outside of the profile's folder.",
+            DotulousError::FailedApplySystemFile(_) => "Failed to apply system file.",
+
+            DotulousError::FailedCheckUpdate(_) => "Failed to check for a newer version of dotulous.",
+            DotulousError::FailedDeserializeRelease(_) => "Failed to deserialize the latest release from JSON.",
+            DotulousError::NoReleaseArtifact => "Latest release has no build for this platform, update with `cargo install dotulous` instead.",
+            DotulousError::FailedDownloadUpdate(_) => "Failed to download the latest release.",
+            DotulousError::UpdateChecksumMismatch => "Downloaded release's checksum did not match, nothing was changed.",
+            DotulousError::FailedReplaceExecutable(_) => "Failed to replace the dotulous executable.",
         }
     }
 }
 impl Display for DotulousError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_str())
+        write!(f, "{}", self.as_str())?;
+        if f.alternate() {
+            let mut source: Option<&dyn Error> = self.source();
+            let mut separator: &str = " Caused by:";
+            while let Some(cause) = source {
+                write!(f, "{separator} {cause}")?;
+                separator = ":";
+                source = cause.source();
+            }
+        }
+        Ok(())
+    }
+}
+impl Error for DotulousError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DotulousError::FailedReadManifest(e) => Some(e),
+            DotulousError::FailedSaveManifest(e) => Some(e),
+            DotulousError::FailedReadProfileDirectory(e) => Some(e),
+            DotulousError::FailedReadDotulousDirectory(e) => Some(e),
+            DotulousError::FailedRenameProfile(e) => Some(e),
+            DotulousError::FailedReadMeta(e) => Some(e),
+            DotulousError::FailedSaveMeta(e) => Some(e),
+            DotulousError::FailedLockDotulousDirectory(e) => Some(e),
+            DotulousError::FailedMigrateLegacyFolder(e) => Some(e),
+            DotulousError::FailedCreateAssetCache(e) => Some(e),
+            DotulousError::FailedDownloadAsset(e) => Some(e),
+            DotulousError::FailedReadAsset(e) => Some(e),
+            DotulousError::FailedRemoveAsset(e) => Some(e),
+            DotulousError::FailedInstallPackages(e) => Some(e),
+            DotulousError::FailedCreateOverlay(e) => Some(e),
+            DotulousError::FailedRemoveOverlay(e) => Some(e),
+            DotulousError::FailedReadGenerations(e) => Some(e),
+            DotulousError::FailedSaveGenerations(e) => Some(e),
+            DotulousError::FailedReadAudit(e) => Some(e),
+            DotulousError::FailedWriteAudit(e) => Some(e),
+            DotulousError::FailedReadLogs(e) => Some(e),
+            DotulousError::FailedReadPins(e) => Some(e),
+            DotulousError::FailedSavePins(e) => Some(e),
+            DotulousError::FailedGitClone(e) => Some(e),
+            DotulousError::FailedUpdateProfile(e) => Some(e),
+            DotulousError::FailedCreateArchive(e) => Some(e),
+            DotulousError::FailedReadArchive(e) => Some(e),
+            DotulousError::FailedDownloadProfile(e) => Some(e),
+            DotulousError::FailedImportStow(e) => Some(e),
+            DotulousError::FailedRunDashboard(e) => Some(e),
+            DotulousError::FailedInstallService(e) => Some(e),
+            DotulousError::FailedRemoveService(e) => Some(e),
+            DotulousError::FailedReadService(e) => Some(e),
+            DotulousError::FailedReadState(e) => Some(e),
+            DotulousError::FailedSaveState(e) => Some(e),
+            DotulousError::FailedCreateBackup(e) => Some(e),
+            DotulousError::FailedReadBackup(e) => Some(e),
+            DotulousError::FailedSaveBackup(e) => Some(e),
+            DotulousError::FailedReadVariables(e) => Some(e),
+            DotulousError::FailedReadSettings(e) => Some(e),
+            DotulousError::FailedSaveSettings(e) => Some(e),
+            DotulousError::FailedCreateDirectory(e) => Some(e),
+            DotulousError::FailedRunCommand(e) => Some(e),
+            DotulousError::FailedAdoptFile(e) => Some(e),
+            DotulousError::FailedDecryptSecret(e) => Some(e),
+            DotulousError::FailedEncryptSecret(e) => Some(e),
+            DotulousError::FailedApplySystemFile(e) => Some(e),
+            DotulousError::FailedCheckUpdate(e) => Some(e),
+            DotulousError::FailedDownloadUpdate(e) => Some(e),
+            DotulousError::FailedReplaceExecutable(e) => Some(e),
+            DotulousError::FailedDeserializeManifest(e) => Some(e.as_ref()),
+            DotulousError::FailedSerializeManifest(e) => Some(e.as_ref()),
+            DotulousError::FailedSerializeMeta(e) => Some(e),
+            DotulousError::FailedDeserializeMeta(e) => Some(e),
+            DotulousError::FailedDeserializeGenerations(e) => Some(e),
+            DotulousError::FailedSerializeGenerations(e) => Some(e),
+            DotulousError::FailedDeserializeAudit(e) => Some(e),
+            DotulousError::FailedSerializeAudit(e) => Some(e),
+            DotulousError::FailedDeserializePins(e) => Some(e),
+            DotulousError::FailedSerializePins(e) => Some(e),
+            DotulousError::FailedDeserializeState(e) => Some(e),
+            DotulousError::FailedSerializeState(e) => Some(e),
+            DotulousError::FailedDeserializeBackup(e) => Some(e),
+            DotulousError::FailedSerializeBackup(e) => Some(e),
+            DotulousError::FailedDeserializeVariables(e) => Some(e),
+            DotulousError::FailedDeserializeSettings(e) => Some(e),
+            DotulousError::FailedSerializeSettings(e) => Some(e),
+            DotulousError::FailedDeserializeRelease(e) => Some(e),
+            DotulousError::FailedReadIgnoreFile(e) => Some(e),
+            DotulousError::InvalidFilter(e) => Some(e),
+            DotulousError::IncludeConflict(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+/// Everything that more than one part of a profile sets, being the profile itself or one of it's
+/// `includes`, for [`DotulousError::IncludeConflict`]. Each is a description of what's set, such
+/// as a destination or a variable's name, followed by the names of the parts, e.g.
+/// `("~/.zshrc", ["shell", "prompt"])` or `("variable \"theme\"", ["shell", "prompt"])`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeConflicts(pub Vec<(String, Vec<String>)>);
+impl Display for IncludeConflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let conflicts: Vec<String> = self.0.iter()
+            .map(|(item, parts)| format!("{item} is set by {}", parts.join(" & ")))
+            .collect();
+        write!(f, "{}", conflicts.join(", "))
+    }
+}
+impl Error for IncludeConflicts {}
+
+/// The broad category of a [`DotulousError`], so scripts can tell failures apart by the CLI's exit
+/// code without parsing it's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Anything that doesn't fit another category, such as a profile already existing.
+    Other,
+    /// Something asked for wasn't found, such as a profile, backup or program.
+    NotFound,
+    /// The profile isn't trusted, or the user declined to trust it.
+    TrustDeclined,
+    /// Reading or writing something failed, such as a file being unreadable or permission being
+    /// denied.
+    Io,
+    /// The profile's manifest is invalid.
+    InvalidManifest,
+    /// Loading a profile failed part way through, such as a file or command failing. Whatever was
+    /// already done is rolled back where possible.
+    PartialApply,
+    /// Dotulous was interrupted, such as with Ctrl-C. Like [`ErrorCategory::PartialApply`],
+    /// whatever was already done is rolled back where possible.
+    Interrupted
+}
+impl ErrorCategory {
+    /// Returns the exit code the CLI exits with for this category. `2` is left to invalid
+    /// command-line arguments, and `3` to needing an answer from the user while non-interactive.
+    /// Being interrupted uses `130`, the same as shells do for Ctrl-C.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorCategory::Other => 1,
+            ErrorCategory::NotFound => 4,
+            ErrorCategory::TrustDeclined => 5,
+            ErrorCategory::Io => 6,
+            ErrorCategory::InvalidManifest => 7,
+            ErrorCategory::PartialApply => 8,
+            ErrorCategory::Interrupted => 130
+        }
+    }
+}
+impl From<&DotulousError> for ErrorCategory {
+    fn from(value: &DotulousError) -> Self {
+        value.category()
+    }
+}
+impl From<&io::Error> for ErrorCategory {
+    fn from(_: &io::Error) -> Self {
+        ErrorCategory::Io
     }
 }
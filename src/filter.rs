@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::error::DotulousError;
+
+/// Picks which of a profile's `files` & `assets` to load or unload, from the `--only` & `--except`
+/// globs given on the command line, so part of a profile can be applied on it's own (see
+/// [`crate::operations::load_partial`]).
+///
+/// An entry is picked if it's destination (as written in the manifest) or source matches any of
+/// the `only` globs, or if there are none, and it matches none of the `except` globs. `*` also
+/// matches `/`, so `.config/nvim*` picks everything inside `.config/nvim` too.
+pub struct EntryFilter {
+    /// The globs an entry has to match one of, or [`None`] to pick every entry.
+    only: Option<GlobSet>,
+    /// The globs an entry mustn't match.
+    except: GlobSet
+}
+impl EntryFilter {
+    /// Compiles the `only` & `except` globs into a filter.
+    ///
+    /// If any glob is invalid, [`Err`] with [`DotulousError::InvalidFilter`] is returned.
+    pub fn new(only: &[String], except: &[String]) -> Result<EntryFilter, DotulousError> {
+        let only: Option<GlobSet> = if only.is_empty() { None } else { Some(build_set(only)?) };
+        Ok(EntryFilter { only, except: build_set(except)? })
+    }
+
+    /// Creates a filter picking only the entry at `destination` (as written in the manifest), along
+    /// with anything inside it, such as the files of a directory linked file by file. Used to load
+    /// or unload a single entry on it's own.
+    pub fn path(destination: &Path) -> EntryFilter {
+        let escaped: String = globset::escape(&destination.to_string_lossy());
+        let only: GlobSet = build_set(&[escaped.clone(), format!("{escaped}/*")]).unwrap_or_else(|_| GlobSet::empty());
+        EntryFilter { only: Some(only), except: GlobSet::empty() }
+    }
+
+    /// Returns if the filter picks every entry, as no globs were given.
+    pub fn is_empty(&self) -> bool {
+        self.only.is_none() && self.except.is_empty()
+    }
+
+    /// Returns if the entry with the given `destination` & `source` is picked.
+    pub fn matches(&self, destination: &Path, source: &str) -> bool {
+        let matches = |set: &GlobSet| set.is_match(destination) || set.is_match(source);
+        self.only.as_ref().is_none_or(matches) && !matches(&self.except)
+    }
+}
+
+/// Compiles every glob in `globs` into one [`GlobSet`].
+fn build_set(globs: &[String]) -> Result<GlobSet, DotulousError> {
+    let mut builder: GlobSetBuilder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).map_err(DotulousError::InvalidFilter)?);
+    }
+    builder.build().map_err(DotulousError::InvalidFilter)
+}
+
+/// Picks which of a profile's `files` & commands to load by their `tags`, from the `--tags` &
+/// `--skip-tags` given when loading, such as to skip GUI configs on a headless server with
+/// `--skip-tags gui`. The filter is remembered in the meta, so reloading picks the same entries.
+///
+/// If any `tags` are given, only entries with at least one of them are picked, otherwise every
+/// entry is. Entries with any of the `skip_tags` are never picked.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct TagFilter {
+    /// The tags an entry has to have one of, or empty to pick every entry.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The tags an entry mustn't have.
+    #[serde(default)]
+    pub skip_tags: Vec<String>
+}
+impl TagFilter {
+    /// Returns if the filter picks every entry, as no tags were given.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.skip_tags.is_empty()
+    }
+
+    /// Returns if an entry with the given `tags` is picked.
+    pub fn matches(&self, tags: &[String]) -> bool {
+        (self.tags.is_empty() || tags.iter().any(|tag| self.tags.contains(tag)))
+            && !tags.iter().any(|tag| self.skip_tags.contains(tag))
+    }
+}
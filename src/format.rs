@@ -0,0 +1,110 @@
+use std::{fmt::Display, path::{Path, PathBuf}, str::FromStr};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::DotulousError;
+
+/// The file format of a profile's manifest, picked by it's file name: `manifest.json`,
+/// `manifest.toml` or `manifest.yaml` (or `manifest.yml`).
+///
+/// Every format shares the same model, [`crate::profile::DotfileProfile`], so manifests are parsed
+/// into JSON [`Value`]s first with [`ManifestFormat::parse`]. This lets older manifests be upgraded
+/// the same way no matter their format, see [`crate::migrate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ManifestFormat {
+    #[default]
+    Json,
+    Toml,
+    Yaml
+}
+impl ManifestFormat {
+    /// Every format, in the order they're looked for when a profile has more than one manifest.
+    pub const ALL: [ManifestFormat; 3] = [ManifestFormat::Json, ManifestFormat::Toml, ManifestFormat::Yaml];
+
+    /// Returns the name of a manifest file in this format, e.g. `manifest.json`.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ManifestFormat::Json => "manifest.json",
+            ManifestFormat::Toml => "manifest.toml",
+            ManifestFormat::Yaml => "manifest.yaml"
+        }
+    }
+
+    /// Returns every name a manifest file in this format is found by, starting with
+    /// [`ManifestFormat::file_name`].
+    pub fn file_names(&self) -> &'static [&'static str] {
+        match self {
+            ManifestFormat::Json => &["manifest.json"],
+            ManifestFormat::Toml => &["manifest.toml"],
+            ManifestFormat::Yaml => &["manifest.yaml", "manifest.yml"]
+        }
+    }
+
+    /// Returns the format of the manifest at `path` from it's extension, defaulting to JSON.
+    pub fn from_path(path: &Path) -> ManifestFormat {
+        let extension: Option<&str> = path.extension().and_then(|extension| extension.to_str());
+        match extension {
+            Some("toml") => ManifestFormat::Toml,
+            Some("yaml") | Some("yml") => ManifestFormat::Yaml,
+            _ => ManifestFormat::Json
+        }
+    }
+
+    /// Returns the *absolute* path of the manifest inside the profile's folder at `profile_path`,
+    /// in whichever format exists under any of it's [`ManifestFormat::file_names`], or [`None`] if
+    /// it has no manifest. If there's more than one, the first of [`ManifestFormat::ALL`] is used.
+    pub fn find_manifest(profile_path: &Path) -> Option<PathBuf> {
+        ManifestFormat::ALL.iter()
+            .flat_map(|format| format.file_names())
+            .map(|file_name| profile_path.join(file_name))
+            .find(|path| path.is_file())
+    }
+
+    /// Parses the `contents` of a manifest in this format.
+    ///
+    /// If the contents aren't valid, [`Err`] with [`DotulousError::FailedDeserializeManifest`] is
+    /// returned.
+    pub fn parse(&self, contents: &str) -> Result<Value, DotulousError> {
+        let parsed: Result<Value, Box<dyn std::error::Error + Send + Sync>> = match self {
+            ManifestFormat::Json => serde_json::from_str(contents).map_err(Into::into),
+            ManifestFormat::Toml => toml::from_str(contents).map_err(Into::into),
+            ManifestFormat::Yaml => serde_yaml_ng::from_str(contents).map_err(Into::into)
+        };
+        parsed.map_err(DotulousError::FailedDeserializeManifest)
+    }
+
+    /// Serializes `value` into a manifest in this format.
+    ///
+    /// If it can't be represented in this format, [`Err`] with
+    /// [`DotulousError::FailedSerializeManifest`] is returned.
+    pub fn serialize(&self, value: &impl Serialize) -> Result<String, DotulousError> {
+        let serialized: Result<String, Box<dyn std::error::Error + Send + Sync>> = match self {
+            ManifestFormat::Json => serde_json::to_string_pretty(value).map_err(Into::into),
+            ManifestFormat::Toml => toml::to_string_pretty(value).map_err(Into::into),
+            ManifestFormat::Yaml => serde_yaml_ng::to_string(value).map_err(Into::into)
+        };
+        serialized.map_err(DotulousError::FailedSerializeManifest)
+    }
+}
+impl Display for ManifestFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestFormat::Json => f.pad("json"),
+            ManifestFormat::Toml => f.pad("toml"),
+            ManifestFormat::Yaml => f.pad("yaml")
+        }
+    }
+}
+impl FromStr for ManifestFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ManifestFormat::Json),
+            "toml" => Ok(ManifestFormat::Toml),
+            "yaml" | "yml" => Ok(ManifestFormat::Yaml),
+            _ => Err(format!("Unknown manifest format \"{s}\", expected json, toml or yaml"))
+        }
+    }
+}
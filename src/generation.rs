@@ -0,0 +1,174 @@
+use std::{collections::{BTreeMap, BTreeSet}, fmt::Display, fs, path::{Path, PathBuf}};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DotulousError, profile::DotfileProfile};
+
+/// The log of every generation applied to this machine, stored in the user's dotulous folder
+/// as `generations.json`.
+///
+/// A new generation is recorded every time the loaded profile changes (loading, reloading,
+/// unloading...) with [`GenerationLog::record`], storing what changed compared to the previous
+/// generation. This lets the user find out what changed on their machine and when, and undo the
+/// latest generation with [`crate::operations::undo`].
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GenerationLog {
+    /// Every recorded generation, oldest first.
+    #[serde(default)]
+    generations: Vec<Generation>
+}
+impl GenerationLog {
+    /// Load the generation log from disk, using `generations.json` inside of the given `dotulous_path`.
+    /// If no log exists yet, an empty log is returned.
+    pub fn load(dotulous_path: &Path) -> Result<GenerationLog, DotulousError> {
+        let path: PathBuf = dotulous_path.join(Path::new("generations.json"));
+        if !path.exists() {
+            return Ok(GenerationLog::default())
+        }
+
+        let contents: String = fs::read_to_string(path).map_err(DotulousError::FailedReadGenerations)?;
+        let deserialized: Self = serde_json::from_str(&contents).map_err(DotulousError::FailedDeserializeGenerations)?;
+        Ok(deserialized)
+    }
+
+    /// Save the generation log to disk, using `generations.json` inside of the given `dotulous_path`.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn save(&self, dotulous_path: &Path) -> Result<(), DotulousError> {
+        let path: PathBuf = dotulous_path.join(Path::new("generations.json"));
+        let serialized: String = serde_json::to_string_pretty(self).map_err(DotulousError::FailedSerializeGenerations)?;
+        fs::write(path, serialized).map_err(DotulousError::FailedSaveGenerations)?;
+        Ok(())
+    }
+
+    /// Records a new generation for the given `action`, diffing the `previous` profile that was on
+    /// the system against the `current` one. Either may be [`None`] if no profile was loaded.
+    ///
+    /// Note that this does not save the log, call [`GenerationLog::save`] afterwards.
+    pub fn record(&mut self, action: &str, previous: Option<&DotfileProfile>, current: Option<&DotfileProfile>) {
+        let number: usize = self.generations.last().map_or(1, |g| g.number + 1);
+        self.generations.push(Generation {
+            number,
+            timestamp: Utc::now(),
+            action: action.to_string(),
+            profile_name: current.map(|p| p.name.clone()),
+            changes: diff(previous, current),
+            before: Some(Snapshot { profile: previous.cloned() })
+        });
+    }
+
+    /// Returns all recorded generations, oldest first.
+    pub fn generations(&self) -> &[Generation] {
+        &self.generations
+    }
+
+    /// Returns the most recent generation, or [`None`] if none have been recorded yet.
+    pub fn latest(&self) -> Option<&Generation> {
+        self.generations.last()
+    }
+}
+
+/// A single generation of the system, recording what changed compared to the previous one.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Generation {
+    /// The generation's number, counting up from `1`.
+    pub number: usize,
+    /// When the generation was applied.
+    pub timestamp: DateTime<Utc>,
+    /// The action that created the generation, such as `load` or `reload`.
+    pub action: String,
+    /// The name of the profile loaded by this generation, or [`None`] if it unloaded the profile.
+    pub profile_name: Option<String>,
+    /// What changed compared to the previous generation.
+    pub changes: Vec<Change>,
+    /// What was loaded before this generation was applied, so it can be undone. [`None`] for
+    /// generations recorded by older versions of dotulous, which can't be undone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Snapshot>
+}
+impl Display for Generation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timestamp: DateTime<Local> = self.timestamp.into();
+        write!(f, "Generation {} - {} - {}", self.number, timestamp.format("%Y-%m-%d %H:%M:%S"), self.action)?;
+        match &self.profile_name {
+            Some(name) => write!(f, " {name}"),
+            None => Ok(())
+        }
+    }
+}
+
+/// The state of the system before a [`Generation`] was applied.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Snapshot {
+    /// The copy of the profile that was loaded (see [`crate::meta::Meta::current_profile`]), or
+    /// [`None`] if no profile was loaded.
+    pub profile: Option<DotfileProfile>
+}
+
+/// A single change between two generations.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    /// A new link was created at `destination`.
+    LinkAdded { destination: PathBuf, source: String },
+    /// The link at `destination` was removed.
+    LinkRemoved { destination: PathBuf, source: String },
+    /// The link at `destination` now points to a different source.
+    LinkChanged { destination: PathBuf, old_source: String, new_source: String },
+    /// A command was added to the `hook` command list.
+    CommandAdded { hook: String, command: String },
+    /// A command was removed from the `hook` command list.
+    CommandRemoved { hook: String, command: String }
+}
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::LinkAdded { destination, source } => write!(f, "+ {destination:?} => {source}"),
+            Change::LinkRemoved { destination, source } => write!(f, "- {destination:?} => {source}"),
+            Change::LinkChanged { destination, old_source, new_source } => write!(f, "~ {destination:?} => {old_source} -> {new_source}"),
+            Change::CommandAdded { hook, command } => write!(f, "+ {hook}: {command}"),
+            Change::CommandRemoved { hook, command } => write!(f, "- {hook}: {command}")
+        }
+    }
+}
+
+/// Returns the changes between the `previous` and `current` profiles' links and hooks.
+fn diff(previous: Option<&DotfileProfile>, current: Option<&DotfileProfile>) -> Vec<Change> {
+    let old_links: BTreeMap<PathBuf, String> = previous.map(|p| p.links()).unwrap_or_default();
+    let new_links: BTreeMap<PathBuf, String> = current.map(|p| p.links()).unwrap_or_default();
+    let mut changes: Vec<Change> = Vec::new();
+
+    for (destination, old_source) in &old_links {
+        match new_links.get(destination) {
+            None => changes.push(Change::LinkRemoved { destination: destination.clone(), source: old_source.clone() }),
+            Some(new_source) if new_source != old_source => changes.push(Change::LinkChanged {
+                destination: destination.clone(),
+                old_source: old_source.clone(),
+                new_source: new_source.clone()
+            }),
+            Some(_) => {}
+        }
+    }
+    for (destination, source) in &new_links {
+        if !old_links.contains_key(destination) {
+            changes.push(Change::LinkAdded { destination: destination.clone(), source: source.clone() });
+        }
+    }
+
+    let old_hooks: BTreeMap<String, Vec<&str>> = previous.map(|p| p.hooks()).unwrap_or_default();
+    let new_hooks: BTreeMap<String, Vec<&str>> = current.map(|p| p.hooks()).unwrap_or_default();
+    let hooks: BTreeSet<&str> = old_hooks.keys().chain(new_hooks.keys()).map(String::as_str).collect();
+    for hook in hooks {
+        let old_commands: &[&str] = old_hooks.get(hook).map(Vec::as_slice).unwrap_or_default();
+        let new_commands: &[&str] = new_hooks.get(hook).map(Vec::as_slice).unwrap_or_default();
+        for command in old_commands.iter().filter(|c| !new_commands.contains(c)) {
+            changes.push(Change::CommandRemoved { hook: hook.to_string(), command: command.to_string() });
+        }
+        for command in new_commands.iter().filter(|c| !old_commands.contains(c)) {
+            changes.push(Change::CommandAdded { hook: hook.to_string(), command: command.to_string() });
+        }
+    }
+    changes
+}
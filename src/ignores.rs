@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::error::DotulousError;
+
+/// The patterns ignored by default when scanning a profile's directory, skipping VCS metadata,
+/// documentation and editor junk.
+const DEFAULT_IGNORES: &[&str] = &[
+    ".hg/",
+    ".svn/",
+    ".gitignore",
+    ".gitmodules",
+    ".gitattributes",
+    "/README*",
+    "/LICENSE*",
+    "/LICENCE*",
+    "/COPYING*",
+    "/CHANGELOG*",
+    "*.swp",
+    "*.swo",
+    "*~",
+    ".#*",
+    "#*#",
+    ".DS_Store",
+    ".idea/",
+    ".vscode/"
+];
+
+/// The files in a profile's directory that are skipped when scanning it, such as by
+/// [`crate::profile::DotfileProfile::fill_files`].
+///
+/// Patterns use the gitignore syntax, and are read from the `.dotulousignore` file at the root of
+/// the profile's directory, on top of the [`DEFAULT_IGNORES`] unless disabled and any extra
+/// patterns given, such as the user's [`crate::settings::Settings::ignore`]. The manifest, the
+/// `.dotulousignore` itself, the `dotulous.lock` (see [`crate::pin`]) & any `.git` folder are
+/// always ignored.
+pub struct Ignores {
+    /// The compiled patterns.
+    matcher: Gitignore
+}
+impl Ignores {
+    /// Loads the patterns for the profile's directory at `repo_path`, including the
+    /// [`DEFAULT_IGNORES`] if `default_ignores` is set and every pattern in `extra`.
+    pub fn load(repo_path: &Path, default_ignores: bool, extra: &[String]) -> Result<Ignores, DotulousError> {
+        let mut builder: GitignoreBuilder = GitignoreBuilder::new(repo_path);
+        let mut patterns: Vec<&str> = vec![".git/", "/manifest.json", "/manifest.toml", "/manifest.yaml", "/manifest.yml", "/.dotulousignore", "/dotulous.lock"];
+        if default_ignores {
+            patterns.extend(DEFAULT_IGNORES);
+        }
+        patterns.extend(extra.iter().map(String::as_str));
+        for pattern in patterns {
+            builder.add_line(None, pattern).map_err(DotulousError::FailedReadIgnoreFile)?;
+        }
+
+        let ignore_path: PathBuf = repo_path.join(".dotulousignore");
+        if ignore_path.exists() {
+            if let Some(e) = builder.add(ignore_path) {
+                return Err(DotulousError::FailedReadIgnoreFile(e))
+            }
+        }
+        let matcher: Gitignore = builder.build().map_err(DotulousError::FailedReadIgnoreFile)?;
+        Ok(Ignores { matcher })
+    }
+
+    /// Returns if the file at `path`, relative to the profile's directory, is ignored. `is_dir`
+    /// should be set if it's a directory, for patterns that only match directories.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.matcher.matched(path, is_dir).is_ignore()
+    }
+}
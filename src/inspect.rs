@@ -0,0 +1,397 @@
+use std::{cmp::Ordering, collections::{BTreeMap, BTreeSet, HashMap}, fmt::Display, fs, path::{Path, PathBuf}};
+
+use serde::Serialize;
+
+use crate::{entry::Strategy, error::DotulousError, format::ManifestFormat, meta::Meta, paths::expand_path, platform, profile::DotfileProfile, secret::SecretStore, template::Renderer};
+
+/// A difference between what a loaded profile should have put onto the system, and what is
+/// actually there. Found with [`loaded_drift`] & [`unmapped_files`].
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Drift {
+    /// Nothing exists at the destination.
+    Missing { destination: PathBuf },
+    /// The destination is a symlink, but not to the profile's file.
+    WrongTarget { destination: PathBuf, expected: PathBuf, actual: PathBuf },
+    /// The destination should be a symlink, but is a regular file or directory.
+    NotSymlink { destination: PathBuf },
+    /// The destination should be a hardlink of the profile's file, but is a different file.
+    NotHardlink { destination: PathBuf },
+    /// A file inside the profile's directory that isn't in the manifest's `files`.
+    Unmapped { source: PathBuf },
+    /// The source of an `optional` entry doesn't exist, so nothing was put at the destination.
+    OptionalAbsent { destination: PathBuf }
+}
+impl Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Drift::Missing { destination } => write!(f, "missing        {destination:?}"),
+            Drift::WrongTarget { destination, expected, actual } => write!(f, "wrong target   {destination:?} -> {actual:?} (expected {expected:?})"),
+            Drift::NotSymlink { destination } => write!(f, "not a symlink  {destination:?}"),
+            Drift::NotHardlink { destination } => write!(f, "not a hardlink {destination:?}"),
+            Drift::Unmapped { source } => write!(f, "unmapped       {source:?}"),
+            Drift::OptionalAbsent { destination } => write!(f, "optional, absent {destination:?}")
+        }
+    }
+}
+
+/// How healthy a single destination of a loaded profile is. Found with [`link_health`].
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkHealth {
+    /// The destination is what loading the profile put there.
+    Ok,
+    /// The destination is a symlink, but what it points to no longer exists.
+    Broken,
+    /// Nothing exists at the destination.
+    Missing,
+    /// Something exists at the destination, but it wasn't put there by the profile.
+    Foreign,
+    /// The source of an `optional` entry doesn't exist, so it was skipped.
+    OptionalAbsent
+}
+impl Display for LinkHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkHealth::Ok => f.pad("OK"),
+            LinkHealth::Broken => f.pad("BROKEN"),
+            LinkHealth::Missing => f.pad("MISSING"),
+            LinkHealth::Foreign => f.pad("FOREIGN"),
+            LinkHealth::OptionalAbsent => f.pad("OPTIONAL, ABSENT")
+        }
+    }
+}
+
+/// The entry of a loaded profile that put a path onto the system. Found with [`find_owner`].
+pub struct Owner {
+    /// Where the entry comes from, as written in the manifest. This is the file inside the
+    /// profile's directory, or the URL of an asset.
+    pub source: String,
+    /// The *absolute* destination of the entry, being either the path itself or a folder it's
+    /// inside of.
+    pub destination: PathBuf,
+    /// How the entry is put onto the system, or [`None`] if it's an asset.
+    pub strategy: Option<Strategy>
+}
+
+/// A destination of one profile that another profile also puts something at (or inside of), so
+/// switching between them replaces each other's files. Found with [`DestinationIndex::shared_with`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SharedDestination {
+    /// The name of the other profile.
+    pub profile: String,
+    /// The *absolute* destination of the profile being checked.
+    pub destination: PathBuf,
+    /// The *absolute* destination of the other profile's entry, either the same as `destination`,
+    /// or a folder one of them is inside of.
+    pub other_destination: PathBuf
+}
+
+/// Every destination of the trusted profiles inside the dotulous folder, so a profile about to be
+/// loaded can be checked against the others. Built with [`DestinationIndex::build`].
+#[derive(Debug, Default)]
+pub struct DestinationIndex {
+    /// Each profile's name & *absolute* folder, along with it's *absolute* destinations.
+    profiles: Vec<(String, PathBuf, Vec<PathBuf>)>
+}
+impl DestinationIndex {
+    /// Scans every profile inside `dotulous_path`, the user's dotulous folder, that has been
+    /// trusted in `meta` (see [`Meta::is_path_trusted`]), including any files from profiles they
+    /// extend. Destinations are resolved against `home_path`. Profiles whose manifest fails to load
+    /// are skipped, as are profiles that have never been trusted, as they can't be loaded without
+    /// the user looking at them first.
+    ///
+    /// If the dotulous folder can't be read, [`Err`] with
+    /// [`DotulousError::FailedReadDotulousDirectory`] is returned.
+    pub fn build(dotulous_path: &Path, home_path: &Path, meta: &Meta) -> Result<DestinationIndex, DotulousError> {
+        let mut profiles: Vec<(String, PathBuf, Vec<PathBuf>)> = Vec::new();
+        for entry in fs::read_dir(dotulous_path).map_err(DotulousError::FailedReadDotulousDirectory)?.filter_map(Result::ok) {
+            let path: PathBuf = entry.path();
+            if ManifestFormat::find_manifest(&path).is_none() || !meta.is_path_trusted(&path) {
+                continue;
+            }
+            let Ok(mut profile) = DotfileProfile::from_manifest(&path) else { continue };
+            if profile.resolve_extends().is_err() {
+                continue;
+            }
+            let target_path: PathBuf = profile.target_path(home_path);
+            let destinations: Vec<PathBuf> = profile.destinations().iter().map(|destination| target_path.join(expand_path(destination, home_path))).collect();
+            profiles.push((profile.name, profile.repo_path, destinations));
+        }
+        profiles.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(DestinationIndex { profiles })
+    }
+
+    /// Returns every destination of `profile` that one of the other profiles in the index also
+    /// puts something at, or inside of, sorted by the other profile's name. `profile` itself is
+    /// never compared against, even if it's in the index.
+    pub fn shared_with(&self, profile: &DotfileProfile, home_path: &Path) -> Vec<SharedDestination> {
+        let target_path: PathBuf = profile.target_path(home_path);
+        let destinations: Vec<PathBuf> = profile.destinations().iter().map(|destination| target_path.join(expand_path(destination, home_path))).collect();
+        let mut shared: Vec<SharedDestination> = Vec::new();
+        for (name, _, others) in self.profiles.iter().filter(|(_, repo_path, _)| *repo_path != profile.repo_path) {
+            for destination in &destinations {
+                let overlapping = others.iter().filter(|other| destination.starts_with(other) || other.starts_with(destination));
+                shared.extend(overlapping.map(|other| SharedDestination {
+                    profile: name.clone(),
+                    destination: destination.clone(),
+                    other_destination: other.clone()
+                }));
+            }
+        }
+        shared
+    }
+}
+
+/// A difference between two versions of a profile's manifest, such as before & after updating it.
+/// Found with [`manifest_changes`].
+pub enum ManifestChange {
+    /// A new link to `destination` was added.
+    LinkAdded { destination: PathBuf, source: String },
+    /// The link to `destination` was removed.
+    LinkRemoved { destination: PathBuf, source: String },
+    /// The link to `destination` now comes from a different source.
+    LinkChanged { destination: PathBuf, old_source: String, new_source: String },
+    /// A command was added to the `hook` (e.g. `pre_commands`).
+    CommandAdded { hook: String, command: String },
+    /// A command was removed from the `hook`.
+    CommandRemoved { hook: String, command: String }
+}
+impl ManifestChange {
+    /// Returns if this change is to the profile's commands.
+    pub fn is_command(&self) -> bool {
+        matches!(self, ManifestChange::CommandAdded { .. } | ManifestChange::CommandRemoved { .. })
+    }
+}
+impl Display for ManifestChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestChange::LinkAdded { destination, source } => write!(f, "+ {destination:?} => {source}"),
+            ManifestChange::LinkRemoved { destination, source } => write!(f, "- {destination:?} => {source}"),
+            ManifestChange::LinkChanged { destination, old_source, new_source } => write!(f, "~ {destination:?} => {new_source} (was {old_source})"),
+            ManifestChange::CommandAdded { hook, command } => write!(f, "+ {hook}: {command}"),
+            ManifestChange::CommandRemoved { hook, command } => write!(f, "- {hook}: {command}")
+        }
+    }
+}
+
+/// Compares two profile versions such as `1.2` and `1.10`, part by part. Parts that are both
+/// numbers are compared as numbers, anything else as text, and a missing part comes first (so
+/// `1.2` is before `1.2.1`). A leading `v` is ignored.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<String> {
+        let version: &str = version.trim();
+        let version: &str = version.strip_prefix(['v', 'V']).unwrap_or(version);
+        version.split(['.', '-', '+']).map(str::to_string).collect()
+    };
+    let (a, b): (Vec<String>, Vec<String>) = (parts(a), parts(b));
+    for (a, b) in a.iter().zip(&b) {
+        let ordering: Ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b)
+        };
+        if ordering != Ordering::Equal {
+            return ordering
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Compares the links & commands of the `old` and `new` versions of a profile, returning every
+/// [`ManifestChange`] between them. Links are compared by destination (see
+/// [`DotfileProfile::links`]), and commands by their text within each hook.
+pub fn manifest_changes(old: &DotfileProfile, new: &DotfileProfile) -> Vec<ManifestChange> {
+    let old_links: BTreeMap<PathBuf, String> = old.links();
+    let new_links: BTreeMap<PathBuf, String> = new.links();
+    let mut changes: Vec<ManifestChange> = Vec::new();
+    for (destination, source) in &old_links {
+        match new_links.get(destination) {
+            None => changes.push(ManifestChange::LinkRemoved { destination: destination.clone(), source: source.clone() }),
+            Some(new_source) if new_source != source => changes.push(ManifestChange::LinkChanged {
+                destination: destination.clone(),
+                old_source: source.clone(),
+                new_source: new_source.clone()
+            }),
+            Some(_) => {}
+        }
+    }
+    for (destination, source) in &new_links {
+        if !old_links.contains_key(destination) {
+            changes.push(ManifestChange::LinkAdded { destination: destination.clone(), source: source.clone() });
+        }
+    }
+
+    let old_hooks: BTreeMap<String, Vec<&str>> = old.hooks();
+    let new_hooks: BTreeMap<String, Vec<&str>> = new.hooks();
+    let hooks: BTreeSet<&String> = old_hooks.keys().chain(new_hooks.keys()).collect();
+    for hook in hooks {
+        let old_commands: &[&str] = old_hooks.get(hook).map(Vec::as_slice).unwrap_or_default();
+        let new_commands: &[&str] = new_hooks.get(hook).map(Vec::as_slice).unwrap_or_default();
+        for command in old_commands.iter().filter(|c| !new_commands.contains(c)) {
+            changes.push(ManifestChange::CommandRemoved { hook: hook.clone(), command: command.to_string() });
+        }
+        for command in new_commands.iter().filter(|c| !old_commands.contains(c)) {
+            changes.push(ManifestChange::CommandAdded { hook: hook.clone(), command: command.to_string() });
+        }
+    }
+    changes
+}
+
+/// Checks every entry in the `files` of the loaded `profile` against the system, returning each
+/// destination that isn't how loading the profile would leave it. `profile` should be the copy
+/// stored in the meta (see [`crate::meta::Meta::current_profile`]), so destinations are checked
+/// where it was actually loaded to.
+///
+/// Entries using [`Strategy::Template`] are expected to link to their render inside the template
+/// cache in `dotulous_path` (and [`Strategy::Encrypted`] to their decryption in the secrets cache),
+/// and entries using [`Strategy::Copy`] are only checked to exist. `optional` entries whose source
+/// doesn't exist are reported as [`Drift::OptionalAbsent`] rather than missing.
+pub fn loaded_drift(profile: &DotfileProfile, dotulous_path: &Path, home_path: &Path) -> Vec<Drift> {
+    let target_path: PathBuf = profile.target_path(home_path);
+    // Only used for finding where templates are rendered to, so no variables are needed
+    let renderer: Renderer = Renderer::new(profile.template_cache_path(dotulous_path), &profile.repo_path, HashMap::new());
+    let secrets: SecretStore = profile.secret_store(dotulous_path);
+    let mut entries: Vec<_> = profile.entries().collect();
+    entries.sort_by(|a, b| a.1.destination.cmp(&b.1.destination));
+
+    let mut drift: Vec<Drift> = Vec::new();
+    for (source, entry) in entries {
+        let source: PathBuf = profile.repo_path.join(expand_path(source, home_path));
+        let destination: PathBuf = target_path.join(expand_path(&entry.destination, home_path));
+        if entry.optional && source.symlink_metadata().is_err() {
+            drift.push(Drift::OptionalAbsent { destination });
+            continue;
+        }
+        if destination.symlink_metadata().is_err() {
+            drift.push(Drift::Missing { destination });
+            continue;
+        }
+
+        match entry.strategy {
+            Strategy::Symlink | Strategy::Template | Strategy::Encrypted => {
+                let expected: PathBuf = match entry.strategy {
+                    Strategy::Template => renderer.rendered_path(&source),
+                    Strategy::Encrypted => secrets.decrypted_path(&source),
+                    _ => source
+                };
+                match fs::read_link(&destination) {
+                    Ok(actual) if actual != expected => drift.push(Drift::WrongTarget { destination, expected, actual }),
+                    Ok(_) => {},
+                    Err(_) => drift.push(Drift::NotSymlink { destination })
+                }
+            },
+            Strategy::Hardlink => {
+                if !platform::same_file(&source, &destination) {
+                    drift.push(Drift::NotHardlink { destination });
+                }
+            },
+            Strategy::Copy => {}
+        }
+    }
+    drift
+}
+
+/// Checks the destination of every entry in the `files` of the loaded `profile`, returning each
+/// *absolute* destination with it's [`LinkHealth`], sorted by destination. Like [`loaded_drift`],
+/// `profile` should be the copy stored in the meta.
+///
+/// Any symlink pointing to something that no longer exists is [`LinkHealth::Broken`], even if it
+/// points to the right place. Otherwise it's checked with [`Strategy::is_applied`], so entries
+/// using [`Strategy::Copy`] whose contents have changed are [`LinkHealth::Foreign`]. `optional`
+/// entries whose source doesn't exist are [`LinkHealth::OptionalAbsent`].
+pub fn link_health(profile: &DotfileProfile, dotulous_path: &Path, home_path: &Path) -> Vec<(PathBuf, LinkHealth)> {
+    let target_path: PathBuf = profile.target_path(home_path);
+    // Only used for finding where templates are rendered to, so no variables are needed
+    let renderer: Renderer = Renderer::new(profile.template_cache_path(dotulous_path), &profile.repo_path, HashMap::new());
+    let secrets: SecretStore = profile.secret_store(dotulous_path);
+    let mut entries: Vec<_> = profile.entries().collect();
+    entries.sort_by(|a, b| a.1.destination.cmp(&b.1.destination));
+
+    entries.into_iter().map(|(source, entry)| {
+        let source: PathBuf = profile.repo_path.join(expand_path(source, home_path));
+        let destination: PathBuf = target_path.join(expand_path(&entry.destination, home_path));
+        let health: LinkHealth = if entry.optional && source.symlink_metadata().is_err() {
+            LinkHealth::OptionalAbsent
+        } else if destination.symlink_metadata().is_err() {
+            LinkHealth::Missing
+        } else if destination.is_symlink() && !destination.exists() {
+            LinkHealth::Broken
+        } else if entry.strategy.is_applied(&source, &destination, &renderer, &secrets) {
+            LinkHealth::Ok
+        } else {
+            LinkHealth::Foreign
+        };
+        (destination, health)
+    }).collect()
+}
+
+/// Returns the entry of the loaded `profile` that put the *absolute* `path` onto the system, either
+/// as it's destination or by `path` being inside the destination's folder, or [`None`] if the
+/// profile doesn't manage it. If more than one entry covers `path`, the one closest to it is
+/// returned. Like [`loaded_drift`], `profile` should be the copy stored in the meta.
+///
+/// Paths are compared as they're written, so `path` should already be normalized (see
+/// [`platform::normalize_path`]).
+pub fn find_owner(profile: &DotfileProfile, home_path: &Path, path: &Path) -> Option<Owner> {
+    let target_path: PathBuf = profile.target_path(home_path);
+    let files = profile.entries()
+        .map(|(source, entry)| Owner {
+            source: source.to_string_lossy().to_string(),
+            destination: target_path.join(expand_path(&entry.destination, home_path)),
+            strategy: Some(entry.strategy)
+        });
+    let assets = profile.assets().iter()
+        .map(|asset| Owner { source: asset.url.clone(), destination: target_path.join(expand_path(&asset.dest, home_path)), strategy: None });
+    files.chain(assets)
+        .filter(|owner| path.starts_with(&owner.destination))
+        .max_by_key(|owner| owner.destination.components().count())
+}
+
+/// Returns every file inside the profile's directory that isn't covered by an entry in
+/// `profile`'s `files`, either directly or by being inside a mapped directory.
+///
+/// `profile` should be freshly read from the manifest, so entries skipped by their `when`
+/// conditions still count as mapped.
+pub fn unmapped_files(profile: &DotfileProfile) -> Result<Vec<Drift>, DotulousError> {
+    let unmapped: Vec<Drift> = profile.repo_files()?.into_iter()
+        .filter(|file| !profile.files().keys().any(|source| file.starts_with(source)))
+        .map(|source| Drift::Unmapped { source })
+        .collect();
+    Ok(unmapped)
+}
+
+/// Returns every dangling symlink found inside the `roots` folders whose target is inside one of
+/// the `owners` folders (such as the dotulous folder), sorted by path. These are left behind when a
+/// profile's folder is deleted without unloading it first.
+///
+/// Folders are searched up to `depth` levels deep, where a `depth` of `0` only checks the `roots`
+/// themselves. Symlinked folders and the `owners` themselves are never searched, and folders
+/// that can't be read are skipped.
+pub fn dangling_links(roots: &[PathBuf], owners: &[PathBuf], depth: usize) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        find_dangling_links(root, owners, depth, &mut found);
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// Does the work of [`dangling_links`] for a single `directory`, adding to `found`.
+fn find_dangling_links(directory: &Path, owners: &[PathBuf], depth: usize, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(directory) else { return };
+    for entry in entries.filter_map(Result::ok) {
+        let path: PathBuf = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_symlink() {
+            let Ok(target) = fs::read_link(&path) else { continue };
+            // Relative targets are relative to the folder the symlink is in
+            let target: PathBuf = platform::normalize_path(&directory.join(target));
+            if owners.iter().any(|owner| target.starts_with(owner)) && !path.exists() {
+                found.push(path);
+            }
+        } else if file_type.is_dir() && depth > 0 && !owners.contains(&path) {
+            find_dangling_links(&path, owners, depth - 1, found);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::{process, sync::atomic::{AtomicBool, AtomicUsize, Ordering}};
+
+use crate::error::{DotulousError, ErrorCategory};
+
+/// Whether dotulous was asked to stop (such as with Ctrl-C) while a [`Guard`] was held.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// How many [`Guard`]s are held.
+static GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+/// Handles SIGINT, SIGTERM & SIGHUP (or Ctrl-C on Windows) for the rest of the process, so that
+/// changing the system is never stopped part way through an operation.
+///
+/// While no [`Guard`] is held, such as when asking the user a question, dotulous exits straight
+/// away with the code for [`ErrorCategory::Interrupted`]. Otherwise it's only remembered, and the
+/// operation stops before it's next step when it calls [`check`], rolling back what it did. Being
+/// interrupted a second time exits straight away regardless.
+pub fn install() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(interrupt)
+}
+
+/// Interrupts dotulous as if it was sent SIGINT, see [`install`]. Used when a command that had the
+/// terminal was interrupted instead, as dotulous itself never saw it (see
+/// [`crate::platform::new_process_group`]).
+pub fn interrupt() {
+    if GUARDS.load(Ordering::SeqCst) == 0 || INTERRUPTED.swap(true, Ordering::SeqCst) {
+        eprintln!();
+        eprintln!("Interrupted.");
+        process::exit(ErrorCategory::Interrupted.exit_code());
+    }
+    eprintln!();
+    eprintln!("Interrupted, stopping after the current step. Interrupt again to quit straight away.");
+}
+
+/// Returns [`Err`] with [`DotulousError::Interrupted`] if dotulous was interrupted while a
+/// [`Guard`] was held, see [`install`]. Called before each step of an operation that changes the
+/// system.
+pub fn check() -> Result<(), DotulousError> {
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        return Err(DotulousError::Interrupted)
+    }
+    Ok(())
+}
+
+/// Holds off being interrupted until it's dropped, so an operation can stop at a point where it can
+/// roll back or save what it did. See [`install`]. Once the last guard is dropped, being interrupted
+/// is forgotten, as the operation has already stopped.
+pub struct Guard {
+    /// Stops the guard being made outside of [`guard`].
+    _private: ()
+}
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if GUARDS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            INTERRUPTED.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Holds off being interrupted until the returned [`Guard`] is dropped.
+pub fn guard() -> Guard {
+    GUARDS.fetch_add(1, Ordering::SeqCst);
+    Guard { _private: () }
+}
@@ -0,0 +1,144 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::{backup::Backup, entry::Strategy, error::DotulousError, say, secret::SecretStore, state::{LoadState, PlacedFile}, template::Renderer};
+
+/// A record of every change made to the system while loading a profile, so that they can all be
+/// undone with [`Journal::rollback`] if loading fails partway through.
+///
+/// Any files that are in the way are moved into a [`Backup`] owned by the journal, which is handed
+/// back by [`Journal::finish`] once loading has succeeded (along with the [`LoadState`] of what
+/// was done), or restored when rolling back.
+///
+/// **Note:** Commands can't be undone, so only changes to the filesystem are recorded.
+#[derive(Default)]
+pub struct Journal {
+    /// Every operation performed, in order.
+    operations: Vec<Operation>,
+    /// The backup of any files that were in the way, if there were any.
+    backup: Option<Backup>
+}
+
+/// A single change to the filesystem recorded in a [`Journal`].
+enum Operation {
+    /// An empty directory was created.
+    CreatedDirectory(PathBuf),
+    /// A file was put at the destination using the strategy.
+    Placed(PathBuf, Strategy),
+    /// A secret may have been decrypted into the secrets cache at the path.
+    Decrypted(PathBuf),
+    /// The destination was left alone, as it was already in place.
+    Skipped(PathBuf)
+}
+
+impl Journal {
+    /// Creates a new, empty journal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the directory at `path` along with any missing parents, recording each directory
+    /// that was actually created.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn create_dir_all(&mut self, path: &Path) -> Result<(), DotulousError> {
+        let missing: Vec<&Path> = path.ancestors()
+            .take_while(|p| !p.as_os_str().is_empty() && p.symlink_metadata().is_err())
+            .collect();
+        for directory in missing.into_iter().rev() {
+            fs::create_dir(directory).map_err(DotulousError::FailedCreateDirectory)?;
+            self.operations.push(Operation::CreatedDirectory(directory.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    /// Moves the file at `destination` into the journal's backup, creating the backup for
+    /// `profile_name` inside `dotulous_path` if this is the first file backed up.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn back_up(&mut self, dotulous_path: &Path, profile_name: &str, destination: &Path) -> Result<(), DotulousError> {
+        self.backup.get_or_insert_with(|| Backup::new(dotulous_path, profile_name)).store(destination)
+    }
+
+    /// Puts the file at `source` onto the system at `destination` using `strategy` (see
+    /// [`Strategy::apply`]), recording it.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn place(&mut self, strategy: Strategy, source: &Path, destination: &Path, renderer: &Renderer, secrets: &SecretStore) -> io::Result<()> {
+        let result: io::Result<()> = strategy.apply(source, destination, None, renderer, secrets);
+        self.record(strategy, source, destination, secrets, result)
+    }
+
+    /// Records the `result` of putting the file at `source` onto the system at `destination` using
+    /// `strategy`, for files placed outside of the journal such as in parallel. Failed files
+    /// aren't recorded as placed, but any secret they decrypted still is so it gets removed.
+    ///
+    /// Returns `result`, so it can be passed on.
+    pub fn record(&mut self, strategy: Strategy, source: &Path, destination: &Path, secrets: &SecretStore, result: io::Result<()>) -> io::Result<()> {
+        if strategy == Strategy::Encrypted {
+            self.operations.push(Operation::Decrypted(secrets.decrypted_path(source)));
+        }
+        result?;
+        self.operations.push(Operation::Placed(destination.to_path_buf(), strategy));
+        Ok(())
+    }
+
+    /// Records that `destination` was skipped as it was already in place. Nothing is undone for
+    /// it, but it's kept in the [`LoadState`] so unloading knows it wasn't created.
+    pub fn skip(&mut self, destination: &Path) {
+        self.operations.push(Operation::Skipped(destination.to_path_buf()));
+    }
+
+    /// Undoes every recorded operation in reverse order, then restores the backup of any files
+    /// that were in the way.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    /// Upon any errors, the function will simply print to stdout and continue.
+    pub fn rollback(self) {
+        for operation in self.operations.into_iter().rev() {
+            match operation {
+                Operation::Placed(destination, strategy) => {
+                    say!("  Removing {destination:?}");
+                    if let Err(e) = strategy.remove(&destination) {
+                        say!("  ERROR: Failed to remove {destination:?}: {e:#}");
+                    }
+                },
+                Operation::Skipped(_) => {},
+                Operation::Decrypted(path) => {
+                    if path.exists() && fs::remove_file(&path).is_err() {
+                        say!("  ERROR: Failed to remove decrypted secret {path:?}");
+                    }
+                },
+                Operation::CreatedDirectory(directory) => {
+                    say!("  Removing directory {directory:?}");
+                    if let Err(e) = fs::remove_dir(&directory) {
+                        say!("  ERROR: Failed to remove directory {directory:?}: {e:#}");
+                    }
+                }
+            }
+        }
+
+        if let Some(mut backup) = self.backup {
+            if let Err(e) = backup.restore() {
+                say!("  ERROR: Failed to restore backup {}: {e:#}", backup.id());
+            }
+        }
+    }
+
+    /// Finishes the journal once loading has succeeded, returning the [`Backup`] of any files that
+    /// were in the way (if there were any), and the [`LoadState`] of everything that was done.
+    pub fn finish(self) -> (Option<Backup>, LoadState) {
+        let mut state: LoadState = LoadState::default();
+        for operation in self.operations {
+            match operation {
+                Operation::Placed(destination, strategy) => state.placed.push(PlacedFile { destination, strategy }),
+                Operation::CreatedDirectory(directory) => state.created_directories.push(directory),
+                Operation::Skipped(destination) => state.skipped.push(destination),
+                Operation::Decrypted(_) => {}
+            }
+        }
+        (self.backup, state)
+    }
+}
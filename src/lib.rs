@@ -0,0 +1,51 @@
+//! Dotulous is an easy to use dotfile manager, working off the concept of "profiles" that you can
+//! load and hotswap.
+//!
+//! The high-level operations the CLI is built on, such as loading and unloading profiles, are in
+//! [`operations`]. These return a [`Result`] with a [`error::DotulousError`] rather than exiting,
+//! so they can be used to build other frontends. For working with profiles and the meta directly,
+//! see [`profile::DotfileProfile`] & [`meta::Meta`].
+//!
+//! Every path taken is *absolute*, with `dotulous_path` being the user's dotulous data folder (see
+//! [`paths::Paths`]) and `home_path` being their home folder.
+
+pub mod archive;
+pub mod asset;
+pub mod audit;
+pub mod backup;
+pub mod condition;
+pub mod entry;
+pub mod error;
+pub mod filter;
+pub mod format;
+pub mod generation;
+pub mod ignores;
+pub mod inspect;
+pub mod interrupt;
+pub mod journal;
+pub mod lock;
+pub mod logs;
+pub mod meta;
+pub mod migrate;
+pub mod operations;
+pub mod overlay;
+pub mod packages;
+pub mod paths;
+pub mod pin;
+pub mod platform;
+pub mod preflight;
+pub mod profile;
+pub mod progress;
+pub mod remote;
+pub mod report;
+pub mod secret;
+pub mod service;
+pub mod settings;
+pub mod source;
+pub mod state;
+pub mod stow;
+pub mod system;
+pub mod template;
+pub mod tui;
+pub mod update;
+pub mod validate;
@@ -0,0 +1,31 @@
+use std::{fs::{File, OpenOptions, TryLockError}, path::{Path, PathBuf}};
+
+use crate::error::DotulousError;
+
+/// An advisory lock on the user's dotulous folder, held while anything changes the meta or the
+/// system so two dotulous instances (such as a watcher and a manual `dotulous load`) can't race
+/// each other. The lock is taken on `dotulous.lock` inside the dotulous folder, and released once
+/// this is dropped, or if dotulous exits in any way.
+///
+/// Only other dotulous instances respect the lock, nothing stops the files from being changed by
+/// hand.
+#[derive(Debug)]
+pub struct Lock {
+    /// The open lock file, which holds the lock until it's closed.
+    _file: File
+}
+impl Lock {
+    /// Locks the dotulous folder at `dotulous_path`, creating the lock file if needed.
+    ///
+    /// If another instance already holds the lock, [`Err`] with [`DotulousError::AlreadyRunning`]
+    /// is returned straight away rather than waiting for it.
+    pub fn acquire(dotulous_path: &Path) -> Result<Lock, DotulousError> {
+        let path: PathBuf = dotulous_path.join("dotulous.lock");
+        let file: File = OpenOptions::new().create(true).truncate(false).write(true).open(path).map_err(DotulousError::FailedLockDotulousDirectory)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Lock { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(DotulousError::AlreadyRunning),
+            Err(TryLockError::Error(e)) => Err(DotulousError::FailedLockDotulousDirectory(e))
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::{fmt::Display, fs::{self, File, OpenOptions}, io::{self, ErrorKind, Write}, path::{Path, PathBuf}, sync::Mutex};
+
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+
+use crate::error::DotulousError;
+
+/// How many logs are kept for each profile. Older ones are removed when a new one is made.
+pub const MAX_LOGS: usize = 100;
+
+/// The format of the timestamp each log's name starts with, in UTC.
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// The full output of a single command ran by a profile, stored in the user's dotulous folder as
+/// `logs/<profile>/<timestamp>-<n>.log`, so it can be looked at after the fact with
+/// `dotulous logs`. See [`list`].
+///
+/// The log starts with the command (`$ command`), followed by everything it printed to stdout &
+/// stderr in the order it arrived, and ends with how it exited (`[exit 1]`) once [`CommandLog::finish`]
+/// is called.
+pub struct CommandLog {
+    /// The *absolute* path to the log.
+    path: PathBuf,
+    /// The open log, shared by the threads reading the command's stdout & stderr.
+    file: Mutex<File>
+}
+impl CommandLog {
+    /// Creates a new log inside `dotulous_path` for `command`, ran by the profile called
+    /// `profile_name`, removing the oldest logs of the profile beyond [`MAX_LOGS`].
+    pub fn create(dotulous_path: &Path, profile_name: &str, command: &str) -> io::Result<CommandLog> {
+        let folder: PathBuf = logs_path(dotulous_path).join(sanitize_filename::sanitize(profile_name));
+        fs::create_dir_all(&folder)?;
+        let timestamp: String = Utc::now().format(TIMESTAMP_FORMAT).to_string();
+        // Several commands can start within the same second, so the first free number is used
+        let (path, mut file): (PathBuf, File) = (1..).find_map(|n| {
+            let path: PathBuf = folder.join(format!("{timestamp}-{n}.log"));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => Some(Ok((path, file))),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => None,
+                Err(e) => Some(Err(e))
+            }
+        }).unwrap_or_else(|| Err(io::Error::from(ErrorKind::AlreadyExists)))?;
+        writeln!(file, "$ {command}")?;
+        prune(&folder)?;
+        Ok(CommandLog { path, file: Mutex::new(file) })
+    }
+
+    /// Returns the *absolute* path to the log.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a `line` of the command's output. Failing to write is ignored, as the output is
+    /// still shown as it runs.
+    pub fn write_line(&self, line: &str) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{line}");
+        }
+    }
+
+    /// Ends the log with the code the command exited with, or [`None`] if it didn't start, timed
+    /// out or was killed by a signal.
+    pub fn finish(&self, exit_code: Option<i32>) {
+        let exit_code: String = exit_code.map_or("none".to_string(), |code| code.to_string());
+        self.write_line(&format!("[exit {exit_code}]"));
+    }
+}
+
+/// A log made by [`CommandLog`], found with [`list`].
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    /// The *absolute* path to the log.
+    pub path: PathBuf,
+    /// The name of the profile's folder inside `logs`, being the sanitized profile name.
+    pub profile_name: String,
+    /// When the command started.
+    pub timestamp: DateTime<Utc>,
+    /// The command that was ran, with any `{{variables}}` in the manifest filled in.
+    pub command: String,
+    /// The code the command exited with, or [`None`] if it didn't start, timed out, was killed by
+    /// a signal or is still running.
+    pub exit_code: Option<i32>
+}
+impl LogFile {
+    /// Reads the log at `path` inside the folder for `profile_name`, returning [`None`] if it
+    /// wasn't made by [`CommandLog`].
+    fn read(path: PathBuf, profile_name: &str) -> Option<LogFile> {
+        let stem: String = path.file_stem()?.to_string_lossy().to_string();
+        let (timestamp, _) = stem.rsplit_once('-')?;
+        let timestamp: DateTime<Utc> = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?.and_utc();
+        let contents: String = fs::read_to_string(&path).ok()?;
+        let command: String = contents.lines().next()?.strip_prefix("$ ")?.to_string();
+        let exit_code: Option<i32> = contents.lines().last()
+            .and_then(|line| line.strip_prefix("[exit ")?.strip_suffix(']')?.parse().ok());
+        Some(LogFile { path, profile_name: profile_name.to_string(), timestamp, command, exit_code })
+    }
+
+    /// Returns up to the last `count` lines of the command's output, leaving out the command and
+    /// how it exited.
+    pub fn tail(&self, count: usize) -> Result<Vec<String>, DotulousError> {
+        let contents: String = fs::read_to_string(&self.path).map_err(DotulousError::FailedReadLogs)?;
+        let mut lines: Vec<&str> = contents.lines().skip(1).collect();
+        if lines.last().is_some_and(|line| line.starts_with("[exit ")) {
+            lines.pop();
+        }
+        Ok(lines[lines.len().saturating_sub(count)..].iter().map(ToString::to_string).collect())
+    }
+}
+impl Display for LogFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timestamp: DateTime<Local> = self.timestamp.into();
+        let exit_code: String = self.exit_code.map_or("none".to_string(), |code| code.to_string());
+        write!(f, "{} - {} - exit {exit_code} - {}", timestamp.format("%Y-%m-%d %H:%M:%S"), self.profile_name, self.command)
+    }
+}
+
+/// Returns every log inside `dotulous_path`, newest first. Only logs of the profile called
+/// `profile_name` are returned when given. If no command has been logged yet, nothing is returned.
+pub fn list(dotulous_path: &Path, profile_name: Option<&str>) -> Result<Vec<LogFile>, DotulousError> {
+    let logs_path: PathBuf = logs_path(dotulous_path);
+    let folders: Vec<PathBuf> = match profile_name {
+        Some(name) => vec![logs_path.join(sanitize_filename::sanitize(name))],
+        None => match fs::read_dir(&logs_path) {
+            Ok(entries) => entries.filter_map(Result::ok).map(|entry| entry.path()).filter(|path| path.is_dir()).collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(DotulousError::FailedReadLogs(e))
+        }
+    };
+
+    let mut logs: Vec<LogFile> = Vec::new();
+    for folder in folders {
+        let Some(name) = folder.file_name().map(|name| name.to_string_lossy().to_string()) else { continue };
+        let entries = match fs::read_dir(&folder) {
+            Ok(r) => r,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(DotulousError::FailedReadLogs(e))
+        };
+        logs.extend(entries.filter_map(Result::ok).filter_map(|entry| LogFile::read(entry.path(), &name)));
+    }
+    logs.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| b.path.cmp(&a.path)));
+    Ok(logs)
+}
+
+/// Removes the oldest logs inside `folder` beyond [`MAX_LOGS`]. Their names start with when they
+/// were made, so they're sorted by name.
+fn prune(folder: &Path) -> io::Result<()> {
+    let mut logs: Vec<PathBuf> = fs::read_dir(folder)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "log"))
+        .collect();
+    if logs.len() <= MAX_LOGS {
+        return Ok(())
+    }
+    logs.sort();
+    for path in &logs[..logs.len() - MAX_LOGS] {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Returns the path of the `logs` folder inside `dotulous_path`.
+fn logs_path(dotulous_path: &Path) -> PathBuf {
+    dotulous_path.join("logs")
+}
@@ -1,117 +1,862 @@
-use std::{env, fs, io, path::{Path, PathBuf}, process::exit};
+use std::{cmp::Ordering, collections::BTreeMap, env, fs, io::{self, Write}, path::{Path, PathBuf}, process::{exit, Command, Stdio}};
 
+use chrono::{DateTime, Local};
 use clap::{Parser, Subcommand};
-use profile::DotfileProfile;
-use meta::Meta;
+use dialoguer::{FuzzySelect, MultiSelect};
+use dotulous::{
+    archive,
+    audit::{self, AuditEntry},
+    backup::{Backup, Retention},
+    condition::Host,
+    entry::{FileEntry, Strategy},
+    error::{DotulousError, ErrorCategory},
+    filter::{EntryFilter, TagFilter},
+    format::ManifestFormat,
+    generation::GenerationLog,
+    inspect::{self, DestinationIndex, Drift, LinkHealth, ManifestChange, SharedDestination},
+    interrupt,
+    journal::Journal,
+    lock::Lock,
+    logs::{self, LogFile},
+    meta::{Meta, TrustOnce, TrustScope, TrustStatus, TrustedProfile},
+    migrate::MANIFEST_VERSION,
+    operations::{self, ProfileSummary, ReloadPlan, Status},
+    overlay::Overlay,
+    packages::Distro,
+    paths::{self, Paths},
+    pin::Pins,
+    platform,
+    profile::{ApplyMode, DotfileProfile, Hook, OnConflict, Skip},
+    remote::Remote,
+    report,
+    say,
+    secret::SecretConfig,
+    service::{self, ServiceStatus},
+    settings::Settings,
+    source,
+    state::LoadState,
+    stow,
+    system,
+    tui,
+    update::{self, Release},
+    validate::{self, Problem}
+};
+use serde_json::json;
 
-mod profile;
-mod meta;
-mod error;
-
-/// Prints the given formatted string to stderror, prefixed with `"ERROR: "`, and exits with code -1.
-/// Output is done using the [`eprintln`] macro.
+/// Prints the given formatted string to stderror, prefixed with `"ERROR: "`, and exits with
+/// [`EXIT_FAILURE`]. See [`exit_with_error`].
+///
+/// If an error is given first (e.g. `error_and_exit!(e, "Failed: {e:#}")`), this instead exits
+/// with the code for it's [`ErrorCategory`], see [`ErrorCategory::exit_code`]. A category can also
+/// be given directly, e.g. `error_and_exit!(ErrorCategory::NotFound, "No profiles were found.")`.
 macro_rules! error_and_exit {
+    (ErrorCategory::$category: ident, $format: literal) => {
+        exit_with_error(format!($format), ErrorCategory::$category.exit_code());
+    };
+    ($error: ident, $format: literal) => {
+        exit_with_error(format!($format), ErrorCategory::from(&$error).exit_code());
+    };
     ($format: expr) => {
-        eprint!("ERROR: ");
-        eprintln!($format);
-        exit(-1);
+        exit_with_error(format!($format), EXIT_FAILURE);
     };
     ($format: expr, $($arg:tt)*) => {
-        eprint!("ERROR: ");
-        eprintln!($format, format_args!($($arg)*));
-        exit(-1);
+        exit_with_error(format!($format, format_args!($($arg)*)), EXIT_FAILURE);
     };
 }
 
+/// Prints `message` to stderror, prefixed with `"ERROR: "`, and exits with `code`. With `--json`,
+/// the error is also reported on stdout as `{"error": message, "exit_code": code}`.
+fn exit_with_error(message: String, code: i32) -> ! {
+    eprintln!("ERROR: {message}");
+    report::emit(&json!({"error": message, "exit_code": code}));
+    exit(code)
+}
+
+/// The exit code used when something fails that isn't a [`DotulousError`], or when the user
+/// declines to carry on. See [`ErrorCategory::exit_code`] for the rest.
+const EXIT_FAILURE: i32 = 1;
+
+/// The exit code used when dotulous needs to ask the user something, but isn't interactive (see
+/// [`platform::is_interactive`]).
+const EXIT_NEEDS_INPUT: i32 = 3;
+
 /// The command-line arguments that can be accepted. These are parsed with [`clap`].
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct CmdlineArgs {
     /// The [`Action`] to run.
     #[command(subcommand)]
-    action: Action
+    action: Action,
+    /// Print more detail, such as every file as it's linked instead of a progress bar.
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    /// Don't ask for confirmation before files are removed or replaced, for use in scripts.
+    #[arg(short, long, global = true)]
+    yes: bool,
+    /// Never ask anything, failing instead of waiting for an answer, for use in scripts & CI. Can
+    /// also be set with `$DOTULOUS_NONINTERACTIVE`.
+    #[arg(long, global = true)]
+    non_interactive: bool,
+    /// Print results as JSON on stdout instead of text, for use in scripts & status bars. Anything
+    /// else dotulous prints goes to stderr.
+    #[arg(long, global = true)]
+    json: bool,
+    /// Trust the profile if it isn't already, instead of asking.
+    #[arg(long, global = true)]
+    trust: bool,
+    /// What to do when something is already where a profile's file goes while loading: prompt,
+    /// skip, backup, overwrite or abort. By default you're asked about each one, unless using
+    /// --yes or dotulous isn't interactive, in which case they're backed up.
+    #[arg(long, global = true, value_name = "POLICY")]
+    on_conflict: Option<OnConflict>,
+    /// Use this folder as your home folder, instead of `$HOME`.
+    #[arg(long, global = true, value_name = "PATH")]
+    home: Option<PathBuf>,
+    /// Keep your profiles and everything else dotulous stores inside this folder, the same as
+    /// setting `$DOTULOUS_HOME`.
+    #[arg(long, global = true, value_name = "PATH")]
+    dotulous_dir: Option<PathBuf>
 }
 /// An action for Dotulous to run.
 #[derive(Subcommand, Debug)]
 enum Action {
     /// Select & Load a new active dotfile configuration. 
     Load {
-        /// The dotfile profile name to use.
-        profile_name: String
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>,
+        /// Load the profile temporarily. The previously loaded profile is restored when running
+        /// `dotulous ephemeral clear`.
+        #[arg(long)]
+        ephemeral: bool,
+        /// Only load the files & assets whose destination or source matches this glob, without
+        /// running any of the profile's commands. Can be given more than once.
+        #[arg(long, value_name = "GLOB", conflicts_with = "ephemeral")]
+        only: Vec<String>,
+        /// Don't load the files & assets whose destination or source matches this glob, without
+        /// running any of the profile's commands. Can be given more than once.
+        #[arg(long, value_name = "GLOB", conflicts_with = "ephemeral")]
+        except: Vec<String>,
+        /// Only load the files & commands with one of these tags. Remembered when reloading.
+        #[arg(long, value_name = "TAG", value_delimiter = ',', conflicts_with_all = ["only", "except"])]
+        tags: Vec<String>,
+        /// Don't load the files & commands with any of these tags, such as `--skip-tags gui` on a
+        /// headless server. Remembered when reloading.
+        #[arg(long, value_name = "TAG", value_delimiter = ',', conflicts_with_all = ["only", "except"])]
+        skip_tags: Vec<String>,
+        /// Don't run any of the profile's commands. The profile then only has to be trusted to link
+        /// it's files, see `trust --files-only`.
+        #[arg(long)]
+        no_hooks: bool,
+        /// Don't touch any files, only updating what dotulous has recorded as loaded, such as after
+        /// cleaning up by hand.
+        #[arg(long, conflicts_with_all = ["only", "except"])]
+        keep_files: bool,
+        /// Only print what would be done, without changing anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Refuse to load if the profile, or any of it's `pinned_repos`, has moved from the commit
+        /// recorded in it's `dotulous.lock` when it was trusted.
+        #[arg(long)]
+        frozen: bool
     },
 
     /// Unloads the current active profile
-    Unload {},
+    Unload {
+        /// Don't run any of the profile's commands.
+        #[arg(long)]
+        no_hooks: bool,
+        /// Don't touch any files, only updating what dotulous has recorded as loaded, such as after
+        /// cleaning up by hand.
+        #[arg(long, conflicts_with_all = ["only", "except"])]
+        keep_files: bool,
+        /// Only unload the files & assets whose destination or source matches this glob, without
+        /// running any of the profile's commands. Can be given more than once.
+        #[arg(long, value_name = "GLOB")]
+        only: Vec<String>,
+        /// Don't unload the files & assets whose destination or source matches this glob, without
+        /// running any of the profile's commands. Can be given more than once.
+        #[arg(long, value_name = "GLOB")]
+        except: Vec<String>,
+        /// Only print what would be done, without changing anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove destinations even if they weren't created when loading, or no longer match
+        /// the profile. Only symlinks into the dotulous folder are ever removed where a symlink
+        /// should be.
+        #[arg(long)]
+        force: bool
+    },
 
     /// Unloads & Reloads the current active profile, use this if you've updated your profile and
     /// want to reload it to your system quickly.
-    Reload {},
+    Reload {
+        /// Don't run any of the profile's commands. The profile then only has to be trusted to link
+        /// it's files, see `trust --files-only`.
+        #[arg(long)]
+        no_hooks: bool,
+        /// Don't touch any files, only updating what dotulous has recorded as loaded, such as after
+        /// cleaning up by hand.
+        #[arg(long, conflicts_with_all = ["only", "except"])]
+        keep_files: bool,
+        /// Only reload the files & assets whose destination or source matches this glob, without
+        /// running any of the profile's commands. Can be given more than once.
+        #[arg(long, value_name = "GLOB")]
+        only: Vec<String>,
+        /// Don't reload the files & assets whose destination or source matches this glob, without
+        /// running any of the profile's commands. Can be given more than once.
+        #[arg(long, value_name = "GLOB")]
+        except: Vec<String>,
+        /// Unload and load the whole profile, running it's commands, rather than only what changed
+        /// in it's manifest.
+        #[arg(long, conflicts_with_all = ["only", "except"])]
+        full: bool,
+        /// Only print what would be done, without changing anything.
+        #[arg(long)]
+        dry_run: bool
+    },
 
     /// Create a new dotfile configuration
     Create {
+        /// The dotfile profile name to use.
+        profile_name: String,
+        /// Adopt existing files from your home folder into the new profile, moving them into it
+        /// and symlinking them back. Paths are relative to your home folder. If no paths are
+        /// given, you'll be asked to pick them.
+        #[arg(long, num_args = 0..)]
+        from_home: Option<Vec<PathBuf>>
+    },
+
+    /// Clone a dotfile configuration from a git repository
+    Clone {
+        /// The URL of the git repository to clone.
+        url: String,
+        /// The dotfile profile name to use. Defaults to the repository's name.
+        profile_name: Option<String>
+    },
+
+    /// Bundle a dotfile configuration into a .tar.gz archive, to move it to another machine
+    /// without git.
+    Export {
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>,
+        /// Where to write the archive. Defaults to `{profile}.tar.gz` in the current folder.
+        #[arg(short, long)]
+        output: Option<PathBuf>
+    },
+
+    /// Unpack a dotfile configuration from an archive made with `export`. It will have to be
+    /// trusted before loading it.
+    Import {
+        /// The .tar.gz archive to import.
+        archive: PathBuf
+    },
+
+    /// Import a GNU Stow directory as a new profile, copying every package into it and mapping
+    /// their files to the same place in your home folder. It will have to be trusted before loading
+    /// it.
+    ImportStow {
+        /// The stow directory, with a folder for each package.
+        path: PathBuf,
+        /// The name of the new profile. Defaults to the stow directory's name.
+        #[arg(long)]
+        name: Option<String>
+    },
+
+    /// Install a dotfile configuration from a shorthand such as github:user/repo, a git URL or a
+    /// URL to a .tar.gz archive, showing it's commands so you can decide whether to trust it.
+    Install {
+        /// Where to install the profile from, e.g. github:user/repo, gitlab:user/repo,
+        /// codeberg:user/repo, sourcehut:user/repo, a git URL or a URL ending in .tar.gz.
+        source: String,
+        /// The dotfile profile name to use for git repositories. Defaults to the repository's
+        /// name.
+        #[arg(long)]
+        name: Option<String>
+    },
+
+    /// Rename a dotfile configuration, moving it's folder and changing the name in it's manifest.
+    /// Works even if the profile is loaded.
+    Rename {
+        /// The dotfile profile name to rename.
+        profile_name: String,
+        /// The new name for the profile.
+        new_name: String
+    },
+
+    /// Delete a dotfile configuration. The profile must not be loaded.
+    Delete {
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>
+    },
+
+    /// Trust a dotfile configuration, after showing the commands it runs. Profiles must be trusted
+    /// before they can be loaded.
+    Trust {
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>,
+        /// List every trusted profile instead, with when it was trusted and if it's manifest has
+        /// changed since.
+        #[arg(long, conflicts_with_all = ["profile_name", "note", "files_only"])]
+        list: bool,
+        /// Only trust the profile to link it's files, not to run any of it's commands. Enough for
+        /// `load --no-hooks`.
+        #[arg(long)]
+        files_only: bool,
+        /// A note to keep with the profile's trust, e.g. why you trust it. Shown by `trust --list`.
+        #[arg(long)]
+        note: Option<String>
+    },
+
+    /// Stop trusting a dotfile configuration. It will have to be trusted again before loading it.
+    Untrust {
         /// The dotfile profile name to use.
         profile_name: String
     },
 
+    /// Open a dotfile configuration's manifest in your editor (`$VISUAL` or `$EDITOR`). The
+    /// manifest is checked for problems before being saved.
+    Edit {
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>
+    },
+
+    /// Check a dotfile configuration's manifest for problems, without loading it.
+    Validate {
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>
+    },
+
+    /// Rewrite a dotfile configuration's manifest made by an older version of dotulous in the
+    /// current format. Older manifests are upgraded automatically when read, this saves the result.
+    Migrate {
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>
+    },
+
+    /// Rewrite a dotfile configuration's manifest in another format, replacing the old manifest.
+    Convert {
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>,
+        /// The format to convert to: json, toml or yaml.
+        #[arg(long)]
+        to: ManifestFormat
+    },
+
     /// Auto-Fills the files for a dotfile configuration, saving you time manually filling them out
-    /// Will only work if the JSON array is already empty!
+    /// Will only work if the JSON array is already empty, unless --merge is given!
     AutoFill {
         /// The dotfile profile name to use.
-        profile_name: String
+        profile_name: String,
+        /// How many levels of directories to scan into. Directories deeper than this are added as
+        /// a single entry. Scans all the way down by default.
+        #[arg(long)]
+        depth: Option<usize>,
+        /// Don't skip the default ignores (VCS metadata, documentation & editor files). Patterns
+        /// in the profile's `.dotulousignore` are still skipped.
+        #[arg(long, visible_alias = "include-hidden")]
+        no_default_ignores: bool,
+        /// Keep the existing entries and only add files that aren't mapped yet, reporting entries
+        /// whose source no longer exists.
+        #[arg(long)]
+        merge: bool,
+        /// Treat each folder in the profile as a GNU Stow package, mapping it's files to the same
+        /// place in your home folder without the package's name (`dot-` prefixes become `.`).
+        #[arg(long)]
+        stow_layout: bool
+    },
+
+    /// Move an existing file into the currently loaded profile, symlinking it back in it's place and
+    /// adding it to the profile's manifest.
+    Adopt {
+        /// The file (or directory) to adopt.
+        path: PathBuf
+    },
+
+    /// Add a file already inside a profile's folder to it's manifest. If the file is already in
+    /// the manifest, the destination is added as another place to put it.
+    Add {
+        /// The dotfile profile name to use.
+        profile_name: String,
+        /// The file (or directory) to add, relative to the profile's folder.
+        source: PathBuf,
+        /// Where the file goes, relative to your home folder (or the profile's `target_root`).
+        destination: PathBuf,
+        /// Link the file straight away if the profile is loaded, rather than on the next reload.
+        #[arg(long)]
+        link: bool
+    },
+
+    /// Remove a file from a profile's manifest along with all of it's destinations, unlinking them
+    /// if the profile is loaded. The file itself is left in the profile's folder.
+    RemoveFile {
+        /// The dotfile profile name to use.
+        profile_name: String,
+        /// The file (or directory) to remove, relative to the profile's folder.
+        source: PathBuf
+    },
+
+    /// Check the current "status" of your loaded dotfiles. With --verbose, the health of every
+    /// file is checked too.
+    Status {},
+
+    /// List every profile, along with if it's trusted or loaded.
+    List {},
+
+    /// Show everything about a profile: it's details, files (and whether they exist), assets,
+    /// commands, environment variables and whether it's trusted. Profiles it extends are included.
+    Show {
+        /// The dotfile profile name to use. If not given, you'll be asked to pick one.
+        profile_name: Option<String>
+    },
+
+    /// Compare the currently loaded profile with the system, showing any links that are missing or
+    /// changed, and any files in the profile that aren't in it's manifest.
+    Diff {},
+
+    /// Open an interactive dashboard of your profiles, to load, unload & reload them and look over
+    /// their files, hooks and health.
+    Tui {},
+
+    /// Show whether a file is managed by the loaded profile, which entry in it's manifest puts it
+    /// there and where it links to.
+    Which {
+        /// The file (or directory) to look up.
+        path: PathBuf
+    },
+
+    /// Run a profile's pre, post or removal commands, or one of the named `hooks` in it's manifest,
+    /// without linking any files. If no profile is given, the loaded profile's commands are ran as
+    /// they were when it was loaded, reload it to pick up changes.
+    #[command(allow_missing_positional = true)]
+    Run {
+        /// The dotfile profile name to use. If not given, the loaded profile is used.
+        profile_name: Option<String>,
+        /// Which commands to run: pre, post, removal or the name of one of the profile's hooks.
+        hook: Hook,
+        /// Only print the commands, without running them.
+        #[arg(long)]
+        dry_run: bool
+    },
+
+    /// Run a command with a profile temporarily applied, without changing the loaded profile.
+    /// The profile's files are linked into a temporary copy of your home folder, and the profile's
+    /// commands are not ran.
+    Exec {
+        /// The dotfile profile name to use.
+        profile_name: String,
+        /// The command to run, given after `--`.
+        #[arg(last = true, required = true)]
+        command: Vec<String>
+    },
+
+    /// Copy a profile's system_files to their places outside of your home folder, such as /etc.
+    /// This is never done when loading, and has to be ran as root with `sudo`. The profile must
+    /// already be trusted. You're asked to confirm before any existing system file is overwritten,
+    /// unless --yes is given.
+    ApplySystem {
+        /// The dotfile profile name to use.
+        profile_name: String,
+        /// Removes the profile's system files instead, putting back whatever was there before.
+        #[arg(long)]
+        remove: bool,
+        /// Only prints what would be done, without changing anything.
+        #[arg(long)]
+        dry_run: bool
     },
 
-    /// Check the current "status" of your loaded dotfiles
-    Status {}
+    /// Encrypt or decrypt files inside a profile, using the backend in the profile's `secrets`.
+    /// Files are decrypted on loading when their `strategy` is `encrypted`.
+    Secret {
+        /// The [`SecretAction`] to run.
+        #[command(subcommand)]
+        action: SecretAction
+    },
+
+    /// Manage the systemd user service that reapplies the loaded profile at every login.
+    Service {
+        /// The [`ServiceAction`] to run.
+        #[command(subcommand)]
+        action: ServiceAction
+    },
+
+    /// Manage ephemeral (temporary) profile loads.
+    Ephemeral {
+        /// The [`EphemeralAction`] to run.
+        #[command(subcommand)]
+        action: EphemeralAction
+    },
+
+    /// List, restore & prune backups of files that were in the way of loading a profile. Backups
+    /// are normally restored automatically when the profile is unloaded.
+    #[command(alias = "backup")]
+    Backups {
+        /// The [`BackupAction`] to run.
+        #[command(subcommand)]
+        action: BackupAction
+    },
+
+    /// Show the log of changes made to this machine by each generation, newest first
+    #[command(alias = "history")]
+    Log {
+        /// Only show the most recent generations.
+        #[arg(short = 'n', long)]
+        limit: Option<usize>
+    },
+
+    /// Show every command profiles have ran on this machine, newest first, along with how it
+    /// exited and the hash of the profile's manifest at the time.
+    Audit {
+        /// Only show commands ran by this dotfile profile.
+        #[arg(long)]
+        profile: Option<String>,
+        /// Only show commands containing this text.
+        #[arg(long)]
+        command: Option<String>,
+        /// Only show commands that failed.
+        #[arg(long)]
+        failed: bool,
+        /// Only show the most recent commands.
+        #[arg(short = 'n', long)]
+        limit: Option<usize>
+    },
+
+    /// List the logs of the output of every command profiles have ran, newest first. Each command's
+    /// stdout & stderr is kept in it's own log, so you can see why it failed after the fact.
+    Logs {
+        /// Only show logs of this dotfile profile.
+        profile_name: Option<String>,
+        /// Only show the most recent logs.
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+        /// Also print the last this many lines of each log.
+        #[arg(long, value_name = "LINES")]
+        tail: Option<usize>
+    },
+
+    /// Undo the latest generation, putting back the profile that was loaded before it exactly as
+    /// it was. Undoing again puts back what was undone.
+    Undo {},
+
+    /// Update a profile from where it came from with `git pull` (or the profile's `update_command`),
+    /// showing what changed in it's manifest. The profile is reloaded if it's currently loaded.
+    /// Updates the currently loaded profile if no profile name is given.
+    Update {
+        /// The dotfile profile name to use.
+        profile_name: Option<String>
+    },
+
+    /// Check a profile's requirements are met, such as it's packages being installed. Checks the
+    /// currently loaded profile if no profile name is given.
+    Doctor {
+        /// The dotfile profile name to use.
+        profile_name: Option<String>
+    },
+
+    /// Find & remove dangling symlinks into the dotulous folder, such as ones left behind by
+    /// deleting a loaded profile's folder by hand.
+    Clean {
+        /// The folders to search, relative to your home folder. Defaults to your home folder.
+        paths: Vec<PathBuf>,
+        /// How many folders deep to search.
+        #[arg(long, default_value_t = 4)]
+        depth: usize
+    },
+
+    /// Show or change your settings, kept in `config.toml` inside dotulous's config folder
+    /// (`~/.config/dotulous` by default).
+    Config {
+        /// The [`ConfigAction`] to run.
+        #[command(subcommand)]
+        action: ConfigAction
+    },
+
+    /// Update dotulous to the latest release on GitHub, for when it was installed from a release
+    /// rather than with cargo. The download is checked against the release's checksum before
+    /// replacing the executable.
+    SelfUpdate {
+        /// Only check whether there's a newer version.
+        #[arg(long)]
+        check: bool,
+        /// Only print what would be updated.
+        #[arg(long)]
+        dry_run: bool
+    }
 }
 
-fn main() {
-    // Are we defo in Linux?
-    // If your compiling this for some other platform and trust what your doing, comment out this
-    // check at your own risk.
-    if env::consts::OS != "linux" {
-        println!("Dotulous is only supported on Linux.");
-        exit(0);
+impl Action {
+    /// Returns if the action never changes the meta or the system, so it can run while another
+    /// dotulous instance holds the [`Lock`]. `exec` isn't, as it may trust the profile, but it
+    /// lets go of the lock before running it's command, as that may run dotulous itself.
+    /// `apply-system` only changes files outside the home folder, and is ran as root, so it
+    /// doesn't take the lock either, which would leave the lock file owned by root.
+    fn is_read_only(&self) -> bool {
+        matches!(self,
+            Action::Export { .. } | Action::Validate { .. } | Action::Status { } | Action::List { .. } | Action::Show { .. } | Action::Diff { }
+            | Action::Which { .. } | Action::Log { .. } | Action::Audit { .. } | Action::Logs { .. } | Action::Doctor { .. }
+            | Action::Config { action: ConfigAction::Get { .. } } | Action::Secret { action: SecretAction::Decrypt { .. } }
+            | Action::Service { action: ServiceAction::Status { } } | Action::Trust { list: true, .. }
+            | Action::Backups { action: BackupAction::List { .. } } | Action::SelfUpdate { .. } | Action::ApplySystem { .. }
+        )
+    }
+
+    /// Returns if old restored backups should be pruned once the action is done, being after a
+    /// profile is really loaded, reloaded or unloaded. Dry runs never delete anything.
+    fn prunes_backups(&self) -> bool {
+        matches!(self, Action::Load { dry_run: false, .. } | Action::Unload { dry_run: false, .. } | Action::Reload { dry_run: false, .. })
+    }
+}
+
+/// An action to run on a profile's secrets.
+#[derive(Subcommand, Debug)]
+enum SecretAction {
+    /// Encrypts a plain file inside the profile, replacing it with the encrypted file.
+    Encrypt {
+        /// The dotfile profile name to use.
+        profile_name: String,
+        /// The file to encrypt, relative to the profile's folder.
+        path: PathBuf
+    },
+    /// Decrypts a file inside the profile, printing it to stdout. The file itself is left
+    /// encrypted.
+    Decrypt {
+        /// The dotfile profile name to use.
+        profile_name: String,
+        /// The file to decrypt, relative to the profile's folder.
+        path: PathBuf
+    }
+}
+
+/// An action to run on the systemd user service.
+#[derive(Subcommand, Debug)]
+enum ServiceAction {
+    /// Write & enable the service. By default it runs `dotulous reload --no-hooks`, give other
+    /// arguments after `--` to change that, e.g. `dotulous service install -- reload`.
+    Install {
+        /// The arguments to run dotulous with, `--yes` is always added before them.
+        #[arg(last = true)]
+        arguments: Vec<String>
+    },
+    /// Show whether the service is installed & enabled, and how it last ran.
+    Status { },
+    /// Disable & remove the service.
+    Remove { }
+}
+
+/// An action to run on the user's settings.
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Print the value of a setting, or every setting that's set if no name is given.
+    Get {
+        /// The name of the setting: on_conflict, shell, timeout, ignore, color, backup_retention,
+        /// backup_max_age_days, backup_max_size_mb or update_check.
+        key: Option<String>
+    },
+    /// Change a setting. The value is written the same as it would be in `config.toml`, e.g.
+    /// `dotulous config set shell '["bash", "-c"]'`, though quotes around text can be left out.
+    Set {
+        /// The name of the setting.
+        key: String,
+        /// The new value.
+        value: String
+    }
+}
+
+/// An action to run on backups.
+#[derive(Subcommand, Debug)]
+enum BackupAction {
+    /// List every backup, oldest first, with how many files it holds and if it has been restored.
+    List {
+        /// Only list backups of this profile.
+        profile_name: Option<String>
+    },
+    /// Restore a backup, moving it's files back to where they were.
+    Restore {
+        /// The id of the backup to restore, as shown by `backups list`.
+        backup_id: String
+    },
+    /// Delete old backups according to your `backup_retention`, `backup_max_age_days` &
+    /// `backup_max_size_mb` settings, or the limits given. Restored backups are also pruned after
+    /// every load, reload & unload. Only this deletes backups that haven't been restored, along with
+    /// the files they hold, after asking first. The loaded profile's backup is always kept.
+    Prune {
+        /// How many backups to keep for each profile.
+        #[arg(long, value_name = "COUNT")]
+        keep: Option<usize>,
+        /// How many days to keep backups for.
+        #[arg(long, value_name = "DAYS")]
+        max_age_days: Option<u64>,
+        /// How many mebibytes every backup may take up together.
+        #[arg(long, value_name = "MB")]
+        max_size_mb: Option<u64>,
+        /// Only print which backups would be deleted.
+        #[arg(long)]
+        dry_run: bool
     }
+}
 
-    let home_folder: String = match env::var("HOME") {
+/// An action to run on ephemeral profile loads.
+#[derive(Subcommand, Debug)]
+enum EphemeralAction {
+    /// Unloads the ephemeral profile, restoring the profile that was loaded before it.
+    /// Add `trap 'dotulous ephemeral clear' EXIT` to your shell's config to clear it when your
+    /// shell exits.
+    Clear {}
+}
+
+fn main() {
+    let args = CmdlineArgs::parse();
+    report::set_json(args.json);
+    if args.non_interactive {
+        env::set_var(platform::NONINTERACTIVE_VAR, "1");
+    }
+    if let Err(e) = interrupt::install() {
+        say!("WARNING: Failed to handle Ctrl-C, interrupting dotulous may leave a profile half loaded: {e:#}");
+    }
+    let home_folder: PathBuf = match &args.home {
+        Some(path) => absolute_path(path),
+        None => match platform::home_dir() {
+            Some(r) => r,
+            None => { error_and_exit!(ErrorCategory::NotFound, "Unable to find suitable home folder."); }
+        }
+    };
+    if !home_folder.is_dir() {
+        error_and_exit!(ErrorCategory::NotFound, "Home folder {home_folder:?} doesn't exist.");
+    }
+    let home_path: &Path = &home_folder;
+    let paths: Paths = match &args.dotulous_dir {
+        Some(path) => Paths::in_folder(absolute_path(path)),
+        None => Paths::resolve(home_path)
+    };
+    // A folder given on the command line is used as-is, so nothing is moved into it
+    let migration = if args.dotulous_dir.is_some() { Ok(None) } else { paths.migrate_legacy(home_path) };
+    match migration {
+        Ok(Some(legacy_path)) => {
+            say!("NOTE: Moved {legacy_path:?} to {:?}", paths.data);
+            say!("NOTE: Reload your profile to point it's links at the new location.");
+        },
+        Ok(None) => {},
+        Err(e) => { error_and_exit!(e, "Unable to move legacy dotulous folder: {e:#}"); }
+    }
+    let settings: Settings = match Settings::load(&paths.config) {
         Ok(r) => r,
-        Err(e) => { error_and_exit!("Unable to find suitable home folder: {e}"); }
+        Err(e) => {
+            let config_path: &Path = &paths.config;
+            error_and_exit!(e, "Failed to load settings from {config_path:?}: {e:#}");
+        }
     };
-    let home_path: &Path = Path::new(&home_folder);
-    let dotulous_path_str: String = format!("{home_folder}/.dotulous/");
-    let dotulous_path: &Path = Path::new(&dotulous_path_str);
+    settings.color.apply();
+    let on_conflict: OnConflict = conflict_policy(args.on_conflict, settings.on_conflict, args.yes);
+    let dotulous_path: &Path = &paths.data;
     if !dotulous_path.exists() {
         if let Err(e) = fs::create_dir_all(dotulous_path) {
-            error_and_exit!("Unable to create dotulous folder: {e}");
+            error_and_exit!(e, "Unable to create dotulous folder: {e:#}");
         }
         let meta: Meta = Meta::new();
         if let Err(e) = meta.save_meta(dotulous_path) {
-            error_and_exit!("Failed to save meta: {e}");
+            error_and_exit!(e, "Failed to save meta: {e:#}");
         }
-        println!("NOTE: Created dotulous folder at {dotulous_path_str}");
-        println!("NOTE: This is where your dotfile configurations will be!");
+        say!("NOTE: Created dotulous folder at {dotulous_path:?}");
+        say!("NOTE: This is where your dotfile configurations will be!");
     }
+    // Held until dotulous exits, so nothing else changes the meta or the system meanwhile
+    let lock: Option<Lock> = if args.action.is_read_only() {
+        None
+    } else {
+        match Lock::acquire(dotulous_path) {
+            Ok(r) => Some(r),
+            Err(e) => { error_and_exit!(e, "{e:#}"); }
+        }
+    };
 
-    let args = CmdlineArgs::parse();
+    let prunes_backups: bool = args.action.prunes_backups();
+    // Never as root, as the file remembering the last check would then be owned by root
+    let checks_update: bool = settings.update_check && !report::is_json() && !platform::is_root() && !matches!(args.action, Action::SelfUpdate { .. });
     match args.action {
-        Action::Load { profile_name } => action_load_profile(dotulous_path, home_path, &profile_name),
-        Action::Unload { } => action_unload_profile(dotulous_path, home_path),
-        Action::Reload { } => action_reload_profile(dotulous_path, home_path),
-        Action::Create { profile_name } => action_create_profile(dotulous_path, &profile_name),
-        Action::AutoFill { profile_name } => action_fill_profile(dotulous_path, &profile_name),
-        Action::Status { } => action_status(dotulous_path)
+        Action::Load { profile_name, ephemeral, only, except, tags, skip_tags, no_hooks, keep_files, dry_run, frozen } => action_load_profile(dotulous_path, &paths.config, home_path, &settings, &pick_profile(dotulous_path, profile_name), ephemeral, &entry_filter(&only, &except), &TagFilter { tags, skip_tags }, Skip { hooks: no_hooks, files: keep_files }, apply_mode(dry_run), on_conflict, frozen, args.verbose, args.yes, args.trust),
+        Action::Unload { no_hooks, keep_files, only, except, dry_run, force } => action_unload_profile(dotulous_path, &paths.config, home_path, &settings, &entry_filter(&only, &except), Skip { hooks: no_hooks, files: keep_files }, apply_mode(dry_run), force, args.yes),
+        Action::Reload { no_hooks, keep_files, only, except, full, dry_run } => action_reload_profile(dotulous_path, &paths.config, home_path, &settings, &entry_filter(&only, &except), Skip { hooks: no_hooks, files: keep_files }, apply_mode(dry_run), on_conflict, full, args.verbose, args.yes, args.trust),
+        Action::Create { profile_name, from_home } => action_create_profile(dotulous_path, home_path, &profile_name, from_home),
+        Action::Clone { url, profile_name } => action_clone_profile(dotulous_path, &url, profile_name.as_deref()),
+        Action::Export { profile_name, output } => action_export_profile(dotulous_path, &pick_profile(dotulous_path, profile_name), output),
+        Action::Import { archive } => action_import_profile(dotulous_path, &archive),
+        Action::ImportStow { path, name } => action_import_stow(dotulous_path, &path, name.as_deref()),
+        Action::Install { source, name } => action_install_profile(dotulous_path, home_path, &source, name.as_deref(), args.trust),
+        Action::Rename { profile_name, new_name } => action_rename_profile(dotulous_path, home_path, &profile_name, &new_name),
+        Action::Delete { profile_name } => action_delete_profile(dotulous_path, &pick_profile(dotulous_path, profile_name), args.yes),
+        Action::Trust { list: true, .. } => action_list_trusted(dotulous_path),
+        Action::Trust { profile_name, note, files_only, .. } => action_trust_profile(dotulous_path, home_path, &pick_profile(dotulous_path, profile_name), note, if files_only { TrustScope::Files } else { TrustScope::Commands }, args.trust),
+        Action::Untrust { profile_name } => action_untrust_profile(dotulous_path, &profile_name),
+        Action::Edit { profile_name } => action_edit_profile(dotulous_path, &pick_profile(dotulous_path, profile_name)),
+        Action::Validate { profile_name } => action_validate_profile(dotulous_path, &pick_profile(dotulous_path, profile_name)),
+        Action::Migrate { profile_name } => action_migrate_profile(dotulous_path, &pick_profile(dotulous_path, profile_name)),
+        Action::Convert { profile_name, to } => action_convert_profile(dotulous_path, &pick_profile(dotulous_path, profile_name), to),
+        Action::AutoFill { profile_name, depth, no_default_ignores, merge, stow_layout } => action_fill_profile(dotulous_path, &settings, &profile_name, depth, !no_default_ignores, merge, stow_layout),
+        Action::Adopt { path } => action_adopt(dotulous_path, home_path, &path),
+        Action::Add { profile_name, source, destination, link } => action_add_file(dotulous_path, &paths.config, home_path, &settings, &profile_name, &source, &destination, link, on_conflict, args.verbose, args.trust),
+        Action::RemoveFile { profile_name, source } => action_remove_file(dotulous_path, &paths.config, home_path, &settings, &profile_name, &source),
+        Action::Status { } => action_status(dotulous_path, home_path, args.verbose),
+        Action::List { } => action_list(dotulous_path),
+        Action::Diff { } => action_diff(dotulous_path, home_path),
+        Action::Tui { } => action_tui(dotulous_path, &paths.config, home_path, &settings, on_conflict),
+        Action::Show { profile_name } => action_show(dotulous_path, home_path, &pick_profile(dotulous_path, profile_name)),
+        Action::Which { path } => action_which(dotulous_path, home_path, &path),
+        Action::Run { profile_name, hook, dry_run } => action_run_hook(dotulous_path, &paths.config, home_path, &settings, profile_name.as_deref(), &hook, apply_mode(dry_run), args.verbose, args.trust),
+        Action::Exec { profile_name, command } => action_exec(dotulous_path, &paths.config, home_path, &profile_name, &command, args.trust, lock),
+        Action::ApplySystem { profile_name, remove, dry_run } => action_apply_system(dotulous_path, &profile_name, remove, apply_mode(dry_run), args.yes),
+        Action::Secret { action: SecretAction::Encrypt { profile_name, path } } => action_encrypt_secret(dotulous_path, &profile_name, &path),
+        Action::Secret { action: SecretAction::Decrypt { profile_name, path } } => action_decrypt_secret(dotulous_path, &profile_name, &path),
+        Action::Ephemeral { action: EphemeralAction::Clear { } } => action_clear_ephemeral(dotulous_path, &paths.config, home_path, &settings, on_conflict, args.verbose, args.yes, args.trust),
+        Action::Backups { action: BackupAction::List { profile_name } } => action_list_backups(dotulous_path, profile_name.as_deref()),
+        Action::Backups { action: BackupAction::Restore { backup_id } } => action_restore(dotulous_path, &backup_id, args.yes),
+        Action::Backups { action: BackupAction::Prune { keep, max_age_days, max_size_mb, dry_run } } => {
+            let retention: Retention = settings.retention();
+            let retention: Retention = Retention {
+                keep: keep.or(retention.keep),
+                max_age_days: max_age_days.or(retention.max_age_days),
+                max_size_mb: max_size_mb.or(retention.max_size_mb)
+            };
+            action_prune_backups(dotulous_path, &retention, apply_mode(dry_run), args.yes);
+        },
+        Action::Log { limit } => action_log(dotulous_path, limit),
+        Action::Audit { profile, command, failed, limit } => action_audit(dotulous_path, profile.as_deref(), command.as_deref(), failed, limit),
+        Action::Logs { profile_name, limit, tail } => action_logs(dotulous_path, profile_name.as_deref(), limit, tail),
+        Action::Undo { } => action_undo(dotulous_path, &paths.config, home_path, &settings, on_conflict, args.verbose, args.yes),
+        Action::Update { profile_name } => action_update_profile(dotulous_path, &paths.config, home_path, &settings, profile_name.as_deref(), on_conflict, args.verbose, args.yes, args.trust),
+        Action::Doctor { profile_name } => action_doctor(dotulous_path, profile_name.as_deref()),
+        Action::Clean { paths, depth } => action_clean(dotulous_path, home_path, &paths, depth, args.yes),
+        Action::Service { action: ServiceAction::Install { arguments } } => action_service_install(home_path, &paths, &arguments),
+        Action::Service { action: ServiceAction::Status { } } => action_service_status(home_path),
+        Action::Service { action: ServiceAction::Remove { } } => action_service_remove(home_path),
+        Action::Config { action: ConfigAction::Get { key } } => action_config_get(&settings, key.as_deref()),
+        Action::Config { action: ConfigAction::Set { key, value } } => action_config_set(&paths.config, settings.clone(), &key, &value),
+        Action::SelfUpdate { check, dry_run } => action_self_update(check, apply_mode(dry_run), args.yes)
+    }
+
+    if prunes_backups && !settings.retention().is_unlimited() {
+        if let Err(e) = Backup::prune(dotulous_path, &settings.retention(), current_backup(dotulous_path).as_deref(), false, ApplyMode::Apply) {
+            say!("WARNING: Failed to remove old backups: {e:#}");
+        }
+    }
+    // Failing is ignored, as the user didn't ask for it & may well be offline
+    if checks_update {
+        if let Ok(Some(release)) = update::check_daily(dotulous_path) {
+            eprintln!("NOTE: Dotulous {} is available (you have {}), run `dotulous self-update` to update.", release.version(), update::CURRENT_VERSION);
+        }
     }
 }
 
 
 // Actions
 
-/// User action that creates a new profile with `profile_name`, where `dotulous_path` is the user's `.dotulous` folder.
+/// User action that creates a new profile with `profile_name`, where `dotulous_path` is the user's dotulous folder.
 /// The folder for the profile is just the sanitized `profile_name`.
 ///
+/// If `from_home` is given, the files at those paths (relative to `home_path`) are adopted into the
+/// new profile, see [`adopt_from_home`].
+///
 /// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
 /// called by the CLI. Instead, look at [`DotfileProfile::new`] & [`DotfileProfile::save_manifest`].
-fn action_create_profile(dotulous_path: &Path, profile_name: &str) {
+fn action_create_profile(dotulous_path: &Path, home_path: &Path, profile_name: &str, from_home: Option<Vec<PathBuf>>) {
     // Create the folder
     let folder_name = sanitize_filename::sanitize(profile_name);
     let folder_path: &Path = Path::new(&folder_name);
@@ -120,183 +865,2607 @@ fn action_create_profile(dotulous_path: &Path, profile_name: &str) {
         error_and_exit!("Profile path \"{full_path:?}\" already exists!");
     }
     if let Err(e) = fs::create_dir_all(&full_path) {
-        error_and_exit!("Unable to create folder \"{full_path:?}\": {e}");
+        error_and_exit!(e, "Unable to create folder \"{full_path:?}\": {e:#}");
     }
 
     // Create the manifest inside of it
-    let manifest: DotfileProfile = DotfileProfile::new(profile_name, &full_path);
+    let mut manifest: DotfileProfile = DotfileProfile::new(profile_name, &full_path);
     if let Err(e) = manifest.save_manifest() {
-        error_and_exit!("Failed to save profile manifest for \"{profile_name}\": {e}");
+        error_and_exit!(e, "Failed to save profile manifest for \"{profile_name}\": {e:#}");
+    }
+
+    say!("Created new profile at: {}", full_path.to_str().unwrap());
+    if let Some(paths) = from_home {
+        adopt_from_home(dotulous_path, home_path, &mut manifest, paths);
+    }
+}
+
+/// Adopts the files at `paths` (relative to `home_path`) into the newly created `profile`, asking
+/// the user to pick them if `paths` is empty. See [`DotfileProfile::adopt_file`].
+///
+/// As the adopted files are already linked in place, the profile is trusted and marked as loaded
+/// if no other profile is loaded. Otherwise, the user is told to load it themselves.
+fn adopt_from_home(dotulous_path: &Path, home_path: &Path, profile: &mut DotfileProfile, paths: Vec<PathBuf>) {
+    let paths: Vec<PathBuf> = if paths.is_empty() { pick_home_files(home_path) } else { paths };
+    say!();
+    say!("Adopting files.");
+    for path in paths {
+        if let Err(e) = profile.adopt_file(home_path, &home_path.join(&path)) {
+            say!("  ERROR: Failed to adopt {path:?}: {e:#}");
+        }
+    }
+    if let Err(e) = profile.save_manifest() {
+        error_and_exit!(e, "Failed to save profile manifest: {e:#}");
     }
 
-    println!("Created new profile at: {}", full_path.to_str().unwrap());
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    if meta.current_profile().is_some() {
+        say!();
+        say!("NOTE: Another profile is loaded, so this one has been left unloaded.");
+        say!("NOTE: It's files are still linked in place, load it to have dotulous manage them.");
+        return
+    }
+    match profile.manifest_hash() {
+        Ok(hash) => meta.trust_profile(profile.repo_path.clone(), hash, profile.version.clone(), TrustScope::Commands),
+        Err(e) => { error_and_exit!(e, "Failed to hash profile manifest: {e:#}"); }
+    }
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        error_and_exit!(e, "Failed to save meta: {e:#}");
+    }
+    if let Err(e) = operations::mark_loaded(dotulous_path, home_path, profile) {
+        error_and_exit!(e, "Failed to mark profile as loaded: {e:#}");
+    }
+    say!();
+    say!("Profile {} is now loaded.", profile.name);
 }
 
 /// User action for loading a profile to the system, after finding the profile from `profile_name`, 
-/// where `dotulous_path` is the user's `.dotulous` folder.
+/// where `dotulous_path` is the user's dotulous folder.
 /// If the profile is not trusted, it will confirm with the user to trust it or not.
+/// Any missing packages the profile declares will be offered to be installed, see [`check_packages`].
 ///
-/// This function will also update the Meta file.
+/// If `ephemeral` is set, the previously loaded profile is remembered so that it can be restored
+/// with [`action_clear_ephemeral`]. Otherwise any ephemeral load is made permanent.
+///
+/// If `filter` isn't empty, only the entries it picks are loaded and no commands are ran, see
+/// [`operations::load_partial`]. Otherwise only the files & commands picked by `tags` are loaded.
+/// Commands and/or files are left out according to `skip`, see [`operations::load`].
+///
+/// Before anything is changed, the user is asked to confirm what will be unloaded & loaded, unless
+/// `yes` is set (see [`confirm_changes`]). Anything already in the way of the profile's files is
+/// dealt with according to `on_conflict`, see [`conflict_policy`].
+///
+/// If `frozen` is set, this exits before doing anything if the profile has no `dotulous.lock` or
+/// has moved from the commits in it, see [`Pins::check`].
+///
+/// This function will also update the Meta file, unless `mode` is [`ApplyMode::DryRun`] in which
+/// case nothing is changed at all and the user is not asked to trust the profile.
 ///
 /// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
-/// called by the CLI. Instead, look at [`DotfileProfile::load_profile_to_system`].
-fn action_load_profile(dotulous_path: &Path, home_path: &Path, profile_name: &str) {
-    println!("Using home folder: {home_path:?}");
+/// called by the CLI. Instead, look at [`operations::load`].
+#[allow(clippy::too_many_arguments)]
+fn action_load_profile(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, profile_name: &str, ephemeral: bool, filter: &EntryFilter, tags: &TagFilter, skip: Skip, mode: ApplyMode, on_conflict: OnConflict, frozen: bool, verbose: bool, yes: bool, trust: bool) {
+    say!("Using home folder: {home_path:?}");
+    print_dry_run_notice(mode);
 
-    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+    let mut profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
         Ok(r) => r,
-        Err(e) => { error_and_exit!("Could not load current meta: {e}"); },
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
     };
-    if let Some(current_profile) = meta.current_profile() {
-        current_profile.unload_profile_from_system(home_path);
-        println!();
+    if let Err(e) = profile.resolve_extends() {
+        error_and_exit!(e, "Failed to resolve profiles extended or included by \"{profile_name}\": {e:#}");
+    }
+    if frozen {
+        let differences: Vec<String> = match Pins::check(&profile, home_path) {
+            Ok(r) => r,
+            Err(e) => { error_and_exit!(e, "Failed to check the pinned commits of \"{profile_name}\": {e:#}"); },
+        };
+        if !differences.is_empty() {
+            say!("Changed since \"{profile_name}\" was pinned:");
+            for difference in &differences {
+                say!("  {difference}");
+            }
+            error_and_exit!("Refusing to load a profile that has moved from it's dotulous.lock. Run `dotulous trust {profile_name}` to pin it again.");
+        }
+    }
+    let mut trusted_once: Option<TrustOnce> = None;
+    if mode == ApplyMode::Apply {
+        let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+            Ok(r) => r,
+            Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+        };
+        // Every command is shown when trusting, even those skipped by the tags this time. Loading
+        // only some entries never runs commands, so only needs the files to be trusted
+        let trust_skip: Skip = Skip { hooks: skip.hooks || !filter.is_empty(), ..skip };
+        trusted_once = confirm_trust(&mut meta, home_path, &profile, profile.required_trust(trust_skip), trust);
+        if let Err(e) = meta.save_meta(dotulous_path) {
+            error_and_exit!(e, "Failed to save meta for \"{profile_name}\": {e:#}");
+        }
+        profile.resolve_tags(tags);
+        if filter.is_empty() {
+            warn_shared_destinations(dotulous_path, home_path, &meta, &profile);
+        } else {
+            warn_shared_destinations(dotulous_path, home_path, &meta, &profile.partition(filter).0);
+        }
+        let mut summary: Vec<String> = Vec::new();
+        if let Some(current_profile) = meta.current_profile() {
+            if filter.is_empty() || current_profile.repo_path != profile.repo_path {
+                summary.push(unload_summary(dotulous_path, &current_profile.skipping(skip)));
+            }
+        }
+        if filter.is_empty() {
+            summary.push(load_summary(home_path, &profile.skipping(skip)));
+        } else {
+            summary.push(partial_summary("Load", &picked_entries(&profile, filter)));
+        }
+        confirm_changes(&summary, yes);
+        if filter.is_empty() {
+            // Installing packages runs commands, so isn't offered when they're skipped
+            check_packages(&profile, !skip.hooks);
+        }
     }
 
-    let profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
-        Ok(r) => r,
-        Err(e) => { error_and_exit!("Failed to load profile \"{profile_name}\": {e}"); },
+    let loaded = if filter.is_empty() {
+        operations::load(dotulous_path, config_path, home_path, settings, &profile, ephemeral, tags, skip, mode, on_conflict, verbose, trusted_once.as_ref())
+    } else {
+        operations::load_partial(dotulous_path, config_path, home_path, settings, &profile, filter, mode, on_conflict, verbose, trusted_once.as_ref())
     };
+    if let Err(e) = loaded {
+        error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}");
+    }
+    if ephemeral && mode == ApplyMode::Apply {
+        say!();
+        say!("Loaded ephemerally. Run `dotulous ephemeral clear` to restore your previous profile.");
+    }
+    if !profile.system_files().is_empty() && filter.is_empty() {
+        // System files are only ever applied by the separate, privileged sub-command
+        say!();
+        say!("NOTE: System files are not loaded with the profile. Apply them with `sudo dotulous --dotulous-dir {dotulous_path:?} apply-system {profile_name}`.");
+    }
+    emit_change("load", &profile, mode);
+}
 
-    if !meta.is_trusted(&profile.repo_path) {
-        println!("WARNING: Profile has not been marked as trusted.");
-        println!("Please verify the contents of the profile! Remember that profiles can run ANY ARBITRARY COMMANDS on your system, and can install ANY ARBITRARY FILES.");
-        println!("You're essentially going to be running random code off of the internet, so be careful!");
-        println!();
-        println!("Do you trust this profile? (y/N)");
-        let mut input: String = String::new();
-        if let Err(e) = io::stdin().read_line(&mut input) {
-            error_and_exit!("Failed to read from stdin: {e}");
-        }
-        if input.trim().to_lowercase() != "y" {
-            println!("Quitting...");
-            exit(-1);
-        }
+/// User action for unloading the currently loaded profile from the system, where `dotulous_path`
+/// is the user's dotulous folder. If `force` is set, destinations are removed even if they weren't
+/// put there by the profile. Unless `yes` is set, the user is asked to confirm first (see
+/// [`confirm_changes`]). If `filter` isn't empty, only the entries it picks are unloaded and no
+/// commands are ran, see [`operations::unload_partial`]. Otherwise commands and/or files are left
+/// out according to `skip`.
+///
+/// This function will also update the Meta file, unless `mode` is [`ApplyMode::DryRun`].
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::unload`].
+#[allow(clippy::too_many_arguments)]
+fn action_unload_profile(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, filter: &EntryFilter, skip: Skip, mode: ApplyMode, force: bool, yes: bool) {
+    say!("Using home folder: {home_path:?}");
+    print_dry_run_notice(mode);
 
-        meta.trust_profile(profile.repo_path.clone());
-        println!("Trusting profile {}", profile.name);
+    if mode == ApplyMode::Apply {
+        let meta: Meta = match Meta::load_meta(dotulous_path) {
+            Ok(r) => r,
+            Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+        };
+        match meta.current_profile() {
+            Some(profile) if !filter.is_empty() => confirm_changes(&[partial_summary("Unload", &picked_entries(&profile, filter))], yes),
+            Some(profile) => confirm_changes(&[unload_summary(dotulous_path, &profile.skipping(skip))], yes),
+            None => {}
+        }
     }
-    profile.load_profile_to_system(home_path);
 
-    meta.set_current_profile(&profile);
-    if let Err(e) = meta.save_meta(dotulous_path) {
-        error_and_exit!("Failed to save meta for \"{profile_name}\": {e}");
+    let unloaded = if filter.is_empty() {
+        operations::unload(dotulous_path, config_path, home_path, settings, skip, mode, force)
+    } else {
+        operations::unload_partial(dotulous_path, config_path, home_path, settings, filter, mode, force)
+    };
+    match unloaded {
+        Ok(profile) => emit_change("unload", &profile, mode),
+        Err(e) => { error_and_exit!(e, "Failed to unload profile: {e:#}"); }
     }
 }
 
-/// User action for unloading the currently loaded profile from the system, where `dotulous_path`
-/// is the user's `.dotulous` folder.
+/// User action for re-loading the current profile from it's manifest, where `dotulous_path` is the
+/// user's dotulous folder. Only what changed is reloaded, unless `full` is set, see
+/// [`operations::reload`].
 ///
-/// This function will also update the Meta file.
+/// Unless `yes` is set, the user is asked to confirm first (see [`confirm_changes`]). If `filter`
+/// isn't empty, only the entries it picks are reloaded and no commands are ran, see
+/// [`operations::reload_partial`]. Otherwise commands and/or files are left out according to
+/// `skip`.
 ///
+/// This function will also update the Meta file, unless `mode` is [`ApplyMode::DryRun`].
+/// 
 /// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
-/// called by the CLI. Instead, look at [`Meta::current_profile`] & [`DotfileProfile::unload_profile_from_system`].
-fn action_unload_profile(dotulous_path: &Path, home_path: &Path) {
-    println!("Using home folder: {home_path:?}");
+/// called by the CLI. Instead, look at [`operations::reload`].
+#[allow(clippy::too_many_arguments)]
+fn action_reload_profile(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, filter: &EntryFilter, skip: Skip, mode: ApplyMode, on_conflict: OnConflict, full: bool, verbose: bool, yes: bool, trust: bool) {
+    say!("Using home folder: {home_path:?}");
+    print_dry_run_notice(mode);
 
-    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
-        Ok(r) => r,
-        Err(e) => { error_and_exit!("Could not load current meta: {e}"); },
+    let mut trusted_once: Option<TrustOnce> = None;
+    if mode == ApplyMode::Apply {
+        let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+            Ok(r) => r,
+            Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+        };
+        if let Some(profile) = meta.current_profile() {
+            let trust_skip: Skip = Skip { hooks: skip.hooks || !filter.is_empty(), ..skip };
+            trusted_once = reconfirm_trust(dotulous_path, home_path, &mut meta, &profile.repo_path, trust_skip, trust);
+            if filter.is_empty() {
+                confirm_reload(dotulous_path, home_path, &profile, skip, full, yes);
+            } else {
+                let new_profile: DotfileProfile = DotfileProfile::from_manifest(&profile.repo_path).unwrap_or(profile);
+                confirm_changes(&[partial_summary("Reload", &picked_entries(&new_profile, filter))], yes);
+            }
+        }
+    }
+    let reloaded = if filter.is_empty() {
+        operations::reload(dotulous_path, config_path, home_path, settings, skip, mode, on_conflict, full, verbose, trusted_once.as_ref())
+    } else {
+        operations::reload_partial(dotulous_path, config_path, home_path, settings, filter, mode, on_conflict, verbose, trusted_once.as_ref())
     };
-    let Some(profile) = meta.current_profile() else {
-        error_and_exit!("No currently loaded profile was found. Nothing to do.");
+    match reloaded {
+        Ok(profile) => emit_change("reload", &profile, mode),
+        Err(e) => { error_and_exit!(e, "Failed to reload profile: {e:#}"); }
+    }
+}
+
+/// Reports with `--json` that `action` (`load`, `unload` or `reload`) was done with `profile`, e.g.
+/// `{"action": "load", "profile": "work", "dry_run": false}`. See [`report::emit`].
+fn emit_change(action: &str, profile: &DotfileProfile, mode: ApplyMode) {
+    report::emit(&json!({
+        "action": action,
+        "profile": profile.name,
+        "path": profile.repo_path,
+        "dry_run": mode == ApplyMode::DryRun
+    }));
+}
+
+/// User action for cloning a profile from the git repository at `url`, where `dotulous_path` is the
+/// user's dotulous folder. The folder for the profile is the sanitized `profile_name`, or the
+/// repository's name if not given.
+///
+/// If the repository has a manifest, it is validated by loading it. Otherwise a new, empty
+/// manifest is created for the user to fill in. The cloned profile is **not** trusted.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`source::git_clone`] & [`DotfileProfile::from_manifest`].
+fn action_clone_profile(dotulous_path: &Path, url: &str, profile_name: Option<&str>) {
+    let profile_name: String = match profile_name {
+        Some(r) => r.to_string(),
+        None => match source::name_from_url(url) {
+            Some(r) => r,
+            None => { error_and_exit!("Could not find a profile name from \"{url}\", please provide one."); }
+        }
     };
+    let folder_name = sanitize_filename::sanitize(&profile_name);
+    let full_path: PathBuf = dotulous_path.join(Path::new(&folder_name));
+    if full_path.exists() {
+        error_and_exit!("Profile path \"{full_path:?}\" already exists!");
+    }
 
-    profile.unload_profile_from_system(home_path);
+    say!("Cloning {url} into {full_path:?}");
+    if let Err(e) = source::git_clone(url, &full_path) {
+        error_and_exit!(e, "Failed to clone \"{url}\": {e:#}");
+    }
 
-    meta.empty_current_profile();
-    if let Err(e) = meta.save_meta(dotulous_path) {
-        error_and_exit!("Failed to save meta: {e}");
+    if ManifestFormat::find_manifest(&full_path).is_some() {
+        if let Err(e) = DotfileProfile::from_manifest(&full_path) {
+            error_and_exit!(e, "Cloned profile has an invalid manifest: {e:#} Fix it inside {full_path:?} before loading it.");
+        }
+    } else {
+        say!("NOTE: Repository has no manifest, creating an empty one. Fill it in before loading the profile!");
+        let manifest: DotfileProfile = DotfileProfile::new(&profile_name, &full_path);
+        if let Err(e) = manifest.save_manifest() {
+            error_and_exit!(e, "Failed to save profile manifest for \"{profile_name}\": {e:#}");
+        }
     }
+
+    say!("Cloned new profile \"{profile_name}\". Load it with `dotulous load {folder_name}`.");
 }
 
-/// User action for unloading and then immedietely re-loading the current profile, where `dotulous_path` 
-/// is the user's `.dotulous` folder.
+/// User action for bundling the profile with `profile_name` into a `.tar.gz` archive at `output`,
+/// where `dotulous_path` is the user's dotulous folder. If no `output` is given, the archive is
+/// written to the current folder, named after the profile's folder.
 ///
-/// This function will also update the Meta file, emptying the currently loaded profile when the old 
-/// profile is unloaded until the new profile is loaded as to prevent errors from loading the new 
-/// profile leaving the user with an incorrect meta file.
-/// 
 /// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
-/// called by the CLI. Instead, look at [`Meta::current_profile`], [`DotfileProfile::load_profile_to_system`] & [`DotfileProfile::unload_profile_from_system`].
-fn action_reload_profile(dotulous_path: &Path, home_path: &Path) {
-    println!("Using home folder: {home_path:?}");
-    // Unload the current profile, keeping a note of it's path
-    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+/// called by the CLI. Instead, look at [`archive::export`].
+fn action_export_profile(dotulous_path: &Path, profile_name: &str, output: Option<PathBuf>) {
+    let profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
         Ok(r) => r,
-        Err(e) => { error_and_exit!("Could not load current meta: {e}"); },
-    };
-    let Some(old_profile) = meta.current_profile() else {
-        error_and_exit!("No currently loaded profile was found. Nothing to do.");
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
     };
+    let output: PathBuf = output.unwrap_or_else(|| PathBuf::from(format!("{}.tar.gz", sanitize_filename::sanitize(profile_name))));
+    if output.exists() {
+        error_and_exit!("Output path \"{output:?}\" already exists!");
+    }
+
+    if let Err(e) = archive::export(&profile, &output) {
+        let _ = fs::remove_file(&output);
+        error_and_exit!(e, "Failed to export profile \"{profile_name}\": {e:#}");
+    }
+    say!("Exported profile \"{}\" to {output:?}", profile.name);
+}
 
-    let profile_path: &Path = &old_profile.repo_path;
-    // Load the profile from that path. Done up here so if it fails we don't leave the user with a
-    // system without a profile on it
-    let new_profile: DotfileProfile = match DotfileProfile::from_manifest(profile_path) {
+/// User action for importing the profile inside the archive at `archive` (made by
+/// [`action_export_profile`]), where `dotulous_path` is the user's dotulous folder.
+///
+/// This function will also update the Meta file, making sure the imported profile isn't trusted.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`archive::import`].
+fn action_import_profile(dotulous_path: &Path, archive: &Path) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let profile: DotfileProfile = match archive::import(dotulous_path, archive) {
         Ok(r) => r,
-        Err(e) => { error_and_exit!("Failed to find profile from path \"{profile_path:?}\": {e}"); },
+        Err(e) => { error_and_exit!(e, "Failed to import {archive:?}: {e:#}"); },
     };
 
-    old_profile.unload_profile_from_system(home_path);
-    meta.empty_current_profile();
-    new_profile.load_profile_to_system(home_path);
-    meta.set_current_profile(&new_profile);
+    // A profile may have been trusted at this path before, which shouldn't carry over
+    meta.untrust_profile(&profile.repo_path);
     if let Err(e) = meta.save_meta(dotulous_path) {
-        error_and_exit!("Failed to save meta: {e}");
+        error_and_exit!(e, "Failed to save meta: {e:#}");
     }
+    let folder_name: String = sanitize_filename::sanitize(&profile.name);
+    say!("Imported profile \"{}\" into {:?}", profile.name, profile.repo_path);
+    say!("It is not trusted yet. Look over it's manifest, then run `dotulous trust {folder_name}` before loading it.");
 }
 
-/// User action for auto-filling a profile's `files` array to help them, finding the profile with
-/// the given `profile_name`, and where `dotulous_path` is the user's `.dotulous` folder.
+/// User action for importing the stow directory at `stow_path` as a new profile called
+/// `profile_name`, or the directory's name if not given, where `dotulous_path` is the user's
+/// dotulous folder.
 ///
 /// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
-/// called by the CLI. Instead, look at [`DotfileProfile::fill_files`].
-fn action_fill_profile(dotulous_path: &Path, profile_name: &str) {
-    let mut profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+/// called by the CLI. Instead, look at [`stow::import`].
+fn action_import_stow(dotulous_path: &Path, stow_path: &Path, profile_name: Option<&str>) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
         Ok(r) => r,
-        Err(e) => { error_and_exit!("Failed to load profile \"{profile_name}\": {e}"); },
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
     };
-    if let Err(e) = profile.fill_files() {
-        error_and_exit!("Failed to fill profile files for \"{profile_name}\": {e}");
+    let profile: DotfileProfile = match stow::import(dotulous_path, stow_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to import {stow_path:?}: {e:#}"); },
+    };
+
+    // A profile may have been trusted at this path before, which shouldn't carry over
+    meta.untrust_profile(&profile.repo_path);
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        error_and_exit!(e, "Failed to save meta: {e:#}");
     }
+    let folder_name: String = sanitize_filename::sanitize(&profile.name);
+    say!("Imported {stow_path:?} as profile \"{}\" into {:?}", profile.name, profile.repo_path);
+    say!("If the packages are still stowed, run `stow -D` on them first so their links don't get in the way.");
+    say!("It is not trusted yet. Look over it's manifest, then run `dotulous trust {folder_name}` before loading it.");
 }
 
-/// User action for gathering the current status of dotulous as well as all the profiles the user
-/// can use.
+/// User action for installing a profile from `spec`, a shorthand such as `github:user/repo` or a
+/// URL (see [`Remote::resolve`]), where `dotulous_path` is the user's dotulous folder and
+/// `home_path` is the user's home folder. Git repositories are put in the sanitized
+/// `profile_name`, or the repository's name if not given.
+///
+/// Once installed, the profile's commands are shown and the user is asked to trust it, unless
+/// `trust` is set. If they don't, the profile is kept but left untrusted.
 ///
 /// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
-/// called by the CLI.
-fn action_status(dotulous_path: &Path) {
-    let meta: Meta = match Meta::load_meta(dotulous_path) {
+/// called by the CLI. Instead, look at [`Remote::install`].
+fn action_install_profile(dotulous_path: &Path, home_path: &Path, spec: &str, profile_name: Option<&str>, trust: bool) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
         Ok(r) => r,
-        Err(e) => { error_and_exit!("Could not load current meta: {e}"); },
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
     };
-    let current_profile: Option<DotfileProfile> = meta.current_profile();
-    if let Some(profile) = current_profile {
-        println!("Currently loaded profile: {}", profile.name);
+    let remote: Remote = match Remote::resolve(spec) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Can't install from \"{spec}\": {e:#}"); },
+    };
+    let url: &str = remote.url();
+    if url == spec {
+        say!("Installing {url}");
     } else {
-        println!("No currently loaded profile.");
+        say!("Installing {spec} from {url}");
     }
-    println!();
-    println!("Detected profiles:");
-
-    // Scan for all available profiles 
-    let paths = match fs::read_dir(dotulous_path) {
+    let mut profile: DotfileProfile = match remote.install(dotulous_path, profile_name) {
         Ok(r) => r,
-        Err(e) => { error_and_exit!("Failed to read from directory \"{dotulous_path:?}\": {e}"); }
+        Err(e) => { error_and_exit!(e, "Failed to install \"{spec}\": {e:#}"); },
     };
-    for path in paths {
-        let Ok(path) = path else {
-            continue;
-        };
-        if !path.path().is_dir() {
-            continue
-        }
 
-        let file_os_name = path.file_name();
-        let Some(file_name) = file_os_name.to_str() else {
-            continue;
-        };
-        println!("  {file_name}");
+    // A profile may have been trusted at this path before, which shouldn't carry over
+    meta.untrust_profile(&profile.repo_path);
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        error_and_exit!(e, "Failed to save meta: {e:#}");
+    }
+    let folder_name: String = sanitize_filename::sanitize(profile.repo_path.file_name().unwrap_or_default().to_string_lossy());
+    say!("Installed profile \"{}\" into {:?}", profile.name, profile.repo_path);
+    say!();
+
+    let installed_name: String = profile.name.clone();
+    if let Err(e) = profile.resolve_extends() {
+        error_and_exit!(e, "Failed to resolve profiles extended or included by \"{installed_name}\": {e:#} It is not trusted yet, run `dotulous trust {folder_name}` once it can be.");
+    }
+    confirm_trust(&mut meta, home_path, &profile, TrustScope::Commands, trust);
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        error_and_exit!(e, "Failed to save meta: {e:#}");
+    }
+    say!("Load it with `dotulous load {folder_name}`.");
+}
+
+/// User action for renaming the profile with `profile_name` to `new_name`, where `dotulous_path`
+/// is the user's dotulous folder and `home_path` is the user's home folder.
+///
+/// This function will also update the Meta file.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::rename`].
+fn action_rename_profile(dotulous_path: &Path, home_path: &Path, profile_name: &str, new_name: &str) {
+    let profile: DotfileProfile = match operations::rename(dotulous_path, home_path, profile_name, new_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to rename profile \"{profile_name}\": {e:#}"); },
+    };
+    say!("Renamed profile \"{profile_name}\" to \"{}\", now in {:?}", profile.name, profile.repo_path);
+}
+
+/// User action for deleting the profile with `profile_name` from disk, after confirming with the
+/// user, where `dotulous_path` is the user's dotulous folder.
+/// Refuses to delete the currently loaded profile, or the profile an ephemeral load will restore.
+///
+/// This function will also update the Meta file, removing the profile from the trusted profiles.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Meta::untrust_profile`].
+fn action_delete_profile(dotulous_path: &Path, profile_name: &str, yes: bool) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+    };
+    if meta.current_profile().is_some_and(|p| p.repo_path == profile.repo_path) {
+        error_and_exit!("Profile \"{profile_name}\" is currently loaded. Unload it before deleting it.");
+    }
+    if meta.is_restored_by_ephemeral(&profile.repo_path) {
+        error_and_exit!("Profile \"{profile_name}\" will be restored after the current ephemeral load. Run `dotulous ephemeral clear` first.");
+    }
+
+    confirm_changes(&[format!("Permanently delete {:?} and everything inside of it", profile.repo_path)], yes);
+
+    let repo_path: &Path = &profile.repo_path;
+    if let Err(e) = fs::remove_dir_all(repo_path) {
+        error_and_exit!(e, "Failed to delete {repo_path:?}: {e:#}");
+    }
+    meta.untrust_profile(&profile.repo_path);
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        error_and_exit!(e, "Failed to save meta: {e:#}");
+    }
+    say!("Deleted profile: {}", profile.name);
+}
+
+/// User action for trusting the profile with `profile_name` as far as `scope`, after showing the
+/// user the commands it runs (unless only trusting it's files) and confirming with them, where
+/// `dotulous_path` is the user's dotulous folder and `home_path` is the user's home folder. The
+/// commits the profile is at are recorded in it's `dotulous.lock` (see [`record_pins`]), even if
+/// it's already trusted, so this also re-pins it.
+///
+/// This function will also update the Meta file.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Meta::trust_profile`].
+fn action_trust_profile(dotulous_path: &Path, home_path: &Path, profile_name: &str, note: Option<String>, scope: TrustScope, trust: bool) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let mut profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+    };
+    if let Err(e) = profile.resolve_extends() {
+        error_and_exit!(e, "Failed to resolve profiles extended or included by \"{profile_name}\": {e:#}");
+    }
+    let manifest_hash: String = match profile.manifest_hash() {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to hash manifest for \"{profile_name}\": {e:#}"); },
+    };
+    if meta.is_trusted(&profile.repo_path, &manifest_hash, scope) {
+        say!("Profile \"{profile_name}\" is already trusted.");
+        if note.is_some() {
+            meta.set_trust_note(&profile.repo_path, note);
+            if let Err(e) = meta.save_meta(dotulous_path) {
+                error_and_exit!(e, "Failed to save meta: {e:#}");
+            }
+            say!("Updated it's note.");
+        }
+        record_pins(home_path, &profile);
+        return
+    }
+
+    if meta.is_path_trusted(&profile.repo_path) {
+        print_trusted_version(&meta, &profile);
+    } else {
+        print_introduction(&profile);
+    }
+    let question: String = match scope {
+        TrustScope::Files => {
+            say!("Profile \"{profile_name}\" links {} file(s). None of it's commands will be ran.", profile.destinations().len());
+            format!("Do you trust \"{profile_name}\" to link it's files?")
+        },
+        TrustScope::Commands => {
+            print_commands(&profile);
+            format!("Do you trust \"{profile_name}\" to link it's files and run these commands?")
+        }
+    };
+    say!();
+    if !trust {
+        require_interactive("Can't ask to trust the profile as dotulous isn't interactive. Run again with --trust to trust it anyway.");
+        if !ask_yes_no(&question) {
+            say!("Quitting...");
+            exit(ErrorCategory::TrustDeclined.exit_code());
+        }
+    }
+    meta.trust_profile(profile.repo_path.clone(), manifest_hash, profile.version.clone(), scope);
+    if note.is_some() {
+        meta.set_trust_note(&profile.repo_path, note);
+    }
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        error_and_exit!(e, "Failed to save meta: {e:#}");
+    }
+    match scope {
+        TrustScope::Files => say!("Trusting profile {} to link it's files only", profile.name),
+        TrustScope::Commands => say!("Trusting profile {}", profile.name)
+    }
+    record_pins(home_path, &profile);
+}
+
+/// User action for listing every trusted profile, where `dotulous_path` is the user's dotulous
+/// folder. Each is shown with when it was trusted, it's note, and whether it's manifest has changed
+/// since or the profile is gone.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Meta::trusted_profiles`].
+fn action_list_trusted(dotulous_path: &Path) {
+    let meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let trusted: Vec<(&TrustedProfile, TrustStatus)> = meta.trusted_profiles().iter()
+        .map(|trusted| (trusted, trusted.status()))
+        .collect();
+    if report::is_json() {
+        let trusted: Vec<_> = trusted.iter().map(|(trusted, status)| json!({
+            "path": trusted.path,
+            "status": status,
+            "manifest_hash": trusted.manifest_hash,
+            "version": trusted.version,
+            "trusted_at": trusted.trusted_at,
+            "note": trusted.note,
+            "scope": trusted.scope
+        })).collect();
+        report::emit(&trusted);
+        return
+    }
+
+    if trusted.is_empty() {
+        say!("No profiles are trusted.");
+        return
+    }
+    for (trusted, status) in &trusted {
+        let version: String = trusted.version.as_ref().map(|version| format!(" {version}")).unwrap_or_default();
+        let trusted_at: String = trusted.trusted_at
+            .map(|trusted_at| DateTime::<Local>::from(trusted_at).format(" on %Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let status: &str = match status {
+            TrustStatus::Trusted => "",
+            TrustStatus::Changed => " - MANIFEST CHANGED SINCE",
+            TrustStatus::Missing => " - MISSING"
+        };
+        let scope: &str = match trusted.scope {
+            TrustScope::Files => " (files only)",
+            TrustScope::Commands => ""
+        };
+        say!("  {:?}{version} - trusted{scope}{trusted_at}{status}", trusted.path);
+        if let Some(note) = &trusted.note {
+            say!("      {note}");
+        }
+    }
+}
+
+/// User action for no longer trusting the profile with `profile_name`, where `dotulous_path` is the
+/// user's dotulous folder. Warns if the profile is currently loaded, as it stays loaded.
+///
+/// This function will also update the Meta file.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Meta::untrust_profile`].
+fn action_untrust_profile(dotulous_path: &Path, profile_name: &str) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+    };
+    if !meta.is_path_trusted(&profile.repo_path) {
+        say!("Profile \"{profile_name}\" is not trusted. Nothing to do.");
+        return
+    }
+    if meta.current_profile().is_some_and(|p| p.repo_path == profile.repo_path) {
+        say!("WARNING: Profile \"{profile_name}\" is currently loaded. It will stay loaded, but will have to be trusted again to reload it.");
+    }
+
+    meta.untrust_profile(&profile.repo_path);
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        error_and_exit!(e, "Failed to save meta: {e:#}");
+    }
+    say!("No longer trusting profile {}", profile.name);
+}
+
+/// User action for editing the manifest of the profile with the given `profile_name` in the user's
+/// editor, where `dotulous_path` is the user's dotulous folder.
+///
+/// The manifest is edited as a temporary copy (see [`create_edit_file`]), which is checked with
+/// [`validate::validate_manifest`] once the editor exits. If there are any problems the user can edit it again, or throw their
+/// changes away. The manifest is only replaced once it has no problems.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`validate::validate_manifest`].
+fn action_edit_profile(dotulous_path: &Path, profile_name: &str) {
+    let folder_name = sanitize_filename::sanitize(profile_name);
+    let repo_path: PathBuf = dotulous_path.join(Path::new(&folder_name));
+    let Some(manifest_path) = ManifestFormat::find_manifest(&repo_path) else {
+        error_and_exit!(ErrorCategory::NotFound, "No manifest was found for \"{profile_name}\".");
+    };
+    let format: ManifestFormat = ManifestFormat::from_path(&manifest_path);
+    let original: String = match fs::read_to_string(&manifest_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to read manifest for \"{profile_name}\": {e:#}"); }
+    };
+    let edit_path: PathBuf = match create_edit_file(dotulous_path, &folder_name, format, &original) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to create a copy of the manifest to edit: {e:#}"); }
+    };
+
+    let contents: String = loop {
+        open_editor(&edit_path);
+        let contents: String = match fs::read_to_string(&edit_path) {
+            Ok(r) => r,
+            Err(e) => { error_and_exit!(e, "Failed to read {edit_path:?}: {e:#}"); }
+        };
+        let Err(problems) = validate::validate_manifest(&contents, format) else { break contents };
+        say!("Found problems in the manifest for \"{profile_name}\":");
+        for problem in problems {
+            say!("  {problem}");
+        }
+        if !ask_yes_no("Edit it again? Otherwise your changes are thrown away.") {
+            let _ = fs::remove_file(&edit_path);
+            error_and_exit!("Manifest for \"{profile_name}\" was not changed.");
+        }
+    };
+    let _ = fs::remove_file(&edit_path);
+    if contents == original {
+        say!("No changes made to \"{profile_name}\".");
+        return
+    }
+
+    if let Err(e) = fs::write(&manifest_path, contents) {
+        error_and_exit!(e, "Failed to save manifest for \"{profile_name}\": {e:#}");
+    }
+    say!("Saved manifest for \"{profile_name}\".");
+    if Meta::load_meta(dotulous_path).is_ok_and(|meta| meta.is_path_trusted(&repo_path)) {
+        say!("NOTE: You'll be asked to trust the profile again the next time it's loaded.");
+    }
+}
+
+/// Creates a new file holding `contents` for the manifest of the profile in `folder_name` to be
+/// edited in, returning it's path. It's put in `$XDG_RUNTIME_DIR` if set, otherwise in
+/// `dotulous_path`, rather than a shared temporary folder where another user could guess it's name.
+/// The file is always newly created & only accessible by the user, and ends with `format`'s file
+/// name so the editor knows what it is.
+fn create_edit_file(dotulous_path: &Path, folder_name: &str, format: ManifestFormat, contents: &str) -> io::Result<PathBuf> {
+    let folder: PathBuf = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).filter(|path| path.is_dir()).unwrap_or_else(|| dotulous_path.to_path_buf());
+    // A previous edit may have been left behind, or be open in another editor, so the first free number is used
+    let (path, mut file): (PathBuf, fs::File) = (1..).find_map(|n| {
+        let path: PathBuf = folder.join(format!(".dotulous-edit-{folder_name}-{n}-{}", format.file_name()));
+        match platform::create_private_new(&path) {
+            Ok(file) => Some(Ok((path, file))),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => None,
+            Err(e) => Some(Err(e))
+        }
+    }).unwrap_or_else(|| Err(io::Error::from(io::ErrorKind::AlreadyExists)))?;
+    if let Err(e) = file.write_all(contents.as_bytes()) {
+        let _ = fs::remove_file(&path);
+        return Err(e)
+    }
+    Ok(path)
+}
+
+/// User action for rewriting the manifest of the profile with the given `profile_name` in the
+/// current format, where `dotulous_path` is the user's dotulous folder.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::migrate`].
+fn action_migrate_profile(dotulous_path: &Path, profile_name: &str) {
+    match operations::migrate(dotulous_path, profile_name) {
+        Ok(Some(version)) => say!("Migrated manifest for \"{profile_name}\" from version {version} to {MANIFEST_VERSION}."),
+        Ok(None) => say!("Manifest for \"{profile_name}\" is already up to date. Nothing to do."),
+        Err(e) => { error_and_exit!(e, "Failed to migrate manifest for \"{profile_name}\": {e:#}"); }
+    }
+}
+
+/// User action for rewriting the manifest of the profile with the given `profile_name` in the
+/// given `format`, where `dotulous_path` is the user's dotulous folder.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::convert`].
+fn action_convert_profile(dotulous_path: &Path, profile_name: &str, format: ManifestFormat) {
+    let profile: DotfileProfile = match operations::convert(dotulous_path, profile_name, format) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to convert manifest for \"{profile_name}\": {e:#}"); }
+    };
+    say!("Manifest for \"{profile_name}\" is now {:?}", profile.manifest_path);
+}
+
+/// User action for checking the manifest of the profile with the given `profile_name` for
+/// problems, where `dotulous_path` is the user's dotulous folder. Exits unsuccessfully if any are
+/// found.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`validate::validate_manifest`] & [`validate::missing_sources`].
+fn action_validate_profile(dotulous_path: &Path, profile_name: &str) {
+    let repo_path: PathBuf = dotulous_path.join(Path::new(&sanitize_filename::sanitize(profile_name)));
+    let Some(manifest_path) = ManifestFormat::find_manifest(&repo_path) else {
+        error_and_exit!(ErrorCategory::NotFound, "No manifest was found for \"{profile_name}\".");
+    };
+    let contents: String = match fs::read_to_string(&manifest_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to read manifest for \"{profile_name}\": {e:#}"); }
+    };
+    let problems: Vec<Problem> = match validate::validate_manifest(&contents, ManifestFormat::from_path(&manifest_path)) {
+        Ok(mut profile) => {
+            profile.repo_path = repo_path;
+            validate::missing_sources(&profile)
+        },
+        Err(problems) => problems
+    };
+    if problems.is_empty() {
+        say!("No problems found in the manifest for \"{profile_name}\".");
+        return
+    }
+
+    say!("Found problems in the manifest for \"{profile_name}\":");
+    for problem in &problems {
+        say!("  {problem}");
+    }
+    let count: usize = problems.len();
+    error_and_exit!(ErrorCategory::InvalidManifest, "Found {count} problem(s).");
+}
+
+/// User action for auto-filling a profile's `files` array to help them, finding the profile with
+/// the given `profile_name`, and where `dotulous_path` is the user's dotulous folder.
+/// Directories are scanned up to `depth` levels deep, skipping the default ignores if
+/// `default_ignores` is set and the `ignore` patterns in the user's `settings`. If `merge` is set,
+/// existing entries are kept and only unmapped files are added, and if `stow_layout` is set, the
+/// profile's folders are treated as stow packages, see [`DotfileProfile::fill_files`].
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`DotfileProfile::fill_files`].
+fn action_fill_profile(dotulous_path: &Path, settings: &Settings, profile_name: &str, depth: Option<usize>, default_ignores: bool, merge: bool, stow_layout: bool) {
+    let mut profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+    };
+    if let Err(e) = profile.fill_files(depth, default_ignores, &settings.ignore, merge, stow_layout) {
+        error_and_exit!(e, "Failed to fill profile files for \"{profile_name}\": {e:#}");
+    }
+}
+
+/// User action for adopting the existing file at `path` into the currently loaded profile, where
+/// `dotulous_path` is the user's dotulous folder. Relative paths are relative to the current
+/// directory.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::adopt`].
+fn action_adopt(dotulous_path: &Path, home_path: &Path, path: &Path) {
+    let path: PathBuf = match std::path::absolute(path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to find {path:?}: {e:#}"); }
+    };
+    say!("Adopting file.");
+    match operations::adopt(dotulous_path, home_path, &path) {
+        Ok(profile) => say!("Adopted {path:?} into profile {}", profile.name),
+        Err(e) => { error_and_exit!(e, "Failed to adopt {path:?}: {e:#}"); }
+    }
+}
+
+/// User action for adding the file at `source` (relative to the profile's folder) to the manifest
+/// of the profile with `profile_name`, going to `destination`, where `dotulous_path` is the user's
+/// dotulous folder.
+///
+/// If `link` is set and the profile is loaded, the file is linked straight away (see
+/// [`operations::load_partial`]), dealing with anything in the way according to `on_conflict`.
+/// The user is asked to trust the profile first if it isn't, unless `trust` is set.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::add_file`].
+#[allow(clippy::too_many_arguments)]
+fn action_add_file(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, profile_name: &str, source: &Path, destination: &Path, link: bool, on_conflict: OnConflict, verbose: bool, trust: bool) {
+    let profile: DotfileProfile = match operations::add_file(dotulous_path, home_path, profile_name, source, destination) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to add {source:?} to \"{profile_name}\": {e:#}"); }
+    };
+    say!("Added {source:?} -> {destination:?} to profile {}", profile.name);
+
+    let is_loaded: bool = Meta::load_meta(dotulous_path).is_ok_and(|meta| meta.current_profile().is_some_and(|loaded| loaded.repo_path == profile.repo_path));
+    if !is_loaded {
+        return
+    }
+    if !link {
+        say!("NOTE: Run `dotulous reload --only {destination:?}` to link it, or pass --link next time.");
+        return
+    }
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let trusted_once: Option<TrustOnce> = reconfirm_trust(dotulous_path, home_path, &mut meta, &profile.repo_path, Skip { hooks: true, files: false }, trust);
+    if let Err(e) = operations::load_partial(dotulous_path, config_path, home_path, settings, &profile, &EntryFilter::path(destination), ApplyMode::Apply, on_conflict, verbose, trusted_once.as_ref()) {
+        error_and_exit!(e, "Failed to link {destination:?}: {e:#}");
+    }
+}
+
+/// User action for removing the file at `source` (relative to the profile's folder) from the
+/// manifest of the profile with `profile_name`, unlinking it first if the profile is loaded, where
+/// `dotulous_path` is the user's dotulous folder.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::remove_file`].
+fn action_remove_file(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, profile_name: &str, source: &Path) {
+    match operations::remove_file(dotulous_path, config_path, home_path, settings, profile_name, source) {
+        Ok(entries) => {
+            for entry in entries {
+                say!("Removed {source:?} -> {:?} from profile \"{profile_name}\"", entry.destination);
+            }
+        },
+        Err(e) => { error_and_exit!(e, "Failed to remove {source:?} from \"{profile_name}\": {e:#}"); }
+    }
+}
+
+/// User action for encrypting the plain file at `path` (relative to the profile's folder) inside the
+/// profile with the given `profile_name`, where `dotulous_path` is the user's dotulous folder. The
+/// file is replaced with the encrypted file.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`SecretConfig::encrypt_in_place`].
+fn action_encrypt_secret(dotulous_path: &Path, profile_name: &str, path: &Path) {
+    let (profile, secrets): (DotfileProfile, SecretConfig) = find_secrets(dotulous_path, profile_name);
+    let source: PathBuf = profile.repo_path.join(path);
+    if !source.is_file() {
+        error_and_exit!(ErrorCategory::NotFound, "File {path:?} was not found in profile \"{profile_name}\".");
+    }
+    if let Err(e) = secrets.encrypt_in_place(&source) {
+        error_and_exit!(e, "Failed to encrypt {path:?}: {e:#}");
+    }
+    say!("Encrypted {source:?}");
+    if profile.files().get(path).is_none_or(|entries| entries.iter().any(|entry| entry.strategy != Strategy::Encrypted)) {
+        say!("NOTE: Set the file's \"strategy\" to \"encrypted\" in the manifest to decrypt it on loading.");
+    }
+}
+
+/// User action for decrypting the file at `path` (relative to the profile's folder) inside the
+/// profile with the given `profile_name` and printing it to stdout, where `dotulous_path` is the
+/// user's dotulous folder.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`SecretConfig::decrypt`].
+fn action_decrypt_secret(dotulous_path: &Path, profile_name: &str, path: &Path) {
+    let (profile, secrets): (DotfileProfile, SecretConfig) = find_secrets(dotulous_path, profile_name);
+    let contents: Vec<u8> = match secrets.decrypt(&profile.repo_path.join(path)) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to decrypt {path:?}: {e:#}"); }
+    };
+    if let Err(e) = io::stdout().write_all(&contents) {
+        error_and_exit!(e, "Failed to print {path:?}: {e:#}");
+    }
+}
+
+/// User action for clearing an ephemeral load, unloading the current profile and restoring the
+/// profile that was loaded beforehand (if any), where `dotulous_path` is the user's dotulous folder.
+///
+/// Unless `yes` is set, the user is asked to confirm first (see [`confirm_changes`]).
+///
+/// This function will also update the Meta file.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::clear_ephemeral`].
+#[allow(clippy::too_many_arguments)]
+fn action_clear_ephemeral(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, on_conflict: OnConflict, verbose: bool, yes: bool, trust: bool) {
+    say!("Using home folder: {home_path:?}");
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let previous_profile: Option<DotfileProfile> = meta.ephemeral().and_then(|e| e.previous_profile.clone());
+    let trusted_once: Option<TrustOnce> = previous_profile.as_ref().and_then(|previous| reconfirm_trust(dotulous_path, home_path, &mut meta, &previous.repo_path, Skip::default(), trust));
+    if meta.is_ephemeral() {
+        let mut summary: Vec<String> = Vec::new();
+        if let Some(current_profile) = meta.current_profile() {
+            summary.push(unload_summary(dotulous_path, &current_profile));
+        }
+        if let Some(previous) = &previous_profile {
+            summary.push(load_summary(home_path, previous));
+        }
+        confirm_changes(&summary, yes);
+    }
+    if let Err(e) = operations::clear_ephemeral(dotulous_path, config_path, home_path, settings, on_conflict, verbose, trusted_once.as_ref()) {
+        error_and_exit!(e, "Failed to clear ephemeral load: {e:#}");
+    }
+}
+
+/// User action for gathering the current status of dotulous as well as all the profiles the user
+/// can use. If `verbose`, every file of the loaded profile is printed with it's [`LinkHealth`],
+/// followed by how many there are of each. With `--json`, everything is printed as JSON instead,
+/// always including every file.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::status`].
+fn action_status(dotulous_path: &Path, home_path: &Path, verbose: bool) {
+    let status: Status = match operations::status(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not get status: {e:#}"); },
+    };
+    if report::is_json() {
+        let health: Vec<(PathBuf, LinkHealth)> = status.current_profile.as_ref()
+            .map(|profile| inspect::link_health(profile, dotulous_path, home_path))
+            .unwrap_or_default();
+        let files: Vec<_> = health.iter().map(|(destination, health)| json!({"destination": destination, "health": health})).collect();
+        report::emit(&json!({
+            "current_profile": status.current_profile.as_ref().map(|profile| &profile.name),
+            "version": status.current_profile.as_ref().and_then(|profile| profile.version.as_ref()),
+            "extends": status.current_profile.as_ref().map(|profile| &profile.extends_chain),
+            "ephemeral": status.ephemeral,
+            "files": files,
+            "profiles": status.profiles
+        }));
+        return
+    }
+    if let Some(profile) = &status.current_profile {
+        say!("Currently loaded profile: {}", profile.name);
+        if let Some(description) = &profile.description {
+            say!("  {description}");
+        }
+        print_details(profile);
+        if !profile.extends_chain.is_empty() {
+            say!("Extends: {}", profile.extends_chain.join(" -> "));
+        }
+        if status.ephemeral {
+            say!("This profile is loaded ephemerally, run `dotulous ephemeral clear` to restore your previous profile.");
+        }
+        if verbose {
+            say!();
+            say!("Files:");
+            let health: Vec<(PathBuf, LinkHealth)> = inspect::link_health(profile, dotulous_path, home_path);
+            for (destination, health) in &health {
+                say!("  {health:<7} {destination:?}");
+            }
+            let count = |wanted: LinkHealth| health.iter().filter(|(_, health)| *health == wanted).count();
+            say!("{} ok, {} broken, {} missing, {} foreign, {} optional & absent.", count(LinkHealth::Ok), count(LinkHealth::Broken), count(LinkHealth::Missing), count(LinkHealth::Foreign), count(LinkHealth::OptionalAbsent));
+        }
+    } else {
+        say!("No currently loaded profile.");
+    }
+    say!();
+    say!("Detected profiles:");
+    for profile in &status.profiles {
+        say!("  {profile}");
+    }
+}
+
+/// User action for listing every profile the user has, where `dotulous_path` is the user's
+/// dotulous folder. With `--json`, the list is printed as JSON instead.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::list`].
+fn action_list(dotulous_path: &Path) {
+    let profiles: Vec<ProfileSummary> = match operations::list(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not list profiles: {e:#}"); },
+    };
+    if report::is_json() {
+        report::emit(&profiles);
+        return
+    }
+
+    if profiles.is_empty() {
+        say!("No profiles were found.");
+        return
+    }
+    for profile in &profiles {
+        let version: String = profile.version.as_ref().map(|version| format!(" {version}")).unwrap_or_default();
+        say!("  {}{version}{} - {} file(s) - {:?}", profile.name, summary_flags(profile), profile.file_count, profile.path);
+        if let Some(description) = &profile.description {
+            say!("      {description}");
+        }
+    }
+}
+
+/// User action for showing everything about the profile with `profile_name`, where
+/// `dotulous_path` is the user's dotulous folder and `home_path` is the user's home folder. The
+/// profile is shown with the profiles it extends merged in, as it would be loaded. Each file is
+/// marked with whether it's source exists in the profile. With `--json`, it's printed as JSON
+/// instead.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`DotfileProfile::resolve_extends`].
+fn action_show(dotulous_path: &Path, home_path: &Path, profile_name: &str) {
+    let meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let mut profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+    };
+    let trust_scope: Option<TrustScope> = profile.manifest_hash().ok().and_then(|hash| meta.trust_scope(&profile.repo_path, &hash));
+    // How it's trusted, both for JSON and for reading
+    let (trust, trust_text): (&str, &str) = match trust_scope {
+        Some(TrustScope::Commands) => ("trusted", "trusted"),
+        Some(TrustScope::Files) => ("files_only", "trusted to link it's files only"),
+        None if meta.is_path_trusted(&profile.repo_path) => ("changed", "NOT TRUSTED, it's manifest has changed since it was trusted"),
+        None => ("untrusted", "NOT TRUSTED")
+    };
+    if let Err(e) = profile.resolve_extends() {
+        error_and_exit!(e, "Failed to resolve profiles extended or included by \"{profile_name}\": {e:#}");
+    }
+    let loaded: bool = meta.current_profile().is_some_and(|current| current.repo_path == profile.repo_path);
+    let files: Vec<(&PathBuf, &FileEntry, bool)> = profile.entries()
+        .map(|(source, entry)| (source, entry, profile.repo_path.join(paths::expand_path(source, home_path)).symlink_metadata().is_ok()))
+        .collect();
+    let env: BTreeMap<&String, &String> = profile.env().iter().collect();
+
+    if report::is_json() {
+        let files: Vec<_> = files.iter().map(|(source, entry, exists)| json!({
+            "source": source,
+            "destination": entry.destination,
+            "strategy": entry.strategy,
+            "optional": entry.optional,
+            "tags": entry.tags,
+            "exists": exists
+        })).collect();
+        report::emit(&json!({
+            "name": profile.name,
+            "description": profile.description,
+            "version": profile.version,
+            "author": profile.author,
+            "homepage": profile.homepage,
+            "path": profile.repo_path,
+            "manifest_path": profile.manifest_path,
+            "extends": profile.extends_chain,
+            "includes": profile.included,
+            "trust": trust,
+            "loaded": loaded,
+            "files": files,
+            "assets": profile.assets(),
+            "directories": profile.directories(),
+            "system_files": profile.system_files(),
+            "hooks": profile.hooks(),
+            "env": env
+        }));
+        return
+    }
+
+    say!("Profile: {}", profile.name);
+    if let Some(description) = &profile.description {
+        say!("  {description}");
+    }
+    print_details(&profile);
+    say!("Manifest: {:?}", profile.manifest_path);
+    if !profile.extends_chain.is_empty() {
+        say!("Extends: {}", profile.extends_chain.join(" -> "));
+    }
+    if !profile.included.is_empty() {
+        say!("Includes: {}", profile.included.join(", "));
+    }
+    say!("Trust: {trust_text}{}", if loaded { " - currently loaded" } else { "" });
+
+    say!();
+    say!("Files:");
+    if files.is_empty() {
+        say!("  (none)");
+    }
+    for (source, entry, exists) in &files {
+        let mut notes: Vec<String> = Vec::new();
+        if entry.strategy != Strategy::Symlink {
+            notes.push(entry.strategy.to_string());
+        }
+        if let Some(mode) = entry.mode {
+            notes.push(format!("mode {mode}"));
+        }
+        if entry.optional {
+            notes.push("optional".to_string());
+        }
+        if !entry.tags.is_empty() {
+            notes.push(format!("tags: {}", entry.tags.join(", ")));
+        }
+        let notes: String = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+        let marker: &str = if *exists { "ok" } else { "MISSING" };
+        say!("  {marker:<7} {source:?} => {:?}{notes}", entry.destination);
+    }
+    if !profile.assets().is_empty() {
+        say!();
+        say!("Assets:");
+        for asset in profile.assets() {
+            say!("  {} => {:?}", asset.url, asset.dest);
+        }
+    }
+    if !profile.directories().is_empty() {
+        say!();
+        say!("Folders:");
+        for directory in profile.directories() {
+            say!("  {directory:?}");
+        }
+    }
+    if !profile.system_files().is_empty() {
+        say!();
+        say!("System files:");
+        for (source, destination) in profile.system_files() {
+            say!("  {source:?} => {destination:?}");
+        }
+    }
+    say!();
+    say!("Commands:");
+    say!("{}", format_commands(&profile).trim_end());
+    say!();
+    say!("Environment:");
+    if env.is_empty() {
+        say!("  (none)");
+    }
+    for (name, value) in &env {
+        say!("  {name}={value}");
+    }
+}
+
+/// User action for running the commands for `hook` of the profile with `profile_name` on their
+/// own, where `dotulous_path` is the user's dotulous folder. If `mode` is [`ApplyMode::DryRun`],
+/// they're only printed.
+///
+/// If `profile_name` is [`None`], the commands are ran from the copy of the loaded profile stored
+/// in the meta, so they're the same ones that were trusted & ran when it was loaded, as long as
+/// it's still trusted to run it's commands (it may have been loaded with `--no-hooks`). Otherwise the
+/// profile is read from it's manifest, and if it isn't trusted the user is asked to trust it
+/// first, unless `trust` is set (see [`confirm_trust`]).
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`DotfileProfile::run_hook`].
+#[allow(clippy::too_many_arguments)]
+fn action_run_hook(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, profile_name: Option<&str>, hook: &Hook, mode: ApplyMode, verbose: bool, trust: bool) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let profile: DotfileProfile = match profile_name {
+        Some(profile_name) => {
+            let mut profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+                Ok(r) => r,
+                Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+            };
+            if let Err(e) = profile.resolve_extends() {
+                error_and_exit!(e, "Failed to resolve profiles extended or included by \"{profile_name}\": {e:#}");
+            }
+            profile.resolve_conditions(&Host::detect());
+            if mode == ApplyMode::Apply {
+                confirm_trust(&mut meta, home_path, &profile, TrustScope::Commands, trust);
+                if let Err(e) = meta.save_meta(dotulous_path) {
+                    error_and_exit!(e, "Failed to save meta for \"{profile_name}\": {e:#}");
+                }
+            }
+            profile
+        },
+        None => match meta.current_profile() {
+            Some(profile) if mode == ApplyMode::Apply && !is_trusted_with_commands(&meta, &profile) => {
+                let profile_name: &str = &profile.name;
+                error_and_exit!(ErrorCategory::TrustDeclined, "Profile \"{profile_name}\" isn't trusted to run it's commands. Give the profile's name to trust it first.");
+            },
+            Some(profile) => profile,
+            None => { error_and_exit!(ErrorCategory::NotFound, "No currently loaded profile was found. Give the profile to run the hook of."); }
+        }
+    };
+
+    print_dry_run_notice(mode);
+    say!("Running {hook} commands for profile: {}", profile.name);
+    if let Err(e) = profile.run_hook(hook, dotulous_path, config_path, home_path, settings, mode, verbose) {
+        let profile_name: &str = &profile.name;
+        match e {
+            DotulousError::HookNotFound => {
+                let hooks: Vec<&str> = profile.custom_hook_names().collect();
+                let available: String = if hooks.is_empty() { "it has none".to_string() } else { hooks.join(", ") };
+                error_and_exit!(ErrorCategory::NotFound, "Profile \"{profile_name}\" has no hook called \"{hook}\" ({available}).");
+            },
+            e => { error_and_exit!(e, "Failed to run {hook} commands for \"{profile_name}\": {e:#}"); }
+        }
+    }
+}
+
+/// User action for comparing the currently loaded profile with the system, where `dotulous_path`
+/// is the user's dotulous folder. Prints every destination that is no longer how loading the
+/// profile left it, along with any files in the profile's directory that aren't in it's manifest.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`inspect::loaded_drift`] & [`inspect::unmapped_files`].
+fn action_diff(dotulous_path: &Path, home_path: &Path) {
+    let meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let Some(profile) = meta.current_profile() else {
+        error_and_exit!(ErrorCategory::NotFound, "No currently loaded profile was found. Nothing to do.");
+    };
+
+    say!("Comparing loaded profile: {}", profile.name);
+    let mut drift: Vec<Drift> = inspect::loaded_drift(&profile, dotulous_path, home_path);
+    match DotfileProfile::from_manifest(&profile.repo_path).and_then(|manifest| inspect::unmapped_files(&manifest)) {
+        Ok(r) => drift.extend(r),
+        Err(e) => say!("WARNING: Could not check for unmapped files: {e:#}")
+    }
+    if report::is_json() {
+        report::emit(&json!({"profile": profile.name, "differences": drift}));
+        return
+    }
+    if drift.is_empty() {
+        say!("No differences found.");
+        return
+    }
+    for difference in &drift {
+        say!("  {difference}");
+    }
+}
+
+/// User action for opening the interactive dashboard, where `dotulous_path` is the user's dotulous
+/// folder and `home_path` is the user's home folder. Anything in the way while loading is dealt
+/// with according to `on_conflict`.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`tui::run`].
+fn action_tui(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, on_conflict: OnConflict) {
+    if !platform::is_interactive() {
+        error_and_exit!("The dashboard needs an interactive terminal.");
+    }
+    if let Err(e) = tui::run(dotulous_path, config_path, home_path, settings, on_conflict) {
+        error_and_exit!(e, "Dashboard failed: {e:#}");
+    }
+}
+
+/// User action for showing what manages the file at `path`, where `dotulous_path` is the user's
+/// dotulous folder. Relative paths are relative to the current directory. If the loaded profile
+/// has an entry covering it, the entry is shown along with where it links to and it's
+/// [`LinkHealth`], otherwise the file is said to be unmanaged.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`inspect::find_owner`].
+fn action_which(dotulous_path: &Path, home_path: &Path, path: &Path) {
+    let meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let path: PathBuf = platform::normalize_path(&absolute_path(path));
+    let Some(profile) = meta.current_profile() else {
+        say!("{path:?} isn't managed, as no profile is loaded.");
+        print_link_target(&path);
+        return
+    };
+    let profile_name: &str = &profile.name;
+    let Some(owner) = inspect::find_owner(&profile, home_path, &path) else {
+        say!("{path:?} isn't managed by the loaded profile \"{profile_name}\".");
+        print_link_target(&path);
+        return
+    };
+
+    say!("{path:?} is managed by the loaded profile \"{profile_name}\".");
+    let strategy: String = owner.strategy.map_or("asset".to_string(), |strategy| strategy.to_string());
+    say!("  Entry:    {} => {:?} ({strategy})", owner.source, owner.destination);
+    print_link_target(&owner.destination);
+    let health: Option<LinkHealth> = inspect::link_health(&profile, dotulous_path, home_path).into_iter()
+        .find(|(destination, _)| *destination == owner.destination)
+        .map(|(_, health)| health);
+    if let Some(health) = health {
+        say!("  Health:   {health}");
+    }
+}
+
+/// User action for updating the profile with the given `profile_name` (or the currently loaded
+/// profile if [`None`]) from it's source, where `dotulous_path` is the user's dotulous folder.
+///
+/// Every change to the profile's links & commands is shown, along with any change to it's
+/// `version` (see [`version_change`]). If the profile was trusted, it stays trusted when only it's
+/// links changed, otherwise (including when it's version changed) the user is asked to trust it
+/// again after seeing it's commands. If the profile is currently loaded, it is then reloaded, asking the user
+/// to trust it first if needed, and to confirm the reload unless `yes` is set.
+///
+/// This function will also update the Meta file.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`source::update`], [`inspect::manifest_changes`] & [`operations::reload`].
+#[allow(clippy::too_many_arguments)]
+fn action_update_profile(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, profile_name: Option<&str>, on_conflict: OnConflict, verbose: bool, yes: bool, trust: bool) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let loaded_path: Option<PathBuf> = meta.current_profile().map(|p| p.repo_path);
+    let repo_path: PathBuf = match (profile_name, &loaded_path) {
+        (Some(profile_name), _) => dotulous_path.join(Path::new(&sanitize_filename::sanitize(profile_name))),
+        (None, Some(loaded_path)) => loaded_path.clone(),
+        (None, None) => { error_and_exit!(ErrorCategory::NotFound, "No currently loaded profile was found, and no profile name was given."); }
+    };
+    let old_profile: DotfileProfile = match DotfileProfile::from_manifest(&repo_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile at {repo_path:?}: {e:#}"); },
+    };
+    let profile_name: &str = &old_profile.name;
+    let was_trusted: Option<TrustScope> = old_profile.manifest_hash().ok().and_then(|hash| meta.trust_scope(&repo_path, &hash));
+    if old_profile.update_command.is_some() && was_trusted != Some(TrustScope::Commands) {
+        error_and_exit!(ErrorCategory::TrustDeclined, "Profile \"{profile_name}\" has an update command, but isn't trusted to run commands. Trust it first with `dotulous trust`.");
+    }
+
+    say!("Updating profile: {profile_name}");
+    if let Err(e) = source::update(&repo_path, old_profile.update_command.as_deref(), old_profile.shell.as_ref()) {
+        error_and_exit!(e, "Failed to update \"{profile_name}\": {e:#}");
+    }
+    let new_profile: DotfileProfile = match DotfileProfile::from_manifest(&repo_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Updated profile has an invalid manifest: {e:#}"); },
+    };
+
+    say!();
+    let changes: Vec<ManifestChange> = inspect::manifest_changes(&old_profile, &new_profile);
+    if changes.is_empty() {
+        say!("No changes to the profile's links or commands.");
+    } else {
+        say!("Changes to the profile's manifest:");
+        for change in &changes {
+            say!("  {change}");
+        }
+    }
+    if let Some(version_change) = version_change(&old_profile, &new_profile) {
+        say!("{version_change}");
+    }
+
+    let new_hash: String = match new_profile.manifest_hash() {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to hash manifest for \"{profile_name}\": {e:#}"); },
+    };
+    let is_loaded: bool = loaded_path.as_ref() == Some(&repo_path);
+    let mut trusted_once: Option<TrustOnce> = None;
+    if meta.trust_scope(&repo_path, &new_hash).is_none() && (was_trusted.is_some() || is_loaded) {
+        let needs_trust: bool = was_trusted.is_none()
+            || changes.iter().any(ManifestChange::is_command)
+            || new_profile.version != old_profile.version
+            || new_profile.allow_outside_home != old_profile.allow_outside_home
+            || new_profile.system_files() != old_profile.system_files()
+            || new_profile.update_command != old_profile.update_command
+            || new_profile.command_env(home_path) != old_profile.command_env(home_path);
+        let scope: TrustScope = was_trusted.unwrap_or_else(|| new_profile.required_trust(Skip::default()));
+        if needs_trust {
+            say!();
+            trusted_once = confirm_trust(&mut meta, home_path, &new_profile, scope, trust);
+        } else {
+            meta.trust_profile(repo_path.clone(), new_hash, new_profile.version.clone(), scope);
+            record_pins(home_path, &new_profile);
+        }
+        if let Err(e) = meta.save_meta(dotulous_path) {
+            error_and_exit!(e, "Failed to save meta: {e:#}");
+        }
+    }
+
+    if is_loaded {
+        say!();
+        if let Some(loaded_profile) = meta.current_profile() {
+            confirm_reload(dotulous_path, home_path, &loaded_profile, Skip::default(), false, yes);
+        }
+        if let Err(e) = operations::reload(dotulous_path, config_path, home_path, settings, Skip::default(), ApplyMode::Apply, on_conflict, false, verbose, trusted_once.as_ref()) {
+            error_and_exit!(e, "Failed to reload profile: {e:#}");
+        }
+    }
+    say!("Updated profile \"{profile_name}\".");
+}
+
+/// User action for checking a profile's requirements are met, finding the profile with the given
+/// `profile_name` (or the currently loaded profile if [`None`]), and where `dotulous_path` is the
+/// user's dotulous folder.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Distro::select`] & [`packages::PackageManager::missing`].
+fn action_doctor(dotulous_path: &Path, profile_name: Option<&str>) {
+    let meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let profile: DotfileProfile = match profile_name {
+        Some(profile_name) => match DotfileProfile::find_profile(dotulous_path, profile_name) {
+            Ok(r) => r,
+            Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+        },
+        None => {
+            let Some(profile) = meta.current_profile() else {
+                error_and_exit!(ErrorCategory::NotFound, "No currently loaded profile was found, and no profile name was given.");
+            };
+            profile
+        }
+    };
+
+    say!("Checking profile: {}", profile.name);
+    if check_packages(&profile, is_trusted_with_commands(&meta, &profile)) {
+        say!("Everything looks good!");
+    }
+}
+
+/// User action for removing dangling symlinks into the dotulous folder at `dotulous_path` (or the
+/// legacy `~/.dotulous` folder), searching `paths` relative to `home_path` up to `depth` folders
+/// deep. The home folder itself is searched if no `paths` are given. Confirms with the user
+/// before removing anything, unless `yes`.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`inspect::dangling_links`].
+fn action_clean(dotulous_path: &Path, home_path: &Path, paths: &[PathBuf], depth: usize, yes: bool) {
+    let roots: Vec<PathBuf> = if paths.is_empty() {
+        vec![home_path.to_path_buf()]
+    } else {
+        paths.iter().map(|path| home_path.join(path)).collect()
+    };
+    let owners: [PathBuf; 2] = [dotulous_path.to_path_buf(), home_path.join(".dotulous")];
+    let links: Vec<PathBuf> = inspect::dangling_links(&roots, &owners, depth);
+    if links.is_empty() {
+        say!("No dangling symlinks were found. Nothing to do.");
+        return
+    }
+
+    say!("Found {} dangling symlink(s):", links.len());
+    for link in &links {
+        say!("  {link:?}");
+    }
+    say!();
+    confirm_changes(&[format!("Remove {} dangling symlink(s)", links.len())], yes);
+
+    let mut failed: usize = 0;
+    for link in &links {
+        if let Err(e) = fs::remove_file(link) {
+            say!("ERROR: Failed to remove {link:?}: {e:#}");
+            failed += 1;
+        }
+    }
+    say!("Removed {} symlink(s), {failed} failed.", links.len() - failed);
+}
+
+/// User action for copying the `system_files` of the profile with `profile_name` to their places
+/// outside of the home folder, or removing them if `remove` is set, where `dotulous_path` is the
+/// user's dotulous folder. If `mode` is [`ApplyMode::DryRun`], nothing is changed.
+///
+/// As this is ran as root, the user is never asked to trust the profile here. It must already be
+/// trusted with it's current manifest, otherwise this exits. Unless `yes` is set, the user is
+/// asked to confirm before any existing system file is overwritten (see [`confirm_changes`]).
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`system::apply`] & [`system::remove`].
+fn action_apply_system(dotulous_path: &Path, profile_name: &str, remove: bool, mode: ApplyMode, yes: bool) {
+    if !platform::is_root() {
+        error_and_exit!(ErrorCategory::Other, "System files can only be applied as root. Run `sudo dotulous --dotulous-dir {dotulous_path:?} apply-system {profile_name}`.");
+    }
+    let meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let mut profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+    };
+    let manifest_hash: String = match profile.manifest_hash() {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to hash manifest for \"{profile_name}\": {e:#}"); },
+    };
+    if !meta.is_trusted(&profile.repo_path, &manifest_hash, TrustScope::Commands) {
+        error_and_exit!(ErrorCategory::TrustDeclined, "Profile \"{profile_name}\" is not trusted. Run `dotulous trust {profile_name}` as your own user first.");
+    }
+    if let Err(e) = profile.resolve_extends() {
+        error_and_exit!(e, "Failed to resolve profiles extended or included by \"{profile_name}\": {e:#}");
+    }
+    if profile.system_files().is_empty() {
+        say!("Profile \"{profile_name}\" has no system files, nothing to do.");
+        return
+    }
+
+    let overwrites: Vec<PathBuf> = system::overwrites(&profile);
+    if !remove && mode == ApplyMode::Apply && !overwrites.is_empty() {
+        let summary: Vec<String> = overwrites.iter().map(|destination| format!("Overwrite {destination:?}, backing it up if it isn't already")).collect();
+        confirm_changes(&summary, yes);
+    }
+
+    print_dry_run_notice(mode);
+    let result: Result<(), DotulousError> = if remove { system::remove(&profile, mode) } else { system::apply(&profile, mode) };
+    if let Err(e) = result {
+        error_and_exit!(e, "Failed to apply system files for \"{profile_name}\": {e:#}");
+    }
+}
+
+/// User action for running `command` with the profile found from `profile_name` temporarily
+/// applied, where `dotulous_path` is the user's dotulous folder.
+/// If the profile is not trusted, it will confirm with the user to trust it or not.
+///
+/// The profile is linked into an [`Overlay`] of `home_path` rather than the real home folder, and
+/// the command is ran with `HOME` (and any `XDG_*_HOME` variables inside of the home folder)
+/// pointing at the overlay. The overlay is removed afterwards, leaving the system as it was.
+/// The profile's commands are **not** ran, as they would affect the real system. The `lock` on
+/// the dotulous folder is let go of before `command` is ran, as it may run dotulous itself.
+///
+/// Exits with the exit code of `command`.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Overlay`] & [`DotfileProfile::link_files`].
+fn action_exec(dotulous_path: &Path, config_path: &Path, home_path: &Path, profile_name: &str, command: &[String], trust: bool, lock: Option<Lock>) {
+    let mut meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let mut profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+    };
+    if let Err(e) = profile.resolve_extends() {
+        error_and_exit!(e, "Failed to resolve profiles extended or included by \"{profile_name}\": {e:#}");
+    }
+    profile.resolve_conditions(&Host::detect());
+    if let Err(e) = profile.resolve_link_modes() {
+        error_and_exit!(e, "Failed to read the folders linked by \"{profile_name}\": {e:#}");
+    }
+    let target_path: PathBuf = profile.target_path(home_path);
+    let Ok(relative_target) = target_path.strip_prefix(home_path) else {
+        error_and_exit!("Profile \"{profile_name}\" targets {target_path:?}, which is outside of the home folder. This is not supported by exec.");
+    };
+    let relative_target: PathBuf = relative_target.to_path_buf();
+    confirm_trust(&mut meta, home_path, &profile, TrustScope::Commands, trust);
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        error_and_exit!(e, "Failed to save meta for \"{profile_name}\": {e:#}");
+    }
+
+    let overlay: Overlay = match Overlay::create(home_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to create overlay: {e:#}"); },
+    };
+    profile.target_root = Some(overlay.path.join(&relative_target));
+    for destination in profile.destinations() {
+        let destination: PathBuf = relative_target.join(destination);
+        if let Err(e) = overlay.prepare_destination(&destination) {
+            let _ = overlay.destroy();
+            error_and_exit!(e, "Failed to prepare overlay for {destination:?}: {e:#}");
+        }
+    }
+    say!("Loading profile: {}", profile.name);
+    // The overlay's mirrored entries are removed by `prepare_destination`, so nothing is backed up,
+    // and the whole overlay is thrown away afterwards so the journal is never needed
+    if let Err(e) = profile.link_files(dotulous_path, config_path, &overlay.path, ApplyMode::Apply, OnConflict::Backup, false, &mut Journal::new()) {
+        let _ = overlay.destroy();
+        error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}");
+    }
+    say!();
+
+    let (program, args) = command.split_first().expect("Clap requires at least one argument.");
+    let mut process = Command::new(program);
+    process.args(args).env("HOME", &overlay.path);
+    for variable in ["XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_STATE_HOME", "XDG_CACHE_HOME"] {
+        if let Some(remapped) = env::var(variable).ok().and_then(|v| overlay.remap(Path::new(&v))) {
+            process.env(variable, remapped);
+        }
+    }
+    // The command may run dotulous itself, which would otherwise find the dotulous folder locked
+    drop(lock);
+    let status = process.status();
+
+    if let Err(e) = overlay.destroy() {
+        say!("WARNING: {e:#}");
+    }
+    match status {
+        Ok(status) => exit(status.code().unwrap_or(-1)),
+        Err(e) => { error_and_exit!(e, "Failed to run {program:?}: {e:#}"); }
+    }
+}
+
+/// User action for listing every backup, or only those of the profile with `profile_name`, where
+/// `dotulous_path` is the user's dotulous folder. With `--json`, the list is printed as JSON
+/// instead.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Backup::list`].
+fn action_list_backups(dotulous_path: &Path, profile_name: Option<&str>) {
+    let backups: Vec<Backup> = Backup::list(dotulous_path, profile_name);
+    if report::is_json() {
+        let backups: Vec<_> = backups.iter().map(|backup| json!({
+            "id": backup.id(),
+            "profile_name": backup.profile_name,
+            "created": backup.created,
+            "files": backup.entries.len(),
+            "size": backup.size(),
+            "restored": backup.restored
+        })).collect();
+        report::emit(&backups);
+        return
+    }
+
+    if backups.is_empty() {
+        say!("No backups were found.");
+        return
+    }
+    say!("Backups:");
+    for backup in &backups {
+        let restored: &str = if backup.restored { " (restored)" } else { "" };
+        say!("  {} - {} file(s), {}{restored}", backup.id(), backup.entries.len(), format_size(backup.size()));
+    }
+}
+
+/// User action for manually restoring the backup with the given `backup_id`, where
+/// `dotulous_path` is the user's dotulous folder. Unless `yes` is set, the user is asked to confirm
+/// before restoring (see [`confirm_changes`]).
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Backup::find`] & [`Backup::restore`].
+fn action_restore(dotulous_path: &Path, backup_id: &str, yes: bool) {
+    let mut backup: Backup = match Backup::find(dotulous_path, backup_id) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to find backup \"{backup_id}\": {e:#}"); },
+    };
+    confirm_changes(&[format!("Restore {} file(s) from backup \"{backup_id}\"", backup.entries.len())], yes);
+    if let Err(e) = backup.restore() {
+        error_and_exit!(e, "Failed to restore backup \"{backup_id}\": {e:#}");
+    }
+}
+
+/// User action for deleting the backups `retention` doesn't keep, restored or not, where
+/// `dotulous_path` is the user's dotulous folder. The loaded profile's backup is always kept.
+/// Deleting backups that were never restored is confirmed with the user first, unless `yes`. If
+/// `mode` is [`ApplyMode::DryRun`], they're only printed.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Backup::prune`].
+fn action_prune_backups(dotulous_path: &Path, retention: &Retention, mode: ApplyMode, yes: bool) {
+    if retention.is_unlimited() {
+        say!("Every backup is kept, as no limits are set. Pass --keep, --max-age-days or --max-size-mb, or set them with `dotulous config set`.");
+        return
+    }
+    print_dry_run_notice(mode);
+    // Sized before anything is deleted
+    let in_use: Option<PathBuf> = current_backup(dotulous_path);
+    let pruned: Vec<Backup> = match Backup::prune(dotulous_path, retention, in_use.as_deref(), true, ApplyMode::DryRun) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to find old backups: {e:#}"); },
+    };
+    let sizes: Vec<u64> = pruned.iter().map(Backup::size).collect();
+    for (backup, size) in pruned.iter().zip(&sizes) {
+        let restored: &str = if backup.restored { "" } else { ", not restored" };
+        say!("  Deleting {} - {} file(s), {}{restored}", backup.id(), backup.entries.len(), format_size(*size));
+    }
+    if mode == ApplyMode::Apply && pruned.iter().any(|backup| !backup.restored) {
+        confirm_changes(&[format!("Delete {} backup(s), including files that were never restored", pruned.len())], yes);
+    }
+    if mode == ApplyMode::Apply {
+        if let Err(e) = Backup::prune(dotulous_path, retention, in_use.as_deref(), true, mode) {
+            error_and_exit!(e, "Failed to delete old backups: {e:#}");
+        }
+    }
+    say!("Deleted {} backup(s), freeing {}.", pruned.len(), format_size(sizes.iter().sum()));
+}
+
+/// Returns the path of the loaded profile's backup inside `dotulous_path`, which must never be
+/// pruned, or [`None`] if there isn't one or the meta can't be read.
+fn current_backup(dotulous_path: &Path) -> Option<PathBuf> {
+    Meta::load_meta(dotulous_path).ok()?.current_backup().map(Path::to_path_buf)
+}
+
+/// Returns `bytes` as a short, readable size such as `12.3 MiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B")
+    }
+    let mut size: f64 = bytes as f64 / 1024.0;
+    let mut unit: usize = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// User action for showing the generation log, newest first, where `dotulous_path` is the
+/// user's dotulous folder. If `limit` is given, only that many generations are shown.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`GenerationLog`].
+fn action_log(dotulous_path: &Path, limit: Option<usize>) {
+    let log: GenerationLog = match GenerationLog::load(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load generation log: {e:#}"); },
+    };
+    if log.generations().is_empty() {
+        say!("No generations have been recorded yet.");
+        return
+    }
+
+    for generation in log.generations().iter().rev().take(limit.unwrap_or(usize::MAX)) {
+        say!("{generation}");
+        if generation.changes.is_empty() {
+            say!("  No changes.");
+        }
+        for change in &generation.changes {
+            say!("  {change}");
+        }
+        say!();
+    }
+}
+
+/// User action for printing the commands recorded in the audit log, newest first, where
+/// `dotulous_path` is the user's dotulous folder. Only commands ran by `profile_name`, containing
+/// `command` and that `failed` are shown when given, up to `limit` of them.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`audit::read`].
+fn action_audit(dotulous_path: &Path, profile_name: Option<&str>, command: Option<&str>, failed: bool, limit: Option<usize>) {
+    let entries: Vec<AuditEntry> = match audit::read(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load audit log: {e:#}"); },
+    };
+    let shown: Vec<&AuditEntry> = entries.iter().rev()
+        .filter(|entry| profile_name.is_none_or(|name| entry.profile_name == name))
+        .filter(|entry| command.is_none_or(|command| entry.command.contains(command)))
+        .filter(|entry| !failed || !entry.succeeded())
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    if shown.is_empty() {
+        say!("No commands have been recorded{}.", if entries.is_empty() { " yet" } else { " matching the filters" });
+        return
+    }
+
+    for entry in shown {
+        say!("{entry}");
+    }
+}
+
+/// User action for listing the command logs, newest first, where `dotulous_path` is the user's
+/// dotulous folder. Only logs of `profile_name` are shown when given, up to `limit` of them. If
+/// `tail` is given, that many of the last lines of each log are printed under it.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`logs::list`].
+fn action_logs(dotulous_path: &Path, profile_name: Option<&str>, limit: Option<usize>, tail: Option<usize>) {
+    let logs: Vec<LogFile> = match logs::list(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not list command logs: {e:#}"); },
+    };
+    if logs.is_empty() {
+        say!("No commands have been logged yet.");
+        return
+    }
+
+    for log in logs.iter().take(limit.unwrap_or(usize::MAX)) {
+        say!("{log}");
+        say!("  {:?}", log.path);
+        let Some(count) = tail else { continue };
+        match log.tail(count) {
+            Ok(lines) => lines.iter().for_each(|line| say!("    {line}")),
+            Err(e) => say!("  WARNING: Could not read log: {e:#}")
+        }
+    }
+}
+
+/// User action for undoing the latest generation, where `dotulous_path` is the user's dotulous
+/// folder. The user is asked to confirm what will be unloaded & loaded first, unless `yes` is set
+/// (see [`confirm_changes`]).
+///
+/// This function will also update the Meta file.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`operations::undo`].
+fn action_undo(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, on_conflict: OnConflict, verbose: bool, yes: bool) {
+    say!("Using home folder: {home_path:?}");
+    let meta: Meta = match Meta::load_meta(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load current meta: {e:#}"); },
+    };
+    let log: GenerationLog = match GenerationLog::load(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not load generation log: {e:#}"); },
+    };
+    let Some(generation) = log.latest() else {
+        error_and_exit!(ErrorCategory::NotFound, "No generations have been recorded yet. Nothing to undo.");
+    };
+    let number: usize = generation.number;
+    let Some(before) = &generation.before else {
+        error_and_exit!("Generation {number} was recorded by an older version of dotulous, and can't be undone.");
+    };
+
+    say!("Undoing {generation}");
+    let mut summary: Vec<String> = Vec::new();
+    if let Some(current_profile) = meta.current_profile() {
+        summary.push(unload_summary(dotulous_path, &current_profile));
+    }
+    if let Some(profile) = &before.profile {
+        summary.push(load_summary(home_path, profile));
+    }
+    if summary.is_empty() {
+        say!("No profile was loaded before or after it. Nothing to do.");
+        return
+    }
+    confirm_changes(&summary, yes);
+
+    match operations::undo(dotulous_path, config_path, home_path, settings, on_conflict, verbose) {
+        Ok(Some(profile)) => say!("Undone, profile \"{}\" is loaded again.", profile.name),
+        Ok(None) => say!("Undone, no profile is loaded."),
+        Err(e) => { error_and_exit!(e, "Failed to undo generation {number}: {e:#}"); }
+    }
+}
+
+/// User action for writing & enabling the systemd user service, which runs dotulous with
+/// `arguments` at every login, where `home_path` is the user's home folder and `paths` are the
+/// dotulous folders the service uses.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`service::install`].
+fn action_service_install(home_path: &Path, paths: &Paths, arguments: &[String]) {
+    match service::install(home_path, paths, arguments) {
+        Ok(path) => say!("Installed & enabled {} at {path:?}, it will run at your next login.", service::UNIT_NAME),
+        Err(e) => { error_and_exit!(e, "Failed to install the service: {e:#}"); }
+    }
+}
+
+/// User action for printing whether the systemd user service is installed & enabled, and how it
+/// last ran, where `home_path` is the user's home folder.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`service::status`].
+fn action_service_status(home_path: &Path) {
+    let status: ServiceStatus = match service::status(home_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to get the service's status: {e:#}"); },
+    };
+    let Some(command) = status.command else {
+        say!("The service isn't installed. Install it with `dotulous service install`.");
+        return
+    };
+    say!("Unit: {:?}", status.unit_path);
+    say!("Runs: {command}");
+    let unknown = |value: String| if value.is_empty() { "unknown".to_string() } else { value };
+    say!("Enabled: {}", unknown(status.enabled));
+    say!("Last result: {}", unknown(status.result));
+}
+
+/// User action for disabling & removing the systemd user service, where `home_path` is the
+/// user's home folder.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`service::remove`].
+fn action_service_remove(home_path: &Path) {
+    match service::remove(home_path) {
+        Ok(true) => say!("Removed {}.", service::UNIT_NAME),
+        Ok(false) => say!("The service isn't installed. Nothing to do."),
+        Err(e) => { error_and_exit!(e, "Failed to remove the service: {e:#}"); }
+    }
+}
+
+/// User action for printing the value of the setting `key` in the user's `settings`, or every
+/// setting that's set if [`None`]. Values are printed as they'd be written in `config.toml`, or
+/// as a JSON object of keys to values with `--json`.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Settings::get`].
+fn action_config_get(settings: &Settings, key: Option<&str>) {
+    let Some(key) = key else {
+        let mut values: BTreeMap<&str, toml::Value> = BTreeMap::new();
+        for key in Settings::KEYS {
+            if let Ok(Some(value)) = settings.get(key) {
+                say!("{key} = {value}");
+                values.insert(key, value);
+            }
+        }
+        report::emit(&values);
+        return
+    };
+    match settings.get(key) {
+        Ok(Some(value)) => {
+            say!("{value}");
+            report::emit(&json!({ key: value }));
+        },
+        Ok(None) => { error_and_exit!(ErrorCategory::NotFound, "Setting \"{key}\" isn't set."); },
+        Err(e) => { error_and_exit!(e, "Failed to get setting \"{key}\": {e:#}"); }
+    }
+}
+
+/// User action for changing the setting `key` of the user's `settings` to `value`, then saving
+/// them to `config.toml` inside `config_path`, the user's config folder.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`Settings::set`] & [`Settings::save`].
+fn action_config_set(config_path: &Path, mut settings: Settings, key: &str, value: &str) {
+    if let Err(e) = settings.set(key, value) {
+        error_and_exit!(e, "Failed to set \"{key}\" to {value}: {e:#}");
+    }
+    if let Err(e) = settings.save(config_path) {
+        error_and_exit!(e, "Failed to save settings: {e:#}");
+    }
+    say!("Set \"{key}\".");
+}
+
+/// User action for updating dotulous to the latest release on GitHub. With `check`, it's only
+/// printed whether there's a newer version. Unless `yes` is set, the user is asked to confirm
+/// before the executable is replaced (see [`confirm_changes`]). If `mode` is
+/// [`ApplyMode::DryRun`], nothing is downloaded or changed.
+///
+/// Can internally fail, however will not return a `Result` but rather simply exit since this is intended to only be
+/// called by the CLI. Instead, look at [`update::latest_release`] & [`update::install`].
+fn action_self_update(check: bool, mode: ApplyMode, yes: bool) {
+    let current: &str = update::CURRENT_VERSION;
+    let release: Release = match update::latest_release() {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Unable to look up the latest release: {e:#}"); }
+    };
+    let latest: &str = release.version();
+    if !release.is_newer() || check {
+        if release.is_newer() {
+            say!("Dotulous {latest} is available (you have {current}), run `dotulous self-update` to update.");
+        } else {
+            say!("Dotulous {current} is the latest version.");
+        }
+        report::emit(&json!({ "current": current, "latest": latest, "newer": release.is_newer(), "updated": false }));
+        return
+    }
+
+    print_dry_run_notice(mode);
+    if mode == ApplyMode::Apply {
+        confirm_changes(&[format!("Update dotulous {current} to {latest}")], yes);
+    }
+    let executable: PathBuf = match update::install(&release, mode) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to update dotulous to {latest}: {e:#}"); }
+    };
+    match mode {
+        ApplyMode::Apply => say!("Updated {executable:?} from {current} to {latest}."),
+        ApplyMode::DryRun => say!("Would update {executable:?} from {current} to {latest}.")
+    }
+    report::emit(&json!({ "current": current, "latest": latest, "newer": true, "updated": mode == ApplyMode::Apply }));
+}
+
+
+// Helpers
+
+/// Returns the [`ApplyMode`] to use from the `--dry-run` flag.
+fn apply_mode(dry_run: bool) -> ApplyMode {
+    if dry_run { ApplyMode::DryRun } else { ApplyMode::Apply }
+}
+
+/// Returns `path` given on the command line as an *absolute* path, relative to the current folder.
+/// Exits if it can't be made absolute.
+fn absolute_path(path: &Path) -> PathBuf {
+    match std::path::absolute(path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!("Invalid path {path:?}: {e}"); }
+    }
+}
+
+/// Prints where the symlink at `path` points to, if it is one.
+fn print_link_target(path: &Path) {
+    if let Ok(target) = fs::read_link(path) {
+        say!("  Links to: {target:?}");
+    }
+}
+
+/// Prints a notice that nothing will be changed if `mode` is [`ApplyMode::DryRun`].
+fn print_dry_run_notice(mode: ApplyMode) {
+    if mode == ApplyMode::DryRun {
+        say!("Dry run, nothing will be changed and no commands will be ran.");
+    }
+}
+
+/// Returns `profile_name` if given, otherwise lets the user pick a profile from a fuzzy-searchable
+/// list of every profile, returning it's folder name. Exits if there are no profiles to pick from,
+/// stdin isn't a terminal, or the user cancels.
+fn pick_profile(dotulous_path: &Path, profile_name: Option<String>) -> String {
+    if let Some(profile_name) = profile_name {
+        return profile_name
+    }
+    require_interactive("No profile name given, and dotulous isn't interactive to ask for one.");
+    let profiles: Vec<ProfileSummary> = match operations::list(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Could not list profiles: {e:#}"); },
+    };
+    if profiles.is_empty() {
+        error_and_exit!(ErrorCategory::NotFound, "No profiles were found.");
+    }
+
+    let items: Vec<String> = profiles.iter()
+        .map(|profile| format!("{}{}", profile.name, summary_flags(profile)))
+        .collect();
+    let selection: Option<usize> = match FuzzySelect::new().with_prompt("Pick a profile").items(&items).default(0).interact_opt() {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!("Failed to pick a profile: {e:#}"); }
+    };
+    let Some(index) = selection else { exit(0) };
+    profiles[index].path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// Lets the user pick files from their home folder at `home_path` to adopt, returning them relative
+/// to it. The hidden files in the home folder are offered, along with everything in `.config`,
+/// skipping dotulous's own folders and anything that's already a symlink.
+fn pick_home_files(home_path: &Path) -> Vec<PathBuf> {
+    require_interactive("No paths to adopt given, and dotulous isn't interactive to ask for them.");
+    let skipped: [&Path; 5] = [Path::new(".config"), Path::new(".local"), Path::new(".cache"), Path::new(".dotulous"), Path::new(".config/dotulous")];
+    let mut candidates: Vec<PathBuf> = [Path::new(""), Path::new(".config")].iter()
+        .filter_map(|folder| fs::read_dir(home_path.join(folder)).ok().map(|entries| (folder, entries)))
+        .flat_map(|(folder, entries)| entries.filter_map(Result::ok).map(move |e| folder.join(e.file_name())))
+        .filter(|path| path.starts_with(".config") || path.to_string_lossy().starts_with('.'))
+        .filter(|path| !skipped.contains(&path.as_path()) && !home_path.join(path).is_symlink())
+        .collect();
+    candidates.sort();
+    if candidates.is_empty() {
+        error_and_exit!(ErrorCategory::NotFound, "No files were found to adopt.");
+    }
+
+    let items: Vec<String> = candidates.iter().map(|path| path.to_string_lossy().to_string()).collect();
+    let selection: Option<Vec<usize>> = match MultiSelect::new().with_prompt("Pick files to adopt").items(&items).interact_opt() {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!("Failed to pick files: {e:#}"); }
+    };
+    let Some(indexes) = selection else { exit(0) };
+    indexes.into_iter().map(|index| candidates[index].clone()).collect()
+}
+
+/// Returns the flags shown after a profile's name when listing it, e.g. `" (loaded, trusted)"`, or
+/// an empty string if it has none.
+fn summary_flags(profile: &ProfileSummary) -> String {
+    let mut flags: Vec<&str> = Vec::new();
+    if profile.loaded { flags.push("loaded") }
+    match profile.trust_scope {
+        Some(TrustScope::Files) => flags.push("files trusted"),
+        Some(TrustScope::Commands) => flags.push("trusted"),
+        None => {}
+    }
+    if flags.is_empty() { String::new() } else { format!(" ({})", flags.join(", ")) }
+}
+
+/// Opens the file at `path` in the user's editor, waiting for it to exit. The editor is taken from
+/// `$VISUAL` or `$EDITOR` and may include arguments (e.g. `code --wait`), falling back to `vi` (or
+/// `notepad` on Windows). Exits if the editor fails to run.
+fn open_editor(path: &Path) {
+    let fallback: &str = if cfg!(windows) { "notepad" } else { "vi" };
+    let editor: String = ["VISUAL", "EDITOR"].iter()
+        .filter_map(|variable| env::var(variable).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| fallback.to_string());
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        error_and_exit!(ErrorCategory::NotFound, "No editor was found, set $EDITOR to the editor you'd like to use.");
+    };
+    match Command::new(program).args(words).arg(path).status() {
+        Ok(status) if status.success() => {},
+        Ok(status) => { error_and_exit!("Editor \"{editor}\" exited unsuccessfully ({status})."); },
+        Err(e) => { error_and_exit!(e, "Failed to run editor \"{editor}\": {e:#}"); }
+    }
+}
+
+/// Asks the user the given yes/no `question` on stdout, returning `true` only if they answer `y`.
+/// Exits if stdin could not be read from, or if dotulous isn't interactive.
+fn ask_yes_no(question: &str) -> bool {
+    require_interactive(&format!("Can't ask \"{question}\" as dotulous isn't interactive."));
+    say!("{question} (y/N)");
+    let mut input: String = String::new();
+    if let Err(e) = io::stdin().read_line(&mut input) {
+        error_and_exit!(e, "Failed to read from stdin: {e:#}");
+    }
+    input.trim().to_lowercase() == "y"
+}
+
+/// Returns the [`OnConflict`] policy to load profiles with, from `--on-conflict` or otherwise the
+/// `default` in the user's settings. If neither is given (or the default is to prompt), the user is
+/// asked about each conflict, unless `yes` is set or dotulous isn't interactive, in which case
+/// anything in the way is backed up.
+///
+/// Exits with [`EXIT_NEEDS_INPUT`] if asked to prompt with `--on-conflict`, but dotulous isn't
+/// interactive.
+fn conflict_policy(on_conflict: Option<OnConflict>, default: Option<OnConflict>, yes: bool) -> OnConflict {
+    match on_conflict {
+        Some(OnConflict::Prompt) => {
+            require_interactive("Can't ask what to do with files in the way as dotulous isn't interactive. Run again with another --on-conflict.");
+            OnConflict::Prompt
+        },
+        Some(on_conflict) => on_conflict,
+        None => match default.filter(|default| *default != OnConflict::Prompt) {
+            Some(default) => default,
+            None if yes || !platform::is_interactive() => OnConflict::Backup,
+            None => OnConflict::Prompt
+        }
+    }
+}
+
+/// Exits with [`EXIT_NEEDS_INPUT`], printing `message`, if dotulous isn't interactive (see
+/// [`platform::is_interactive`]). Called before asking the user anything.
+fn require_interactive(message: &str) {
+    if !platform::is_interactive() {
+        eprintln!("ERROR: {message}");
+        exit(EXIT_NEEDS_INPUT);
+    }
+}
+
+/// Shows the user a `summary` of the changes about to be made to their system, one per line, and
+/// asks them to confirm, exiting if they don't. Used before anything that removes or replaces
+/// files, such as unloading a profile.
+///
+/// If `yes` is set (from `--yes`), the summary is shown without asking. If dotulous isn't
+/// interactive, this exits rather than waiting for an answer, so scripts must pass `--yes`.
+fn confirm_changes(summary: &[String], yes: bool) {
+    say!("This will:");
+    for line in summary {
+        say!("  - {line}");
+    }
+    if yes {
+        return
+    }
+    require_interactive("Can't ask for confirmation as dotulous isn't interactive. Run again with --yes to continue anyway.");
+    if !ask_yes_no("Continue?") {
+        say!("Quitting...");
+        exit(EXIT_FAILURE);
+    }
+}
+
+/// Returns a line for [`confirm_changes`] saying what unloading the loaded `profile` will do, where
+/// `dotulous_path` is the user's dotulous folder. Files are counted from the [`LoadState`] if
+/// there is one, otherwise from the profile's destinations. A profile with no destinations, such
+/// as one skipping it's files, counts none either way.
+fn unload_summary(dotulous_path: &Path, profile: &DotfileProfile) -> String {
+    let files: usize = match LoadState::load(dotulous_path) {
+        _ if profile.destinations().is_empty() => 0,
+        Ok(Some(state)) => state.placed.len(),
+        _ => profile.destinations().len()
+    };
+    let commands: usize = profile.hooks().get("removal_commands").map_or(0, Vec::len);
+    format!("Unload \"{}\", removing {files} file(s) and running {commands} command(s)", profile.name)
+}
+
+/// Returns a line for [`confirm_changes`] saying what loading `profile` will do, counting only the
+/// entries that match this machine. Existing files in the way are counted separately, as they're
+/// backed up rather than removed.
+fn load_summary(home_path: &Path, profile: &DotfileProfile) -> String {
+    let mut profile: DotfileProfile = profile.clone();
+    profile.resolve_conditions(&Host::detect());
+    // Only a summary, so a folder that can't be read is just counted once
+    let _ = profile.resolve_link_modes();
+    let target_path: PathBuf = profile.target_path(home_path);
+    let destinations: Vec<PathBuf> = profile.destinations();
+    let existing: usize = destinations.iter()
+        .filter(|destination| target_path.join(paths::expand_path(destination, home_path)).symlink_metadata().is_ok_and(|m| !m.is_symlink()))
+        .count();
+    let hooks = profile.hooks();
+    let commands: usize = ["pre_commands", "post_commands"].iter().filter_map(|hook| hooks.get(*hook)).map(Vec::len).sum();
+    let mut line: String = format!("Load \"{}\", linking {} file(s) and running {commands} command(s)", profile.name, destinations.len());
+    if existing > 0 {
+        line.push_str(&format!(", backing up {existing} existing file(s)"));
+    }
+    line
+}
+
+/// Returns a line for [`confirm_changes`] saying `action` will be done to only the entries of
+/// `picked`, as returned by [`picked_entries`].
+fn partial_summary(action: &str, picked: &DotfileProfile) -> String {
+    format!("{action} {} file(s) of \"{}\", without running any commands", picked.destinations().len(), picked.name)
+}
+
+/// Returns the part of `profile` picked by `filter` that matches this machine, see
+/// [`DotfileProfile::partition`]. Exits if nothing is picked, as there'd be nothing to do.
+fn picked_entries(profile: &DotfileProfile, filter: &EntryFilter) -> DotfileProfile {
+    let mut profile: DotfileProfile = profile.clone();
+    profile.resolve_conditions(&Host::detect());
+    // Only a summary, so a folder that can't be read is just counted once
+    let _ = profile.resolve_link_modes();
+    let (picked, _): (DotfileProfile, DotfileProfile) = profile.partition(filter);
+    if picked.destinations().is_empty() {
+        let profile_name: &str = &profile.name;
+        error_and_exit!(ErrorCategory::NotFound, "Nothing in \"{profile_name}\" matches the given --only & --except globs.");
+    }
+    picked
+}
+
+/// Returns the filter for the `--only` & `--except` globs given to `load`, `unload` or `reload`.
+/// Exits if any glob is invalid.
+fn entry_filter(only: &[String], except: &[String]) -> EntryFilter {
+    match EntryFilter::new(only, except) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Invalid --only or --except glob: {e:#}"); }
+    }
+}
+
+/// Asks the user to confirm reloading the loaded `profile` from it's manifest with the tags it was
+/// loaded with, leaving out what `skip` does, unless `yes` is set. Unless `full` is set, only what
+/// changed is counted (see [`operations::plan_reload`]), and nothing is asked if nothing changed.
+/// See [`confirm_changes`].
+fn confirm_reload(dotulous_path: &Path, home_path: &Path, profile: &DotfileProfile, skip: Skip, full: bool, yes: bool) {
+    let plan: ReloadPlan = match operations::plan_reload(dotulous_path, home_path, skip, full) {
+        Ok(r) => r,
+        Err(_) => return confirm_changes(&[unload_summary(dotulous_path, &profile.skipping(skip)), format!("Load \"{}\" again", profile.name)], yes)
+    };
+    if plan.full {
+        return confirm_changes(&[unload_summary(dotulous_path, &plan.unload), load_summary(home_path, &plan.load)], yes)
+    }
+    if plan.is_empty() {
+        return
+    }
+    let commands: usize = plan.unload.commands(&Hook::Removal).map_or(0, <[_]>::len);
+    let unload_line: String = format!("Unload {} changed file(s) of \"{}\", running {commands} command(s)", plan.unload.destinations().len(), profile.name);
+    confirm_changes(&[unload_line, load_summary(home_path, &plan.load)], yes);
+}
+
+/// Checks `profile` is trusted in the given `meta` at least as far as `scope`, and if not,
+/// confirms with the user to trust it that far or not (see [`ask_trust`]). If the user trusts it,
+/// it is added to `meta`'s trusted profiles, otherwise this exits. If `trust` is set (from
+/// `--trust`), it's trusted without asking. Once trusted, the commits it's at are recorded in it's
+/// `dotulous.lock`, see [`record_pins`].
+///
+/// Only trusting the profile's files (see [`TrustScope::Files`]) leaves out it's commands, so the
+/// user is told none of them will be ran rather than being shown them.
+///
+/// The user can also trust the profile once, which is never saved (see [`Meta::trust_once`]). This
+/// is then returned, to hand to any operation that loads the meta again, such as
+/// [`operations::load`].
+///
+/// The caller is responsible for saving `meta` afterwards.
+fn confirm_trust(meta: &mut Meta, home_path: &Path, profile: &DotfileProfile, scope: TrustScope, trust: bool) -> Option<TrustOnce> {
+    let profile_name: &str = &profile.name;
+    let manifest_hash: String = match profile.manifest_hash() {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to hash manifest for \"{profile_name}\": {e:#}"); },
+    };
+    if meta.is_trusted(&profile.repo_path, &manifest_hash, scope) {
+        return None
+    }
+
+    if meta.trust_scope(&profile.repo_path, &manifest_hash).is_some() {
+        say!("WARNING: Profile is only trusted to link it's files, but this runs it's commands.");
+    } else if meta.is_path_trusted(&profile.repo_path) {
+        say!("WARNING: Profile's manifest has changed since it was trusted.");
+        print_trusted_version(meta, profile);
+    } else {
+        print_introduction(profile);
+        say!("WARNING: Profile has not been marked as trusted.");
+    }
+    match scope {
+        TrustScope::Files => say!("Profile \"{profile_name}\" links {} file(s). None of it's commands will be ran.", profile.destinations().len()),
+        TrustScope::Commands => print_commands(profile)
+    }
+    say!();
+    if profile.allow_outside_home {
+        say!("WARNING: Profile is allowed to put files outside of your home folder.");
+    }
+    if scope == TrustScope::Commands && !profile.system_files().is_empty() {
+        say!("WARNING: Profile has system files, which `sudo dotulous apply-system` copies to:");
+        for destination in profile.system_files().values() {
+            say!("    {destination:?}");
+        }
+    }
+    match scope {
+        TrustScope::Files => {
+            say!("Please verify the contents of the profile! Remember that profiles can install ANY ARBITRARY FILES, such as shell configs that run every time you open a terminal.");
+        },
+        TrustScope::Commands => {
+            say!("Please verify the contents of the profile! Remember that profiles can run ANY ARBITRARY COMMANDS on your system, and can install ANY ARBITRARY FILES.");
+            say!("You're essentially going to be running random code off of the internet, so be careful!");
+        }
+    }
+    say!();
+    let answer: TrustAnswer = if trust {
+        TrustAnswer::Always
+    } else {
+        require_interactive("Can't ask to trust the profile as dotulous isn't interactive. Run again with --trust to trust it anyway.");
+        ask_trust(profile, scope)
+    };
+    let what: &str = match scope {
+        TrustScope::Files => " to link it's files only",
+        TrustScope::Commands => ""
+    };
+    match answer {
+        TrustAnswer::Always => {
+            meta.trust_profile(profile.repo_path.clone(), manifest_hash, profile.version.clone(), scope);
+            say!("Trusting profile {}{what}", profile.name);
+            record_pins(home_path, profile);
+            None
+        },
+        TrustAnswer::Once => {
+            let once: TrustOnce = TrustOnce { path: profile.repo_path.clone(), manifest_hash, scope };
+            meta.trust_once(once.clone());
+            say!("Trusting profile {}{what} this once, you'll be asked again next time.", profile.name);
+            Some(once)
+        },
+        TrustAnswer::Decline => {
+            say!("Quitting...");
+            exit(ErrorCategory::TrustDeclined.exit_code());
+        }
+    }
+}
+
+/// How the user answered [`ask_trust`].
+enum TrustAnswer {
+    /// Trust the profile from now on.
+    Always,
+    /// Trust the profile for this time only, see [`Meta::trust_once`].
+    Once,
+    /// Don't trust the profile.
+    Decline
+}
+
+/// Asks the user if they trust `profile` as far as `scope`, until they give an answer. Besides yes
+/// & no, they can trust it once, or view it's manifest & hooks in their pager (see
+/// [`view_profile`]) before being asked again. Declines if stdin is closed.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+fn ask_trust(profile: &DotfileProfile, scope: TrustScope) -> TrustAnswer {
+    let question: &str = match scope {
+        TrustScope::Files => "Do you trust this profile to link it's files?",
+        TrustScope::Commands => "Do you trust this profile to link it's files and run it's commands?"
+    };
+    loop {
+        say!("{question} [y]es, [n]o, only [o]nce, or [v]iew the manifest & hooks (y/N/o/v)");
+        let mut input: String = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) => return TrustAnswer::Decline,
+            Ok(_) => {},
+            Err(e) => { error_and_exit!(e, "Failed to read from stdin: {e:#}"); }
+        }
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => return TrustAnswer::Always,
+            "" | "n" | "no" => return TrustAnswer::Decline,
+            "o" | "once" => return TrustAnswer::Once,
+            "v" | "view" => view_profile(profile),
+            _ => say!("Please answer y, n, o or v.")
+        }
+    }
+}
+
+/// Shows `profile`'s manifest followed by every command it runs in the user's pager, waiting for
+/// it to exit. The pager is taken from `$PAGER` and may include arguments (e.g. `less -R`),
+/// falling back to `less` (or `more` on Windows). If the pager can't be ran, everything is printed
+/// instead.
+fn view_profile(profile: &DotfileProfile) {
+    let mut text: String = format!("{}:\n", profile.manifest_path.display());
+    match fs::read_to_string(&profile.manifest_path) {
+        Ok(manifest) => text.push_str(&manifest),
+        Err(e) => text.push_str(&format!("(Failed to read the manifest: {e:#})\n")),
+    }
+    text.push_str("\nCommands:\n");
+    text.push_str(&format_commands(profile));
+
+    let fallback: &str = if cfg!(windows) { "more" } else { "less" };
+    let pager: String = env::var("PAGER").ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| fallback.to_string());
+    let mut words = pager.split_whitespace();
+    let shown: bool = words.next().is_some_and(|program| {
+        let Ok(mut child) = Command::new(program).args(words).stdin(Stdio::piped()).spawn() else { return false };
+        // The pager may quit before reading everything, which isn't a problem
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        child.wait().is_ok()
+    });
+    if !shown {
+        say!("{text}");
+    }
+}
+
+/// Records the commits `profile` and it's `pinned_repos` are at in it's `dotulous.lock`, for
+/// loading with `--frozen` later. Failing to do so only warns, as the profile is still trusted.
+/// See [`Pins::record`].
+fn record_pins(home_path: &Path, profile: &DotfileProfile) {
+    if let Err(e) = Pins::record(profile, home_path) {
+        say!("WARNING: Failed to pin the commits of \"{}\": {e:#}", profile.name);
+    }
+}
+
+/// Prints each of `profile`'s command lists, so the user can see what it will run before trusting
+/// it.
+fn print_commands(profile: &DotfileProfile) {
+    say!("Profile \"{}\" runs the following commands:", profile.name);
+    say!("{}", format_commands(profile).trim_end());
+}
+
+/// Returns every command `profile` runs, listed under the hook they're ran on, one per line.
+fn format_commands(profile: &DotfileProfile) -> String {
+    let mut text: String = String::new();
+    for (hook, commands) in profile.hooks() {
+        text.push_str(&format!("  {hook}:\n"));
+        if commands.is_empty() {
+            text.push_str("    (none)\n");
+        }
+        for command in commands {
+            text.push_str(&format!("    {command}\n"));
+        }
+    }
+    text
+}
+
+/// The most lines of a profile's `README.md` shown by [`print_introduction`].
+const README_PREVIEW_LINES: usize = 30;
+
+/// Prints `profile`'s `description`, it's other details (see [`print_details`]) and the start of
+/// the `README.md` in it's folder, if it has them, so the user knows what a profile they've never
+/// trusted before is for.
+fn print_introduction(profile: &DotfileProfile) {
+    if let Some(description) = &profile.description {
+        say!("Profile \"{}\": {description}", profile.name);
+    }
+    if print_details(profile) || profile.description.is_some() {
+        say!();
+    }
+    let readme_path: PathBuf = profile.repo_path.join("README.md");
+    let Ok(readme) = fs::read_to_string(&readme_path) else { return };
+    say!("README.md:");
+    let lines: Vec<&str> = readme.lines().collect();
+    for line in lines.iter().take(README_PREVIEW_LINES) {
+        say!("  {line}");
+    }
+    if lines.len() > README_PREVIEW_LINES {
+        say!("  ... {} more lines in {readme_path:?}", lines.len() - README_PREVIEW_LINES);
+    }
+    say!();
+}
+
+/// Prints `profile`'s `version`, `author` & `homepage`, each on their own line, returning if it
+/// had any of them.
+fn print_details(profile: &DotfileProfile) -> bool {
+    let details: [(&str, &Option<String>); 3] = [("Version", &profile.version), ("Author", &profile.author), ("Homepage", &profile.homepage)];
+    let mut printed: bool = false;
+    for (label, value) in details {
+        if let Some(value) = value {
+            say!("  {label}: {value}");
+            printed = true;
+        }
+    }
+    printed
+}
+
+/// Prints the version `profile` was trusted at in `meta` and the version it's at now, if they
+/// differ, so the user knows which version they're trusting again.
+fn print_trusted_version(meta: &Meta, profile: &DotfileProfile) {
+    let Some(version) = &profile.version else { return };
+    let trusted_version: Option<&str> = meta.trusted_version(&profile.repo_path);
+    if trusted_version != Some(version.as_str()) {
+        say!("Profile was trusted at version {}, and is now at version {version}.", trusted_version.unwrap_or("(none)"));
+    }
+}
+
+/// Returns a line describing how the `version` of a profile changed from `old` to `new`, such as
+/// `Profile upgraded 1.2 -> 1.3`, or [`None`] if it didn't. See [`inspect::compare_versions`].
+fn version_change(old: &DotfileProfile, new: &DotfileProfile) -> Option<String> {
+    match (&old.version, &new.version) {
+        (Some(old), Some(new)) => match inspect::compare_versions(old, new) {
+            Ordering::Less => Some(format!("Profile upgraded {old} -> {new}")),
+            Ordering::Greater => Some(format!("Profile downgraded {old} -> {new}")),
+            Ordering::Equal if old != new => Some(format!("Profile version changed {old} -> {new}")),
+            Ordering::Equal => None
+        },
+        (None, Some(new)) => Some(format!("Profile is now at version {new}")),
+        (Some(old), None) => Some(format!("Profile no longer has a version (was {old})")),
+        (None, None) => None
+    }
+}
+
+/// Re-reads the profile at `repo_path` from it's manifest and confirms the user still trusts it
+/// with [`confirm_trust`] as far as is needed to load it leaving out what `skip` does (see
+/// [`DotfileProfile::required_trust`]), saving `meta`. Used before re-loading a profile from it's
+/// manifest, as it may have changed since it was trusted.
+///
+/// If the manifest can't be read nothing is done, leaving the error to the action itself.
+/// Returns the profile if the user only trusted it this once, see [`confirm_trust`].
+fn reconfirm_trust(dotulous_path: &Path, home_path: &Path, meta: &mut Meta, repo_path: &Path, skip: Skip, trust: bool) -> Option<TrustOnce> {
+    let Ok(profile) = DotfileProfile::from_manifest(repo_path) else { return None };
+    let trusted_once: Option<TrustOnce> = confirm_trust(meta, home_path, &profile, profile.required_trust(skip), trust);
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        let profile_name: &str = &profile.name;
+        error_and_exit!(e, "Failed to save meta for \"{profile_name}\": {e:#}");
+    }
+    trusted_once
+}
+
+/// Finds the profile with the given `profile_name` inside `dotulous_path`, returning it along with
+/// it's `secrets`. Exits if the profile can't be found or has no secrets configuration.
+fn find_secrets(dotulous_path: &Path, profile_name: &str) -> (DotfileProfile, SecretConfig) {
+    let profile: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, profile_name) {
+        Ok(r) => r,
+        Err(e) => { error_and_exit!(e, "Failed to load profile \"{profile_name}\": {e:#}"); },
+    };
+    let Some(secrets) = profile.secrets.clone() else {
+        error_and_exit!(ErrorCategory::NotFound, "Profile \"{profile_name}\" has no \"secrets\" in it's manifest.");
+    };
+    (profile, secrets)
+}
+
+/// Warns about every destination of `profile` that another trusted profile inside `dotulous_path`
+/// also puts something at, as switching between them replaces each other's files. See
+/// [`DestinationIndex`]. Nothing is printed if the other profiles can't be scanned.
+fn warn_shared_destinations(dotulous_path: &Path, home_path: &Path, meta: &Meta, profile: &DotfileProfile) {
+    let Ok(index) = DestinationIndex::build(dotulous_path, home_path, meta) else { return };
+    let shared: Vec<SharedDestination> = index.shared_with(profile, home_path);
+    let mut profiles: Vec<&str> = shared.iter().map(|shared| shared.profile.as_str()).collect();
+    profiles.dedup();
+    for other in profiles {
+        say!("WARNING: Profile \"{other}\" also puts files at these destinations, switching between them will replace each other's files:");
+        for shared in shared.iter().filter(|shared| shared.profile == other) {
+            if shared.destination == shared.other_destination {
+                say!("  {:?}", shared.destination);
+            } else {
+                say!("  {:?} (overlaps {:?})", shared.destination, shared.other_destination);
+            }
+        }
+    }
+}
+
+/// Returns if `profile` is trusted in `meta` to run it's commands with the current hash of it's
+/// manifest. If the manifest can't be read, it isn't.
+fn is_trusted_with_commands(meta: &Meta, profile: &DotfileProfile) -> bool {
+    profile.manifest_hash().is_ok_and(|hash| meta.is_trusted(&profile.repo_path, &hash, TrustScope::Commands))
+}
+
+/// Checks the packages `profile` declares for the current distro are installed, offering to
+/// install any missing ones with the native package manager after confirming with the user. They
+/// are only offered to be installed if `may_install`, which should only be set once the profile
+/// is trusted to run it's commands (see [`TrustScope::Commands`]).
+///
+/// Returns `true` if all packages are installed (or none are declared) by the time this returns.
+///
+/// **Note:** This function prints to stdout, as it is only called by the user in the CLI.
+fn check_packages(profile: &DotfileProfile, may_install: bool) -> bool {
+    if profile.packages.is_empty() {
+        return true
+    }
+    let Some(distro) = Distro::detect() else {
+        say!("WARNING: Could not detect your distro, skipping package checks.");
+        return false
+    };
+    let Some(packages) = distro.select(&profile.packages) else {
+        say!("WARNING: Profile does not list any packages for your distro ({}).", distro.ids.join(", "));
+        return false
+    };
+    let Some(manager) = distro.package_manager() else {
+        say!("WARNING: Unknown package manager for your distro ({}), skipping package checks.", distro.ids.join(", "));
+        return false
+    };
+
+    let missing: Vec<String> = manager.missing(packages);
+    if missing.is_empty() {
+        return true
+    }
+    say!("WARNING: Profile requires packages that are not installed:");
+    for package in &missing {
+        say!("  {package}");
+    }
+    say!();
+    if !may_install {
+        say!("Not installing packages, as the profile isn't trusted to run it's commands.");
+        say!();
+        return false
+    }
+    if !platform::is_interactive() {
+        say!("Not installing packages, as dotulous isn't interactive.");
+        say!();
+        return false
+    }
+    if !ask_yes_no(&format!("Install them with \"sudo {} {}\"?", manager.install_command().join(" "), missing.join(" "))) {
+        say!("Not installing packages.");
+        say!();
+        return false
+    }
+
+    if let Err(e) = manager.install(&missing) {
+        say!("ERROR: {e:#}");
+        say!();
+        return false
     }
+    say!();
+    true
 }
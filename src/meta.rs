@@ -1,16 +1,17 @@
 use std::{fs, path::{Path, PathBuf}};
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::{error::DotulousError, profile::DotfileProfile};
+use crate::{error::DotulousError, filter::TagFilter, profile::DotfileProfile};
 
 /// The meta file is dotulous's main way of keeping track of what profile is loaded, where it is,
 /// and what other profiles it has already trusted.
-/// This file should be stored in the user's `.dotulous` folder, as `meta.json`.
+/// This file should be stored in the user's dotulous folder, as `meta.json`.
 ///
 /// **This file should never be modified by a normal user.**
 ///
-/// Loading the meta should be done with [`Meta::load_meta`], providing the `.dotulous` path to it.
+/// Loading the meta should be done with [`Meta::load_meta`], providing the dotulous path to it.
 ///
 /// ### Currently Loaded Profile 
 /// To update the currently loaded profile, use 
@@ -20,11 +21,28 @@ use crate::{error::DotulousError, profile::DotfileProfile};
 /// To find and read the currently loaded profile use [`Meta::current_profile`]. This will return
 /// the currently loaded profile, *at the time of loading*. 
 ///
+/// The backup of any files that were in the way of the current profile is tracked with
+/// [`Meta::set_current_backup`] & [`Meta::take_current_backup`], and the tags it was loaded with
+/// by [`Meta::set_current_tags`].
+///
 /// ### Trusted Profiles 
 /// To trust a profile you can call [`Meta::trust_profile`] - **Only do this with the confirmation
 /// of the user!**.
 ///
-/// To check if a given profile's path is trusted, use [`Meta::is_trusted`]
+/// Profiles are trusted along with the hash of their manifest (see
+/// [`DotfileProfile::manifest_hash`]), so a trusted profile whose manifest is later changed (e.g.
+/// by a `git pull`) is no longer trusted. To check if a given profile is trusted, use
+/// [`Meta::is_trusted`]. Every trusted profile, along with when it was trusted and any note the
+/// user left, can be listed with [`Meta::trusted_profiles`] or looked up with
+/// [`Meta::trusted_profile`], and trust is revoked with [`Meta::untrust_profile`]. A profile can
+/// also be trusted by a single meta with [`Meta::trust_once`], which is never saved.
+/// Profiles can be trusted to only link their files rather than also run their commands, see
+/// [`TrustScope`].
+///
+/// ### Ephemeral Loads
+/// When a profile is loaded temporarily, call [`Meta::set_ephemeral`] with the profile that was
+/// loaded beforehand. [`Meta::take_ephemeral`] will then hand it back when the ephemeral load is
+/// cleared, so it can be restored.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Meta {
     /// Stub field, present in the serialized JSON to warn the user to not touch this file.
@@ -33,9 +51,21 @@ pub struct Meta {
     /// The currently in-use profile data.
     #[serde(default)]
     current_profile: Option<DotfileProfile>,
-    /// A list of trusted profile paths.
+    /// A list of trusted profiles.
+    #[serde(default)]
+    trusted_profiles: Vec<TrustedProfile>,
+    /// If the currently loaded profile was loaded ephemerally, the details needed to undo it.
     #[serde(default)]
-    trusted_profiles: Vec<PathBuf>
+    ephemeral: Option<EphemeralLoad>,
+    /// The *absolute* path to the backup of files that were in the way of the current profile.
+    #[serde(default)]
+    current_backup: Option<PathBuf>,
+    /// The tags the current profile was loaded with, so reloading picks the same entries.
+    #[serde(default, skip_serializing_if = "TagFilter::is_empty")]
+    current_tags: TagFilter,
+    /// Profiles trusted with [`Meta::trust_once`], which are never saved.
+    #[serde(skip)]
+    trusted_once: Vec<TrustOnce>
 }
 impl Meta {
     /// Creates a new Meta object, with empty values.
@@ -46,7 +76,11 @@ impl Meta {
         Self {
             do_not_touch_this_file: "Don't touch this file! You'll break something!".to_string(),
             current_profile: None,
-            trusted_profiles: Vec::new()
+            trusted_profiles: Vec::new(),
+            ephemeral: None,
+            current_backup: None,
+            current_tags: TagFilter::default(),
+            trusted_once: Vec::new()
         }
     }
 
@@ -56,51 +90,314 @@ impl Meta {
     /// for [`Err`] variants. 
     pub fn save_meta(&self, dotulous_path: &Path) -> Result<(), DotulousError> {
         let path: PathBuf = dotulous_path.join(Path::new("meta.json"));
-        let Ok(serialized) = serde_json::to_string_pretty(self) else {
-            return Err(DotulousError::FailedSerializeMeta)
-        };
-        if fs::write(path, serialized).is_err() {
-            return Err(DotulousError::FailedSaveMeta)
-        } 
+        let serialized: String = serde_json::to_string_pretty(self).map_err(DotulousError::FailedSerializeMeta)?;
+        fs::write(path, serialized).map_err(DotulousError::FailedSaveMeta)?;
         Ok(())
     }
 
     /// Load the current meta file from disk, using `meta.json` inside of the given `dotulous_path`.
-    /// If the meta file cannot be found, [`Err`] with [`DotulousError::MetaNotFound`] is returned.
+    /// If the meta file cannot be found, [`Err`] with [`DotulousError::MetaNotFound`] is returned,
+    /// or if it can't be read, [`Err`] with [`DotulousError::FailedReadMeta`].
     pub fn load_meta(dotulous_path: &Path) -> Result<Meta, DotulousError> {
         let path: PathBuf = dotulous_path.join(Path::new("meta.json"));
         if !path.exists() {
             return Err(DotulousError::MetaNotFound)
         }
 
-        let contents: String = fs::read_to_string(path).expect("Can't read meta file.");
-        match serde_json::from_str::<Self>(&contents) {
-            Ok(r) => Ok(r),
-            Err(_) => Err(DotulousError::FailedDeserializeMeta),
-        }
+        let contents: String = fs::read_to_string(path).map_err(DotulousError::FailedReadMeta)?;
+        serde_json::from_str::<Self>(&contents).map_err(DotulousError::FailedDeserializeMeta)
     }
 
-    /// Set the currently loaded profile inside the manifest, changing `current_profile` and
-    /// `profile_path`.
-    pub fn set_current_profile(&mut self, profile: &DotfileProfile) {
-        self.current_profile = Some(profile.clone());
+    /// Set the currently loaded profile inside the manifest, changing `current_profile`.
+    ///
+    /// The stored profile has it's `target_root` resolved against `home_path`, so the meta tracks
+    /// exactly where the profile was loaded to. See [`DotfileProfile::resolve_target_root`].
+    pub fn set_current_profile(&mut self, profile: &DotfileProfile, home_path: &Path) {
+        let mut profile: DotfileProfile = profile.clone();
+        profile.resolve_target_root(home_path);
+        self.current_profile = Some(profile);
     }
-    /// Clear's the current profile, making `current_profile` and `profile_path` to be [`None`].
+    /// Clear's the current profile, making `current_profile` to be [`None`], along with the tags it
+    /// was loaded with.
     pub fn empty_current_profile(&mut self) {
         self.current_profile = None;
+        self.current_tags = TagFilter::default();
     }
     /// Returns the current profile, or [`None`] if no profile is currently loaded.
     pub fn current_profile(&self) -> Option<DotfileProfile> {
         self.current_profile.clone()
     }
 
-    /// Trusts the profile path provided, adding it to `trusted_profiles`.
-    pub fn trust_profile(&mut self, path: PathBuf) {
-        self.trusted_profiles.push(path);
+    /// Marks the currently loaded profile as ephemeral, remembering `previous_profile` as the profile
+    /// to restore once it is cleared.
+    ///
+    /// If the current profile is already ephemeral, the originally remembered profile is kept, so
+    /// chaining ephemeral loads still restores the last permanent profile.
+    pub fn set_ephemeral(&mut self, previous_profile: Option<DotfileProfile>) {
+        if self.ephemeral.is_none() {
+            self.ephemeral = Some(EphemeralLoad { previous_profile });
+        }
+    }
+    /// Returns the ephemeral load details, or [`None`] if the current profile is not ephemeral.
+    pub fn ephemeral(&self) -> Option<&EphemeralLoad> {
+        self.ephemeral.as_ref()
     }
-    /// Checks if the profile path provided is trusted and inside `trusted_profiles`.
-    pub fn is_trusted(&self, path: &Path) -> bool {
-        self.trusted_profiles.contains(&path.to_path_buf())
+    /// Returns if the currently loaded profile is ephemeral.
+    pub fn is_ephemeral(&self) -> bool {
+        self.ephemeral.is_some()
+    }
+    /// Checks if the profile path provided is the profile that will be restored once the current
+    /// ephemeral load is cleared.
+    pub fn is_restored_by_ephemeral(&self, path: &Path) -> bool {
+        self.ephemeral.as_ref()
+            .and_then(|e| e.previous_profile.as_ref())
+            .is_some_and(|p| p.repo_path == path)
+    }
+    /// Removes and returns the ephemeral load details, or [`None`] if the current profile is not
+    /// ephemeral. Use this both to clear an ephemeral load, and to make it permanent.
+    pub fn take_ephemeral(&mut self) -> Option<EphemeralLoad> {
+        self.ephemeral.take()
+    }
+
+    /// Sets the path of the backup made while loading the current profile, which should be
+    /// restored once it is unloaded.
+    pub fn set_current_backup(&mut self, path: Option<PathBuf>) {
+        self.current_backup = path;
+    }
+    /// Sets the tags the current profile was loaded with, see [`TagFilter`].
+    pub fn set_current_tags(&mut self, tags: TagFilter) {
+        self.current_tags = tags;
+    }
+    /// Returns the tags the current profile was loaded with, which pick every entry if none were
+    /// given.
+    pub fn current_tags(&self) -> &TagFilter {
+        &self.current_tags
+    }
+
+    /// Returns the path of the current profile's backup, or [`None`] if there isn't one.
+    pub fn current_backup(&self) -> Option<&Path> {
+        self.current_backup.as_deref()
+    }
+    /// Removes and returns the path of the current profile's backup, or [`None`] if there isn't one.
+    pub fn take_current_backup(&mut self) -> Option<PathBuf> {
+        self.current_backup.take()
+    }
+
+    /// Trusts the profile path provided with the given `manifest_hash` as far as `scope`, adding it
+    /// to `trusted_profiles`. The profile's `version` is remembered alongside it, see
+    /// [`Meta::trusted_version`]. Any previously trusted hash for the path is replaced, keeping the
+    /// user's note (see [`Meta::set_trust_note`]).
+    pub fn trust_profile(&mut self, path: PathBuf, manifest_hash: String, version: Option<String>, scope: TrustScope) {
+        let note: Option<String> = self.untrust_profile(&path).and_then(|previous| previous.note);
+        self.trusted_profiles.push(TrustedProfile { path, manifest_hash: Some(manifest_hash), version, trusted_at: Some(Utc::now()), note, scope });
+    }
+    /// Removes the profile path provided from `trusted_profiles`, returning what was stored about
+    /// it, or [`None`] if it wasn't trusted.
+    pub fn untrust_profile(&mut self, path: &Path) -> Option<TrustedProfile> {
+        let index: usize = self.trusted_profiles.iter().position(|p| p.path == path)?;
+        Some(self.trusted_profiles.remove(index))
+    }
+    /// Returns every trusted profile, in the order they were trusted. Profiles whose manifest has
+    /// changed since are included, see [`Meta::is_trusted`].
+    pub fn trusted_profiles(&self) -> &[TrustedProfile] {
+        &self.trusted_profiles
+    }
+    /// Returns what's stored about the profile path provided, or [`None`] if it was never trusted.
+    pub fn trusted_profile(&self, path: &Path) -> Option<&TrustedProfile> {
+        self.trusted_profiles.iter().find(|p| p.path == path)
+    }
+    /// Sets the user's `note` on the trusted profile path provided, e.g. why they trust it. Returns
+    /// `false` if the profile isn't trusted, in which case nothing is changed.
+    pub fn set_trust_note(&mut self, path: &Path, note: Option<String>) -> bool {
+        let Some(trusted) = self.trusted_profiles.iter_mut().find(|p| p.path == path) else { return false };
+        trusted.note = note;
+        true
+    }
+    /// Trusts the profile in `once` for as long as this meta is around, such as for a single load,
+    /// without adding it to `trusted_profiles`, so it's never saved. Other metas don't count it as
+    /// trusted, so it has to be handed to anything that loads the meta again, such as
+    /// [`crate::operations::load`]. **Only do this with the confirmation of the user!**
+    pub fn trust_once(&mut self, once: TrustOnce) {
+        self.trusted_once.push(once);
+    }
+    /// Checks if the profile path provided is trusted with the given `manifest_hash` at least as
+    /// far as `scope`, either in `trusted_profiles` or by [`Meta::trust_once`].
+    pub fn is_trusted(&self, path: &Path, manifest_hash: &str, scope: TrustScope) -> bool {
+        self.trust_scope(path, manifest_hash).is_some_and(|trusted| trusted >= scope)
+    }
+    /// Returns how far the profile path provided is trusted with the given `manifest_hash`, or
+    /// [`None`] if it isn't. See [`Meta::is_trusted`].
+    pub fn trust_scope(&self, path: &Path, manifest_hash: &str) -> Option<TrustScope> {
+        let saved = self.trusted_profiles.iter()
+            .filter(|p| p.path == path && p.manifest_hash.as_deref() == Some(manifest_hash))
+            .map(|p| p.scope);
+        let once = self.trusted_once.iter()
+            .filter(|once| once.path == path && once.manifest_hash == manifest_hash)
+            .map(|once| once.scope);
+        saved.chain(once).max()
+    }
+    /// Checks if the profile path provided has been trusted before, even if it's manifest has
+    /// since changed.
+    pub fn is_path_trusted(&self, path: &Path) -> bool {
+        self.trusted_profile(path).is_some()
+    }
+    /// Returns the `version` the profile path provided was at when it was last trusted, or
+    /// [`None`] if it wasn't trusted or had no version.
+    pub fn trusted_version(&self, path: &Path) -> Option<&str> {
+        self.trusted_profile(path).and_then(|p| p.version.as_deref())
+    }
+
+    /// Rewrites every path stored in the meta that is inside `from` to be inside `to` instead, used
+    /// when the dotulous folder is moved (see [`crate::paths::Paths::migrate_legacy`]).
+    pub fn relocate(&mut self, from: &Path, to: &Path) {
+        let previous_profile = self.ephemeral.as_mut().and_then(|e| e.previous_profile.as_mut());
+        for profile in self.current_profile.iter_mut().chain(previous_profile) {
+            relocate_path(&mut profile.repo_path, from, to);
+            relocate_path(&mut profile.manifest_path, from, to);
+        }
+        for trusted in &mut self.trusted_profiles {
+            relocate_path(&mut trusted.path, from, to);
+        }
+        if let Some(backup) = &mut self.current_backup {
+            relocate_path(backup, from, to);
+        }
+    }
+
+    /// Points everything stored in the meta about the profile at `from` to `to` instead, with
+    /// `name` as it's new name, used when a profile is renamed. Loaded copies of the profile are
+    /// renamed too. See [`Meta::relocate`].
+    ///
+    /// The profile's trusted manifest hash is kept as-is, so it is up to the caller to trust the
+    /// renamed manifest again if it was trusted.
+    pub fn rename_profile(&mut self, from: &Path, to: &Path, name: &str) {
+        self.relocate(from, to);
+        let previous_profile = self.ephemeral.as_mut().and_then(|e| e.previous_profile.as_mut());
+        for profile in self.current_profile.iter_mut().chain(previous_profile) {
+            if profile.repo_path == to {
+                profile.name = name.to_string();
+            }
+        }
+    }
+}
+impl Default for Meta {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A profile the user has trusted, stored inside the [`Meta`]. See [`Meta::trusted_profiles`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "TrustedProfileRepr")]
+pub struct TrustedProfile {
+    /// The *absolute* path to the profile's folder.
+    pub path: PathBuf,
+    /// The hash of the profile's manifest when it was trusted. [`None`] for profiles trusted before
+    /// manifest hashes were stored, which are never trusted again until re-confirmed.
+    pub manifest_hash: Option<String>,
+    /// The profile's `version` when it was trusted, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// When the profile was last trusted. [`None`] for profiles trusted before this was stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted_at: Option<DateTime<Utc>>,
+    /// The user's note on the profile, e.g. why they trust it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// How far the profile is trusted. [`TrustScope::Commands`] for profiles trusted before this
+    /// was stored.
+    #[serde(default)]
+    pub scope: TrustScope
+}
+
+impl TrustedProfile {
+    /// Returns if the profile is still trusted, by reading it's manifest from disk and comparing
+    /// it's hash with the one it was trusted with.
+    pub fn status(&self) -> TrustStatus {
+        match DotfileProfile::from_manifest(&self.path).and_then(|profile| profile.manifest_hash()) {
+            Ok(hash) if self.manifest_hash.as_deref() == Some(hash.as_str()) => TrustStatus::Trusted,
+            Ok(_) => TrustStatus::Changed,
+            Err(_) => TrustStatus::Missing
+        }
+    }
+}
+
+/// Whether a [`TrustedProfile`] is still trusted, found with [`TrustedProfile::status`].
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustStatus {
+    /// The manifest is the same as when it was trusted.
+    Trusted,
+    /// The manifest has changed since it was trusted, so it has to be trusted again.
+    Changed,
+    /// The profile's manifest can no longer be read, e.g. as it's folder was removed.
+    Missing
+}
+
+/// A profile the user trusted for a single action, which is never saved, see [`Meta::trust_once`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustOnce {
+    /// The *absolute* path to the profile's folder.
+    pub path: PathBuf,
+    /// The hash of the profile's manifest when it was trusted, see
+    /// [`DotfileProfile::manifest_hash`].
+    pub manifest_hash: String,
+    /// How far the profile is trusted.
+    pub scope: TrustScope
+}
+
+/// How far a profile is trusted, see [`Meta::is_trusted`]. Linking a profile's files is far less
+/// risky than running it's commands, so a profile can be trusted with just it's files, such as
+/// for `dotulous load --no-hooks`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustScope {
+    /// The profile may link it's files & assets, but none of it's commands are ran.
+    Files,
+    /// The profile may link it's files and run any of it's commands.
+    #[default]
+    Commands
+}
+
+/// The stored representation of a [`TrustedProfile`], allowing older metas with just the path.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TrustedProfileRepr {
+    /// Just the path, from older metas.
+    Path(PathBuf),
+    /// The full entry.
+    Full {
+        path: PathBuf,
+        manifest_hash: Option<String>,
+        #[serde(default)]
+        version: Option<String>,
+        #[serde(default)]
+        trusted_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        note: Option<String>,
+        #[serde(default)]
+        scope: TrustScope
+    }
+}
+impl From<TrustedProfileRepr> for TrustedProfile {
+    fn from(value: TrustedProfileRepr) -> Self {
+        match value {
+            TrustedProfileRepr::Path(path) => TrustedProfile { path, manifest_hash: None, version: None, trusted_at: None, note: None, scope: TrustScope::Commands },
+            TrustedProfileRepr::Full { path, manifest_hash, version, trusted_at, note, scope } => TrustedProfile { path, manifest_hash, version, trusted_at, note, scope }
+        }
+    }
+}
+
+/// The details of an ephemeral (temporary) load, stored inside the [`Meta`].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EphemeralLoad {
+    /// The profile that was loaded before the ephemeral load, to restore once it's cleared.
+    pub previous_profile: Option<DotfileProfile>
+}
+
+/// Replaces the `from` prefix of `path` with `to`, leaving it untouched if it's not inside `from`.
+fn relocate_path(path: &mut PathBuf, from: &Path, to: &Path) {
+    if let Ok(relative) = path.strip_prefix(from) {
+        *path = to.join(relative);
     }
 }
 
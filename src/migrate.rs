@@ -0,0 +1,48 @@
+use serde_json::{Map, Value};
+
+use crate::error::DotulousError;
+
+/// The version of the manifest format written by this version of dotulous, stored in each
+/// manifest's `manifest_version`. Manifests without one are version `0`.
+///
+/// When the format changes, bump this and add a migration to the end of [`MIGRATIONS`] upgrading
+/// manifests from the previous version, so older manifests keep loading.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Upgrades a manifest's JSON object by one version, see [`MIGRATIONS`].
+type Migration = fn(&mut Map<String, Value>);
+
+/// Every migration, in order. The migration at index `i` upgrades a manifest from version `i` to
+/// version `i + 1`.
+const MIGRATIONS: [Migration; MANIFEST_VERSION as usize] = [
+    unversioned
+];
+
+/// Returns the `manifest_version` of the JSON `manifest`, or `0` if it doesn't have one.
+pub fn manifest_version(manifest: &Value) -> u32 {
+    manifest.get("manifest_version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| u32::try_from(version).unwrap_or(u32::MAX))
+}
+
+/// Upgrades the JSON `manifest` to [`MANIFEST_VERSION`] in place, running every migration it
+/// needs in order, and returns the version it was at beforehand.
+///
+/// If the manifest is from a newer version of dotulous, [`Err`] with
+/// [`DotulousError::ManifestTooNew`] is returned. A manifest that isn't a JSON object is left
+/// alone, for deserializing it to report.
+pub fn migrate_manifest(manifest: &mut Value) -> Result<u32, DotulousError> {
+    let version: u32 = manifest_version(manifest);
+    if version > MANIFEST_VERSION {
+        return Err(DotulousError::ManifestTooNew)
+    }
+    let Some(object) = manifest.as_object_mut() else { return Ok(version) };
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(object);
+    }
+    object.insert("manifest_version".to_string(), Value::from(MANIFEST_VERSION));
+    Ok(version)
+}
+
+/// Version `0` to `1`: manifests from before they were versioned, which already match version `1`.
+fn unversioned(_manifest: &mut Map<String, Value>) {}
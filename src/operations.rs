@@ -0,0 +1,1159 @@
+use std::{fs::{self, ReadDir}, path::{Component, Path, PathBuf}};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{asset::{self, Asset}, backup::Backup, condition::Host, entry::{FileEntry, Strategy}, error::DotulousError, filter::{EntryFilter, TagFilter}, format::ManifestFormat, generation::GenerationLog, interrupt, meta::{Meta, TrustOnce, TrustScope}, migrate::{manifest_version, MANIFEST_VERSION}, paths::expand_path, platform, profile::{ApplyMode, DotfileProfile, OnConflict, Skip}, say, settings::Settings, state::{LoadState, PlacedFile}};
+
+/// The current state of dotulous, returned by [`status`].
+#[derive(Debug)]
+pub struct Status {
+    /// The currently loaded profile, *at the time of loading*.
+    pub current_profile: Option<DotfileProfile>,
+    /// If the currently loaded profile was loaded ephemerally.
+    pub ephemeral: bool,
+    /// The folder name of every profile inside the dotulous folder, these being the names used
+    /// to find them with [`DotfileProfile::find_profile`].
+    pub profiles: Vec<String>
+}
+
+/// A summary of a single profile, returned by [`list`].
+#[derive(Serialize, Debug)]
+pub struct ProfileSummary {
+    /// The user-friendly name of the profile.
+    pub name: String,
+    /// The *absolute* path to the profile's folder.
+    pub path: PathBuf,
+    /// The profile's `description`, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The profile's `author`, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// The profile's `homepage`, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// The profile's `version`, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// If the profile has been marked as trusted, even if only to link it's files.
+    pub trusted: bool,
+    /// How far the profile is trusted, if it is. See [`TrustScope`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust_scope: Option<TrustScope>,
+    /// If the profile is the currently loaded profile.
+    pub loaded: bool,
+    /// How many entries are in the profile's `files`.
+    pub file_count: usize
+}
+
+/// Loads `profile` to the system, unloading the currently loaded profile (and restoring it's
+/// backup) first, where `dotulous_path` is the user's dotulous folder.
+///
+/// Any profiles it extends are merged in (see [`DotfileProfile::resolve_extends`]), and only
+/// entries matching this machine are loaded, see [`DotfileProfile::resolve_conditions`]. Only the
+/// entries picked by `tags` are loaded, which are remembered for reloading, see
+/// [`DotfileProfile::resolve_tags`]. Folders using [`crate::entry::LinkMode::Contents`] are loaded
+/// file by file, see [`DotfileProfile::resolve_link_modes`].
+///
+/// The profile **must** already be trusted with it's current manifest (see [`Meta::trust_profile`]),
+/// otherwise [`Err`] with [`DotulousError::ProfileNotTrusted`] is returned before anything is
+/// changed. Asking the user to trust it is left to the caller, who hands over `trusted_once` if
+/// the user only trusted it this once (see [`Meta::trust_once`]). The previously loaded profile's
+/// `removal_commands` are only ran if it's trusted to run them, see [`unload`].
+///
+/// If `ephemeral` is set, the previously loaded profile is remembered so that it can be restored
+/// with [`clear_ephemeral`]. Otherwise any ephemeral load is made permanent.
+///
+/// Commands and/or files are left out of both unloading & loading according to `skip`, see
+/// [`DotfileProfile::skipping`]. If files are skipped, the current profile's backup is left in
+/// place rather than restored, and no [`LoadState`] is kept.
+///
+/// If loading fails, it's changes are rolled back (see [`DotfileProfile::load_profile_to_system`])
+/// and the meta is left with no profile loaded, as the previous profile was already unloaded.
+/// The same happens if dotulous is interrupted while loading, see [`interrupt::install`].
+/// Anything already in the way of a file is dealt with according to `on_conflict`. Every file is
+/// printed as it's linked if `verbose`, otherwise a progress bar is shown. Commands are ran with
+/// the shell & timeout from the user's `settings` when the profile doesn't set them.
+///
+/// The meta is updated and the generation is recorded, unless `mode` is [`ApplyMode::DryRun`] in
+/// which case nothing is changed at all. Any assets of the previous profile that the new one (or
+/// the previous one, if `ephemeral`) doesn't use are removed from the asset cache.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+#[allow(clippy::too_many_arguments)]
+pub fn load(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, profile: &DotfileProfile, ephemeral: bool, tags: &TagFilter, skip: Skip, mode: ApplyMode, on_conflict: OnConflict, verbose: bool, trusted_once: Option<&TrustOnce>) -> Result<(), DotulousError> {
+    let _guard: interrupt::Guard = interrupt::guard();
+    let mut meta: Meta = load_meta_trusting(dotulous_path, trusted_once)?;
+    if mode == ApplyMode::Apply && !is_trusted(&meta, profile, profile.required_trust(skip)) {
+        return Err(DotulousError::ProfileNotTrusted)
+    }
+    let mut profile: DotfileProfile = profile.clone();
+    profile.resolve_extends()?;
+    profile.resolve_conditions(&Host::detect());
+    profile.resolve_tags(tags);
+    profile.resolve_link_modes()?;
+
+    let previous_profile: Option<DotfileProfile> = meta.current_profile();
+    if let Some(current_profile) = &previous_profile {
+        trusted_commands(&meta, &current_profile.skipping(skip)).unload_profile_from_system(dotulous_path, config_path, home_path, settings, mode, false, load_state(dotulous_path).as_ref());
+        release_current_backup(&mut meta, mode, skip);
+        forget_state(dotulous_path, mode);
+        say!();
+    }
+
+    let (backup, state): (Option<Backup>, LoadState) = match profile.skipping(skip).load_profile_to_system(dotulous_path, config_path, home_path, settings, mode, on_conflict, verbose) {
+        Ok(_) if mode == ApplyMode::DryRun => return Ok(()),
+        Ok(r) => r,
+        Err(e) if mode == ApplyMode::DryRun => return Err(e),
+        Err(e) => return Err(abort_load(dotulous_path, &mut meta, previous_profile.as_ref(), e))
+    };
+
+    keep_state(dotulous_path, &state, skip);
+    meta.set_current_profile(&profile, home_path);
+    meta.set_current_tags(tags.clone());
+    meta.set_current_backup(backup.map(|b| b.path));
+    if ephemeral {
+        meta.set_ephemeral(previous_profile.clone());
+    } else {
+        meta.take_ephemeral();
+    }
+    meta.save_meta(dotulous_path)?;
+    if let Some(previous_profile) = &previous_profile {
+        clean_assets(dotulous_path, &meta, &previous_profile.skipping(skip));
+    }
+    record_generation(dotulous_path, if ephemeral { "load --ephemeral" } else { "load" }, previous_profile.as_ref(), Some(&profile));
+    Ok(())
+}
+
+/// Unloads the currently loaded profile from the system and restores it's backup, where
+/// `dotulous_path` is the user's dotulous folder. Any ephemeral load is forgotten.
+///
+/// Destinations that weren't put there by the profile are left alone, unless `force` is set (see
+/// [`DotfileProfile::unload_profile_from_system`]). Commands and/or files are left out according
+/// to `skip`, see [`DotfileProfile::skipping`]. If files are skipped, the backup is left in place
+/// rather than restored. The profile's `removal_commands` are also left out (with a warning) if it
+/// isn't trusted to run them with it's current manifest, such as when it was loaded with
+/// `--no-hooks` and only it's files trusted.
+///
+/// If no profile is loaded, [`Err`] with [`DotulousError::NoProfileLoaded`] is returned.
+///
+/// The meta is updated, the generation is recorded and the profile's assets are removed from the
+/// asset cache (see [`asset::remove_cached`]), unless `mode` is [`ApplyMode::DryRun`].
+///
+/// Returns the profile that was unloaded.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn unload(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, skip: Skip, mode: ApplyMode, force: bool) -> Result<DotfileProfile, DotulousError> {
+    let _guard: interrupt::Guard = interrupt::guard();
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    let Some(profile) = meta.current_profile() else { return Err(DotulousError::NoProfileLoaded) };
+
+    trusted_commands(&meta, &profile.skipping(skip)).unload_profile_from_system(dotulous_path, config_path, home_path, settings, mode, force, load_state(dotulous_path).as_ref());
+    release_current_backup(&mut meta, mode, skip);
+    forget_state(dotulous_path, mode);
+    if mode == ApplyMode::DryRun {
+        return Ok(profile)
+    }
+
+    meta.empty_current_profile();
+    meta.take_ephemeral();
+    meta.save_meta(dotulous_path)?;
+    clean_assets(dotulous_path, &meta, &profile.skipping(skip));
+    record_generation(dotulous_path, "unload", Some(&profile), None);
+    Ok(profile)
+}
+
+/// What [`reload`] will change on the system, found with [`plan_reload`].
+#[derive(Debug)]
+pub struct ReloadPlan {
+    /// The part of the loaded profile that's unloaded.
+    pub unload: DotfileProfile,
+    /// The part of the profile read from it's manifest that's loaded.
+    pub load: DotfileProfile,
+    /// The whole profile read from it's manifest, resolved the same way it's loaded.
+    pub profile: DotfileProfile,
+    /// If the whole profile is unloaded and loaded again, rather than only what changed.
+    pub full: bool
+}
+impl ReloadPlan {
+    /// Plans reloading `old_profile` (the loaded profile) as `new_profile`, leaving out what `skip`
+    /// does, where `home_path` is the user's home folder.
+    ///
+    /// Unless `full` is set, only the entries that changed are unloaded & loaded again (see
+    /// [`DotfileProfile::changed_from`]), and the profile's commands are only ran if they changed
+    /// (see [`DotfileProfile::commands_changed_from`]), so configs that didn't change are never
+    /// removed from under the programs using them. The whole profile is still reloaded if it's
+    /// [`DotfileProfile::target_path`] changed, or if `skip` leaves out files.
+    pub fn between(home_path: &Path, old_profile: &DotfileProfile, new_profile: DotfileProfile, skip: Skip, full: bool) -> ReloadPlan {
+        if full || skip.files || old_profile.target_path(home_path) != new_profile.target_path(home_path) {
+            return ReloadPlan { unload: old_profile.skipping(skip), load: new_profile.skipping(skip), profile: new_profile, full: true }
+        }
+        let skip: Skip = Skip { hooks: skip.hooks || !new_profile.commands_changed_from(old_profile), files: false };
+        ReloadPlan {
+            unload: old_profile.changed_from(&new_profile).skipping(skip),
+            load: new_profile.changed_from(old_profile).skipping(skip),
+            profile: new_profile,
+            full: false
+        }
+    }
+
+    /// Returns if reloading won't change anything, as no entries or commands changed.
+    pub fn is_empty(&self) -> bool {
+        !self.full
+            && self.unload.destinations().is_empty() && self.load.destinations().is_empty()
+            && self.unload.command_lists().chain(self.load.command_lists()).all(|(_, commands)| commands.is_empty())
+    }
+}
+
+/// Plans reloading the current profile from it's manifest, where `dotulous_path` is the user's
+/// dotulous folder and `home_path` is the user's home folder. See [`ReloadPlan::between`] for what
+/// `skip` & `full` do.
+///
+/// The profile is resolved the same way it was loaded, with the same tags. If no profile is
+/// loaded, [`Err`] with [`DotulousError::NoProfileLoaded`] is returned.
+pub fn plan_reload(dotulous_path: &Path, home_path: &Path, skip: Skip, full: bool) -> Result<ReloadPlan, DotulousError> {
+    let meta: Meta = Meta::load_meta(dotulous_path)?;
+    let Some(old_profile) = meta.current_profile() else { return Err(DotulousError::NoProfileLoaded) };
+
+    let mut new_profile: DotfileProfile = DotfileProfile::from_manifest(&old_profile.repo_path)?;
+    new_profile.resolve_extends()?;
+    new_profile.resolve_conditions(&Host::detect());
+    new_profile.resolve_tags(meta.current_tags());
+    new_profile.resolve_link_modes()?;
+    Ok(ReloadPlan::between(home_path, &old_profile, new_profile, skip, full))
+}
+
+/// Re-loads the current profile from it's manifest, where `dotulous_path` is the user's dotulous
+/// folder. Only the entries & commands that changed are unloaded and loaded again, unless `full`
+/// is set, in which case the whole profile is unloaded and loaded again. See [`plan_reload`].
+///
+/// The manifest is read before anything is unloaded, so a broken manifest doesn't leave the user
+/// without a profile. It's loaded with the same tags as before, and commands and/or files are left
+/// out of both halves according to `skip`, see [`load`]. Like [`load`], the profile must still be
+/// trusted with it's current manifest (or by `trusted_once`),
+/// otherwise [`Err`] with [`DotulousError::ProfileNotTrusted`] is returned. If loading fails, it's changes are rolled back and the meta is left with no
+/// profile loaded (or only the entries that didn't change). If no profile is loaded, [`Err`] with [`DotulousError::NoProfileLoaded`] is
+/// returned. Conflicts are dealt with according to `on_conflict`, see [`load`]. Every file is
+/// printed as it's linked if `verbose`, otherwise a progress bar is shown.
+///
+/// The meta is updated and the generation is recorded, unless `mode` is [`ApplyMode::DryRun`].
+///
+/// Returns the newly loaded profile.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+#[allow(clippy::too_many_arguments)]
+pub fn reload(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, skip: Skip, mode: ApplyMode, on_conflict: OnConflict, full: bool, verbose: bool, trusted_once: Option<&TrustOnce>) -> Result<DotfileProfile, DotulousError> {
+    let _guard: interrupt::Guard = interrupt::guard();
+    let mut meta: Meta = load_meta_trusting(dotulous_path, trusted_once)?;
+    let Some(old_profile) = meta.current_profile() else { return Err(DotulousError::NoProfileLoaded) };
+
+    let manifest_profile: DotfileProfile = DotfileProfile::from_manifest(&old_profile.repo_path)?;
+    if mode == ApplyMode::Apply && !is_trusted(&meta, &manifest_profile, manifest_profile.required_trust(skip)) {
+        return Err(DotulousError::ProfileNotTrusted)
+    }
+    let plan: ReloadPlan = plan_reload(dotulous_path, home_path, skip, full)?;
+    let tags: TagFilter = meta.current_tags().clone();
+    if !plan.full {
+        return reload_changes(dotulous_path, config_path, home_path, settings, &mut meta, &old_profile, plan, mode, on_conflict, verbose)
+    }
+
+    trusted_commands(&meta, &plan.unload).unload_profile_from_system(dotulous_path, config_path, home_path, settings, mode, false, load_state(dotulous_path).as_ref());
+    release_current_backup(&mut meta, mode, skip);
+    forget_state(dotulous_path, mode);
+    meta.empty_current_profile();
+    let (backup, state): (Option<Backup>, LoadState) = match plan.load.load_profile_to_system(dotulous_path, config_path, home_path, settings, mode, on_conflict, verbose) {
+        Ok(_) if mode == ApplyMode::DryRun => return Ok(plan.profile),
+        Ok(r) => r,
+        Err(e) if mode == ApplyMode::DryRun => return Err(e),
+        Err(e) => return Err(abort_load(dotulous_path, &mut meta, Some(&old_profile), e))
+    };
+    keep_state(dotulous_path, &state, skip);
+    meta.set_current_profile(&plan.profile, home_path);
+    meta.set_current_tags(tags);
+    meta.set_current_backup(backup.map(|b| b.path));
+    meta.save_meta(dotulous_path)?;
+    clean_assets(dotulous_path, &meta, &plan.unload);
+    record_generation(dotulous_path, "reload", Some(&old_profile), Some(&plan.profile));
+    Ok(plan.profile)
+}
+
+/// Does the work of [`reload`] when only what changed is reloaded, unloading `plan.unload` and
+/// loading `plan.load` while the rest of `old_profile` stays where it is. If loading fails, only
+/// it's changes are rolled back, leaving the entries that didn't change loaded.
+#[allow(clippy::too_many_arguments)]
+fn reload_changes(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, meta: &mut Meta, old_profile: &DotfileProfile, plan: ReloadPlan, mode: ApplyMode, on_conflict: OnConflict, verbose: bool) -> Result<DotfileProfile, DotulousError> {
+    if plan.is_empty() {
+        say!("Nothing has changed in \"{}\", so nothing was reloaded.", plan.profile.name);
+        return Ok(plan.profile)
+    }
+
+    let mut state: Option<LoadState> = load_state(dotulous_path);
+    trusted_commands(meta, &plan.unload).unload_profile_from_system(dotulous_path, config_path, home_path, settings, mode, false, state.as_ref());
+    if mode == ApplyMode::Apply {
+        let destinations: Vec<PathBuf> = absolute_destinations(&plan.unload, home_path);
+        restore_current_backup_of(meta, &destinations);
+        if let Some(state) = &mut state {
+            state.forget(&destinations);
+        }
+    }
+    say!();
+
+    let (backup, new_state): (Option<Backup>, LoadState) = match plan.load.load_profile_to_system(dotulous_path, config_path, home_path, settings, mode, on_conflict, verbose) {
+        Ok(_) if mode == ApplyMode::DryRun => return Ok(plan.profile),
+        Ok(r) => r,
+        Err(e) if mode == ApplyMode::DryRun => return Err(e),
+        Err(e) => {
+            // The entries that didn't change are still loaded, so only the changed ones are forgotten
+            let mut unchanged: DotfileProfile = old_profile.clone();
+            unchanged.remove_entries(&plan.unload.destinations());
+            if let Some(state) = &state {
+                save_state(dotulous_path, state);
+            }
+            meta.set_current_profile(&unchanged, home_path);
+            meta.save_meta(dotulous_path)?;
+            record_generation(dotulous_path, "reload", Some(old_profile), Some(&unchanged));
+            return Err(e)
+        }
+    };
+
+    let mut state: LoadState = state.unwrap_or_default();
+    state.extend(new_state);
+    save_state(dotulous_path, &state);
+    keep_backup(meta, backup);
+    meta.set_current_profile(&plan.profile, home_path);
+    meta.save_meta(dotulous_path)?;
+    clean_assets(dotulous_path, meta, &plan.unload);
+    record_generation(dotulous_path, "reload", Some(old_profile), Some(&plan.profile));
+    Ok(plan.profile)
+}
+
+/// Loads only the entries of `profile` picked by `filter` (see [`DotfileProfile::partition`]),
+/// where `dotulous_path` is the user's dotulous folder. None of the profile's commands are ran.
+///
+/// If `profile` is the loaded profile, only the picked entries are unloaded and then loaded again
+/// with the tags it was loaded with, leaving the rest of it alone. Otherwise the loaded profile is unloaded as with [`load`], and only
+/// the picked entries are loaded, so the rest can be loaded later the same way.
+///
+/// Like [`load`], the profile must already be trusted with it's current manifest (or by
+/// `trusted_once`), otherwise [`Err`] with [`DotulousError::ProfileNotTrusted`] is returned before
+/// anything is changed.
+/// Conflicts are dealt with according to `on_conflict`, and every file is printed as it's linked
+/// if `verbose`, otherwise a progress bar is shown. If loading fails, it's changes are rolled back
+/// and the picked entries are left unloaded.
+///
+/// The meta is updated and the generation is recorded, unless `mode` is [`ApplyMode::DryRun`].
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+#[allow(clippy::too_many_arguments)]
+pub fn load_partial(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, profile: &DotfileProfile, filter: &EntryFilter, mode: ApplyMode, on_conflict: OnConflict, verbose: bool, trusted_once: Option<&TrustOnce>) -> Result<(), DotulousError> {
+    let _guard: interrupt::Guard = interrupt::guard();
+    let mut meta: Meta = load_meta_trusting(dotulous_path, trusted_once)?;
+    // Only the picked files are loaded, without running any commands
+    if mode == ApplyMode::Apply && !is_trusted(&meta, profile, TrustScope::Files) {
+        return Err(DotulousError::ProfileNotTrusted)
+    }
+    let mut profile: DotfileProfile = profile.clone();
+    profile.resolve_extends()?;
+    profile.resolve_conditions(&Host::detect());
+    if meta.current_profile().is_some_and(|current| current.repo_path == profile.repo_path) {
+        profile.resolve_tags(&meta.current_tags().clone());
+    }
+    profile.resolve_link_modes()?;
+    apply_partial(dotulous_path, config_path, home_path, settings, &mut meta, &profile, filter, mode, on_conflict, verbose, "load --only")
+}
+
+/// Unloads only the entries of the loaded profile picked by `filter` (see
+/// [`DotfileProfile::partition`]), where `dotulous_path` is the user's dotulous folder, leaving the
+/// rest of it loaded. None of the profile's commands are ran, and any files backed up from where
+/// the picked entries were are restored.
+///
+/// Destinations that weren't put there by the profile are left alone, unless `force` is set (see
+/// [`DotfileProfile::unload_profile_from_system`]). If no profile is loaded, [`Err`] with
+/// [`DotulousError::NoProfileLoaded`] is returned.
+///
+/// The meta is updated and the generation is recorded, unless `mode` is [`ApplyMode::DryRun`].
+///
+/// Returns the part of the profile that was unloaded.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn unload_partial(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, filter: &EntryFilter, mode: ApplyMode, force: bool) -> Result<DotfileProfile, DotulousError> {
+    let _guard: interrupt::Guard = interrupt::guard();
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    let Some(profile) = meta.current_profile() else { return Err(DotulousError::NoProfileLoaded) };
+    let (picked, rest): (DotfileProfile, DotfileProfile) = profile.partition(filter);
+
+    let mut state: Option<LoadState> = load_state(dotulous_path);
+    picked.unload_profile_from_system(dotulous_path, config_path, home_path, settings, mode, force, state.as_ref());
+    if mode == ApplyMode::DryRun {
+        return Ok(picked)
+    }
+
+    let destinations: Vec<PathBuf> = absolute_destinations(&picked, home_path);
+    restore_current_backup_of(&meta, &destinations);
+    if let Some(state) = &mut state {
+        state.forget(&destinations);
+        save_state(dotulous_path, state);
+    }
+    meta.set_current_profile(&rest, home_path);
+    meta.save_meta(dotulous_path)?;
+    clean_assets(dotulous_path, &meta, &picked);
+    record_generation(dotulous_path, "unload --only", Some(&profile), Some(&rest));
+    Ok(picked)
+}
+
+/// Reloads only the entries of the loaded profile picked by `filter` from it's manifest, where
+/// `dotulous_path` is the user's dotulous folder, leaving the rest of it alone. See
+/// [`load_partial`], which this calls with the profile read from it's manifest.
+///
+/// If no profile is loaded, [`Err`] with [`DotulousError::NoProfileLoaded`] is returned.
+///
+/// Returns the newly loaded profile.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+#[allow(clippy::too_many_arguments)]
+pub fn reload_partial(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, filter: &EntryFilter, mode: ApplyMode, on_conflict: OnConflict, verbose: bool, trusted_once: Option<&TrustOnce>) -> Result<DotfileProfile, DotulousError> {
+    let _guard: interrupt::Guard = interrupt::guard();
+    let meta: Meta = Meta::load_meta(dotulous_path)?;
+    let Some(old_profile) = meta.current_profile() else { return Err(DotulousError::NoProfileLoaded) };
+    let new_profile: DotfileProfile = DotfileProfile::from_manifest(&old_profile.repo_path)?;
+    load_partial(dotulous_path, config_path, home_path, settings, &new_profile, filter, mode, on_conflict, verbose, trusted_once)?;
+    Ok(new_profile)
+}
+
+/// Does the work of [`load_partial`], loading the entries of the already resolved `profile` picked
+/// by `filter` and recording the generation as `action`.
+#[allow(clippy::too_many_arguments)]
+fn apply_partial(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, meta: &mut Meta, profile: &DotfileProfile, filter: &EntryFilter, mode: ApplyMode, on_conflict: OnConflict, verbose: bool, action: &str) -> Result<(), DotulousError> {
+    let (picked, _): (DotfileProfile, DotfileProfile) = profile.partition(filter);
+    let previous_profile: Option<DotfileProfile> = meta.current_profile();
+    let mut state: Option<LoadState> = load_state(dotulous_path);
+
+    // Only the picked part of the same profile is swapped out, anything else is unloaded entirely
+    let (unloaded, remaining): (Option<DotfileProfile>, Option<DotfileProfile>) = match &previous_profile {
+        Some(current) if current.repo_path == profile.repo_path => {
+            let (old_picked, rest): (DotfileProfile, DotfileProfile) = current.partition(filter);
+            old_picked.unload_profile_from_system(dotulous_path, config_path, home_path, settings, mode, false, state.as_ref());
+            if mode == ApplyMode::Apply {
+                let destinations: Vec<PathBuf> = absolute_destinations(&old_picked, home_path);
+                restore_current_backup_of(meta, &destinations);
+                if let Some(state) = &mut state {
+                    state.forget(&destinations);
+                }
+            }
+            (Some(old_picked), Some(rest))
+        },
+        Some(current) => {
+            trusted_commands(meta, current).unload_profile_from_system(dotulous_path, config_path, home_path, settings, mode, false, state.as_ref());
+            restore_current_backup(meta, mode);
+            forget_state(dotulous_path, mode);
+            meta.empty_current_profile();
+            state = None;
+            (Some(current.clone()), None)
+        },
+        None => (None, None)
+    };
+    say!();
+
+    let (backup, new_state): (Option<Backup>, LoadState) = match picked.load_profile_to_system(dotulous_path, config_path, home_path, settings, mode, on_conflict, verbose) {
+        Ok(_) if mode == ApplyMode::DryRun => return Ok(()),
+        Ok(r) => r,
+        Err(e) if mode == ApplyMode::DryRun => return Err(e),
+        Err(e) => {
+            let Some(rest) = remaining else { return Err(abort_load(dotulous_path, meta, previous_profile.as_ref(), e)) };
+            // The rest of the profile is still loaded, so only the picked part is forgotten
+            if let Some(state) = &state {
+                save_state(dotulous_path, state);
+            }
+            meta.set_current_profile(&rest, home_path);
+            meta.save_meta(dotulous_path)?;
+            record_generation(dotulous_path, action, previous_profile.as_ref(), Some(&rest));
+            return Err(e)
+        }
+    };
+
+    let loaded: DotfileProfile = match remaining {
+        Some(mut rest) => { rest.absorb_entries(picked); rest },
+        None => picked
+    };
+    let mut state: LoadState = state.unwrap_or_default();
+    state.extend(new_state);
+    save_state(dotulous_path, &state);
+    keep_backup(meta, backup);
+    meta.set_current_profile(&loaded, home_path);
+    meta.take_ephemeral();
+    meta.save_meta(dotulous_path)?;
+    if let Some(unloaded) = &unloaded {
+        clean_assets(dotulous_path, meta, unloaded);
+    }
+    record_generation(dotulous_path, action, previous_profile.as_ref(), Some(&loaded));
+    Ok(())
+}
+
+/// Clears an ephemeral load, unloading the current profile and restoring the profile that was
+/// loaded beforehand (if any), where `dotulous_path` is the user's dotulous folder.
+///
+/// The previous profile is re-read from it's manifest, falling back to the copy stored in the
+/// meta if that fails. If the manifest was read but is no longer trusted (or by `trusted_once`, see
+/// [`load`]), [`Err`] with [`DotulousError::ProfileNotTrusted`] is returned before anything is
+/// changed. If no ephemeral
+/// load is active, [`Err`] with
+/// [`DotulousError::NoEphemeralLoad`] is returned. Conflicts are dealt with according to
+/// `on_conflict`, see [`load`]. Every file is printed as it's linked if `verbose`, otherwise a
+/// progress bar is shown.
+///
+/// Returns the profile that was restored, if any.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn clear_ephemeral(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, on_conflict: OnConflict, verbose: bool, trusted_once: Option<&TrustOnce>) -> Result<Option<DotfileProfile>, DotulousError> {
+    let _guard: interrupt::Guard = interrupt::guard();
+    let mut meta: Meta = load_meta_trusting(dotulous_path, trusted_once)?;
+    let Some(ephemeral) = meta.take_ephemeral() else { return Err(DotulousError::NoEphemeralLoad) };
+
+    let restored_profile: Option<DotfileProfile> = match ephemeral.previous_profile {
+        Some(previous) => match DotfileProfile::from_manifest(&previous.repo_path) {
+            Ok(profile) if !is_trusted(&meta, &profile, profile.required_trust(Skip::default())) => return Err(DotulousError::ProfileNotTrusted),
+            Ok(mut profile) => {
+                profile.resolve_extends()?;
+                profile.resolve_conditions(&Host::detect());
+                profile.resolve_link_modes()?;
+                Some(profile)
+            },
+            Err(_) => Some(previous)
+        },
+        None => None
+    };
+
+    let ephemeral_profile: Option<DotfileProfile> = meta.current_profile();
+    if let Some(profile) = &ephemeral_profile {
+        trusted_commands(&meta, profile).unload_profile_from_system(dotulous_path, config_path, home_path, settings, ApplyMode::Apply, false, load_state(dotulous_path).as_ref());
+        restore_current_backup(&mut meta, ApplyMode::Apply);
+        forget_state(dotulous_path, ApplyMode::Apply);
+        meta.empty_current_profile();
+    }
+    if let Some(profile) = &restored_profile {
+        say!();
+        let (backup, state): (Option<Backup>, LoadState) = match trusted_commands(&meta, profile).load_profile_to_system(dotulous_path, config_path, home_path, settings, ApplyMode::Apply, on_conflict, verbose) {
+            Ok(r) => r,
+            Err(e) => return Err(abort_load(dotulous_path, &mut meta, ephemeral_profile.as_ref(), e))
+        };
+        save_state(dotulous_path, &state);
+        meta.set_current_profile(profile, home_path);
+        meta.set_current_backup(backup.map(|b| b.path));
+    }
+    meta.save_meta(dotulous_path)?;
+    if let Some(profile) = &ephemeral_profile {
+        clean_assets(dotulous_path, &meta, profile);
+    }
+    record_generation(dotulous_path, "ephemeral clear", ephemeral_profile.as_ref(), restored_profile.as_ref());
+    Ok(restored_profile)
+}
+
+/// Undoes the latest generation (see [`GenerationLog`]), putting back the profile that was loaded
+/// before it, where `dotulous_path` is the user's dotulous folder. The current profile is
+/// unloaded, and the copy of the previous profile stored in the generation is loaded exactly as it
+/// was, even if it's manifest has changed since, though the commands of either are only ran if
+/// they're trusted to run them with their current manifest. If no profile was loaded before,
+/// nothing is loaded. Any ephemeral load is forgotten.
+///
+/// Undoing is itself recorded as a new generation, so undoing again puts back what was undone.
+///
+/// If there's no generation to undo, [`Err`] with [`DotulousError::NothingToUndo`] is returned.
+/// If it was recorded by an older version of dotulous, [`Err`] with
+/// [`DotulousError::GenerationNotUndoable`] is returned. If the previous profile is no longer
+/// trusted, [`Err`] with [`DotulousError::ProfileNotTrusted`] is returned. All before anything is
+/// changed. Conflicts are dealt with according to `on_conflict`, see [`load`]. Every file is
+/// printed as it's linked if `verbose`, otherwise a progress bar is shown.
+///
+/// Returns the profile that was loaded, if any.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn undo(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, on_conflict: OnConflict, verbose: bool) -> Result<Option<DotfileProfile>, DotulousError> {
+    let _guard: interrupt::Guard = interrupt::guard();
+    let log: GenerationLog = GenerationLog::load(dotulous_path)?;
+    let Some(generation) = log.latest() else { return Err(DotulousError::NothingToUndo) };
+    let Some(before) = &generation.before else { return Err(DotulousError::GenerationNotUndoable) };
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    if before.profile.as_ref().is_some_and(|profile| !meta.is_path_trusted(&profile.repo_path)) {
+        return Err(DotulousError::ProfileNotTrusted)
+    }
+
+    let current_profile: Option<DotfileProfile> = meta.current_profile();
+    if let Some(profile) = &current_profile {
+        trusted_commands(&meta, profile).unload_profile_from_system(dotulous_path, config_path, home_path, settings, ApplyMode::Apply, false, load_state(dotulous_path).as_ref());
+        restore_current_backup(&mut meta, ApplyMode::Apply);
+        forget_state(dotulous_path, ApplyMode::Apply);
+        meta.empty_current_profile();
+    }
+    meta.take_ephemeral();
+    if let Some(profile) = &before.profile {
+        say!();
+        let (backup, state): (Option<Backup>, LoadState) = match trusted_commands(&meta, profile).load_profile_to_system(dotulous_path, config_path, home_path, settings, ApplyMode::Apply, on_conflict, verbose) {
+            Ok(r) => r,
+            Err(e) => return Err(abort_load(dotulous_path, &mut meta, current_profile.as_ref(), e))
+        };
+        save_state(dotulous_path, &state);
+        meta.set_current_profile(profile, home_path);
+        meta.set_current_backup(backup.map(|b| b.path));
+    }
+    meta.save_meta(dotulous_path)?;
+    if let Some(profile) = &current_profile {
+        clean_assets(dotulous_path, &meta, profile);
+    }
+    record_generation(dotulous_path, &format!("undo {}", generation.number), current_profile.as_ref(), before.profile.as_ref());
+    Ok(before.profile.clone())
+}
+
+/// Adopts the existing file at `path` into the currently loaded profile, where `dotulous_path` is
+/// the user's dotulous folder. The file is moved into the profile's directory and symlinked back
+/// in it's place, see [`DotfileProfile::adopt_file`].
+///
+/// The new entry is saved to the profile's manifest, and added to the loaded copy in the meta (and
+/// the [`LoadState`], if there is one) so it is removed on unload. If the profile was trusted, it stays trusted with it's new manifest.
+///
+/// If no profile is loaded, [`Err`] with [`DotulousError::NoProfileLoaded`] is returned.
+///
+/// Returns the profile, as read from it's manifest after adopting the file.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn adopt(dotulous_path: &Path, home_path: &Path, path: &Path) -> Result<DotfileProfile, DotulousError> {
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    let Some(mut loaded_profile) = meta.current_profile() else { return Err(DotulousError::NoProfileLoaded) };
+    let mut profile: DotfileProfile = DotfileProfile::from_manifest(&loaded_profile.repo_path)?;
+    let was_trusted: Option<TrustScope> = trust_scope(&meta, &profile);
+
+    let source: PathBuf = profile.adopt_file(home_path, path)?;
+    profile.save_manifest()?;
+    if let Some(scope) = was_trusted {
+        meta.trust_profile(profile.repo_path.clone(), profile.manifest_hash()?, profile.version.clone(), scope);
+    }
+    if let Some(mut state) = load_state(dotulous_path) {
+        state.placed.push(PlacedFile { destination: loaded_profile.target_path(home_path).join(&source), strategy: Strategy::Symlink });
+        save_state(dotulous_path, &state);
+    }
+    loaded_profile.insert_file(source.clone(), FileEntry::new(source));
+    meta.set_current_profile(&loaded_profile, home_path);
+    meta.save_meta(dotulous_path)?;
+    Ok(profile)
+}
+
+/// Adds the file at `source`, relative to the folder of the profile with `profile_name`, to the
+/// profile's manifest going to `destination`, relative to the profile's
+/// [`DotfileProfile::target_path`]. If `source` is already in the profile, `destination` is added
+/// as another place to put it. `dotulous_path` is the user's dotulous folder. If the profile
+/// was trusted, it stays trusted with it's new manifest, as adding a file doesn't change what
+/// commands it runs. Nothing is linked, see [`load_partial`] with [`EntryFilter::path`] for that.
+///
+/// If `source` doesn't exist inside the profile's folder, [`Err`] with
+/// [`DotulousError::SourceNotFound`] is returned. The destination is checked the same way as
+/// when loading (see [`DotfileProfile::check_destination`] &
+/// [`DotfileProfile::check_not_dotulous`]), and mustn't already be in the profile, see
+/// [`DotfileProfile::add_file`] & [`DotfileProfile::add_destination`].
+///
+/// Returns the profile, as saved to it's manifest.
+pub fn add_file(dotulous_path: &Path, home_path: &Path, profile_name: &str, source: &Path, destination: &Path) -> Result<DotfileProfile, DotulousError> {
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    let mut profile: DotfileProfile = DotfileProfile::find_profile(dotulous_path, profile_name)?;
+    let was_trusted: Option<TrustScope> = trust_scope(&meta, &profile);
+
+    let source: PathBuf = relative_source(&profile, source)?;
+    let absolute_destination: PathBuf = profile.target_path(home_path).join(expand_path(destination, home_path));
+    profile.check_destination(home_path, &absolute_destination)?;
+    profile.check_not_dotulous(dotulous_path, &absolute_destination)?;
+    if profile.files().contains_key(&source) {
+        profile.add_destination(&source, FileEntry::new(destination.to_path_buf()))?;
+    } else {
+        profile.add_file(source, FileEntry::new(destination.to_path_buf()))?;
+    }
+    profile.save_manifest()?;
+    if let Some(scope) = was_trusted {
+        meta.trust_profile(profile.repo_path.clone(), profile.manifest_hash()?, profile.version.clone(), scope);
+        meta.save_meta(dotulous_path)?;
+    }
+    Ok(profile)
+}
+
+/// Removes the file at `source`, relative to the folder of the profile with `profile_name`, from
+/// the profile's manifest along with every place it's put, where `dotulous_path` is the user's
+/// dotulous folder. If the profile is loaded, the file is unloaded first (see
+/// [`unload_partial`]), restoring anything it replaced. The file itself is left in the profile's
+/// folder. Like [`add_file`], the profile stays trusted if it was.
+///
+/// If `source` isn't in the profile's own manifest, [`Err`] with [`DotulousError::FileNotMapped`]
+/// is returned and nothing is changed.
+///
+/// Returns the removed entries.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn remove_file(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, profile_name: &str, source: &Path) -> Result<Vec<FileEntry>, DotulousError> {
+    let meta: Meta = Meta::load_meta(dotulous_path)?;
+    let mut profile: DotfileProfile = DotfileProfile::find_profile(dotulous_path, profile_name)?;
+    let was_trusted: Option<TrustScope> = trust_scope(&meta, &profile);
+
+    let source: PathBuf = source.components().filter(|component| *component != Component::CurDir).collect();
+    let Some(entries) = profile.remove_file(&source) else { return Err(DotulousError::FileNotMapped) };
+    for entry in &entries {
+        let filter: EntryFilter = EntryFilter::path(&entry.destination);
+        let is_linked: bool = Meta::load_meta(dotulous_path)?.current_profile()
+            .is_some_and(|loaded| loaded.repo_path == profile.repo_path && !loaded.partition(&filter).0.destinations().is_empty());
+        if is_linked {
+            unload_partial(dotulous_path, config_path, home_path, settings, &filter, ApplyMode::Apply, false)?;
+        }
+    }
+
+    profile.save_manifest()?;
+    if let Some(scope) = was_trusted {
+        let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+        meta.trust_profile(profile.repo_path.clone(), profile.manifest_hash()?, profile.version.clone(), scope);
+        meta.save_meta(dotulous_path)?;
+    }
+    Ok(entries)
+}
+
+/// Returns `source` relative to `profile`'s folder, for [`add_file`]. It may be given relative
+/// to the folder, or as an *absolute* path inside it.
+///
+/// If it doesn't exist inside the folder, such as by escaping it with `..`, [`Err`] with
+/// [`DotulousError::SourceNotFound`] is returned.
+fn relative_source(profile: &DotfileProfile, source: &Path) -> Result<PathBuf, DotulousError> {
+    let source: &Path = source.strip_prefix(&profile.repo_path).unwrap_or(source);
+    if source.is_absolute() || source.components().any(|component| component == Component::ParentDir) {
+        return Err(DotulousError::SourceNotFound)
+    }
+    let source: PathBuf = source.components().filter(|component| *component != Component::CurDir).collect();
+    if source.as_os_str().is_empty() || fs::symlink_metadata(profile.repo_path.join(&source)).is_err() {
+        return Err(DotulousError::SourceNotFound)
+    }
+    Ok(source)
+}
+
+/// Rewrites the manifest of the profile with `profile_name` in the current manifest format (see
+/// [`crate::migrate::migrate_manifest`]), where `dotulous_path` is the user's dotulous folder. If
+/// the profile (or any profile extending it) was trusted, it stays trusted with the rewritten
+/// manifest, as migrating never changes what a profile does.
+///
+/// Returns the version the manifest was at beforehand, or [`None`] if it was already up to date and
+/// nothing was changed.
+pub fn migrate(dotulous_path: &Path, profile_name: &str) -> Result<Option<u32>, DotulousError> {
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    let profile: DotfileProfile = DotfileProfile::find_profile(dotulous_path, profile_name)?;
+    let contents: String = fs::read_to_string(&profile.manifest_path).map_err(DotulousError::FailedReadManifest)?;
+    let manifest: Value = ManifestFormat::from_path(&profile.manifest_path).parse(&contents)?;
+    let version: u32 = manifest_version(&manifest);
+    if version == MANIFEST_VERSION {
+        return Ok(None)
+    }
+
+    let trusted: Vec<(TrustScope, DotfileProfile)> = trusted_dependents(dotulous_path, &meta, &profile)?;
+    profile.save_manifest()?;
+    retrust(&mut meta, &trusted)?;
+    meta.save_meta(dotulous_path)?;
+    Ok(Some(version))
+}
+
+/// Rewrites the manifest of the profile with `profile_name` in the given `format` (see
+/// [`ManifestFormat`]), removing it's old manifest, where `dotulous_path` is the user's dotulous
+/// folder. Like [`migrate`], the profile (and any profile extending it) stays trusted if it was.
+///
+/// Returns the profile, as read from it's new manifest.
+pub fn convert(dotulous_path: &Path, profile_name: &str, format: ManifestFormat) -> Result<DotfileProfile, DotulousError> {
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    let mut profile: DotfileProfile = DotfileProfile::find_profile(dotulous_path, profile_name)?;
+    let old_manifest_path: PathBuf = profile.manifest_path.clone();
+    let new_manifest_path: PathBuf = profile.repo_path.join(format.file_name());
+    if old_manifest_path == new_manifest_path {
+        return Ok(profile)
+    }
+
+    let trusted: Vec<(TrustScope, DotfileProfile)> = trusted_dependents(dotulous_path, &meta, &profile)?;
+    profile.manifest_path = new_manifest_path;
+    profile.save_manifest()?;
+    fs::remove_file(&old_manifest_path).map_err(DotulousError::FailedSaveManifest)?;
+    let trusted: Vec<(TrustScope, DotfileProfile)> = trusted.into_iter()
+        .filter_map(|(scope, other)| Some((scope, DotfileProfile::from_manifest(&other.repo_path).ok()?)))
+        .collect();
+    retrust(&mut meta, &trusted)?;
+    meta.save_meta(dotulous_path)?;
+    DotfileProfile::from_manifest(&profile.repo_path)
+}
+
+/// Returns every trusted profile inside `dotulous_path` whose trust depends on `profile`'s
+/// manifest, being `profile` itself and any profile extending it. Used to keep them trusted with
+/// [`retrust`] when the manifest is rewritten without changing what it does.
+fn trusted_dependents(dotulous_path: &Path, meta: &Meta, profile: &DotfileProfile) -> Result<Vec<(TrustScope, DotfileProfile)>, DotulousError> {
+    let trusted: Vec<(TrustScope, DotfileProfile)> = list(dotulous_path)?.into_iter()
+        .filter_map(|summary| DotfileProfile::from_manifest(&summary.path).ok())
+        .filter_map(|other| Some((trust_scope(meta, &other)?, other)))
+        .filter(|(_, other)| other.repo_path == profile.repo_path
+            || other.base_profiles().is_ok_and(|bases| bases.iter().any(|base| base.repo_path == profile.repo_path)))
+        .collect();
+    Ok(trusted)
+}
+
+/// Trusts each of `profiles` again as far as they were with their current manifest hash, in
+/// `meta`. See [`trusted_dependents`].
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+fn retrust(meta: &mut Meta, profiles: &[(TrustScope, DotfileProfile)]) -> Result<(), DotulousError> {
+    for (scope, profile) in profiles {
+        meta.trust_profile(profile.repo_path.clone(), profile.manifest_hash()?, profile.version.clone(), *scope);
+    }
+    Ok(())
+}
+
+/// Renames the profile with `profile_name` to `new_name`, where `dotulous_path` is the user's
+/// dotulous folder. It's folder is moved to the sanitized `new_name`, and the `name` in it's
+/// manifest is changed. Returns the renamed profile, as read from it's new manifest.
+///
+/// The meta is updated to point at the new folder (see [`Meta::rename_profile`]), and if the
+/// profile was trusted it stays trusted with it's renamed manifest. If the profile is currently
+/// loaded, it's template & secrets caches are moved along with it, and every symlink it put onto
+/// the system is pointed at the new locations, so it can still be unloaded. Profiles extending it
+/// by name are **not** changed.
+///
+/// If a profile with the new folder name already exists, [`Err`] with
+/// [`DotulousError::ProfileAlreadyExists`] is returned and nothing is changed.
+pub fn rename(dotulous_path: &Path, home_path: &Path, profile_name: &str, new_name: &str) -> Result<DotfileProfile, DotulousError> {
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    let profile: DotfileProfile = DotfileProfile::find_profile(dotulous_path, profile_name)?;
+    let new_path: PathBuf = dotulous_path.join(sanitize_filename::sanitize(new_name));
+    if new_path.exists() && new_path != profile.repo_path {
+        return Err(DotulousError::ProfileAlreadyExists)
+    }
+    let was_trusted: Option<TrustScope> = trust_scope(&meta, &profile);
+
+    if new_path != profile.repo_path {
+        fs::rename(&profile.repo_path, &new_path).map_err(DotulousError::FailedRenameProfile)?;
+    }
+    let mut renamed: DotfileProfile = DotfileProfile::from_manifest(&new_path)?;
+    renamed.name = new_name.to_string();
+    renamed.save_manifest()?;
+
+    meta.rename_profile(&profile.repo_path, &new_path, new_name);
+    if let Some(scope) = was_trusted {
+        meta.trust_profile(new_path.clone(), renamed.manifest_hash()?, renamed.version.clone(), scope);
+    }
+    if let Some(loaded) = meta.current_profile().filter(|p| p.repo_path == new_path) {
+        let moves: [(PathBuf, PathBuf); 3] = [
+            (profile.repo_path.clone(), new_path.clone()),
+            (profile.template_cache_path(dotulous_path), loaded.template_cache_path(dotulous_path)),
+            (profile.secret_cache_path(dotulous_path), loaded.secret_cache_path(dotulous_path))
+        ];
+        for (from, to) in moves.iter().skip(1) {
+            if from.exists() && from != to {
+                fs::rename(from, to).map_err(DotulousError::FailedRenameProfile)?;
+            }
+        }
+        relink(&loaded, home_path, &moves)?;
+    }
+    meta.save_meta(dotulous_path)?;
+    Ok(renamed)
+}
+
+/// Points every symlink the loaded `profile` put onto the system that leads inside one of the
+/// `from` folders of `moves` at the matching `to` folder instead. Each is swapped in place with
+/// [`platform::replace_symlink`], so none go missing if this fails part way. See [`rename`].
+fn relink(profile: &DotfileProfile, home_path: &Path, moves: &[(PathBuf, PathBuf)]) -> Result<(), DotulousError> {
+    let target_path: PathBuf = profile.target_path(home_path);
+    let destinations = profile.entries().map(|(_, entry)| &entry.destination)
+        .chain(profile.assets().iter().map(|asset| &asset.dest))
+        .map(|destination| target_path.join(expand_path(destination, home_path)));
+    for destination in destinations {
+        let Ok(link) = fs::read_link(&destination) else { continue };
+        let moved: Option<PathBuf> = moves.iter()
+            .find_map(|(from, to)| link.strip_prefix(from).ok().map(|relative| to.join(relative)));
+        let Some(moved) = moved else { continue };
+        platform::replace_symlink(&moved, &destination).map_err(DotulousError::FailedRenameProfile)?;
+    }
+    Ok(())
+}
+
+/// Marks `profile` as the currently loaded profile without changing the system, where
+/// `dotulous_path` is the user's dotulous folder. This is for profiles whose files are already in
+/// place, such as ones just made by adopting existing files (see [`DotfileProfile::adopt_file`]).
+///
+/// If a profile is already loaded, [`Err`] with [`DotulousError::ProfileAlreadyLoaded`] is
+/// returned, as it would otherwise be forgotten about without being unloaded. As nothing is
+/// created, no [`LoadState`] is kept, so unloading falls back to checking each destination.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+pub fn mark_loaded(dotulous_path: &Path, home_path: &Path, profile: &DotfileProfile) -> Result<(), DotulousError> {
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    if meta.current_profile().is_some() {
+        return Err(DotulousError::ProfileAlreadyLoaded)
+    }
+
+    forget_state(dotulous_path, ApplyMode::Apply);
+    meta.set_current_profile(profile, home_path);
+    meta.save_meta(dotulous_path)?;
+    record_generation(dotulous_path, "adopt", None, Some(profile));
+    Ok(())
+}
+
+/// Returns the current [`Status`] of dotulous, where `dotulous_path` is the user's dotulous
+/// folder. Only folders with a manifest are counted as profiles, so dotulous's own folders
+/// (e.g. the cache) are skipped.
+pub fn status(dotulous_path: &Path) -> Result<Status, DotulousError> {
+    let meta: Meta = Meta::load_meta(dotulous_path)?;
+    let paths: ReadDir = fs::read_dir(dotulous_path).map_err(DotulousError::FailedReadDotulousDirectory)?;
+    let mut profiles: Vec<String> = paths.filter_map(Result::ok)
+        .filter(|path| ManifestFormat::find_manifest(&path.path()).is_some())
+        .filter_map(|path| path.file_name().to_str().map(str::to_string))
+        .collect();
+    profiles.sort();
+
+    Ok(Status {
+        current_profile: meta.current_profile(),
+        ephemeral: meta.is_ephemeral(),
+        profiles
+    })
+}
+
+/// Returns a [`ProfileSummary`] of every profile inside `dotulous_path`, the user's dotulous
+/// folder, sorted by folder name. Profiles whose manifest fails to load are skipped.
+pub fn list(dotulous_path: &Path) -> Result<Vec<ProfileSummary>, DotulousError> {
+    let meta: Meta = Meta::load_meta(dotulous_path)?;
+    let current_profile: Option<DotfileProfile> = meta.current_profile();
+    let summaries: Vec<ProfileSummary> = status(dotulous_path)?.profiles.iter()
+        .filter_map(|folder_name| DotfileProfile::find_profile(dotulous_path, folder_name).ok())
+        .map(|profile| ProfileSummary {
+            trusted: trust_scope(&meta, &profile).is_some(),
+            trust_scope: trust_scope(&meta, &profile),
+            loaded: current_profile.as_ref().is_some_and(|p| p.repo_path == profile.repo_path),
+            file_count: profile.files().len(),
+            name: profile.name,
+            path: profile.repo_path,
+            description: profile.description,
+            author: profile.author,
+            homepage: profile.homepage,
+            version: profile.version
+        })
+        .collect();
+    Ok(summaries)
+}
+
+/// Loads the meta inside `dotulous_path` like [`Meta::load_meta`], also trusting the profile in
+/// `trusted_once` if given, see [`Meta::trust_once`].
+fn load_meta_trusting(dotulous_path: &Path, trusted_once: Option<&TrustOnce>) -> Result<Meta, DotulousError> {
+    let mut meta: Meta = Meta::load_meta(dotulous_path)?;
+    if let Some(once) = trusted_once {
+        meta.trust_once(once.clone());
+    }
+    Ok(meta)
+}
+
+/// Returns if `profile` is trusted in `meta` at least as far as `scope`, with the current hash of
+/// it's manifest on disk. If the manifest can't be read, it's not trusted.
+fn is_trusted(meta: &Meta, profile: &DotfileProfile, scope: TrustScope) -> bool {
+    trust_scope(meta, profile).is_some_and(|trusted| trusted >= scope)
+}
+
+/// Returns `profile` without it's commands (see [`DotfileProfile::skipping`]) unless it's trusted
+/// in `meta` to run them with the current hash of it's manifest, warning that they're skipped.
+/// Used before running the commands of a profile that isn't read fresh from it's manifest, such as
+/// the loaded profile's `removal_commands`, as it may have been loaded with only it's files
+/// trusted or it's manifest may have changed since.
+fn trusted_commands(meta: &Meta, profile: &DotfileProfile) -> DotfileProfile {
+    if !profile.runs_commands() || is_trusted(meta, profile, TrustScope::Commands) {
+        return profile.clone()
+    }
+    say!("WARNING: Profile \"{}\" isn't trusted to run it's commands, so they're being skipped.", profile.name);
+    profile.skipping(Skip { hooks: true, files: false })
+}
+
+/// Returns how far `profile` is trusted in `meta` with the current hash of it's manifest on disk,
+/// or [`None`] if it isn't. If the manifest can't be read, it's not trusted.
+fn trust_scope(meta: &Meta, profile: &DotfileProfile) -> Option<TrustScope> {
+    let hash: String = profile.manifest_hash().ok()?;
+    meta.trust_scope(&profile.repo_path, &hash)
+}
+
+/// Restores the backup made while loading the current profile, if there is one, removing it from
+/// `meta`. This should be called right after the current profile is unloaded. Nothing is restored
+/// if `mode` is [`ApplyMode::DryRun`].
+///
+/// As the backup is kept on disk either way, failing to restore it only prints a warning.
+fn restore_current_backup(meta: &mut Meta, mode: ApplyMode) {
+    if mode == ApplyMode::DryRun {
+        return
+    }
+    let Some(path) = meta.take_current_backup() else { return };
+    let mut backup: Backup = match Backup::load(&path) {
+        Ok(r) => r,
+        Err(e) => {
+            say!("WARNING: Could not load backup {path:?}: {e:#}");
+            return
+        }
+    };
+    say!();
+    if let Err(e) = backup.restore() {
+        say!("WARNING: Could not restore backup {path:?}: {e:#}");
+    }
+}
+
+/// Restores the current profile's backup like [`restore_current_backup`], unless `skip` leaves out
+/// files, in which case it's only forgotten from `meta` and left on disk to be restored by hand.
+fn release_current_backup(meta: &mut Meta, mode: ApplyMode, skip: Skip) {
+    if !skip.files {
+        return restore_current_backup(meta, mode)
+    }
+    if mode == ApplyMode::DryRun {
+        return
+    }
+    let Some(path) = meta.take_current_backup() else { return };
+    match Backup::load(&path) {
+        Ok(backup) => say!("NOTE: Leaving backup {path:?} in place, use `dotulous backups restore {}` to restore it.", backup.id()),
+        Err(_) => say!("NOTE: Leaving backup {path:?} in place, use `dotulous backups list` to find & restore it.")
+    }
+}
+
+/// Saves the [`LoadState`] of the profile that was just loaded, see [`save_state`]. If `skip`
+/// left out files, nothing was put onto the system, so no state is kept and unloading falls back
+/// to checking each destination.
+fn keep_state(dotulous_path: &Path, state: &LoadState, skip: Skip) {
+    if skip.files {
+        forget_state(dotulous_path, ApplyMode::Apply);
+    } else {
+        save_state(dotulous_path, state);
+    }
+}
+
+/// Restores only the files in the current profile's backup that were at one of `destinations`,
+/// leaving the backup in `meta` for the rest of the profile, see [`Backup::restore_some`]. This
+/// should be called right after part of the current profile is unloaded.
+///
+/// As the backup is kept on disk either way, failing to load it only prints a warning.
+fn restore_current_backup_of(meta: &Meta, destinations: &[PathBuf]) {
+    let Some(path) = meta.current_backup() else { return };
+    match Backup::load(path) {
+        Ok(mut backup) => backup.restore_some(destinations),
+        Err(e) => say!("WARNING: Could not load backup {path:?}: {e:#}")
+    }
+}
+
+/// Keeps the `backup` made while loading part of the current profile, moving it's files into the
+/// current profile's backup if there already is one, so they're all restored together.
+///
+/// As the backup is kept on disk either way, failing to merge it only prints a warning, and it's
+/// left as it is.
+fn keep_backup(meta: &mut Meta, backup: Option<Backup>) {
+    let Some(backup) = backup else { return };
+    let Some(path) = meta.current_backup() else {
+        meta.set_current_backup(Some(backup.path));
+        return
+    };
+    let merged: Result<(), DotulousError> = Backup::load(path).and_then(|mut current| current.absorb(backup));
+    if let Err(e) = merged {
+        say!("WARNING: Could not merge backups into {path:?}: {e:#}");
+    }
+}
+
+/// Returns the *absolute* path of every destination in `profile`, as recorded in the
+/// [`LoadState`] and backups.
+fn absolute_destinations(profile: &DotfileProfile, home_path: &Path) -> Vec<PathBuf> {
+    let target_path: PathBuf = profile.target_path(home_path);
+    profile.destinations().iter().map(|destination| target_path.join(expand_path(destination, home_path))).collect()
+}
+
+/// Loads the [`LoadState`] of the currently loaded profile, where `dotulous_path` is the user's
+/// dotulous folder. Returns [`None`] if there isn't one, so unloading falls back to checking each
+/// destination.
+///
+/// As unloading still works without it, failing to load it only prints a warning.
+fn load_state(dotulous_path: &Path) -> Option<LoadState> {
+    match LoadState::load(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => {
+            say!("WARNING: Could not load state: {e:#}");
+            None
+        }
+    }
+}
+
+/// Saves the [`LoadState`] of the profile that was just loaded, where `dotulous_path` is the
+/// user's dotulous folder.
+///
+/// As unloading still works without it, failing to save it only prints a warning.
+fn save_state(dotulous_path: &Path, state: &LoadState) {
+    if let Err(e) = state.save(dotulous_path) {
+        say!("WARNING: Could not save state: {e:#}");
+    }
+}
+
+/// Removes the [`LoadState`] of the profile that was just unloaded, where `dotulous_path` is the
+/// user's dotulous folder. Nothing is removed if `mode` is [`ApplyMode::DryRun`].
+///
+/// As a stale state is only ever used for a profile that is loaded, failing to remove it only
+/// prints a warning.
+fn forget_state(dotulous_path: &Path, mode: ApplyMode) {
+    if mode == ApplyMode::DryRun {
+        return
+    }
+    if let Err(e) = LoadState::remove(dotulous_path) {
+        say!("WARNING: Could not remove state: {e:#}");
+    }
+}
+
+/// Records a new generation in the generation log for `action`, going from the `previous` profile
+/// to the `current` one, where `dotulous_path` is the user's dotulous folder.
+///
+/// As the generation log is only informational, failing to update it only prints a warning.
+fn record_generation(dotulous_path: &Path, action: &str, previous: Option<&DotfileProfile>, current: Option<&DotfileProfile>) {
+    let mut log: GenerationLog = match GenerationLog::load(dotulous_path) {
+        Ok(r) => r,
+        Err(e) => {
+            say!("WARNING: Could not load generation log: {e:#}");
+            return
+        }
+    };
+    log.record(action, previous, current);
+    if let Err(e) = log.save(dotulous_path) {
+        say!("WARNING: Could not save generation log: {e:#}");
+    }
+}
+
+/// Removes the assets `unloaded` linked from the asset cache inside `dotulous_path`, unless the
+/// profile loaded in `meta`, or the one restored once it's ephemeral load is cleared, still uses
+/// them (see [`asset::remove_cached`]).
+///
+/// As the assets can be downloaded again, failing to remove them only prints a warning.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+fn clean_assets(dotulous_path: &Path, meta: &Meta, unloaded: &DotfileProfile) {
+    if unloaded.assets().is_empty() {
+        return
+    }
+    let in_use: Vec<Asset> = meta.current_profile().into_iter()
+        .chain(meta.ephemeral().and_then(|ephemeral| ephemeral.previous_profile.clone()))
+        .flat_map(|profile| profile.assets().to_vec())
+        .collect();
+    match asset::remove_cached(&asset::cache_path(dotulous_path), unloaded.assets(), &in_use) {
+        Ok(removed) => for path in removed {
+            say!("  Removed cached asset {path:?}");
+        },
+        Err(e) => say!("WARNING: Failed to remove unused assets from the cache: {e:#}")
+    }
+}
+
+/// Cleans up after loading a profile failed with `error` and was rolled back, returning `error`.
+/// As `previous_profile` was already unloaded beforehand, `meta` is saved with no profile loaded
+/// and the unload is recorded as a generation.
+fn abort_load(dotulous_path: &Path, meta: &mut Meta, previous_profile: Option<&DotfileProfile>, error: DotulousError) -> DotulousError {
+    forget_state(dotulous_path, ApplyMode::Apply);
+    meta.empty_current_profile();
+    if let Err(e) = meta.save_meta(dotulous_path) {
+        return e
+    }
+    if previous_profile.is_some() {
+        record_generation(dotulous_path, "unload", previous_profile, None);
+    }
+    error
+}
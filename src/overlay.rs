@@ -0,0 +1,91 @@
+use std::{env, fs::{self, DirEntry, ReadDir}, path::{Path, PathBuf}, process};
+
+use crate::{error::DotulousError, platform::symlink};
+
+/// A temporary home folder that mirrors the user's real home folder, used to apply a profile to a
+/// single command without touching the real system.
+///
+/// The overlay starts out as a folder of symlinks to every entry in the real home folder. Before a
+/// profile's files are linked into it, each destination is "unfolded" with
+/// [`Overlay::prepare_destination`]: any mirrored parent directories are replaced with real
+/// directories (themselves mirroring their contents), so the profile's symlinks never end up
+/// being written inside the user's real home folder.
+///
+/// Once finished with, the overlay should be removed with [`Overlay::destroy`]. This only removes
+/// the overlay's own symlinks, never what they point to.
+pub struct Overlay {
+    /// The *absolute* path to the overlay, to be used as the home folder.
+    pub path: PathBuf,
+    /// The *absolute* path to the user's real home folder.
+    real_home: PathBuf
+}
+impl Overlay {
+    /// Creates a new overlay inside the system's temporary directory, mirroring `real_home`.
+    pub fn create(real_home: &Path) -> Result<Overlay, DotulousError> {
+        let path: PathBuf = env::temp_dir().join(format!("dotulous-exec-{}", process::id()));
+        fs::create_dir_all(&path).map_err(DotulousError::FailedCreateOverlay)?;
+
+        let overlay: Overlay = Overlay { path, real_home: real_home.to_path_buf() };
+        overlay.mirror_dir(Path::new(""))?;
+        Ok(overlay)
+    }
+
+    /// Prepares the overlay for a symlink to be created at `destination`, relative to the home
+    /// folder. Every parent of the destination is unfolded into a real directory, and the mirrored
+    /// destination itself is removed if present.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn prepare_destination(&self, destination: &Path) -> Result<(), DotulousError> {
+        let mut relative: PathBuf = PathBuf::new();
+        let components: Vec<_> = destination.components().collect();
+        let Some((_, parents)) = components.split_last() else { return Ok(()) };
+        for component in parents {
+            relative.push(component);
+            let path: PathBuf = self.path.join(&relative);
+            if path.is_symlink() {
+                fs::remove_file(&path).map_err(DotulousError::FailedCreateOverlay)?;
+                fs::create_dir(&path).map_err(DotulousError::FailedCreateOverlay)?;
+                self.mirror_dir(&relative)?;
+            } else if !path.exists() {
+                fs::create_dir(&path).map_err(DotulousError::FailedCreateOverlay)?;
+            }
+        }
+
+        let path: PathBuf = self.path.join(destination);
+        if path.is_symlink() {
+            fs::remove_file(&path).map_err(DotulousError::FailedCreateOverlay)?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites `path` to point inside the overlay if it's a path inside the real home folder,
+    /// returning [`None`] otherwise. Used for remapping environment variables such as
+    /// `XDG_CONFIG_HOME`, and a profile's `target_root`.
+    pub fn remap(&self, path: &Path) -> Option<PathBuf> {
+        let stripped: &Path = path.strip_prefix(&self.real_home).ok()?;
+        Some(self.path.join(stripped))
+    }
+
+    /// Removes the overlay from disk. This does not follow any symlinks, so the real home folder
+    /// is left untouched.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn destroy(self) -> Result<(), DotulousError> {
+        fs::remove_dir_all(&self.path).map_err(DotulousError::FailedRemoveOverlay)?;
+        Ok(())
+    }
+
+    /// Fills the directory at `relative` inside the overlay with symlinks to every entry of the same
+    /// directory inside the real home folder.
+    fn mirror_dir(&self, relative: &Path) -> Result<(), DotulousError> {
+        let entries: ReadDir = fs::read_dir(self.real_home.join(relative)).map_err(DotulousError::FailedCreateOverlay)?;
+        for entry in entries {
+            let entry: DirEntry = entry.map_err(DotulousError::FailedCreateOverlay)?;
+            let link: PathBuf = self.path.join(relative).join(entry.file_name());
+            symlink(entry.path(), link).map_err(DotulousError::FailedCreateOverlay)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use std::{collections::HashMap, fs, process::{Command, Stdio}};
+
+use crate::{error::DotulousError, platform, report};
+
+/// The distribution the user is currently running, read from `/etc/os-release`.
+///
+/// A distro is identified by a list of ids, most specific first. This is the `ID` field followed
+/// by any ids in the `ID_LIKE` field, so for example Ubuntu would be `["ubuntu", "debian"]`.
+/// These ids are what a profile's `packages` keys are matched against.
+#[derive(Debug)]
+pub struct Distro {
+    /// The ids of the distro, most specific first.
+    pub ids: Vec<String>
+}
+impl Distro {
+    /// Detects the current distro from `/etc/os-release`, returning [`None`] if it could not be
+    /// read or does not contain an `ID`.
+    pub fn detect() -> Option<Distro> {
+        let contents: String = fs::read_to_string("/etc/os-release").ok()?;
+        let mut id: Option<String> = None;
+        let mut id_like: Vec<String> = Vec::new();
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let value: &str = value.trim().trim_matches('"').trim_matches('\'');
+            match key.trim() {
+                "ID" => id = Some(value.to_lowercase()),
+                "ID_LIKE" => id_like = value.split_whitespace().map(str::to_lowercase).collect(),
+                _ => {}
+            }
+        }
+
+        let mut ids: Vec<String> = vec![id?];
+        ids.append(&mut id_like);
+        Some(Distro { ids })
+    }
+
+    /// Selects the package list from a profile's `packages` map that applies to this distro,
+    /// trying each of the distro's ids in order. Returns [`None`] if no list matches.
+    pub fn select<'a>(&self, packages: &'a HashMap<String, Vec<String>>) -> Option<&'a Vec<String>> {
+        self.ids.iter().find_map(|id| packages.get(id))
+    }
+
+    /// Returns the native [`PackageManager`] for this distro, or [`None`] if it is not known.
+    pub fn package_manager(&self) -> Option<PackageManager> {
+        self.ids.iter().find_map(|id| PackageManager::from_distro_id(id))
+    }
+}
+
+/// A native package manager that dotulous knows how to query and install packages with.
+#[derive(Clone, Copy, Debug)]
+pub enum PackageManager {
+    /// Arch Linux and derivatives.
+    Pacman,
+    /// Debian, Ubuntu and derivatives.
+    Apt,
+    /// Fedora and RHEL.
+    Dnf,
+    /// openSUSE.
+    Zypper,
+    /// Void Linux.
+    Xbps,
+    /// Alpine Linux.
+    Apk
+}
+impl PackageManager {
+    /// Returns the package manager used by the distro with the given `/etc/os-release` `id`.
+    fn from_distro_id(id: &str) -> Option<PackageManager> {
+        match id {
+            "arch" | "manjaro" | "endeavouros" => Some(PackageManager::Pacman),
+            "debian" | "ubuntu" | "linuxmint" | "pop" => Some(PackageManager::Apt),
+            "fedora" | "rhel" | "centos" => Some(PackageManager::Dnf),
+            "opensuse" | "opensuse-tumbleweed" | "opensuse-leap" | "suse" => Some(PackageManager::Zypper),
+            "void" => Some(PackageManager::Xbps),
+            "alpine" => Some(PackageManager::Apk),
+            _ => None
+        }
+    }
+
+    /// Returns the program & arguments used to query if a single package is installed, which the
+    /// package's name is appended to.
+    fn query_command(&self) -> (&str, &[&str]) {
+        match self {
+            PackageManager::Pacman => ("pacman", &["-Q"]),
+            PackageManager::Apt => ("dpkg", &["-s"]),
+            PackageManager::Dnf | PackageManager::Zypper => ("rpm", &["-q"]),
+            PackageManager::Xbps => ("xbps-query", &[]),
+            PackageManager::Apk => ("apk", &["info", "-e"])
+        }
+    }
+
+    /// Returns the command (program and arguments) used to install packages, which the package
+    /// names are appended to. This is ran through `sudo`.
+    pub fn install_command(&self) -> &[&str] {
+        match self {
+            PackageManager::Pacman => &["pacman", "-S", "--needed"],
+            PackageManager::Apt => &["apt-get", "install"],
+            PackageManager::Dnf => &["dnf", "install"],
+            PackageManager::Zypper => &["zypper", "install"],
+            PackageManager::Xbps => &["xbps-install"],
+            PackageManager::Apk => &["apk", "add"]
+        }
+    }
+
+    /// Checks if the given `package` is installed on the system. If the package manager can't be
+    /// ran, such as when it isn't installed, the package counts as not installed.
+    pub fn is_installed(&self, package: &str) -> bool {
+        let (program, args) = self.query_command();
+        Command::new(program)
+            .args(args)
+            .arg(package)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+
+    /// Returns all of the given `packages` that are not currently installed.
+    pub fn missing(&self, packages: &[String]) -> Vec<String> {
+        packages.iter().filter(|p| !self.is_installed(p)).cloned().collect()
+    }
+
+    /// Installs the given `packages` with `sudo`, inheriting stdio so the user can interact with
+    /// the package manager's own prompts. It's output goes to stderr if JSON output is on, see
+    /// [`report::child_output`].
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn install(&self, packages: &[String]) -> Result<(), DotulousError> {
+        let status = Command::new("sudo")
+            .args(self.install_command())
+            .args(packages)
+            .stdout(report::child_output())
+            .status();
+        platform::check_status(status).map_err(DotulousError::FailedInstallPackages)
+    }
+}
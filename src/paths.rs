@@ -0,0 +1,108 @@
+use std::{env, fs, path::{Path, PathBuf}};
+
+use crate::{error::DotulousError, meta::Meta, platform::symlink};
+
+/// The folders dotulous keeps it's files in, following the XDG base directory specification.
+///
+/// - `data` holds the profiles, the meta and everything dotulous manages itself, defaulting to
+///   `$XDG_DATA_HOME/dotulous` (or `~/.local/share/dotulous`). This is the `dotulous_path` taken
+///   throughout the crate.
+/// - `config` holds the files edited by the user, such as `vars.json`, defaulting to
+///   `$XDG_CONFIG_HOME/dotulous` (or `~/.config/dotulous`).
+///
+/// Setting `DOTULOUS_HOME` overrides both, keeping everything in that one folder.
+///
+/// Older versions of dotulous kept everything inside `~/.dotulous`, which is moved over with
+/// [`Paths::migrate_legacy`].
+#[derive(Debug, Clone)]
+pub struct Paths {
+    /// The *absolute* path to the data folder.
+    pub data: PathBuf,
+    /// The *absolute* path to the config folder.
+    pub config: PathBuf
+}
+impl Paths {
+    /// Resolves the dotulous folders for the user with the home folder at `home_path`, from the
+    /// environment.
+    pub fn resolve(home_path: &Path) -> Self {
+        if let Some(dotulous_home) = env_path("DOTULOUS_HOME") {
+            return Self::in_folder(dotulous_home)
+        }
+
+        let data_home: PathBuf = env_path("XDG_DATA_HOME").unwrap_or_else(|| home_path.join(".local/share"));
+        let config_home: PathBuf = env_path("XDG_CONFIG_HOME").unwrap_or_else(|| home_path.join(".config"));
+        Self {
+            data: data_home.join("dotulous"),
+            config: config_home.join("dotulous")
+        }
+    }
+
+    /// Keeps everything inside the one folder at `path`, the same as setting `DOTULOUS_HOME`.
+    pub fn in_folder(path: PathBuf) -> Self {
+        Self { data: path.clone(), config: path }
+    }
+
+    /// Moves the legacy `~/.dotulous` folder (inside `home_path`) to the data folder, if it exists
+    /// and the data folder doesn't yet. Files belonging in the config folder are moved there, and
+    /// every path stored in the meta is updated to the new location.
+    ///
+    /// A symlink is left at the legacy location pointing to the data folder, so links made by the
+    /// currently loaded profile keep working until it is next reloaded.
+    ///
+    /// Returns the legacy path if a migration took place.
+    pub fn migrate_legacy(&self, home_path: &Path) -> Result<Option<PathBuf>, DotulousError> {
+        let legacy_path: PathBuf = home_path.join(".dotulous");
+        if legacy_path.is_symlink() || !legacy_path.is_dir() || self.data.exists() || legacy_path == self.data {
+            return Ok(None)
+        }
+
+        if let Some(parent) = self.data.parent() {
+            fs::create_dir_all(parent).map_err(DotulousError::FailedMigrateLegacyFolder)?;
+        }
+        fs::rename(&legacy_path, &self.data).map_err(DotulousError::FailedMigrateLegacyFolder)?;
+        symlink(&self.data, &legacy_path).map_err(DotulousError::FailedMigrateLegacyFolder)?;
+
+        let legacy_vars: PathBuf = self.data.join("vars.json");
+        if self.config != self.data && legacy_vars.exists() {
+            fs::create_dir_all(&self.config).map_err(DotulousError::FailedMigrateLegacyFolder)?;
+            fs::rename(&legacy_vars, self.config.join("vars.json")).map_err(DotulousError::FailedMigrateLegacyFolder)?;
+        }
+
+        let mut meta: Meta = Meta::load_meta(&self.data)?;
+        meta.relocate(&legacy_path, &self.data);
+        meta.save_meta(&self.data)?;
+        Ok(Some(legacy_path))
+    }
+}
+
+/// Expands `path`, a source or destination from a manifest, where `home_path` is the user's home
+/// folder. A leading `~` and the variables `$HOME` & `$XDG_CONFIG_HOME` (also written as `${HOME}`)
+/// are expanded, with `$XDG_CONFIG_HOME` falling back to `~/.config` as in [`Paths::resolve`]. Any
+/// other variables are left as they are.
+///
+/// As the expanded path is *absolute*, it replaces whatever it's joined onto, so `~/.bashrc` always
+/// ends up in the home folder, even for a profile with a `target_root`.
+pub fn expand_path(path: &Path, home_path: &Path) -> PathBuf {
+    let Some(text) = path.to_str() else { return path.to_path_buf() };
+    if !text.starts_with('~') && !text.contains('$') {
+        return path.to_path_buf()
+    }
+    let expanded = shellexpand::full_with_context_no_errors(
+        text,
+        || Some(home_path.to_string_lossy()),
+        |variable| match variable {
+            "HOME" => Some(home_path.to_path_buf()),
+            "XDG_CONFIG_HOME" => Some(env_path("XDG_CONFIG_HOME").unwrap_or_else(|| home_path.join(".config"))),
+            _ => None
+        }.map(|path| path.to_string_lossy().to_string())
+    );
+    PathBuf::from(expanded.as_ref())
+}
+
+/// Returns the path inside the environment variable `name`, if it is set to an absolute path. As
+/// per the XDG specification, relative paths are ignored.
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
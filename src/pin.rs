@@ -0,0 +1,148 @@
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::{Path, PathBuf}, process::Command};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DotulousError, profile::DotfileProfile};
+
+/// The name of the lockfile inside a profile's folder.
+pub const LOCKFILE_NAME: &str = "dotulous.lock";
+
+/// The git commits a profile and it's `pinned_repos` were at when it was trusted, stored inside
+/// the profile's folder as `dotulous.lock`. Loading with `--frozen` refuses to carry on if they've
+/// moved since, see [`Pins::check`].
+/// ```json
+/// {
+///     "profile": "4f2a...",
+///     "repos": { "~/.tmux/plugins/tpm": "9b1c..." }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Pins {
+    /// The commit the profile's own folder was at, or [`None`] if it isn't a git repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// The commit each of the profile's `pinned_repos` was at, keyed by the path written in the
+    /// manifest. [`None`] if the repository didn't exist.
+    #[serde(default)]
+    pub repos: BTreeMap<String, Option<String>>
+}
+impl Pins {
+    /// Finds the commits `profile` and it's `pinned_repos` are at right now, where `home_path` is
+    /// the user's home folder. See [`resolve_repo_path`] for how the repositories are found.
+    pub fn current(profile: &DotfileProfile, home_path: &Path) -> Pins {
+        let repos: BTreeMap<String, Option<String>> = profile.pinned_repos.iter()
+            .map(|repo| (repo.clone(), head_commit(&resolve_repo_path(profile, home_path, repo))))
+            .collect();
+        Pins { profile: head_commit(&profile.repo_path), repos }
+    }
+
+    /// Returns if there's nothing to pin, as the profile isn't a git repository and has no
+    /// `pinned_repos`.
+    pub fn is_empty(&self) -> bool {
+        self.profile.is_none() && self.repos.is_empty()
+    }
+
+    /// Load the pins from `dotulous.lock` inside the profile's folder at `repo_path`, returning
+    /// [`None`] if there isn't one.
+    pub fn load(repo_path: &Path) -> Result<Option<Pins>, DotulousError> {
+        let contents: String = match fs::read_to_string(repo_path.join(LOCKFILE_NAME)) {
+            Ok(r) => r,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(DotulousError::FailedReadPins(e))
+        };
+        serde_json::from_str(&contents).map(Some).map_err(DotulousError::FailedDeserializePins)
+    }
+
+    /// Save the pins to `dotulous.lock` inside the profile's folder at `repo_path`.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn save(&self, repo_path: &Path) -> Result<(), DotulousError> {
+        let serialized: String = serde_json::to_string_pretty(self).map_err(DotulousError::FailedSerializePins)?;
+        fs::write(repo_path.join(LOCKFILE_NAME), serialized).map_err(DotulousError::FailedSavePins)?;
+        Ok(())
+    }
+
+    /// Records the commits `profile` is at right now (see [`Pins::current`]) into it's
+    /// `dotulous.lock`, done whenever it's trusted. Nothing is written if there's nothing to pin,
+    /// unless the profile already has a lockfile.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn record(profile: &DotfileProfile, home_path: &Path) -> Result<(), DotulousError> {
+        let pins: Pins = Pins::current(profile, home_path);
+        if pins.is_empty() && !profile.repo_path.join(LOCKFILE_NAME).exists() {
+            return Ok(())
+        }
+        pins.save(&profile.repo_path)
+    }
+
+    /// Returns a description of everything that's different in `current` compared to these pins,
+    /// such as `profile: 4f2a... -> 9b1c...`. Empty if nothing has moved.
+    pub fn differences(&self, current: &Pins) -> Vec<String> {
+        let describe = |commit: Option<&String>| commit.map_or("(none)".to_string(), |commit| commit.chars().take(12).collect());
+        let mut differences: Vec<String> = Vec::new();
+        if self.profile != current.profile {
+            differences.push(format!("profile: {} -> {}", describe(self.profile.as_ref()), describe(current.profile.as_ref())));
+        }
+        let repos: Vec<&String> = self.repos.keys().chain(current.repos.keys().filter(|repo| !self.repos.contains_key(*repo))).collect();
+        for repo in repos {
+            let pinned: Option<&String> = self.repos.get(repo).and_then(Option::as_ref);
+            let now: Option<&String> = current.repos.get(repo).and_then(Option::as_ref);
+            if pinned != now || self.repos.contains_key(repo) != current.repos.contains_key(repo) {
+                differences.push(format!("{repo}: {} -> {}", describe(pinned), describe(now)));
+            }
+        }
+        differences
+    }
+
+    /// Checks `profile` and it's `pinned_repos` are still at the commits in it's `dotulous.lock`,
+    /// for loading with `--frozen`. Returns every difference found (see [`Pins::differences`]),
+    /// empty if nothing has moved.
+    ///
+    /// The profile itself counts as unchanged if the only difference since it's pinned commit is
+    /// `dotulous.lock`, so the lockfile can be committed alongside the profile.
+    ///
+    /// If the profile has no lockfile, [`Err`] with [`DotulousError::PinsNotFound`] is returned.
+    pub fn check(profile: &DotfileProfile, home_path: &Path) -> Result<Vec<String>, DotulousError> {
+        let Some(pinned) = Pins::load(&profile.repo_path)? else { return Err(DotulousError::PinsNotFound) };
+        let mut current: Pins = Pins::current(profile, home_path);
+        if let (Some(pinned_commit), Some(_)) = (&pinned.profile, &current.profile) {
+            if only_lockfile_changed(&profile.repo_path, pinned_commit) {
+                current.profile = Some(pinned_commit.clone());
+            }
+        }
+        Ok(pinned.differences(&current))
+    }
+}
+
+/// Returns the *absolute* path of the repository at `repo` in `profile`'s `pinned_repos`. Paths
+/// starting with `~` are inside `home_path`, other relative paths are inside the profile's folder.
+pub fn resolve_repo_path(profile: &DotfileProfile, home_path: &Path, repo: &str) -> PathBuf {
+    let expanded = shellexpand::tilde_with_context(repo, || Some(home_path.to_string_lossy()));
+    profile.repo_path.join(expanded.as_ref())
+}
+
+/// Returns the commit the git repository at `path` is at, or [`None`] if it isn't one (or git
+/// isn't installed).
+fn head_commit(path: &Path) -> Option<String> {
+    if !path.is_dir() {
+        return None
+    }
+    let output = Command::new("git").arg("-C").arg(path).args(["rev-parse", "HEAD"]).output().ok()?;
+    if !output.status.success() {
+        return None
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Returns if nothing but `dotulous.lock` differs between `commit` and the current commit of the
+/// git repository at `path`.
+fn only_lockfile_changed(path: &Path, commit: &str) -> bool {
+    let status = Command::new("git")
+        .arg("-C").arg(path)
+        .args(["diff", "--quiet", commit, "HEAD", "--", "."])
+        .arg(format!(":(exclude){LOCKFILE_NAME}"))
+        .status();
+    status.is_ok_and(|status| status.success())
+}
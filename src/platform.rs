@@ -0,0 +1,485 @@
+use std::{collections::BTreeSet, env, ffi::OsString, fs, io::{self, IsTerminal}, path::{Component, Path, PathBuf}, process::{Child, Command, ExitStatus}, thread, time::{Duration, Instant}};
+
+/// Creates a symlink at `destination` pointing to `source`.
+#[cfg(unix)]
+pub fn symlink(source: impl AsRef<Path>, destination: impl AsRef<Path>) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, destination)
+}
+
+/// Creates a symlink at `destination` pointing to `source`, using a directory symlink if `source`
+/// is a directory.
+#[cfg(windows)]
+pub fn symlink(source: impl AsRef<Path>, destination: impl AsRef<Path>) -> io::Result<()> {
+    if source.as_ref().is_dir() {
+        std::os::windows::fs::symlink_dir(source, destination)
+    } else {
+        std::os::windows::fs::symlink_file(source, destination)
+    }
+}
+
+/// Points the symlink at `destination` to `source` instead. The new symlink is made next to it then
+/// renamed over it, so `destination` is never missing, and is left as it was if anything fails.
+#[cfg(unix)]
+pub fn replace_symlink(source: &Path, destination: &Path) -> io::Result<()> {
+    let temp: PathBuf = sibling_temp_path(destination);
+    let _ = fs::remove_file(&temp);
+    symlink(source, &temp)?;
+    fs::rename(&temp, destination).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// Points the symlink at `destination` to `source` instead. The new symlink is made next to it then
+/// renamed over it, and is left as it was if making it fails.
+///
+/// Windows can't rename over a directory symlink, so one is removed first, leaving `destination`
+/// missing if the rename then fails.
+#[cfg(windows)]
+pub fn replace_symlink(source: &Path, destination: &Path) -> io::Result<()> {
+    let temp: PathBuf = sibling_temp_path(destination);
+    let _ = fs::remove_file(&temp).or_else(|_| fs::remove_dir(&temp));
+    symlink(source, &temp)?;
+    if fs::rename(&temp, destination).is_err() {
+        fs::remove_dir(destination)?;
+        fs::rename(&temp, destination)?;
+    }
+    Ok(())
+}
+
+/// Returns a hidden path next to `path` for this process to write to before renaming it over
+/// `path`, such as `.bashrc.dotulous-1234` for `.bashrc`.
+fn sibling_temp_path(path: &Path) -> PathBuf {
+    let mut name: OsString = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".dotulous-{}", std::process::id()));
+    path.with_file_name(name)
+}
+
+/// Returns if `a` & `b` are the same file on disk, such as being hardlinks of each other. Returns
+/// `false` if either can't be read.
+#[cfg(unix)]
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false
+    }
+}
+
+/// Returns if `a` & `b` are the same file on disk, such as being hardlinks of each other.
+///
+/// Windows doesn't expose file ids without unstable APIs, so files are only compared by their
+/// resolved path, meaning hardlinks are never counted as the same file.
+#[cfg(windows)]
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false
+    }
+}
+
+/// Returns if `b` is a real file with the same contents as `a`, or a real folder holding exactly the
+/// same files as `a` (each checked the same way). Symlinks inside `a` are followed, but never in
+/// `b`, as copies are always real files. Returns `false` if either can't be read.
+pub fn same_contents(a: &Path, b: &Path) -> bool {
+    let Ok(metadata) = b.symlink_metadata() else { return false };
+    if !a.is_dir() {
+        return metadata.is_file() && matches!((fs::read(a), fs::read(b)), (Ok(a), Ok(b)) if a == b)
+    }
+    if !metadata.is_dir() {
+        return false
+    }
+    let names = |path: &Path| -> io::Result<BTreeSet<OsString>> {
+        fs::read_dir(path)?.map(|entry| entry.map(|entry| entry.file_name())).collect()
+    };
+    match (names(a), names(b)) {
+        (Ok(a_names), Ok(b_names)) => a_names == b_names && a_names.iter().all(|name| same_contents(&a.join(name), &b.join(name))),
+        _ => false
+    }
+}
+
+/// Sets the permissions of the file at `path` (following symlinks) to the unix `mode`, such as
+/// `0o600`.
+#[cfg(unix)]
+pub fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+/// Does nothing, as Windows has no unix file modes. Files inside the home folder are normally only
+/// accessible by the user anyway.
+#[cfg(windows)]
+pub fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Replaces the executable at `current` with the one at `new`, which must be in the same folder.
+/// The rename is atomic, so `current` is never left half written, and a running process carries on
+/// using the file it started from.
+#[cfg(unix)]
+pub fn replace_executable(new: &Path, current: &Path) -> io::Result<()> {
+    fs::rename(new, current)
+}
+
+/// Replaces the executable at `current` with the one at `new`, which must be in the same folder.
+///
+/// Windows won't replace an executable that's running, but will rename it, so it's first moved to
+/// `<name>.old` (replacing any left from before) and put back if moving `new` into place fails.
+#[cfg(windows)]
+pub fn replace_executable(new: &Path, current: &Path) -> io::Result<()> {
+    let old: PathBuf = current.with_extension("old");
+    let _ = fs::remove_file(&old);
+    fs::rename(current, &old)?;
+    if let Err(e) = fs::rename(new, current) {
+        let _ = fs::rename(&old, current);
+        return Err(e)
+    }
+    Ok(())
+}
+
+/// Checks the current user could write to `path`, such as creating files inside it if it's a
+/// folder, without changing anything. Returns the error writing would fail with, which is
+/// [`io::ErrorKind::ReadOnlyFilesystem`] if `path` is on a read-only mount.
+#[cfg(unix)]
+pub fn check_writable(path: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+    let path: CString = CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    // SAFETY: `path` is a valid NUL-terminated string that outlives the call
+    if unsafe { libc::access(path.as_ptr(), libc::W_OK) } != 0 {
+        return Err(io::Error::last_os_error())
+    }
+    Ok(())
+}
+
+/// Checks `path` isn't marked read-only, as Windows has no unix file modes, see [`set_mode`].
+#[cfg(windows)]
+pub fn check_writable(path: &Path) -> io::Result<()> {
+    if fs::metadata(path)?.permissions().readonly() {
+        return Err(io::Error::from(io::ErrorKind::PermissionDenied))
+    }
+    Ok(())
+}
+
+/// Returns if dotulous is running as root, such as through `sudo`.
+#[cfg(unix)]
+pub fn is_root() -> bool {
+    // SAFETY: `geteuid` has no preconditions and can't fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Always returns `false`, as system files are only supported on unix.
+#[cfg(windows)]
+pub fn is_root() -> bool {
+    false
+}
+
+/// Returns if the file at `path` (following symlinks) can be read by every user on the system.
+#[cfg(unix)]
+pub fn is_world_readable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o004 != 0)
+}
+
+/// Returns `false`, as Windows has no unix file modes, see [`set_mode`].
+#[cfg(windows)]
+pub fn is_world_readable(_path: &Path) -> bool {
+    false
+}
+
+/// Returns `path` with every `.` removed and every `..` applied, without touching the filesystem.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized: PathBuf = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => { normalized.pop(); },
+            component => normalized.push(component)
+        }
+    }
+    normalized
+}
+
+/// Returns the user's home folder, from `HOME` (or `USERPROFILE` on Windows).
+pub fn home_dir() -> Option<PathBuf> {
+    let variables: &[&str] = if cfg!(windows) { &["HOME", "USERPROFILE"] } else { &["HOME"] };
+    variables.iter()
+        .filter_map(env::var_os)
+        .map(PathBuf::from)
+        .find(|path| !path.as_os_str().is_empty())
+}
+
+/// Returns the folders searched for programs, from the `PATH` environment variable.
+pub fn search_path() -> Vec<PathBuf> {
+    env::var_os("PATH").map(|path| env::split_paths(&path).collect()).unwrap_or_default()
+}
+
+/// Returns the *absolute* path of the program `name` inside the first of the `folders` that has
+/// it, or [`None`] if none do. On Windows, `.exe`, `.cmd` & `.bat` files are found without their
+/// extension.
+pub fn find_program(name: &str, folders: &[PathBuf]) -> Option<PathBuf> {
+    let extensions: &[&str] = if cfg!(windows) { &["", ".exe", ".cmd", ".bat"] } else { &[""] };
+    folders.iter()
+        .flat_map(|folder| extensions.iter().map(move |extension| folder.join(format!("{name}{extension}"))))
+        .find(|path| is_executable(path))
+}
+
+/// Returns if the file at `path` exists and can be executed.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Returns if the file at `path` exists. Windows decides what can be executed by it's extension,
+/// see [`find_program`].
+#[cfg(windows)]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Returns a [`Command`] running `command` in the platform's shell, `sh -c` (or `cmd /C` on
+/// Windows).
+pub fn shell_command(command: &str) -> Command {
+    let (shell, flag): (&str, &str) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut process: Command = Command::new(shell);
+    process.arg(flag).arg(command);
+    process
+}
+
+/// Makes `process` start in a new process group, so it can be killed along with anything it starts
+/// with [`kill_process_group`].
+///
+/// If dotulous is in the terminal's foreground, the new group is given the terminal, so the
+/// process can still read input from it and Ctrl-C goes to it. The terminal is given back when the
+/// returned [`Foreground`] is dropped, which should be once the process has exited.
+#[cfg(unix)]
+pub fn new_process_group(process: &mut Command) -> Foreground {
+    use std::os::unix::process::CommandExt;
+    let owned: bool = owns_terminal();
+    process.process_group(0);
+    if owned {
+        // Done by the process itself before it starts, so it can never read from the terminal
+        // before being given it. Only calls that are safe between forking & starting are made.
+        unsafe {
+            process.pre_exec(|| {
+                take_terminal(libc::getpgrp());
+                Ok(())
+            });
+        }
+    }
+    Foreground { owned }
+}
+
+/// Makes `process` start in a new process group, so it can be killed along with anything it starts
+/// with [`kill_process_group`].
+#[cfg(windows)]
+pub fn new_process_group(process: &mut Command) -> Foreground {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+    process.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    Foreground {}
+}
+
+/// Gives the terminal back to dotulous when dropped, if [`new_process_group`] gave it to a process.
+#[cfg(unix)]
+pub struct Foreground {
+    /// If dotulous was in the terminal's foreground, and so gave it away.
+    owned: bool
+}
+#[cfg(unix)]
+impl Drop for Foreground {
+    fn drop(&mut self) {
+        if self.owned {
+            take_terminal(unsafe { libc::getpgrp() });
+        }
+    }
+}
+
+/// Does nothing, as processes on Windows share the console regardless of their group.
+#[cfg(windows)]
+pub struct Foreground {}
+
+/// Returns if stdin is a terminal that dotulous's process group is in the foreground of.
+#[cfg(unix)]
+fn owns_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 && libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp() }
+}
+
+/// Puts the process group `group` in the foreground of the terminal on stdin. `SIGTTOU` is ignored
+/// meanwhile, as a process in the background is otherwise stopped for trying.
+#[cfg(unix)]
+fn take_terminal(group: libc::pid_t) {
+    unsafe {
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(libc::STDIN_FILENO, group);
+        libc::signal(libc::SIGTTOU, previous);
+    }
+}
+
+/// Kills `child` and every process in it's group (see [`new_process_group`]), then waits for it to
+/// exit. If the group can't be killed, only `child` is.
+#[cfg(unix)]
+pub fn kill_process_group(child: &mut Child) {
+    let killed: bool = libc::pid_t::try_from(child.id()).is_ok_and(|group| unsafe { libc::killpg(group, libc::SIGKILL) } == 0);
+    if !killed {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+}
+
+/// Kills `child` and every process it started, then waits for it to exit. If they can't be killed,
+/// only `child` is.
+#[cfg(windows)]
+pub fn kill_process_group(child: &mut Child) {
+    let id: String = child.id().to_string();
+    let killed: bool = Command::new("taskkill").args(["/F", "/T", "/PID", &id]).status().is_ok_and(|status| status.success());
+    if !killed {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+}
+
+/// Returns if a command exited with `status` as it was interrupted, such as with Ctrl-C while it
+/// had the terminal (see [`new_process_group`]).
+#[cfg(unix)]
+pub fn was_interrupted(status: &ExitStatus) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal() == Some(libc::SIGINT)
+}
+
+/// Returns if a command exited with `status` as it was interrupted. Ctrl-C reaches dotulous as well
+/// on Windows, so this is never needed.
+#[cfg(windows)]
+pub fn was_interrupted(_status: &ExitStatus) -> bool {
+    false
+}
+
+/// Waits for `child` to exit for up to `timeout`, returning [`None`] if it's still running after.
+pub fn wait_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline: Instant = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status))
+        }
+        if Instant::now() >= deadline {
+            return Ok(None)
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Turns the `status` of a finished command into an [`io::Result`], so a command that ran but
+/// exited unsuccessfully is an error too, saying how it exited.
+pub fn check_status(status: io::Result<ExitStatus>) -> io::Result<()> {
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(io::Error::other(format!("Command exited with {status}"))),
+        Err(e) => Err(e)
+    }
+}
+
+/// The environment variable that, when set to anything other than `0`, stops dotulous from asking
+/// the user anything. Also set by the `--non-interactive` flag.
+pub const NONINTERACTIVE_VAR: &str = "DOTULOUS_NONINTERACTIVE";
+
+/// Returns if dotulous can ask the user questions, which is only when stdin is a terminal and
+/// [`NONINTERACTIVE_VAR`] isn't set.
+pub fn is_interactive() -> bool {
+    let disabled: bool = env::var_os(NONINTERACTIVE_VAR).is_some_and(|value| !value.is_empty() && value != "0");
+    !disabled && io::stdin().is_terminal()
+}
+
+/// Returns the machine's hostname. On Linux this is read from the kernel, falling back to
+/// `/etc/hostname`, and elsewhere from the `hostname` command. The `HOSTNAME` & `COMPUTERNAME`
+/// environment variables are used as a last resort.
+pub fn hostname() -> Option<String> {
+    let from_files = ["/proc/sys/kernel/hostname", "/etc/hostname"].iter()
+        .filter_map(|path| fs::read_to_string(path).ok());
+    let from_command = std::iter::once_with(|| Command::new("hostname").output().ok())
+        .flatten()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string());
+    let from_env = ["HOSTNAME", "COMPUTERNAME"].iter()
+        .filter_map(|variable| env::var(variable).ok());
+
+    from_files.chain(from_command).chain(from_env)
+        .map(|hostname| hostname.trim().to_string())
+        .find(|hostname| !hostname.is_empty())
+}
+
+/// Creates the folder at `path` and any missing parents, with any folders created only accessible
+/// by the user.
+#[cfg(unix)]
+pub fn create_private_dir_all(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(path)
+}
+
+/// Creates the folder at `path` and any missing parents. Permissions are left to the parent
+/// folder, which on Windows is normally only accessible by the user inside their home folder.
+#[cfg(windows)]
+pub fn create_private_dir_all(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)
+}
+
+/// Creates a new file at `path` that is only readable & writable by the user, failing with
+/// [`io::ErrorKind::AlreadyExists`] if anything is already there, even a symlink.
+#[cfg(unix)]
+pub fn create_private_new(path: &Path) -> io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)
+}
+
+/// Creates a new file at `path`, failing with [`io::ErrorKind::AlreadyExists`] if anything is
+/// already there. Permissions are left to the parent folder, see [`create_private_dir_all`].
+#[cfg(windows)]
+pub fn create_private_new(path: &Path) -> io::Result<fs::File> {
+    fs::OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+/// Writes `contents` to the file at `path`, replacing it if it exists, making sure it is only
+/// readable & writable by the user.
+pub fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_with_mode(path, contents, 0o600)
+}
+
+/// Writes `contents` to the file at `path`, replacing it if it exists, giving it the unix `mode`
+/// (see [`create_with_mode`]).
+pub fn write_with_mode(path: &Path, contents: &[u8], mode: u32) -> io::Result<()> {
+    use std::io::Write;
+    create_with_mode(path, mode)?.write_all(contents)
+}
+
+/// Creates the file at `path` for writing, emptying it if it exists, with the unix `mode` such as
+/// `0o600`. The mode is set before anything is written, so what's written is never readable by
+/// anyone the mode doesn't allow.
+#[cfg(unix)]
+pub fn create_with_mode(path: &Path, mode: u32) -> io::Result<fs::File> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let file: fs::File = fs::OpenOptions::new().write(true).create(true).truncate(true).mode(mode).open(path)?;
+    // The mode is only used when creating the file, and is narrowed by the umask, so set it exactly
+    file.set_permissions(fs::Permissions::from_mode(mode))?;
+    Ok(file)
+}
+
+/// Creates the file at `path` for writing, emptying it if it exists. The mode is ignored, see
+/// [`set_mode`].
+#[cfg(windows)]
+pub fn create_with_mode(path: &Path, _mode: u32) -> io::Result<fs::File> {
+    fs::File::create(path)
+}
+
+/// Creates the folder at `path` with the unix `mode`, such as `0o700`. Like [`create_with_mode`],
+/// the folder never has a wider mode than `mode`.
+#[cfg(unix)]
+pub fn create_dir_with_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    fs::DirBuilder::new().mode(mode).create(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+/// Creates the folder at `path`. The mode is ignored, see [`set_mode`].
+#[cfg(windows)]
+pub fn create_dir_with_mode(path: &Path, _mode: u32) -> io::Result<()> {
+    fs::create_dir(path)
+}
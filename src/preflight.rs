@@ -0,0 +1,68 @@
+use std::{collections::HashSet, fmt::Display, io::ErrorKind, path::{Path, PathBuf}};
+
+use serde::Serialize;
+
+use crate::{paths::expand_path, platform, profile::DotfileProfile};
+
+/// Something that would make loading a profile fail part way through, found with [`check`]
+/// before anything is changed.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// The source of an entry doesn't exist.
+    MissingSource { source: PathBuf },
+    /// Something that isn't a folder is where a destination's folder should be.
+    NotAFolder { path: PathBuf },
+    /// The folder a destination goes in can't be written to by the user.
+    NotWritable { folder: PathBuf },
+    /// The folder a destination goes in is on a read-only mount.
+    ReadOnly { folder: PathBuf }
+}
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingSource { source } => write!(f, "missing source {source:?}"),
+            Problem::NotAFolder { path } => write!(f, "not a folder   {path:?}"),
+            Problem::NotWritable { folder } => write!(f, "not writable   {folder:?}"),
+            Problem::ReadOnly { folder } => write!(f, "read-only      {folder:?}")
+        }
+    }
+}
+
+/// Checks `profile` can be loaded onto the system without anything failing part way through,
+/// where `home_path` is the user's home folder. Nothing is changed, so every problem is found at
+/// once rather than one at a time.
+///
+/// Every source in the profile's `files` must exist, unless all of it's entries are `optional`,
+/// and the folder each destination goes in must be writable and not on a read-only mount, as must
+/// the folder each of it's `directories` goes in. As missing folders are created when loading, the
+/// closest folder that already exists is what's checked. Each folder is only reported once, even if several destinations go in it.
+pub fn check(profile: &DotfileProfile, home_path: &Path) -> Vec<Problem> {
+    let mut problems: Vec<Problem> = profile.files().iter()
+        .filter(|(_, entries)| entries.iter().any(|entry| !entry.optional))
+        .map(|(source, _)| profile.repo_path.join(expand_path(source, home_path)))
+        .filter(|source| source.symlink_metadata().is_err())
+        .map(|source| Problem::MissingSource { source })
+        .collect();
+
+    let target_path: PathBuf = profile.target_path(home_path);
+    let mut checked: HashSet<PathBuf> = HashSet::new();
+    for destination in profile.destinations().iter().chain(profile.directories()) {
+        let destination: PathBuf = target_path.join(expand_path(destination, home_path));
+        let Some(parent) = destination.parent() else { continue };
+        let Some(existing) = parent.ancestors().find(|folder| folder.symlink_metadata().is_ok()) else { continue };
+        if !checked.insert(existing.to_path_buf()) {
+            continue;
+        }
+        if !existing.is_dir() {
+            problems.push(Problem::NotAFolder { path: existing.to_path_buf() });
+            continue;
+        }
+        match platform::check_writable(existing) {
+            Ok(()) => {},
+            Err(e) if e.kind() == ErrorKind::ReadOnlyFilesystem => problems.push(Problem::ReadOnly { folder: existing.to_path_buf() }),
+            Err(_) => problems.push(Problem::NotWritable { folder: existing.to_path_buf() })
+        }
+    }
+    problems
+}
@@ -1,18 +1,123 @@
-use std::{collections::HashMap, fs, io, os::unix::fs::symlink, path::{Path, PathBuf}, process::{Command, Output}};
+use std::{cmp::Reverse, collections::{BTreeMap, HashMap}, env, fmt::Display, fs::{self, DirEntry, ReadDir}, io::{self, BufRead, BufReader, ErrorKind, Read}, iter, mem, path::{Path, PathBuf}, process::{Child, Command, ExitStatus, Stdio}, str::FromStr, sync::Arc, thread, time::Duration};
 
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 
-use crate::error::DotulousError;
+use crate::{asset::{self, Asset}, audit::AuditLog, backup::Backup, condition::Host, entry::{is_owned_link, CommandEntry, FileEntry, LinkMode, OnError, Shell, Strategy}, error::{DotulousError, IncludeConflicts}, filter::{EntryFilter, TagFilter}, format::ManifestFormat, ignores::Ignores, interrupt, journal::Journal, logs::CommandLog, meta::TrustScope, migrate::{migrate_manifest, MANIFEST_VERSION}, paths::expand_path, platform::{self, symlink, Foreground}, preflight, progress::Progress, say, secret::{Cleanup, SecretConfig, SecretStore}, settings::Settings, state::LoadState, stow, template::{self, Renderer}};
+
+/// How a profile should be applied to the system when loading or unloading it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApplyMode {
+    /// Actually make the changes to the system.
+    Apply,
+    /// Only print what would be done, without touching the filesystem or running any commands.
+    DryRun
+}
+
+/// Which parts of loading or unloading a profile to leave out, from `--no-hooks` & `--keep-files`.
+/// See [`DotfileProfile::skipping`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Skip {
+    /// Don't run any of the profile's commands.
+    pub hooks: bool,
+    /// Don't touch any of the profile's files or assets, only updating dotulous's own records.
+    pub files: bool
+}
+
+/// What to do when loading a file, but something that isn't already linked to it is in the way
+/// at it's destination.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    /// Ask the user which of the others to do, for each conflict. Aborts if dotulous isn't
+    /// interactive, see [`platform::is_interactive`].
+    Prompt,
+    /// Leave what's there alone, and don't load the file.
+    Skip,
+    /// Move what's there into a [`Backup`] that's restored when unloading, and load the file.
+    #[default]
+    Backup,
+    /// Delete what's there without backing it up, and load the file.
+    Overwrite,
+    /// Stop loading, rolling back everything already done.
+    Abort
+}
+impl Display for OnConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OnConflict::Prompt => f.pad("prompt"),
+            OnConflict::Skip => f.pad("skip"),
+            OnConflict::Backup => f.pad("backup"),
+            OnConflict::Overwrite => f.pad("overwrite"),
+            OnConflict::Abort => f.pad("abort")
+        }
+    }
+}
+impl FromStr for OnConflict {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "prompt" => Ok(OnConflict::Prompt),
+            "skip" => Ok(OnConflict::Skip),
+            "backup" => Ok(OnConflict::Backup),
+            "overwrite" => Ok(OnConflict::Overwrite),
+            "abort" => Ok(OnConflict::Abort),
+            _ => Err(format!("Unknown conflict policy \"{s}\", expected prompt, skip, backup, overwrite or abort"))
+        }
+    }
+}
+
+/// One of a profile's lists of commands, either ran at a certain point of loading or unloading
+/// it, or one of it's own named `hooks` that are only ran on demand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hook {
+    /// The `pre_commands`, ran before the profile's files are linked.
+    Pre,
+    /// The `post_commands`, ran after the profile's files are linked.
+    Post,
+    /// The `removal_commands`, ran after the profile is unloaded.
+    Removal,
+    /// One of the profile's `hooks`, by name.
+    Custom(String)
+}
+impl Display for Hook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hook::Pre => f.pad("pre"),
+            Hook::Post => f.pad("post"),
+            Hook::Removal => f.pad("removal"),
+            Hook::Custom(name) => f.pad(name)
+        }
+    }
+}
+impl FromStr for Hook {
+    type Err = String;
+
+    /// Parses `pre`, `post` or `removal`, with anything else being the name of a
+    /// [`Hook::Custom`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "" => Err("Hook name can't be empty".to_string()),
+            "pre" | "pre_commands" => Ok(Hook::Pre),
+            "post" | "post_commands" => Ok(Hook::Post),
+            "removal" | "removal_commands" => Ok(Hook::Removal),
+            _ => Ok(Hook::Custom(s.to_string()))
+        }
+    }
+}
 
 /// A dotfile profile, that the user can load and modify. This should be loaded or at least
 /// representitive of the profile's `manifest.json`
 /// The profile's directory should be within `repo_path`, with a `manifest.json` file detailing the
-/// profile inside of the directory.
+/// profile inside of the directory (or `manifest.toml`/`manifest.yaml`, see [`ManifestFormat`]).
 ///
 /// ### Fetching a Profile
 ///
 /// To fetch an already-existing profile, you can use;
-/// - [`DotfileProfile::find_profile`] will search for your profile, with `dotulous_path` being the `.dotulous` folder.
+/// - [`DotfileProfile::find_profile`] will search for your profile, with `dotulous_path` being the dotulous folder.
 /// - *or*, if you already have the location of the profile's directly, you can use [`DotfileProfile::from_manifest`] to load it in directly.
 ///
 /// ### Loading/Unloading Profiles
@@ -30,22 +135,130 @@ use crate::error::DotulousError;
 /// the changed profile `manifest.json` to disk.
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct DotfileProfile {
+    /// The version of the manifest format, see [`crate::migrate`]. Older manifests are upgraded to
+    /// [`MANIFEST_VERSION`] when they're read.
+    #[serde(default)]
+    pub manifest_version: u32,
     /// The user-friendly name of the profile.
     pub name: String,
-    /// The *absolute* path to the profile's `manifest.json`.
+    /// A short description of what the profile is, shown before the user is asked to trust it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Who made the profile, shown alongside it's `description`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Where to find out more about the profile, such as the page of it's repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// The version of the profile, such as `1.2`. `dotulous update` shows when it changes, and the
+    /// version a profile was trusted at is remembered, see [`crate::meta::Meta::trusted_version`].
+    /// Compare versions with [`crate::inspect::compare_versions`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The *absolute* path to the profile's manifest, such as it's `manifest.json`.
     pub manifest_path: PathBuf,
     /// The *absolute* path to the profile's folder itself.
     pub repo_path: PathBuf,
+    /// The folder that the `files` and `assets` destinations are relative to, instead of the home
+    /// folder. May start with `~` or contain environment variables, and if relative is itself
+    /// relative to the home folder. See [`DotfileProfile::target_path`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_root: Option<PathBuf>,
+    /// Lets the profile put files outside of the user's home folder, such as through a
+    /// `target_root` or destinations with `..`. Off by default, see
+    /// [`DotfileProfile::check_destination`]. As this is part of the manifest, the user is warned
+    /// about it when trusting the profile.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub allow_outside_home: bool,
+    /// The command ran inside the profile's folder by `dotulous update`, instead of `git pull`.
+    /// Only ran if the profile is trusted. See [`crate::source::update`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_command: Option<String>,
+    /// The name of another profile this one is built on top of, whose files, commands & variables
+    /// are merged underneath this profile's own when loading. See [`DotfileProfile::resolve_extends`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Git repositories the profile's commands clone or manage, such as plugin managers, whose
+    /// commits are pinned along with the profile's own in it's `dotulous.lock`. Paths starting
+    /// with `~` are in the home folder, other relative paths are in the profile's folder. See
+    /// [`crate::pin::Pins`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned_repos: Vec<String>,
+    /// The names of the profiles merged into this one by [`DotfileProfile::resolve_extends`],
+    /// nearest first. Only set on resolved profiles, such as the copy stored in the meta.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends_chain: Vec<String>,
+    /// Folders inside the profile's folder that hold a manifest of their own, such as `shell` or
+    /// `editor`, so one repository can be split into modules. Their files, commands & variables
+    /// are merged into this profile when loading, see [`DotfileProfile::resolve_includes`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<PathBuf>,
+    /// The modules merged into this one by [`DotfileProfile::resolve_includes`], with those they
+    /// include themselves as `<module>/<nested>`. Only set on resolved profiles, such as the copy
+    /// stored in the meta.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub included: Vec<String>,
     /// The list of files that should be loaded with the profile. Key is the path relative to the
-    /// profile's directory, and the value is where it should be symlinked to in the system upon
-    /// loading - or in the case of unloading, what symlink will be deleted.
-    files: HashMap<PathBuf, PathBuf>,
+    /// profile's directory, and the value is each [`FileEntry`] describing where (and how) it should
+    /// be put in the system upon loading - or in the case of unloading, what will be deleted. A file
+    /// usually has one entry, but can be put in several places with a list of them. Kept sorted, so
+    /// files are always loaded (and saved) in the same order.
+    #[serde(serialize_with = "crate::entry::serialize_targets", deserialize_with = "crate::entry::deserialize_targets")]
+    files: BTreeMap<PathBuf, Vec<FileEntry>>,
+    /// Files put in system folders outside of the home folder, such as `/etc/keyd/default.conf`.
+    /// Key is the path relative to the profile's directory, and the value is the *absolute* path
+    /// it's copied to. These are never touched when loading, only by `sudo dotulous apply-system`,
+    /// see [`crate::system::apply`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    system_files: BTreeMap<PathBuf, PathBuf>,
     /// A list of commands to run on loading *before* the files are symlinked to the system.
-    pre_commands: Vec<String>,
+    pre_commands: Vec<CommandEntry>,
     /// A list of commands to run on loading *after* the files are symlinked to the system.
-    post_commands: Vec<String>,
+    post_commands: Vec<CommandEntry>,
     /// A list of commands to run on unloading, running *after* the files are removed from the system.
-    removal_commands: Vec<String>
+    removal_commands: Vec<CommandEntry>,
+    /// Named lists of commands that are never ran on their own, only when the user runs them with
+    /// [`DotfileProfile::run_hook`], such as switching between themes. Called `hooks` in the
+    /// manifest.
+    #[serde(default, rename = "hooks", skip_serializing_if = "BTreeMap::is_empty")]
+    custom_hooks: BTreeMap<String, Vec<CommandEntry>>,
+    /// A list of external assets to download and symlink to the system on loading, after the
+    /// files are symlinked. See [`Asset`].
+    #[serde(default)]
+    assets: Vec<Asset>,
+    /// Empty folders to create on loading, relative to the [`DotfileProfile::target_path`] like
+    /// the `files` destinations, for programs that need a folder to exist, such as a cache. They're
+    /// only removed on unloading if they're still empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    directories: Vec<PathBuf>,
+    /// The packages the profile needs installed, keyed by distro id (e.g. `arch`, `debian`).
+    /// See [`crate::packages::Distro::select`].
+    #[serde(default)]
+    pub packages: HashMap<String, Vec<String>>,
+    /// Variables available to files using the template strategy. Can be overridden per-machine
+    /// with `vars.json`, see [`DotfileProfile::renderer`].
+    #[serde(default)]
+    variables: HashMap<String, String>,
+    /// Environment variables set for the profile's commands, on top of the built-ins. See
+    /// [`DotfileProfile::command_env`].
+    #[serde(default)]
+    env: HashMap<String, String>,
+    /// What to do when a pre or post command fails, unless the command sets it's own.
+    #[serde(default)]
+    on_error: OnError,
+    /// The shell the profile's commands are ran with, unless the command sets it's own. If
+    /// [`None`], the user's [`Settings::shell`] is used, or otherwise `sh -c` (see
+    /// [`platform::shell_command`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<Shell>,
+    /// How many seconds the profile's commands can run for before they're killed, unless the
+    /// command sets it's own. If [`None`], the user's [`Settings::timeout`] is used, or otherwise
+    /// commands can run forever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// How files using the encrypted strategy are decrypted, see [`SecretConfig`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<SecretConfig>
 }
 impl DotfileProfile {
     /// Creates a new `DotfileProfile`.
@@ -56,18 +269,42 @@ impl DotfileProfile {
     /// `manifest.json`
     pub fn new(name: &str, path: &Path) -> Self {
         Self {
+            manifest_version: MANIFEST_VERSION,
             name: name.to_string(),
+            description: None,
+            author: None,
+            homepage: None,
+            version: None,
             manifest_path: path.join(Path::new("manifest.json")),
             repo_path: path.to_path_buf(),
-            files: HashMap::new(),
+            target_root: None,
+            allow_outside_home: false,
+            update_command: None,
+            extends: None,
+            pinned_repos: Vec::new(),
+            extends_chain: Vec::new(),
+            includes: Vec::new(),
+            included: Vec::new(),
+            files: BTreeMap::new(),
+            system_files: BTreeMap::new(),
             pre_commands: Vec::new(),
             post_commands: Vec::new(),
-            removal_commands: Vec::new()
+            removal_commands: Vec::new(),
+            custom_hooks: BTreeMap::new(),
+            assets: Vec::new(),
+            directories: Vec::new(),
+            packages: HashMap::new(),
+            variables: HashMap::new(),
+            env: HashMap::new(),
+            on_error: OnError::default(),
+            shell: None,
+            timeout: None,
+            secrets: None
         }
     }
 
     /// Find a given profile on-disk with the user-friendly `profile_name`, with `dotulous_path`
-    /// being the user's `.dotulous` folder.
+    /// being the user's dotulous folder.
     /// If the profile is not found, it will return [`Err`] with [`DotulousError::ProfileNotFound`].
     ///
     /// Internally this simply finds if the given profile's path exists using a santized `profile_name`,
@@ -84,18 +321,20 @@ impl DotfileProfile {
         DotfileProfile::from_manifest(&full_path)
     }
 
-    /// Read a profile from disk when you have a known `profile_path` with a `manifest.json` inside
-    /// of it.
+    /// Read a profile from disk when you have a known `profile_path` with a manifest inside of it.
     ///
-    /// This reads the `manifest.json` directly, and deserializes it.
+    /// This reads the manifest directly, in whichever format it's in (see
+    /// [`ManifestFormat::find_manifest`]), and deserializes it. Manifests from older versions of
+    /// dotulous are upgraded first, see [`migrate_manifest`].
     pub fn from_manifest(profile_path: &Path) -> Result<DotfileProfile, DotulousError> {
-        let manifest_path: PathBuf = profile_path.join(Path::new("manifest.json"));
-        if !manifest_path.exists() {
+        let Some(manifest_path) = ManifestFormat::find_manifest(profile_path) else {
             return Err(DotulousError::NoManifestInProfile)
-        }
+        };
 
-        let Ok(contents) = fs::read_to_string(&manifest_path) else { return Err(DotulousError::FailedReadManifest) };
-        let Ok(mut deserialized) = serde_json::from_str::<DotfileProfile>(&contents) else { return Err(DotulousError::FailedDeserializeManifest) };
+        let contents: String = fs::read_to_string(&manifest_path).map_err(DotulousError::FailedReadManifest)?;
+        let mut manifest: Value = ManifestFormat::from_path(&manifest_path).parse(&contents)?;
+        migrate_manifest(&mut manifest)?;
+        let mut deserialized: DotfileProfile = serde_json::from_value(manifest).map_err(|e| DotulousError::FailedDeserializeManifest(Box::new(e)))?;
         // Double-check the manifest/repo paths are correct, as these can be altered by the user 
         deserialized.manifest_path = manifest_path;
         deserialized.repo_path = profile_path.to_path_buf();
@@ -103,56 +342,797 @@ impl DotfileProfile {
         Ok(deserialized)
     }
 
-    /// Save the current profile data to the `manifest.json` of this profile.
-    /// This uses the `manifest_path` property to locate the `manifest.json` location.
+    /// Save the current profile data to the manifest of this profile.
+    /// This uses the `manifest_path` property to locate the manifest, writing it in the format
+    /// matching it's extension (see [`ManifestFormat::from_path`]).
     ///
     /// The returned [`Result`] does not return anything on success, meaning you should only check
     /// for [`Err`] variants. 
     pub fn save_manifest(&self) -> Result<(), DotulousError> {
-        let Ok(serialized) = serde_json::to_string_pretty(self) else { return Err(DotulousError::FailedSerializeManifest) };
-        if fs::write(&self.manifest_path, serialized).is_err() { return Err(DotulousError::FailedSaveManifest) }
+        let serialized: String = ManifestFormat::from_path(&self.manifest_path).serialize(self)?;
+        fs::write(&self.manifest_path, serialized).map_err(DotulousError::FailedSaveManifest)?;
         Ok(())
     }
 
     /// Scans the profile's `repo_path` and automatially adds all found files to the manifest's
     /// `files` property, before saving the manifest to disk.
     ///
+    /// The scan is recursive, so each file gets it's own entry. If `depth` is given, the scan stops
+    /// that many levels deep, adding any directories at that level as a single entry (so a `depth`
+    /// of `1` only adds the top level of the repo). Files matching the profile's [`Ignores`] are
+    /// skipped, which include the default ignores only if `default_ignores` is set, along with any
+    /// patterns in `extra_ignores`.
+    ///
+    /// If `merge` is set, existing entries are kept as they are and only files that aren't mapped
+    /// yet are added. A file is skipped if it's inside an existing entry (or has one inside it), or
+    /// if an existing entry already puts something where it would go. Entries whose source no
+    /// longer exists in the profile are reported, but left for the user to remove. The manifest is
+    /// only saved if anything was added.
+    ///
+    /// If `stow_layout` is set, the profile is treated like a GNU Stow directory: each folder at the
+    /// top of the profile is a package, and it's files go to the same place in the target folder
+    /// without the package's name (see [`stow::destination`]). Files at the top of the profile
+    /// aren't in a package, so they're skipped.
+    ///
     /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
     ///
-    /// This function should only be called if the `files` property is already empty. If not, 
-    /// it will return an [`Err`] with [`DotulousError::FillManifestArrayNotEmpty`].
+    /// Unless `merge` is set, this function should only be called if the `files` property is
+    /// already empty. If not, it will return an [`Err`] with
+    /// [`DotulousError::FillManifestArrayNotEmpty`].
     ///
     /// The returned [`Result`] does not return anything on success, meaning you should only check
     /// for [`Err`] variants. 
-    pub fn fill_files(&mut self) -> Result<(), DotulousError> {
-        if !self.files.is_empty() {
+    pub fn fill_files(&mut self, depth: Option<usize>, default_ignores: bool, extra_ignores: &[String], merge: bool, stow_layout: bool) -> Result<(), DotulousError> {
+        if !merge && !self.files.is_empty() {
             return Err(DotulousError::FillManifestArrayNotEmpty)
         }
 
-        println!("Filling files for profile: {}", self.name);
-        let Ok(paths) = fs::read_dir(&self.repo_path) else { return Err(DotulousError::FailedReadProfileDirectory) };
+        say!("Filling files for profile: {}", self.name);
+        let ignores: Ignores = Ignores::load(&self.repo_path, default_ignores, extra_ignores)?;
+        let mut found: Vec<PathBuf> = Vec::new();
+        if stow_layout {
+            for package in stow::packages(&self.repo_path).map_err(DotulousError::FailedReadProfileDirectory)? {
+                let is_ignored: bool = package.strip_prefix(&self.repo_path).is_ok_and(|package| ignores.is_ignored(package, true));
+                if !is_ignored {
+                    self.scan_directory(&package, depth, &ignores, &mut found)?;
+                }
+            }
+        } else {
+            self.scan_directory(&self.repo_path, depth, &ignores, &mut found)?;
+        }
+        found.sort();
+        let mut destinations: Vec<PathBuf> = self.destinations();
+        let mut added: usize = 0;
+        for final_path in found {
+            let new_destination: PathBuf = if stow_layout {
+                let Some(destination) = stow::destination(&final_path) else { continue };
+                destination
+            } else {
+                final_path.clone()
+            };
+            if self.files.keys().any(|source| overlaps(source, &final_path)) {
+                continue;
+            }
+            if let Some(destination) = destinations.iter().find(|destination| overlaps(destination, &new_destination)) {
+                say!("  WARNING: Skipping {final_path:?}, another entry already puts a file at {destination:?}.");
+                continue;
+            }
+            if stow_layout {
+                say!("  {final_path:?} => {new_destination:?}");
+            } else {
+                say!("  {final_path:?}");
+            }
+            destinations.push(new_destination.clone());
+            self.files.insert(final_path, vec![FileEntry::new(new_destination)]);
+            added += 1;
+        }
+        if merge {
+            for source in self.files.keys().filter(|source| self.repo_path.join(source).symlink_metadata().is_err()) {
+                say!("  WARNING: The source of entry {source:?} no longer exists in the profile!");
+            }
+            say!("Added {added} new file(s).");
+            if added == 0 {
+                return Ok(())
+            }
+        }
+        say!();
+        say!("Done! Make sure to go through them manually to make sure!");
+
+        self.save_manifest()
+    }
+
+    /// Adopts the existing file (or directory) at `path` into the profile, moving it into the
+    /// profile's directory and symlinking it back in it's place, then adding an entry for it to
+    /// `files`.
+    ///
+    /// `path` must be an *absolute* path inside the [`DotfileProfile::target_path`], and is kept at
+    /// the same relative path inside the profile's directory. Symlinks can't be adopted, as they
+    /// are most likely already managed.
+    ///
+    /// Note that this function does **not** save the manifest, call
+    /// [`DotfileProfile::save_manifest`] afterwards.
+    ///
+    /// Returns the adopted file's path relative to the profile's directory, which is also the key
+    /// of it's new entry in `files`.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    pub fn adopt_file(&mut self, home_path: &Path, path: &Path) -> Result<PathBuf, DotulousError> {
+        if path.symlink_metadata().is_err() {
+            return Err(DotulousError::AdoptFileNotFound)
+        }
+        if path.is_symlink() {
+            return Err(DotulousError::AdoptFileIsSymlink)
+        }
+        let target_path: PathBuf = self.target_path(home_path);
+        let Ok(relative) = path.strip_prefix(&target_path) else { return Err(DotulousError::AdoptFileOutsideTarget) };
+        if relative.as_os_str().is_empty() {
+            return Err(DotulousError::AdoptFileOutsideTarget)
+        }
+        let source: PathBuf = self.repo_path.join(relative);
+        if source.symlink_metadata().is_ok() || self.files.contains_key(relative) {
+            return Err(DotulousError::AdoptFileAlreadyInProfile)
+        }
+
+        say!("  {path:?} => {source:?}");
+        if let Some(parent) = source.parent() {
+            fs::create_dir_all(parent).map_err(DotulousError::FailedAdoptFile)?;
+        }
+        fs::rename(path, &source).map_err(DotulousError::FailedAdoptFile)?;
+        if let Err(e) = symlink(&source, path) {
+            // Put it back, so the file isn't left missing
+            let _ = fs::rename(&source, path);
+            return Err(DotulousError::FailedAdoptFile(e))
+        }
+        self.insert_file(relative.to_path_buf(), FileEntry::new(relative.to_path_buf()));
+        Ok(relative.to_path_buf())
+    }
+
+    /// Returns the SHA-256 digest of the profile's manifest on disk, as a hex string. Used to
+    /// notice when a trusted profile's manifest has changed, see [`crate::meta::Meta::is_trusted`].
+    ///
+    /// If the profile extends others or includes modules, their manifests are included in the
+    /// digest too, as changing them changes what this profile does.
+    pub fn manifest_hash(&self) -> Result<String, DotulousError> {
+        let mut hasher: Sha256 = Sha256::new();
+        let mut manifest_paths: Vec<PathBuf> = self.manifest_paths()?;
+        for base in self.base_profiles()? {
+            manifest_paths.extend(base.manifest_paths()?);
+        }
+        for manifest_path in manifest_paths {
+            let contents: Vec<u8> = fs::read(&manifest_path).map_err(DotulousError::FailedReadManifest)?;
+            hasher.update(contents);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Returns the path of the profile's manifest, followed by those of every module it includes,
+    /// including the modules they include.
+    fn manifest_paths(&self) -> Result<Vec<PathBuf>, DotulousError> {
+        let mut paths: Vec<PathBuf> = vec![self.manifest_path.clone()];
+        for module in self.included_profiles()? {
+            paths.extend(module.manifest_paths()?);
+        }
+        Ok(paths)
+    }
+
+    /// Returns every module this profile includes (see `includes`), in order, as read from their
+    /// manifests.
+    ///
+    /// If an included folder has no manifest, [`Err`] with [`DotulousError::IncludeNotFound`] is
+    /// returned. Included folders must be inside the profile's folder (and not the folder itself),
+    /// even once symlinks are followed, otherwise [`Err`] with
+    /// [`DotulousError::IncludeOutsideProfile`] is returned.
+    pub fn included_profiles(&self) -> Result<Vec<DotfileProfile>, DotulousError> {
+        let repo_path: PathBuf = fs::canonicalize(&self.repo_path).unwrap_or_else(|_| self.repo_path.clone());
+        self.includes.iter().map(|include| {
+            let path: PathBuf = self.repo_path.join(include);
+            let Ok(resolved) = fs::canonicalize(&path) else { return Err(DotulousError::IncludeNotFound) };
+            // A module is always deeper than the profile including it, so including can't go round in circles
+            if resolved == repo_path || !resolved.starts_with(&repo_path) {
+                return Err(DotulousError::IncludeOutsideProfile)
+            }
+            match DotfileProfile::from_manifest(&path) {
+                Err(DotulousError::NoManifestInProfile) => Err(DotulousError::IncludeNotFound),
+                result => result
+            }
+        }).collect()
+    }
+
+    /// Returns every profile this one extends (see `extends`), nearest first, as read from their
+    /// manifests. Extended profiles are found in the same folder as this one.
+    ///
+    /// If an extended profile doesn't exist, [`Err`] with [`DotulousError::ExtendedProfileNotFound`]
+    /// is returned. If a profile ends up extending itself, [`Err`] with
+    /// [`DotulousError::ExtendsCycle`] is returned.
+    pub fn base_profiles(&self) -> Result<Vec<DotfileProfile>, DotulousError> {
+        let dotulous_path: &Path = self.repo_path.parent().unwrap_or(&self.repo_path);
+        let mut bases: Vec<DotfileProfile> = Vec::new();
+        let mut extends: Option<String> = self.extends.clone();
+        while let Some(base_name) = extends {
+            let base: DotfileProfile = match DotfileProfile::find_profile(dotulous_path, &base_name) {
+                Ok(r) => r,
+                Err(DotulousError::ProfileNotFound) => return Err(DotulousError::ExtendedProfileNotFound),
+                Err(e) => return Err(e)
+            };
+            if base.repo_path == self.repo_path || bases.iter().any(|b| b.repo_path == base.repo_path) {
+                return Err(DotulousError::ExtendsCycle)
+            }
+            extends = base.extends.clone();
+            bases.push(base);
+        }
+        Ok(bases)
+    }
+
+    /// Returns every file inside the profile's directory (relative to it), sorted. Files matching
+    /// the profile's [`Ignores`] (including the defaults) are skipped.
+    pub fn repo_files(&self) -> Result<Vec<PathBuf>, DotulousError> {
+        let ignores: Ignores = Ignores::load(&self.repo_path, true, &[])?;
+        let mut found: Vec<PathBuf> = Vec::new();
+        self.scan_directory(&self.repo_path, None, &ignores, &mut found)?;
+        found.sort();
+        Ok(found)
+    }
+
+    /// Returns the profile's `files`, keyed by the path relative to the profile's directory, with
+    /// every entry putting that file onto the system.
+    pub fn files(&self) -> &BTreeMap<PathBuf, Vec<FileEntry>> {
+        &self.files
+    }
+
+    /// Returns the profile's `system_files`, keyed by the path relative to the profile's directory,
+    /// with the *absolute* path each is copied to by [`crate::system::apply`].
+    pub fn system_files(&self) -> &BTreeMap<PathBuf, PathBuf> {
+        &self.system_files
+    }
+
+    /// Returns every entry in the profile's `files` along with it's source, relative to the
+    /// profile's directory. A source put in several places comes up once for each of them.
+    pub fn entries(&self) -> impl Iterator<Item = (&PathBuf, &FileEntry)> {
+        self.files.iter().flat_map(|(source, entries)| entries.iter().map(move |entry| (source, entry)))
+    }
+
+    /// Returns the profile's external `assets`.
+    pub fn assets(&self) -> &[Asset] {
+        &self.assets
+    }
+
+    /// Returns the folders the profile creates on loading, relative to the
+    /// [`DotfileProfile::target_path`].
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+
+    /// Returns the profile's own `env`, set for it's commands on top of the built-ins. See
+    /// [`DotfileProfile::command_env`] for everything they're given.
+    pub fn env(&self) -> &HashMap<String, String> {
+        &self.env
+    }
+
+    /// Adds `entry` to the profile's `files` for the file at `source`, relative to the profile's
+    /// directory, replacing any existing entries for it.
+    pub fn insert_file(&mut self, source: PathBuf, entry: FileEntry) {
+        self.files.insert(source, vec![entry]);
+    }
+
+    /// Adds `entry` to the profile's `files` for the file at `source`, relative to the profile's
+    /// directory. Unlike [`DotfileProfile::insert_file`], nothing already in the profile is
+    /// replaced. Returns the profile, so several files can be added in a row.
+    ///
+    /// If `source` is already in the profile, [`Err`] with [`DotulousError::FileAlreadyMapped`] is
+    /// returned. If another file or asset already goes to the same destination, [`Err`] with
+    /// [`DotulousError::DestinationAlreadyMapped`] is returned.
+    pub fn add_file(&mut self, source: PathBuf, entry: FileEntry) -> Result<&mut Self, DotulousError> {
+        if self.files.contains_key(&source) {
+            return Err(DotulousError::FileAlreadyMapped)
+        }
+        if self.destinations().contains(&entry.destination) {
+            return Err(DotulousError::DestinationAlreadyMapped)
+        }
+        self.files.insert(source, vec![entry]);
+        Ok(self)
+    }
+
+    /// Adds `entry` to the profile's `files` as another place to put the file at `source`, which
+    /// must already be in the profile (see [`DotfileProfile::add_file`]). Returns the profile, so
+    /// several destinations can be added in a row.
+    ///
+    /// If `source` isn't in the profile, [`Err`] with [`DotulousError::FileNotMapped`] is
+    /// returned. If another file or asset already goes to the same destination, [`Err`] with
+    /// [`DotulousError::DestinationAlreadyMapped`] is returned.
+    pub fn add_destination(&mut self, source: &Path, entry: FileEntry) -> Result<&mut Self, DotulousError> {
+        if self.destinations().contains(&entry.destination) {
+            return Err(DotulousError::DestinationAlreadyMapped)
+        }
+        let Some(entries) = self.files.get_mut(source) else { return Err(DotulousError::FileNotMapped) };
+        entries.push(entry);
+        Ok(self)
+    }
+
+    /// Removes the file at `source`, relative to the profile's directory, from the profile's
+    /// `files`. Returns every entry it had, or [`None`] if it wasn't in the profile.
+    pub fn remove_file(&mut self, source: &Path) -> Option<Vec<FileEntry>> {
+        self.files.remove(source)
+    }
+
+    /// Returns the profile's commands for `hook`, or [`None`] if the profile has no named hook
+    /// called that (see [`Hook::Custom`]).
+    pub fn commands(&self, hook: &Hook) -> Option<&[CommandEntry]> {
+        match hook {
+            Hook::Pre => Some(&self.pre_commands),
+            Hook::Post => Some(&self.post_commands),
+            Hook::Removal => Some(&self.removal_commands),
+            Hook::Custom(name) => self.custom_hooks.get(name).map(Vec::as_slice)
+        }
+    }
+
+    /// Replaces the profile's commands for `hook` with `commands`. Setting a named hook with no
+    /// commands removes it. Returns the profile, so several lists can be set in a row.
+    pub fn set_commands(&mut self, hook: &Hook, commands: Vec<CommandEntry>) -> &mut Self {
+        match hook {
+            Hook::Pre => self.pre_commands = commands,
+            Hook::Post => self.post_commands = commands,
+            Hook::Removal => self.removal_commands = commands,
+            Hook::Custom(name) if commands.is_empty() => { self.custom_hooks.remove(name); },
+            Hook::Custom(name) => { self.custom_hooks.insert(name.clone(), commands); }
+        }
+        self
+    }
+
+    /// Returns each of the profile's command lists along with the [`Hook`] they're ran for, the
+    /// pre, post & removal commands first and then the named hooks sorted by name.
+    pub fn command_lists(&self) -> impl Iterator<Item = (Hook, &[CommandEntry])> {
+        [
+            (Hook::Pre, self.pre_commands.as_slice()),
+            (Hook::Post, self.post_commands.as_slice()),
+            (Hook::Removal, self.removal_commands.as_slice())
+        ].into_iter()
+            .chain(self.custom_hooks.iter().map(|(name, commands)| (Hook::Custom(name.clone()), commands.as_slice())))
+    }
+
+    /// Recursively scans `directory` inside the profile's repo for [`DotfileProfile::fill_files`],
+    /// pushing every path found (relative to `repo_path`) into `found`. Directories are only
+    /// descended into while `depth` has levels left, otherwise they're added as a whole. Anything
+    /// matching `ignores` is skipped.
+    fn scan_directory(&self, directory: &Path, depth: Option<usize>, ignores: &Ignores, found: &mut Vec<PathBuf>) -> Result<(), DotulousError> {
+        let paths: ReadDir = fs::read_dir(directory).map_err(DotulousError::FailedReadProfileDirectory)?;
         for path in paths {
-            let Ok(path) = path else { return Err(DotulousError::FailedReadProfileDirectory) };
+            let path: DirEntry = path.map_err(DotulousError::FailedReadProfileDirectory)?;
             let actual_path = path.path();
-            let Ok(stripped_path) = actual_path.strip_prefix(&self.repo_path) else { return Err(DotulousError::FailedReadProfileDirectory) };
-            let final_path = stripped_path.to_path_buf();
+            let stripped_path: &Path = actual_path.strip_prefix(&self.repo_path)
+                .map_err(|e| DotulousError::FailedReadProfileDirectory(io::Error::other(e)))?;
+            let is_dir: bool = path.file_type().is_ok_and(|t| t.is_dir());
+            if ignores.is_ignored(stripped_path, is_dir) {
+                continue;
+            }
 
-            println!("  {final_path:?}");
-            self.files.insert(final_path.clone(), final_path.clone());
+            let remaining_depth: Option<usize> = depth.map(|d| d.saturating_sub(1));
+            if is_dir && remaining_depth != Some(0) {
+                self.scan_directory(&actual_path, remaining_depth, ignores, found)?;
+            } else {
+                found.push(stripped_path.to_path_buf());
+            }
         }
-        println!();
-        println!("Done! Make sure to go through them manually to make sure!");
+        Ok(())
+    }
 
-        self.save_manifest()
+    /// Returns the *absolute* path the profile's destinations are relative to. This is `home_path`,
+    /// unless the profile has a `target_root`, in which case it's expanded (`~` being `home_path`)
+    /// and joined onto `home_path` if relative.
+    pub fn target_path(&self, home_path: &Path) -> PathBuf {
+        let Some(target_root) = &self.target_root else { return home_path.to_path_buf() };
+        let target_root: String = target_root.to_string_lossy().to_string();
+        let expanded = shellexpand::full_with_context_no_errors(
+            &target_root,
+            || Some(home_path.to_string_lossy()),
+            |variable| env::var(variable).ok()
+        );
+        home_path.join(expanded.as_ref())
+    }
+
+    /// Checks the *absolute* `destination` is inside `home_path`, returning [`Err`] with
+    /// [`DotulousError::DestinationOutsideHome`] if not. This is skipped if the profile has
+    /// `allow_outside_home` set.
+    ///
+    /// Both paths are resolved first, so destinations can't escape with `..` or by going through
+    /// a symlinked folder. The destination itself is never followed, as it's what gets replaced
+    /// or removed.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn check_destination(&self, home_path: &Path, destination: &Path) -> Result<(), DotulousError> {
+        if self.allow_outside_home {
+            return Ok(())
+        }
+        let home_path: PathBuf = fs::canonicalize(home_path).unwrap_or_else(|_| platform::normalize_path(home_path));
+        if !resolve_path(destination).starts_with(home_path) {
+            return Err(DotulousError::DestinationOutsideHome)
+        }
+        Ok(())
+    }
+
+    /// Checks the *absolute* `destination` doesn't overlap `dotulous_path` or the profile's own
+    /// folder, returning [`Err`] with [`DotulousError::DestinationInsideDotulous`] if it does. A
+    /// destination inside either would have files put into (and later removed from) dotulous's
+    /// own data, while one holding either, such as `.` or `.local`, would have it moved away or
+    /// overwritten as a whole. Unlike [`DotfileProfile::check_destination`], this can't be turned
+    /// off.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn check_not_dotulous(&self, dotulous_path: &Path, destination: &Path) -> Result<(), DotulousError> {
+        let destination: PathBuf = resolve_path(destination);
+        for protected in [dotulous_path, &self.repo_path] {
+            let protected: PathBuf = fs::canonicalize(protected).unwrap_or_else(|_| platform::normalize_path(protected));
+            if destination.starts_with(&protected) || protected.starts_with(&destination) {
+                return Err(DotulousError::DestinationInsideDotulous)
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every destination of the profile's `files` & `assets`, along with it's `directories`,
+    /// with [`DotfileProfile::check_not_dotulous`], so a profile that would overwrite dotulous's own
+    /// data is refused before anything is done.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    pub fn check_destinations(&self, dotulous_path: &Path, home_path: &Path) -> Result<(), DotulousError> {
+        let target_path: PathBuf = self.target_path(home_path);
+        for destination in self.destinations().iter().chain(&self.directories) {
+            let destination: PathBuf = target_path.join(expand_path(destination, home_path));
+            if let Err(e) = self.check_not_dotulous(dotulous_path, &destination) {
+                say!("  ERROR: Destination {destination:?} is inside the dotulous folder or the profile's own folder! Refusing to load.");
+                return Err(e)
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the profile can be put onto the system before anything is done, with
+    /// [`DotfileProfile::check_destinations`] and then [`preflight::check`]. Every problem found by
+    /// the latter is printed at once, returning [`Err`] with [`DotulousError::PreflightFailed`].
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    pub fn preflight(&self, dotulous_path: &Path, home_path: &Path) -> Result<(), DotulousError> {
+        self.check_destinations(dotulous_path, home_path)?;
+        let problems: Vec<preflight::Problem> = preflight::check(self, home_path);
+        if problems.is_empty() {
+            return Ok(())
+        }
+        say!("  ERROR: Found {} problem(s) that would stop the profile from loading:", problems.len());
+        for problem in &problems {
+            say!("    {problem}");
+        }
+        Err(DotulousError::PreflightFailed)
+    }
+
+    /// Returns the [`Renderer`] for files using the template strategy, rendering into the profile's
+    /// folder inside the template cache in `dotulous_path`. Templates can use the
+    /// [`DotfileProfile::template_variables`], with the host's own read from `config_path`.
+    pub fn renderer(&self, dotulous_path: &Path, config_path: &Path, home_path: &Path) -> Result<Renderer, DotulousError> {
+        Ok(Renderer::new(self.template_cache_path(dotulous_path), &self.repo_path, self.template_variables(config_path, home_path)?))
+    }
+
+    /// Returns the variables that files using the template strategy and the profile's commands can
+    /// use as `{{name}}`, see [`template::render`].
+    ///
+    /// These are the built-ins `home`, `profile_dir`, `profile_name` & `hostname`, along with the
+    /// profile's `variables`. These can be overridden for this machine by `vars.json` inside the
+    /// user's config folder at `config_path` (see [`crate::paths::Paths`]), see
+    /// [`template::load_host_variables`].
+    pub fn template_variables(&self, config_path: &Path, home_path: &Path) -> Result<HashMap<String, String>, DotulousError> {
+        let mut variables: HashMap<String, String> = HashMap::from([
+            ("home".to_string(), home_path.to_string_lossy().to_string()),
+            ("profile_dir".to_string(), self.repo_path.to_string_lossy().to_string()),
+            ("profile_name".to_string(), self.name.clone()),
+            ("hostname".to_string(), Host::detect().hostname.unwrap_or_default())
+        ]);
+        variables.extend(self.variables.clone());
+        variables.extend(template::load_host_variables(config_path)?);
+        Ok(variables)
+    }
+
+    /// Returns the environment variables the profile's commands are ran with, on top of the user's
+    /// own environment.
+    ///
+    /// These are the built-ins `PROFILE_DIR`, `PROFILE_NAME`, `TARGET_DIR` (see
+    /// [`DotfileProfile::target_path`]) & `HOME` (being `home_path`), along with the profile's `env`,
+    /// which can override them.
+    pub fn command_env(&self, home_path: &Path) -> HashMap<String, String> {
+        let mut env: HashMap<String, String> = HashMap::from([
+            ("PROFILE_DIR".to_string(), self.repo_path.to_string_lossy().to_string()),
+            ("PROFILE_NAME".to_string(), self.name.clone()),
+            ("TARGET_DIR".to_string(), self.target_path(home_path).to_string_lossy().to_string()),
+            ("HOME".to_string(), home_path.to_string_lossy().to_string())
+        ]);
+        env.extend(self.env.clone());
+        env
+    }
+
+    /// Returns the *absolute* path of the profile's folder inside the template cache in `dotulous_path`.
+    pub fn template_cache_path(&self, dotulous_path: &Path) -> PathBuf {
+        dotulous_path.join("cache").join("templates").join(sanitize_filename::sanitize(&self.name))
+    }
+
+    /// Returns the *absolute* path of the profile's folder inside the secrets cache in `dotulous_path`.
+    pub fn secret_cache_path(&self, dotulous_path: &Path) -> PathBuf {
+        dotulous_path.join("cache").join("secrets").join(sanitize_filename::sanitize(&self.name))
+    }
+
+    /// Returns the [`SecretStore`] for files using the encrypted strategy, decrypting into the
+    /// profile's [`DotfileProfile::secret_cache_path`].
+    pub fn secret_store(&self, dotulous_path: &Path) -> SecretStore {
+        SecretStore::new(self.secret_cache_path(dotulous_path), &self.repo_path, self.secrets.clone())
+    }
+
+    /// Removes every file and command whose `when` condition doesn't match `host`, so only the
+    /// entries for the current machine are loaded. See [`crate::condition::Condition`].
+    ///
+    /// Like [`DotfileProfile::resolve_target_root`], this should only be done to a profile that is
+    /// about to be loaded, and never saved back to the manifest.
+    pub fn resolve_conditions(&mut self, host: &Host) {
+        self.retain_files(|_, entry| entry.applies_to(host));
+        for commands in [&mut self.pre_commands, &mut self.post_commands, &mut self.removal_commands].into_iter().chain(self.custom_hooks.values_mut()) {
+            commands.retain(|entry| entry.applies_to(host));
+        }
+    }
+
+    /// Removes every file and command not picked by the tag `filter`, so only the groups asked for
+    /// are loaded. See [`TagFilter`].
+    ///
+    /// Like [`DotfileProfile::resolve_conditions`], this should only be done to a profile that is
+    /// about to be loaded, and never saved back to the manifest.
+    pub fn resolve_tags(&mut self, filter: &TagFilter) {
+        self.retain_files(|_, entry| filter.matches(&entry.tags));
+        for commands in [&mut self.pre_commands, &mut self.post_commands, &mut self.removal_commands].into_iter().chain(self.custom_hooks.values_mut()) {
+            commands.retain(|entry| filter.matches(&entry.tags));
+        }
+    }
+
+    /// Replaces every entry in `files` using [`LinkMode::Contents`] whose source is a directory with
+    /// an entry for each file inside it (looking inside any directories within it too), each put at
+    /// the same place inside the entry's destination with the same strategy & condition. Files
+    /// matching the [`Ignores`] of the profile the directory is from are left out. The directories
+    /// are then created for real while loading, rather than linked.
+    ///
+    /// Like [`DotfileProfile::resolve_conditions`], this should only be done to a profile that is
+    /// about to be loaded, and never saved back to the manifest. The expanded entries are what's
+    /// stored in the meta, so unloading removes exactly the files that were loaded, even if the
+    /// directory has changed since.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn resolve_link_modes(&mut self) -> Result<(), DotulousError> {
+        let folded: Vec<PathBuf> = self.files.iter()
+            .filter(|(source, entries)| entries.iter().any(|entry| entry.link_mode == LinkMode::Contents) && self.repo_path.join(source).is_dir())
+            .map(|(source, _)| source.clone())
+            .collect();
+        for source in folded {
+            let Some(entries) = self.files.remove(&source) else { continue };
+            let (contents, dirs): (Vec<FileEntry>, Vec<FileEntry>) = entries.into_iter().partition(|entry| entry.link_mode == LinkMode::Contents);
+            if !dirs.is_empty() {
+                self.files.insert(source.clone(), dirs);
+            }
+            let directory: PathBuf = self.repo_path.join(&source);
+            // Files merged in from other profiles keep their absolute path, and are ignored by
+            // that profile's `.dotulousignore` instead
+            let root: &Path = match source.is_absolute() {
+                true => directory.ancestors().skip(1).find(|path| ManifestFormat::find_manifest(path).is_some()).unwrap_or(&directory),
+                false => &self.repo_path
+            };
+            let ignores: Ignores = Ignores::load(root, true, &[])?;
+            let mut found: Vec<PathBuf> = Vec::new();
+            find_files(&directory, Path::new(""), root, &ignores, &mut found)?;
+            for relative in found {
+                for entry in &contents {
+                    let unfolded: FileEntry = FileEntry {
+                        destination: entry.destination.join(&relative),
+                        link_mode: LinkMode::Dir,
+                        ..entry.clone()
+                    };
+                    self.files.entry(source.join(&relative)).or_default().push(unfolded);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges every profile this one extends (see [`DotfileProfile::base_profiles`]) underneath
+    /// it, recording their names in `extends_chain`. The `includes` of this profile and of each
+    /// profile it extends are merged in first, see [`DotfileProfile::resolve_includes`]. Doing this
+    /// to an already resolved profile does nothing.
+    ///
+    /// Files from extended profiles are kept with their *absolute* source path, and are skipped
+    /// if this profile already maps their destination. Their pre & post commands run before this
+    /// profile's, and their removal commands after. Variables, `env` & packages are combined, with
+    /// this profile's values taking priority.
+    ///
+    /// Like [`DotfileProfile::resolve_conditions`], this should only be done to a profile that is
+    /// about to be loaded, and never saved back to the manifest.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn resolve_extends(&mut self) -> Result<(), DotulousError> {
+        if !self.extends_chain.is_empty() {
+            return Ok(())
+        }
+        self.resolve_includes()?;
+        for mut base in self.base_profiles()? {
+            base.resolve_includes()?;
+            let name: String = base.name.clone();
+            self.merge_base(base);
+            self.extends_chain.push(name);
+        }
+        Ok(())
+    }
+
+    /// Merges every module this profile includes (see [`DotfileProfile::included_profiles`]) into
+    /// it, recording them in `included`. Modules have their own `includes` merged first, so they
+    /// can be split up further. Doing this to an already resolved profile does nothing.
+    ///
+    /// Modules are merged the same way as profiles this one extends, in the order they're
+    /// included, so their pre & post commands run before this profile's own and their removal
+    /// commands after. `{{profile_dir}}` in a module's commands is it's own folder, rather than the
+    /// profile's. Unlike extending, nothing can be overridden: if more than one of the profile &
+    /// it's modules put something at the same destination or system file, or set the same hook,
+    /// variable, `env`, `target_root` or `secrets`, [`Err`] with
+    /// [`DotulousError::IncludeConflict`] is returned listing every one.
+    ///
+    /// Like [`DotfileProfile::resolve_extends`], this should only be done to a profile that is
+    /// about to be loaded, and never saved back to the manifest.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn resolve_includes(&mut self) -> Result<(), DotulousError> {
+        if self.includes.is_empty() || !self.included.is_empty() {
+            return Ok(())
+        }
+        let mut modules: Vec<(String, DotfileProfile)> = Vec::new();
+        for (include, mut module) in self.includes.iter().zip(self.included_profiles()?) {
+            module.resolve_includes()?;
+            modules.push((include.to_string_lossy().to_string(), module));
+        }
+
+        let mut owners: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        let parts = iter::once((self.name.as_str(), &*self)).chain(modules.iter().map(|(name, module)| (name.as_str(), module)));
+        for (name, part) in parts {
+            for item in part.merged_items() {
+                let owners: &mut Vec<&str> = owners.entry(item).or_default();
+                if !owners.contains(&name) {
+                    owners.push(name);
+                }
+            }
+        }
+        let conflicts: Vec<(String, Vec<String>)> = owners.into_iter()
+            .filter(|(_, owners)| owners.len() > 1)
+            .map(|(item, owners)| (item, owners.iter().map(ToString::to_string).collect()))
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(DotulousError::IncludeConflict(IncludeConflicts(conflicts)))
+        }
+
+        for (name, module) in &modules {
+            self.included.push(name.clone());
+            self.included.extend(module.included.iter().map(|nested| format!("{name}/{nested}")));
+        }
+        // Each module goes underneath the ones after it, so the first module's commands run first
+        for (_, mut module) in modules.into_iter().rev() {
+            module.render_profile_dir();
+            self.merge_base(module);
+        }
+        Ok(())
+    }
+
+    /// Returns a description of everything in the profile that [`DotfileProfile::resolve_includes`]
+    /// can't merge if more than one module has it: each destination (see [`place_key`]) & system
+    /// file, each of `hooks`, `variables` & `env` by name, and `target_root` & `secrets` if set.
+    fn merged_items(&self) -> Vec<String> {
+        let destinations = self.destinations().into_iter().map(|destination| place_key(&destination).to_string_lossy().to_string());
+        let system_files = self.system_files.values().map(|destination| destination.to_string_lossy().to_string());
+        let hooks = self.custom_hooks.keys().map(|name| format!("hook \"{name}\""));
+        let variables = self.variables.keys().map(|name| format!("variable \"{name}\""));
+        let env = self.env.keys().map(|name| format!("env \"{name}\""));
+        let target_root = self.target_root.as_ref().map(|_| "target_root".to_string());
+        let secrets = self.secrets.as_ref().map(|_| "secrets".to_string());
+        destinations.chain(system_files).chain(hooks).chain(variables).chain(env).chain(target_root).chain(secrets).collect()
+    }
+
+    /// Replaces `{{profile_dir}}` in each of the profile's commands with it's own folder, so a
+    /// module's commands keep pointing into the module once it's merged into the profile including
+    /// it, see [`DotfileProfile::resolve_includes`].
+    fn render_profile_dir(&mut self) {
+        let folder: HashMap<String, String> = HashMap::from([("profile_dir".to_string(), self.repo_path.to_string_lossy().to_string())]);
+        let commands = [&mut self.pre_commands, &mut self.post_commands, &mut self.removal_commands].into_iter()
+            .chain(self.custom_hooks.values_mut())
+            .flatten();
+        for entry in commands {
+            entry.command = template::render_command(&entry.command, &folder);
+        }
+    }
+
+    /// Merges `base` underneath this profile, see [`DotfileProfile::resolve_extends`] &
+    /// [`DotfileProfile::resolve_includes`].
+    fn merge_base(&mut self, mut base: DotfileProfile) {
+        // Base commands were written for the base's shell & timeout, so they keep them rather than this profile's
+        let commands = [&mut base.pre_commands, &mut base.post_commands, &mut base.removal_commands].into_iter()
+            .chain(base.custom_hooks.values_mut())
+            .flatten();
+        for entry in commands {
+            entry.shell = entry.shell.take().or_else(|| base.shell.clone());
+            entry.timeout = entry.timeout.or(base.timeout);
+        }
+        let destinations: Vec<PathBuf> = self.entries().map(|(_, e)| place_key(&e.destination)).collect();
+        for (source, entries) in base.files {
+            let entries: Vec<FileEntry> = entries.into_iter().filter(|entry| !destinations.contains(&place_key(&entry.destination))).collect();
+            if !entries.is_empty() {
+                self.files.insert(base.repo_path.join(source), entries);
+            }
+        }
+        for (source, destination) in base.system_files {
+            if !self.system_files.values().any(|other| *other == destination) {
+                self.system_files.insert(base.repo_path.join(source), destination);
+            }
+        }
+        self.pre_commands = [base.pre_commands, mem::take(&mut self.pre_commands)].concat();
+        self.post_commands = [base.post_commands, mem::take(&mut self.post_commands)].concat();
+        self.removal_commands.extend(base.removal_commands);
+        for (name, commands) in base.custom_hooks {
+            self.custom_hooks.entry(name).or_insert(commands);
+        }
+        self.assets = [base.assets, mem::take(&mut self.assets)].concat();
+        let mut directories: Vec<PathBuf> = base.directories;
+        directories.retain(|directory| !self.directories.contains(directory));
+        directories.append(&mut self.directories);
+        self.directories = directories;
+        for (distro, packages) in base.packages {
+            self.packages.entry(distro).or_default().extend(packages);
+        }
+        for (name, value) in base.variables {
+            self.variables.entry(name).or_insert(value);
+        }
+        for (name, value) in base.env {
+            self.env.entry(name).or_insert(value);
+        }
+        if self.target_root.is_none() {
+            self.target_root = base.target_root;
+        }
+        if self.secrets.is_none() {
+            self.secrets = base.secrets;
+        }
+    }
+
+    /// Replaces the profile's `target_root` with the resolved, absolute [`DotfileProfile::target_path`].
+    /// This is done to the copy of the profile stored in the meta, so that it is always unloaded from
+    /// the same place it was loaded to.
+    pub fn resolve_target_root(&mut self, home_path: &Path) {
+        self.target_root = Some(self.target_path(home_path));
     }
 
     /// Loads the profile to the system, in three stages;
-    /// - It runs any `pre_commands` that are specified. These are ran in a new `sh` shell, with the
-    ///   working directory being the user's home folder.
-    /// - It will then symlink all the files from the profile's directory to the system, according
-    ///   to the `files` property.
+    /// - It runs any `pre_commands` that are specified. These are ran in a new `sh` shell (unless the
+    ///   user's `settings` say otherwise), with the working directory being the user's home folder.
+    /// - It will then symlink (or copy, hardlink or render, depending on the entry's
+    ///   [`crate::entry::Strategy`]) all the files from the profile's directory to the system, according
+    ///   to the `files` property, relative to the [`DotfileProfile::target_path`]. Any files already
+    ///   in the way are dealt with according to `on_conflict`, by default moving them into a
+    ///   [`Backup`].
+    /// - Any `assets` are then fetched into the asset cache inside `dotulous_path` (downloading them
+    ///   if they are not already cached), verified, and symlinked to the system, and any missing
+    ///   `directories` are created.
     /// - Finally, it will run any `post_commands` in the same way of pre-commands.
     ///
+    /// Every change made to the filesystem is recorded in a [`Journal`]. If any step fails (a
+    /// command exits unsuccessfully, or a file can't be put onto the system), loading stops and
+    /// the journal is rolled back, leaving the system as it was before loading. Commands that were
+    /// already ran can't be undone. A profile with a destination overlapping the dotulous folder
+    /// or it's own folder is refused before anything is ran, as is one with a missing source or a
+    /// destination that can't be written to, see [`DotfileProfile::preflight`].
+    ///
     /// It is **highly advised** to then update the meta via [`Meta::set_current_profile`] & [`Meta::save_meta`].
     /// Otherwise, dotulous will not know what profile is currently loaded.
     ///
@@ -160,64 +1140,483 @@ impl DotfileProfile {
     /// loading two will cause the first profile loaded to be invisible to dotulous, not letting
     /// the user un-load it.
     ///
+    /// If `mode` is [`ApplyMode::DryRun`], every step is printed as normal but nothing is actually
+    /// done, letting the user see what loading the profile would do.
+    ///
+    /// Each phase shows a [`Progress`] bar followed by a summary, unless `verbose` is set, in which
+    /// case every file is printed as it's linked.
+    ///
+    /// Returns the [`Backup`] of any files that were in the way, if there were any. It's path
+    /// should be stored with [`Meta::set_current_backup`] so it can be restored on unload. Also
+    /// returns the [`LoadState`] of what was actually put onto the system, which should be saved
+    /// with [`LoadState::save`] and given to [`DotfileProfile::unload_profile_from_system`].
+    ///
     /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
-    /// Upon any errors, the function will simply print to stdout and continue.
-    pub fn load_profile_to_system(&self, home_path: &Path) {
-        println!("Loading profile: {}", self.name);
+    #[allow(clippy::too_many_arguments)]
+    pub fn load_profile_to_system(&self, dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, mode: ApplyMode, on_conflict: OnConflict, verbose: bool) -> Result<(Option<Backup>, LoadState), DotulousError> {
+        say!("Loading profile: {}", self.name);
+        self.preflight(dotulous_path, home_path)?;
         if !self.pre_commands.is_empty() {
-            println!();
-            println!("Running pre-commands.");
-            for command in &self.pre_commands {
-                println!("  {command}");
-                let command: Result<Output, io::Error> = Command::new("sh")
-                    .current_dir(home_path)
-                    .arg("-c")
-                    .arg(command)
-                    .output();
-                if command.is_err() {
-                    let unwrapped = command.unwrap();
-                    println!("  ERROR: Command failed to run (exit code {}): {}", unwrapped.status, String::from_utf8(unwrapped.stderr).unwrap());
+            say!();
+            say!("Running pre-commands.");
+            run_commands(&self.pre_commands, self, dotulous_path, config_path, home_path, settings, self.on_error, mode, verbose)?;
+        }
+
+        let mut journal: Journal = Journal::new();
+        let result: Result<(), DotulousError> = self.link_files(dotulous_path, config_path, home_path, mode, on_conflict, verbose, &mut journal)
+            .and_then(|()| {
+                if self.post_commands.is_empty() {
+                    return Ok(())
                 }
+                say!();
+                say!("Running post-commands.");
+                run_commands(&self.post_commands, self, dotulous_path, config_path, home_path, settings, self.on_error, mode, verbose)
+            });
+        if let Err(e) = result {
+            say!();
+            match e {
+                DotulousError::Interrupted => say!("Interrupted, rolling back."),
+                _ => say!("Loading failed, rolling back.")
             }
+            journal.rollback();
+            return Err(e)
+        }
+        Ok(journal.finish())
+    }
+
+    /// Symlinks all the files from the profile's directory to the system according to the `files`
+    /// property, then fetches and symlinks any `assets` and creates any missing `directories`. This is the middle stage of
+    /// [`DotfileProfile::load_profile_to_system`], and does **not** run any commands.
+    ///
+    /// Destinations are relative to the [`DotfileProfile::target_path`], which is created if it
+    /// doesn't exist yet, along with any missing folders a destination is inside of. Files are
+    /// linked in order of their path inside the profile's directory. Nothing is created if `mode`
+    /// is [`ApplyMode::DryRun`], or if the profile fails it's [`DotfileProfile::preflight`] checks.
+    ///
+    /// If something already exists at a destination, it's dealt with according to `on_conflict`,
+    /// such as moving it into the `journal`'s [`Backup`] (inside `dotulous_path`). Destinations that
+    /// are already symlinked to the right source are left alone. Once everything in the way is dealt
+    /// with, the files are put onto the system in parallel. Templates are rendered with the host
+    /// variables in `config_path`, see [`DotfileProfile::renderer`].
+    ///
+    /// Every change is recorded in `journal`. This stops at the first error, except for files
+    /// being placed in parallel which are all attempted, reporting every failure. It's up to the
+    /// caller to then [`Journal::rollback`] the changes that were made.
+    ///
+    /// Progress is shown as a [`Progress`] bar unless `verbose`, followed by a summary of how many
+    /// files were linked, skipped & failed.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    #[allow(clippy::too_many_arguments)]
+    pub fn link_files(&self, dotulous_path: &Path, config_path: &Path, home_path: &Path, mode: ApplyMode, on_conflict: OnConflict, verbose: bool, journal: &mut Journal) -> Result<(), DotulousError> {
+        self.preflight(dotulous_path, home_path)?;
+        let progress: Progress = Progress::new(self.entries().count() + self.assets.len(), verbose);
+        let mut summary: LinkSummary = LinkSummary::default();
+        let result: Result<(), DotulousError> = self.link_entries(dotulous_path, config_path, home_path, mode, on_conflict, journal, &progress, &mut summary);
+        progress.finish();
+        if result.is_err() && summary.failed == 0 {
+            summary.failed += 1;
         }
+        say!("  Linked {} file(s), skipped {}, {} failed.", summary.linked, summary.skipped, summary.failed);
+        result
+    }
 
-        println!();
-        for file in &self.files {
-            let source: PathBuf = self.repo_path.join(file.0);
-            let destination: PathBuf = home_path.join(file.1);
-            println!("  {source:?} => {destination:?}");
-            if destination.exists() {
-                println!("  WARNING: Destination {destination:?} already exists! Skipping!");
+    /// Does the work of [`DotfileProfile::link_files`], reporting each file to `progress` and
+    /// counting them in `summary`.
+    #[allow(clippy::too_many_arguments)]
+    fn link_entries(&self, dotulous_path: &Path, config_path: &Path, home_path: &Path, mode: ApplyMode, on_conflict: OnConflict, journal: &mut Journal, progress: &Progress, summary: &mut LinkSummary) -> Result<(), DotulousError> {
+        let target_path: PathBuf = self.target_path(home_path);
+        if let Err(e) = self.check_destination(home_path, &target_path) {
+            progress.println(format!("  ERROR: Target root {target_path:?} is outside of your home folder, set \"allow_outside_home\" to allow it."));
+            return Err(e)
+        }
+        if !target_path.exists() && mode == ApplyMode::Apply {
+            if let Err(e) = journal.create_dir_all(&target_path) {
+                progress.println(format!("  ERROR: Failed to create target root {target_path:?}: {e:#}"));
+                return Err(e)
+            }
+        }
+        let renderer: Renderer = match self.renderer(dotulous_path, config_path, home_path) {
+            Ok(r) => r,
+            Err(e) => {
+                progress.println(format!("  ERROR: Failed to load template variables: {e:#}"));
+                return Err(e)
+            }
+        };
+        let secrets: SecretStore = self.secret_store(dotulous_path);
+        progress.println(String::new());
+        // Anything in the way is backed up first, so the files themselves can be placed in parallel
+        let mut planned: Vec<(&FileEntry, PathBuf, PathBuf)> = Vec::new();
+        for (source, entry) in self.entries() {
+            interrupt::check()?;
+            let source: PathBuf = self.repo_path.join(expand_path(source, home_path));
+            let destination: PathBuf = target_path.join(expand_path(&entry.destination, home_path));
+            if entry.optional && source.symlink_metadata().is_err() {
+                progress.inc();
+                progress.detail(format!("  Skipping optional {source:?}, it doesn't exist"));
+                summary.skipped += 1;
+                continue;
+            }
+            if let Err(e) = self.check_destination(home_path, &destination) {
+                progress.println(format!("  ERROR: Destination {destination:?} is outside of your home folder, set \"allow_outside_home\" to allow it."));
+                return Err(e)
+            }
+            if destination.read_link().is_ok_and(|target| target == source) {
+                progress.inc();
+                progress.println(format!("  WARNING: Destination {destination:?} is already linked! Skipping!"));
+                journal.skip(&destination);
+                summary.skipped += 1;
                 continue;
             }
-            if let Err(e) = symlink(&source, &destination) {
-                println!("  ERROR: Failed to symlink {source:?} -> {destination:?}: {e}");
+            create_parents(&destination, mode, journal, progress)?;
+            if destination.symlink_metadata().is_ok() && !self.resolve_conflict(dotulous_path, &destination, mode, on_conflict, journal, progress)? {
+                progress.inc();
+                summary.skipped += 1;
+                continue;
+            }
+            if mode == ApplyMode::DryRun {
+                progress.inc();
+                progress.detail(format!("  {source:?} => {destination:?}"));
+                summary.linked += 1;
+                continue;
             }
+            planned.push((entry, source, destination));
         }
 
-        if !self.post_commands.is_empty() {
-            println!();
-            println!("Running post-commands.");
-            for command in &self.post_commands {
-                println!("  {command}");
-                let command: Result<Output, io::Error> = Command::new("sh")
-                    .current_dir(home_path)
-                    .arg("-c")
-                    .arg(command)
-                    .output();
-                if command.is_err() {
-                    let unwrapped = command.unwrap();
-                    println!("  ERROR: Command failed to run (exit code {}): {}", unwrapped.status, String::from_utf8(unwrapped.stderr).unwrap());
+        let results: Vec<io::Result<()>> = planned.par_iter()
+            .map(|(entry, source, destination)| {
+                let result: io::Result<()> = entry.strategy.apply(source, destination, entry.mode, &renderer, &secrets);
+                progress.inc();
+                progress.detail(format!("  {source:?} => {destination:?}"));
+                result
+            })
+            .collect();
+        for ((entry, source, destination), result) in planned.iter().zip(results) {
+            let strategy: Strategy = entry.strategy;
+            if let Err(e) = journal.record(strategy, source, destination, &secrets, result) {
+                progress.println(format!("  ERROR: Failed to link {source:?} -> {destination:?} ({strategy:?}): {e:#}"));
+                summary.failed += 1;
+                continue;
+            }
+            // Everything else was created with it's mode. Set once it's recorded, so the file is still
+            // rolled back if this fails
+            if let (Some(mode), Strategy::Symlink | Strategy::Hardlink) = (entry.mode, strategy) {
+                if let Err(e) = platform::set_mode(destination, mode.bits()) {
+                    progress.println(format!("  ERROR: Failed to set the mode of {destination:?} to {mode}: {e:#}"));
+                    summary.failed += 1;
+                    continue;
                 }
             }
+            if entry.is_sensitive() && platform::is_world_readable(destination) {
+                progress.println(format!("  WARNING: {destination:?} looks like it holds secrets, but every user can read it! Give it's entry a \"mode\" such as \"0600\"."));
+            }
+            summary.linked += 1;
+        }
+        if summary.failed > 0 {
+            return Err(DotulousError::FailedPlaceFile)
         }
 
+        if !self.assets.is_empty() {
+            progress.detail(String::new());
+            progress.detail("Fetching assets.".to_string());
+            let cache_path: PathBuf = asset::cache_path(dotulous_path);
+            for asset in &self.assets {
+                interrupt::check()?;
+                let destination: PathBuf = target_path.join(expand_path(&asset.dest, home_path));
+                progress.inc();
+                progress.detail(format!("  {} => {destination:?}", asset.url));
+                if let Err(e) = self.check_destination(home_path, &destination) {
+                    progress.println(format!("  ERROR: Destination {destination:?} is outside of your home folder, set \"allow_outside_home\" to allow it."));
+                    return Err(e)
+                }
+                create_parents(&destination, mode, journal, progress)?;
+                if destination.symlink_metadata().is_ok() && !self.resolve_conflict(dotulous_path, &destination, mode, on_conflict, journal, progress)? {
+                    summary.skipped += 1;
+                    continue;
+                }
+                if mode == ApplyMode::DryRun {
+                    summary.linked += 1;
+                    continue;
+                }
+                let cached: PathBuf = match asset.fetch(&cache_path) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        progress.println(format!("  ERROR: Failed to fetch asset {}: {e:#}", asset.url));
+                        return Err(e)
+                    }
+                };
+                if let Err(e) = journal.place(Strategy::Symlink, &cached, &destination, &renderer, &secrets) {
+                    progress.println(format!("  ERROR: Failed to symlink {cached:?} -> {destination:?}: {e:#}"));
+                    return Err(DotulousError::FailedPlaceFile)
+                }
+                summary.linked += 1;
+            }
+        }
+
+        for directory in &self.directories {
+            interrupt::check()?;
+            let destination: PathBuf = target_path.join(expand_path(directory, home_path));
+            if let Err(e) = self.check_destination(home_path, &destination) {
+                progress.println(format!("  ERROR: Folder {destination:?} is outside of your home folder, set \"allow_outside_home\" to allow it."));
+                return Err(e)
+            }
+            if destination.is_dir() {
+                continue;
+            }
+            if destination.symlink_metadata().is_ok() && !self.resolve_conflict(dotulous_path, &destination, mode, on_conflict, journal, progress)? {
+                continue;
+            }
+            progress.detail(format!("  Creating folder {destination:?}"));
+            if mode == ApplyMode::DryRun {
+                continue;
+            }
+            if let Err(e) = journal.create_dir_all(&destination) {
+                progress.println(format!("  ERROR: Failed to create folder {destination:?}: {e:#}"));
+                return Err(e)
+            }
+        }
+        Ok(())
+    }
+
+    /// Deals with something that's already at `destination` before a file is put there, according
+    /// to `on_conflict`, reporting it to `progress`. If it's [`OnConflict::Prompt`] the user is
+    /// asked, unless `mode` is [`ApplyMode::DryRun`] in which case it's left to when the profile
+    /// is actually loaded.
+    ///
+    /// Returns `true` if the file should still be put there, or `false` if it should be skipped,
+    /// which is recorded in the `journal`. If loading should be aborted, [`Err`] with
+    /// [`DotulousError::DestinationConflict`] is returned.
+    fn resolve_conflict(&self, dotulous_path: &Path, destination: &Path, mode: ApplyMode, on_conflict: OnConflict, journal: &mut Journal, progress: &Progress) -> Result<bool, DotulousError> {
+        let on_conflict: OnConflict = match on_conflict {
+            OnConflict::Prompt if mode == ApplyMode::DryRun => {
+                progress.println(format!("  Destination {destination:?} already exists, you'll be asked what to do with it."));
+                return Ok(true)
+            },
+            OnConflict::Prompt => progress.suspend(|| ask_conflict(destination)),
+            on_conflict => on_conflict
+        };
+        match on_conflict {
+            OnConflict::Skip => {
+                progress.println(format!("  WARNING: Destination {destination:?} already exists! Skipping!"));
+                journal.skip(destination);
+                Ok(false)
+            },
+            OnConflict::Backup => {
+                progress.detail(format!("  Backing up existing {destination:?}"));
+                if mode == ApplyMode::Apply {
+                    if let Err(e) = journal.back_up(dotulous_path, &self.name, destination) {
+                        progress.println(format!("  ERROR: Failed to back up {destination:?}: {e:#}"));
+                        return Err(e)
+                    }
+                }
+                Ok(true)
+            },
+            OnConflict::Overwrite => {
+                progress.detail(format!("  Overwriting existing {destination:?}"));
+                if mode == ApplyMode::Apply {
+                    let is_dir: bool = destination.symlink_metadata().is_ok_and(|m| m.is_dir());
+                    let removed: io::Result<()> = if is_dir { fs::remove_dir_all(destination) } else { fs::remove_file(destination) };
+                    if let Err(e) = removed {
+                        progress.println(format!("  ERROR: Failed to remove {destination:?}: {e:#}"));
+                        return Err(DotulousError::FailedPlaceFile)
+                    }
+                }
+                Ok(true)
+            },
+            OnConflict::Prompt | OnConflict::Abort => {
+                progress.println(format!("  ERROR: Destination {destination:?} already exists! Aborting."));
+                Err(DotulousError::DestinationConflict)
+            }
+        }
+    }
+
+    /// Returns every destination this profile will create when loaded, relative to the
+    /// [`DotfileProfile::target_path`].
+    /// This includes both the `files` and `assets` destinations.
+    pub fn destinations(&self) -> Vec<PathBuf> {
+        let mut destinations: Vec<PathBuf> = self.entries().map(|(_, e)| e.destination.clone()).collect();
+        destinations.extend(self.assets.iter().map(|a| a.dest.clone()));
+        destinations
+    }
+
+    /// Returns a copy of the profile without the parts `skip` leaves out, to load or unload instead
+    /// of the profile itself. The full profile is still what should be stored in the meta, so the
+    /// parts left out are handled next time. Leaving out hooks also leaves out `custom_hooks` &
+    /// `packages`, as installing packages runs commands too.
+    pub fn skipping(&self, skip: Skip) -> DotfileProfile {
+        let mut profile: DotfileProfile = self.clone();
+        if skip.hooks {
+            profile.pre_commands.clear();
+            profile.post_commands.clear();
+            profile.removal_commands.clear();
+            profile.custom_hooks.clear();
+            profile.packages.clear();
+        }
+        if skip.files {
+            profile.files.clear();
+            profile.assets.clear();
+            profile.directories.clear();
+        }
+        profile
+    }
+
+    /// Returns if the profile has any commands that could be ran, including it's `custom_hooks`, or
+    /// `packages` that could be installed. See [`DotfileProfile::required_trust`].
+    pub fn runs_commands(&self) -> bool {
+        self.command_lists().any(|(_, commands)| !commands.is_empty()) || !self.packages.is_empty()
+    }
+
+    /// Returns how far the profile must be trusted to be loaded or unloaded leaving out what `skip`
+    /// does (see [`DotfileProfile::skipping`]), including anything from the profiles it extends.
+    /// Only linking files needs [`TrustScope::Files`], but having any `pre_commands`,
+    /// `post_commands`, `removal_commands`, `custom_hooks` or `packages` to install needs
+    /// [`TrustScope::Commands`]. If the profiles it extends can't be found,
+    /// [`TrustScope::Commands`] is needed to be safe.
+    pub fn required_trust(&self, skip: Skip) -> TrustScope {
+        let mut resolved: DotfileProfile = self.clone();
+        if resolved.resolve_extends().is_err() {
+            return TrustScope::Commands
+        }
+        let resolved: DotfileProfile = resolved.skipping(skip);
+        if !resolved.runs_commands() {
+            TrustScope::Files
+        } else {
+            TrustScope::Commands
+        }
+    }
+
+    /// Splits the profile's `files` & `assets` by `filter`, returning a copy with only the picked
+    /// entries and no commands or `directories`, followed by a copy with the rest of the entries,
+    /// every command and the `directories`.
+    /// Used to load or unload part of the profile on it's own, see
+    /// [`crate::operations::load_partial`].
+    pub fn partition(&self, filter: &EntryFilter) -> (DotfileProfile, DotfileProfile) {
+        let mut picked: DotfileProfile = self.clone();
+        let mut rest: DotfileProfile = self.clone();
+        picked.retain_files(|source, entry| filter.matches(&entry.destination, &source.to_string_lossy()));
+        picked.assets.retain(|asset| filter.matches(&asset.dest, &asset.url));
+        rest.retain_files(|source, entry| !filter.matches(&entry.destination, &source.to_string_lossy()));
+        rest.assets.retain(|asset| !filter.matches(&asset.dest, &asset.url));
+
+        picked.pre_commands.clear();
+        picked.post_commands.clear();
+        picked.removal_commands.clear();
+        picked.custom_hooks.clear();
+        picked.directories.clear();
+        (picked, rest)
+    }
+
+    /// Adds the `files` & `assets` of `other` to this profile, replacing any with the same source
+    /// or destination. Used to put a part loaded on it's own back into the loaded profile, see
+    /// [`DotfileProfile::partition`].
+    pub fn absorb_entries(&mut self, other: DotfileProfile) {
+        self.remove_entries(&other.destinations());
+        for (source, entries) in other.files {
+            self.files.entry(source).or_default().extend(entries);
+        }
+        self.assets.extend(other.assets);
+    }
+
+    /// Returns a copy of the profile with only the `files` & `assets` that aren't in `other` exactly
+    /// the same, such as to find what [`crate::operations::reload`] has to put onto the system
+    /// again. Files that aren't symlinks are always kept, as what they were made from may have
+    /// changed even if their entry hasn't. Both profiles should be resolved the same way.
+    pub fn changed_from(&self, other: &DotfileProfile) -> DotfileProfile {
+        let mut changed: DotfileProfile = self.clone();
+        changed.retain_files(|source, entry| entry.strategy != Strategy::Symlink || !other.files.get(source).is_some_and(|entries| entries.contains(entry)));
+        changed.assets.retain(|asset| !other.assets.contains(asset));
+        changed
+    }
+
+    /// Returns if running the profile's pre, post or removal commands would be any different to
+    /// running `other`'s, either as the commands themselves changed or what they're ran with did.
+    pub fn commands_changed_from(&self, other: &DotfileProfile) -> bool {
+        [Hook::Pre, Hook::Post, Hook::Removal].iter().any(|hook| self.commands(hook) != other.commands(hook))
+            || self.env != other.env
+            || self.variables != other.variables
+            || self.on_error != other.on_error
+            || self.shell != other.shell
+            || self.timeout != other.timeout
+    }
+
+    /// Removes every one of the profile's `files` & `assets` going to one of `destinations`.
+    pub fn remove_entries(&mut self, destinations: &[PathBuf]) {
+        self.retain_files(|_, entry| !destinations.contains(&entry.destination));
+        self.assets.retain(|asset| !destinations.contains(&asset.dest));
+    }
+
+    /// Keeps only the entries in `files` that `keep` returns `true` for, given their source and
+    /// the entry itself. Sources left without any entries are removed.
+    fn retain_files(&mut self, mut keep: impl FnMut(&Path, &FileEntry) -> bool) {
+        self.files.retain(|source, entries| {
+            entries.retain(|entry| keep(source, entry));
+            !entries.is_empty()
+        });
+    }
+
+    /// Returns every link this profile creates when loaded, keyed by the destination relative to the
+    /// [`DotfileProfile::target_path`]. The value is the link's source, either the path relative to the profile's
+    /// directory, or the URL of an asset.
+    pub fn links(&self) -> BTreeMap<PathBuf, String> {
+        let mut links: BTreeMap<PathBuf, String> = self.entries()
+            .map(|(source, entry)| (entry.destination.clone(), source.to_string_lossy().to_string()))
+            .collect();
+        links.extend(self.assets.iter().map(|a| (a.dest.clone(), a.url.clone())));
+        links
+    }
+
+    /// Returns each of the profile's command lists, keyed by their name in the manifest. Named
+    /// `hooks` are keyed as `hooks.{name}`.
+    pub fn hooks(&self) -> BTreeMap<String, Vec<&str>> {
+        [
+            ("pre_commands".to_string(), &self.pre_commands),
+            ("post_commands".to_string(), &self.post_commands),
+            ("removal_commands".to_string(), &self.removal_commands)
+        ].into_iter()
+            .chain(self.custom_hooks.iter().map(|(name, entries)| (format!("hooks.{name}"), entries)))
+            .map(|(hook, entries)| (hook, entries.iter().map(|e| e.command.as_str()).collect()))
+            .collect()
+    }
+
+    /// Returns the names of the profile's own `hooks`, see [`Hook::Custom`].
+    pub fn custom_hook_names(&self) -> impl Iterator<Item = &str> {
+        self.custom_hooks.keys().map(String::as_str)
+    }
+
+    /// Runs the profile's commands for `hook` on their own, without linking or removing any files,
+    /// in the same environment as when loading (see [`DotfileProfile::command_env`]). Pre & post
+    /// commands stop at the first failure according to their `on_error`, like when loading, while
+    /// removal commands carry on unless the command's own `on_error` says otherwise. Named hooks
+    /// act like post commands. Each command is recorded in the audit log inside `dotulous_path`.
+    ///
+    /// If the profile has no named hook called `hook`, [`Err`] with [`DotulousError::HookNotFound`]
+    /// is returned.
+    ///
+    /// If `mode` is [`ApplyMode::DryRun`], the commands are only printed. Each command's output is
+    /// shown as it runs, with a [`Progress`] bar between them unless `verbose`.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_hook(&self, hook: &Hook, dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, mode: ApplyMode, verbose: bool) -> Result<(), DotulousError> {
+        let Some(commands) = self.commands(hook) else { return Err(DotulousError::HookNotFound) };
+        let on_error: OnError = if *hook == Hook::Removal { OnError::Continue } else { self.on_error };
+        run_commands(commands, self, dotulous_path, config_path, home_path, settings, on_error, mode, verbose)
     }
 
     /// Un-loads the profile from system, in two stages;
-    /// - It will destroy any files inside the `files` property, removing any symlinks made.
-    /// - It will then run any `removal_commands` that are specified. These are ran in a new `sh` shell, with the
-    ///   working directory being the user's home folder.
+    /// - It will destroy any files inside the `files` property (relative to the
+    ///   [`DotfileProfile::target_path`]), removing any symlinks, copies or rendered templates made, along
+    ///   with the symlinks of any `assets`. The asset cache itself is left alone, as another profile
+    ///   may use the same assets, see [`asset::remove_cached`].
+    /// - It will then run any `removal_commands` that are specified. These are ran in a new `sh` shell
+    ///   (unless the user's `settings` say otherwise), with the working directory being the user's
+    ///   home folder.
     ///
     /// It is **highly advised** to then update the meta via [`Meta::empty_current_profile`] & [`Meta::save_meta`].
     /// Otherwise, dotulous will not know what profile is currently loaded.
@@ -226,45 +1625,473 @@ impl DotfileProfile {
     /// delete the files anyway, as the Meta is what's responsible for keeping track of what
     /// profile is loaded.
     ///
+    /// If the [`LoadState`] saved when the profile was loaded is given as `state`, only destinations
+    /// it says were created by loading are removed, so files that were already in place are left
+    /// alone. Destinations that weren't put there by the profile (see [`Strategy::is_applied`]),
+    /// such as a symlink to somewhere else, are skipped too. Both checks are skipped if `force` is
+    /// set. Templates are checked against their render in the template cache inside
+    /// `dotulous_path`. Any folders the `state` says were created when loading are removed if
+    /// they're now empty. The decrypted copies of encrypted files are always removed from the
+    /// secrets cache, even if their destination is skipped (see [`SecretStore::clean`]).
+    ///
+    /// Even with `force`, destinations overlapping the dotulous folder or the profile's own folder
+    /// are never removed, nor is anything other than a symlink into the dotulous folder where a
+    /// symlink should be (see [`is_owned_link`]), nor a folder where a copy should be unless
+    /// `state` says it was copied when loading.
+    ///
+    /// If `mode` is [`ApplyMode::DryRun`], every step is printed as normal but nothing is actually
+    /// done, letting the user see what unloading the profile would do.
+    ///
     /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
     /// Upon any errors, the function will simply print to stdout and continue.
-    pub fn unload_profile_from_system(&self, home_path: &Path) {
-        println!("Unloading profile: {}", self.name);
-        for file in &self.files {
-            let destination: PathBuf = home_path.join(file.1);
-            println!("  Removing {destination:?}");
-            if !destination.exists() {
-                println!("  WARNING: Destination {destination:?} doesn't exist! Skipping!");
+    #[allow(clippy::too_many_arguments)]
+    pub fn unload_profile_from_system(&self, dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, mode: ApplyMode, force: bool, state: Option<&LoadState>) {
+        say!("Unloading profile: {}", self.name);
+        let target_path: PathBuf = self.target_path(home_path);
+        // Only used for finding where templates are rendered to, so no variables are needed
+        let renderer: Renderer = Renderer::new(self.template_cache_path(dotulous_path), &self.repo_path, HashMap::new());
+        let secrets: SecretStore = self.secret_store(dotulous_path);
+        for (source, entry) in self.entries() {
+            let source: PathBuf = self.repo_path.join(expand_path(source, home_path));
+            let destination: PathBuf = target_path.join(expand_path(&entry.destination, home_path));
+            // The plain secret never outlives the profile being unloaded, whatever happens to it's destination
+            let _cleanup: Option<Cleanup> = (entry.strategy == Strategy::Encrypted && mode == ApplyMode::Apply).then(|| secrets.clean_on_drop(&source));
+            // Optional files whose source was absent were never loaded
+            if entry.optional && destination.symlink_metadata().is_err() {
+                continue;
+            }
+            say!("  Removing {destination:?}");
+            if self.check_destination(home_path, &destination).is_err() {
+                say!("  ERROR: Destination {destination:?} is outside of your home folder! Skipping!");
+                continue;
+            }
+            if self.check_not_dotulous(dotulous_path, &destination).is_err() {
+                say!("  ERROR: Destination {destination:?} is inside the dotulous folder or the profile's own folder! Skipping!");
+                continue;
+            }
+            if destination.symlink_metadata().is_err() {
+                say!("  WARNING: Destination {destination:?} doesn't exist! Skipping!");
+                continue;
+            }
+            if entry.strategy.is_link() && !is_owned_link(&destination, dotulous_path) {
+                say!("  ERROR: Destination {destination:?} isn't a symlink made by dotulous! Skipping!");
+                continue;
+            }
+            if !entry.strategy.is_link() && destination.is_dir() && !destination.is_symlink() && !state.is_some_and(|state| state.was_placed(&destination)) {
+                say!("  ERROR: Destination {destination:?} is a folder that can't be proven to have been copied when loading! Skipping!");
+                continue;
+            }
+            if !force && state.is_some_and(|state| !state.was_placed(&destination)) {
+                say!("  WARNING: Destination {destination:?} wasn't created when loading! Skipping, use --force to remove it anyway.");
+                continue;
+            }
+            if !force && !entry.strategy.is_applied(&source, &destination, &renderer, &secrets) {
+                say!("  WARNING: Destination {destination:?} wasn't put there by this profile! Skipping, use --force to remove it anyway.");
+                continue;
+            }
+            if mode == ApplyMode::DryRun {
                 continue;
             }
 
-            if destination.is_dir() {
-                // very basic protection
-                assert!(destination != Path::new("/"), "Tried to remove root!");
-                assert!(destination != home_path, "Tried to remove home path!");
-                if fs::remove_dir_all(&destination).is_err() {
-                    println!("  Error: Failed to delete destination {destination:?}.");
+            // very basic protection
+            assert!(destination != Path::new("/"), "Tried to remove root!");
+            assert!(destination != home_path, "Tried to remove home path!");
+            assert!(destination != target_path, "Tried to remove target root!");
+            if let Err(e) = entry.strategy.remove(&destination) {
+                say!("  Error: Failed to delete destination {destination:?}: {e:#}");
+            }
+        }
+        for asset in &self.assets {
+            let destination: PathBuf = target_path.join(expand_path(&asset.dest, home_path));
+            say!("  Removing {destination:?}");
+            if self.check_destination(home_path, &destination).is_err() {
+                say!("  ERROR: Destination {destination:?} is outside of your home folder! Skipping!");
+                continue;
+            }
+            if self.check_not_dotulous(dotulous_path, &destination).is_err() {
+                say!("  ERROR: Destination {destination:?} is inside the dotulous folder or the profile's own folder! Skipping!");
+                continue;
+            }
+            if destination.symlink_metadata().is_err() {
+                say!("  WARNING: Destination {destination:?} doesn't exist! Skipping!");
+                continue;
+            }
+            if !is_owned_link(&destination, dotulous_path) {
+                say!("  ERROR: Destination {destination:?} isn't a symlink made by dotulous! Skipping!");
+                continue;
+            }
+            if !force && state.is_some_and(|state| !state.was_placed(&destination)) {
+                say!("  WARNING: Destination {destination:?} wasn't created when loading! Skipping, use --force to remove it anyway.");
+                continue;
+            }
+            if mode == ApplyMode::DryRun {
+                continue;
+            }
+            if fs::remove_file(&destination).is_err() {
+                say!("  Error: Failed to delete destination {destination:?}.");
+            }
+        }
+        if mode == ApplyMode::Apply {
+            let directories: Vec<PathBuf> = match state {
+                // Deepest first, so folders only holding other created folders are removed too
+                Some(state) => state.created_directories.iter().rev().cloned().collect(),
+                // Without a state, only the profile's own `directories` are known to be it's
+                None => {
+                    let mut directories: Vec<PathBuf> = self.directories.iter()
+                        .map(|directory| target_path.join(expand_path(directory, home_path)))
+                        .filter(|directory| self.check_destination(home_path, directory).is_ok() && self.check_not_dotulous(dotulous_path, directory).is_ok())
+                        .collect();
+                    directories.sort_by_key(|directory| Reverse(directory.components().count()));
+                    directories
+                }
+            };
+            for directory in directories {
+                let is_empty: bool = fs::read_dir(&directory).is_ok_and(|mut entries| entries.next().is_none());
+                if is_empty && fs::remove_dir(&directory).is_ok() {
+                    say!("  Removed empty folder {directory:?}");
                 }
-            } else if fs::remove_file(&destination).is_err() {
-                println!("  Error: Failed to delete destination {destination:?}.");
             }
         }
 
         if !self.removal_commands.is_empty() {
-            println!();
-            println!("Running removal commands.");
-            for command in &self.removal_commands {
-                println!("  {command}");
-                let command: Result<Output, io::Error> = Command::new("sh")
-                    .current_dir(home_path)
-                    .arg("-c")
-                    .arg(command)
-                    .output();
-                if command.is_err() {
-                    let unwrapped = command.unwrap();
-                    println!("  ERROR: Command failed to run (exit code {}): {}", unwrapped.status, String::from_utf8(unwrapped.stderr).unwrap());
-                }
+            say!();
+            say!("Running removal commands.");
+            let env: HashMap<String, String> = self.command_env(home_path);
+            let variables: HashMap<String, String> = self.template_variables(config_path, home_path).unwrap_or_else(|e| {
+                say!("  WARNING: Failed to load variables, they won't be filled in: {e:#}");
+                HashMap::new()
+            });
+            let audit: AuditLog = AuditLog::for_profile(self, dotulous_path);
+            for entry in &self.removal_commands {
+                // Keep going, as the files are already gone
+                let _ = run_command(entry, self, settings, dotulous_path, home_path, &env, &variables, &audit, mode);
             }
         }
     }
 }
+
+/// Creates any missing folders that `destination` is inside of with `journal`, so it can be
+/// placed there, reporting them to `progress`. Nothing is created if `mode` is
+/// [`ApplyMode::DryRun`].
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+fn create_parents(destination: &Path, mode: ApplyMode, journal: &mut Journal, progress: &Progress) -> Result<(), DotulousError> {
+    let Some(parent) = destination.parent() else { return Ok(()) };
+    if parent.exists() {
+        return Ok(())
+    }
+    progress.detail(format!("  Creating folder {parent:?}"));
+    if mode == ApplyMode::DryRun {
+        return Ok(())
+    }
+    if let Err(e) = journal.create_dir_all(parent) {
+        progress.println(format!("  ERROR: Failed to create folder {parent:?}: {e:#}"));
+        return Err(e)
+    }
+    Ok(())
+}
+
+/// Returns where the manifest `destination` goes, for telling if two destinations are the same
+/// place without knowing the home folder, so `~/.bashrc`, `$HOME/.bashrc` & `./.bashrc` all
+/// become `~/.bashrc`. See [`expand_path`].
+fn place_key(destination: &Path) -> PathBuf {
+    // A stand-in for the home folder, which has to be absolute for joining onto it to work the same
+    let home: &Path = Path::new("/~");
+    let place: PathBuf = platform::normalize_path(&home.join(expand_path(destination, home)));
+    match place.strip_prefix(home) {
+        Ok(relative) => Path::new("~").join(relative),
+        Err(_) => place
+    }
+}
+
+/// Returns if `a` and `b` are the same path, or one is inside the other.
+fn overlaps(a: &Path, b: &Path) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// Recursively finds every file inside `directory` for [`DotfileProfile::resolve_link_modes`],
+/// pushing their paths (relative to the directory first given, starting from `relative`) into
+/// `found`. Symlinks are counted as files, and never followed. Anything matching `ignores`, loaded
+/// for the profile's directory at `root`, is skipped.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+fn find_files(directory: &Path, relative: &Path, root: &Path, ignores: &Ignores, found: &mut Vec<PathBuf>) -> Result<(), DotulousError> {
+    let paths: ReadDir = fs::read_dir(directory).map_err(DotulousError::FailedReadProfileDirectory)?;
+    for path in paths {
+        let path: DirEntry = path.map_err(DotulousError::FailedReadProfileDirectory)?;
+        let file_relative: PathBuf = relative.join(path.file_name());
+        let actual_path: PathBuf = path.path();
+        let is_dir: bool = path.file_type().is_ok_and(|t| t.is_dir());
+        if ignores.is_ignored(actual_path.strip_prefix(root).unwrap_or(&actual_path), is_dir) {
+            continue;
+        }
+        if is_dir {
+            find_files(&actual_path, &file_relative, root, ignores, found)?;
+        } else {
+            found.push(file_relative);
+        }
+    }
+    Ok(())
+}
+
+/// Returns where the *absolute* `path` really is, resolving any symlinks in the folders leading to
+/// it, even if it doesn't exist yet. The last component is never resolved.
+fn resolve_path(path: &Path) -> PathBuf {
+    let path: PathBuf = platform::normalize_path(path);
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else { return path };
+    // Resolve the deepest folder that exists, and add the missing ones back on top
+    let mut missing: Vec<&std::ffi::OsStr> = vec![file_name];
+    for ancestor in parent.ancestors() {
+        if let Ok(resolved) = fs::canonicalize(ancestor) {
+            return missing.into_iter().rev().fold(resolved, |resolved, component| resolved.join(component))
+        }
+        if let Some(name) = ancestor.file_name() {
+            missing.push(name);
+        }
+    }
+    path
+}
+
+/// How many files [`DotfileProfile::link_files`] linked, skipped & failed to link.
+#[derive(Default)]
+struct LinkSummary {
+    /// Files put onto the system (or that would be, in a dry run).
+    linked: usize,
+    /// Files skipped as they were already in place.
+    skipped: usize,
+    /// Files that failed to be put onto the system.
+    failed: usize
+}
+
+/// Returns if `value` is `false`, for skipping serializing fields that are off.
+pub(crate) fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Runs each of the given `commands` of `profile` in order with [`run_command`], recording them in
+/// the [`AuditLog`] inside `dotulous_path`. When one fails, it's [`OnError`] policy (or
+/// `default_on_error` if it doesn't have one) decides whether to stop with the error or carry on. Stops with [`DotulousError::Interrupted`] before the next command
+/// if dotulous was interrupted, see [`interrupt::check`].
+///
+/// A [`Progress`] bar is shown between commands unless `verbose`, followed by a summary.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+#[allow(clippy::too_many_arguments)]
+fn run_commands(commands: &[CommandEntry], profile: &DotfileProfile, dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, default_on_error: OnError, mode: ApplyMode, verbose: bool) -> Result<(), DotulousError> {
+    let env: HashMap<String, String> = profile.command_env(home_path);
+    let variables: HashMap<String, String> = profile.template_variables(config_path, home_path)?;
+    let audit: AuditLog = AuditLog::for_profile(profile, dotulous_path);
+    let progress: Progress = Progress::new(commands.len(), verbose);
+    let mut failed: usize = 0;
+    let result: Result<(), DotulousError> = commands.iter().try_for_each(|entry| {
+        interrupt::check()?;
+        progress.inc();
+        // The command prints it's own output and may ask for input, so the bar is hidden while it runs
+        progress.suspend(|| {
+            let Err(e) = run_command(entry, profile, settings, dotulous_path, home_path, &env, &variables, &audit, mode) else { return Ok(()) };
+            failed += 1;
+            // The command was most likely stopped by the same Ctrl-C, so it isn't up to it's policy
+            interrupt::check()?;
+            match entry.on_error.unwrap_or(default_on_error) {
+                OnError::Abort => Err(e),
+                OnError::Continue => { say!("  Continuing anyway."); Ok(()) },
+                OnError::Prompt if ask_continue() => Ok(()),
+                OnError::Prompt => Err(e)
+            }
+        })
+    });
+    progress.finish();
+    say!("  Ran {} command(s), {failed} failed.", commands.len());
+    result
+}
+
+/// Asks the user if they want to carry on loading after a command failed, returning `true` only
+/// if they answer `y`. Returns `false` if dotulous isn't interactive, see
+/// [`platform::is_interactive`].
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+fn ask_continue() -> bool {
+    if !platform::is_interactive() {
+        return false
+    }
+    say!("  Continue loading anyway? (y/N)");
+    let mut input: String = String::new();
+    io::stdin().read_line(&mut input).is_ok() && input.trim().to_lowercase() == "y"
+}
+
+/// Asks the user what to do with the existing `destination` while loading, returning their
+/// choice. Never returns [`OnConflict::Prompt`], and returns [`OnConflict::Abort`] if dotulous
+/// isn't interactive (see [`platform::is_interactive`]) or stdin is closed.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+fn ask_conflict(destination: &Path) -> OnConflict {
+    if !platform::is_interactive() {
+        return OnConflict::Abort
+    }
+    loop {
+        say!("  {destination:?} already exists. [s]kip, [b]ack up & replace, [o]verwrite or [a]bort?");
+        let mut input: String = String::new();
+        if !matches!(io::stdin().read_line(&mut input), Ok(read) if read > 0) {
+            return OnConflict::Abort
+        }
+        match input.trim().to_lowercase().as_str() {
+            "s" | "skip" => return OnConflict::Skip,
+            "b" | "backup" => return OnConflict::Backup,
+            "o" | "overwrite" => return OnConflict::Overwrite,
+            "a" | "abort" => return OnConflict::Abort,
+            _ => say!("  Please answer s, b, o or a.")
+        }
+    }
+}
+
+/// Runs the command of `entry` in a new shell, with the working directory being `home_path` and
+/// the environment variables in `env` set, printing the command as it is ran. If `mode` is
+/// [`ApplyMode::DryRun`], the command is only printed.
+///
+/// Any `{{name}}` placeholders in the command are first filled in from `variables` (see
+/// [`DotfileProfile::template_variables`]), quoted for the shell with [`template::render_command`],
+/// so the command is the same wherever the profile is cloned to and a value can't run commands of
+/// it's own.
+///
+/// The shell & timeout are the entry's own, or `profile`'s if it doesn't set them, or otherwise the
+/// user's `settings`. Without any, the platform's shell is used (see [`platform::shell_command`])
+/// and the command can run forever.
+/// A command with a timeout is ran in it's own process group, which is killed if it runs for too
+/// long. The group is given the terminal meanwhile (see [`platform::new_process_group`]), so if
+/// it's interrupted with Ctrl-C, dotulous is interrupted too (see [`interrupt::interrupt`]).
+///
+/// The command's output is streamed as it runs, indented under the command, with stderr going to
+/// stderr. Stdin is left connected, so commands can still ask the user for input. The output is
+/// also written to a [`CommandLog`] inside `dotulous_path`, which is pointed to if the command
+/// fails.
+///
+/// Once the command finishes (or fails to start), it is recorded in `audit` along with how it
+/// exited. A command that can't be recorded or logged is still ran, with a warning.
+///
+/// If the command fails to start, exits unsuccessfully or times out, [`Err`] with
+/// [`DotulousError::FailedRunCommand`] is returned.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+#[allow(clippy::too_many_arguments)]
+fn run_command(entry: &CommandEntry, profile: &DotfileProfile, settings: &Settings, dotulous_path: &Path, home_path: &Path, env: &HashMap<String, String>, variables: &HashMap<String, String>, audit: &AuditLog, mode: ApplyMode) -> Result<(), DotulousError> {
+    let command: &str = &template::render_command(&entry.command, variables);
+    say!("  {command}");
+    if mode == ApplyMode::DryRun {
+        return Ok(())
+    }
+    let mut process: Command = match entry.shell.as_ref().or(profile.shell.as_ref()).or(settings.shell.as_ref()) {
+        Some(shell) => shell.command(command),
+        None => platform::shell_command(command)
+    };
+    let timeout: Option<u64> = entry.timeout.or(profile.timeout).or(settings.timeout);
+    let foreground: Option<Foreground> = timeout.map(|_| platform::new_process_group(&mut process));
+    let log: Option<Arc<CommandLog>> = create_log(profile, dotulous_path, command);
+    let mut child: Child = match process
+        .current_dir(home_path)
+        .envs(env)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn() {
+        Ok(r) => r,
+        Err(e) => {
+            say!("  ERROR: Command failed to start: {e:#}");
+            record_command(audit, command, None);
+            if let Some(log) = &log {
+                log.write_line(&format!("Command failed to start: {e:#}"));
+                log.finish(None);
+            }
+            return Err(DotulousError::FailedRunCommand(e))
+        }
+    };
+
+    let stdout_log: Option<Arc<CommandLog>> = log.clone();
+    let stderr_log: Option<Arc<CommandLog>> = log.clone();
+    let stdout = child.stdout.take().map(|stdout| thread::spawn(move || stream_output(stdout, false, stdout_log.as_deref())));
+    let stderr = child.stderr.take().map(|stderr| thread::spawn(move || stream_output(stderr, true, stderr_log.as_deref())));
+    let status: io::Result<ExitStatus> = match timeout {
+        Some(seconds) => match platform::wait_timeout(&mut child, Duration::from_secs(seconds)) {
+            Ok(Some(status)) => Ok(status),
+            Ok(None) => {
+                platform::kill_process_group(&mut child);
+                Err(io::Error::new(ErrorKind::TimedOut, format!("Command timed out after {seconds}s")))
+            },
+            Err(e) => Err(e)
+        },
+        None => child.wait()
+    };
+    if foreground.is_some() {
+        drop(foreground);
+        if status.as_ref().is_ok_and(platform::was_interrupted) {
+            interrupt::interrupt();
+        }
+    }
+    // The output is only finished once the command (and anything it started) has exited
+    for handle in [stdout, stderr].into_iter().flatten() {
+        let _ = handle.join();
+    }
+    let exit_code: Option<i32> = status.as_ref().ok().and_then(ExitStatus::code);
+    record_command(audit, command, exit_code);
+    if let Some(log) = &log {
+        log.finish(exit_code);
+    }
+
+    if let Err(e) = status.and_then(|status| platform::check_status(Ok(status))) {
+        if e.kind() == ErrorKind::TimedOut {
+            say!("  ERROR: Killed \"{command}\": {e:#}");
+        } else {
+            say!("  ERROR: Command failed to run: {e:#}");
+        }
+        if let Some(log) = &log {
+            say!("  The full output is in {:?}", log.path());
+        }
+        return Err(DotulousError::FailedRunCommand(e))
+    }
+    Ok(())
+}
+
+/// Records `command` in `audit` with it's `exit_code`, warning if it couldn't be.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+fn record_command(audit: &AuditLog, command: &str, exit_code: Option<i32>) {
+    if let Err(e) = audit.record(command, exit_code) {
+        say!("  WARNING: Failed to record \"{command}\" in the audit log: {e:#}");
+    }
+}
+
+/// Creates the [`CommandLog`] for `command` of `profile` inside the given `dotulous_path`, warning
+/// if it couldn't be.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+fn create_log(profile: &DotfileProfile, dotulous_path: &Path, command: &str) -> Option<Arc<CommandLog>> {
+    match CommandLog::create(dotulous_path, &profile.name, command) {
+        Ok(log) => Some(Arc::new(log)),
+        Err(e) => {
+            say!("  WARNING: Failed to create a log for \"{command}\": {e:#}");
+            None
+        }
+    }
+}
+
+/// Prints every line read from `output` as it arrives, indented under the command that's running,
+/// and writes it to `log` if given. Lines are printed to stderr if `is_stderr` is set, otherwise
+/// stdout.
+fn stream_output(output: impl Read, is_stderr: bool, log: Option<&CommandLog>) {
+    let mut reader = BufReader::new(output);
+    let mut line: Vec<u8> = Vec::new();
+    while reader.read_until(b'\n', &mut line).is_ok_and(|read| read > 0) {
+        let text = String::from_utf8_lossy(&line);
+        let text: &str = text.trim_end_matches(['\r', '\n']);
+        if let Some(log) = log {
+            log.write_line(text);
+        }
+        if is_stderr {
+            eprintln!("    {text}");
+        } else {
+            say!("    {text}");
+        }
+        line.clear();
+    }
+}
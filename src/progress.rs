@@ -0,0 +1,68 @@
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::say;
+
+/// Shows how far through a phase of loading a profile (such as running it's pre-commands or
+/// linking it's files) dotulous is, as a progress bar on stderr.
+///
+/// If `verbose`, no bar is drawn and every detail is printed on it's own line instead, the way
+/// dotulous always used to. The bar is also hidden when stderr isn't a terminal.
+pub struct Progress {
+    /// The bar being drawn, or [`None`] if verbose.
+    bar: Option<ProgressBar>
+}
+impl Progress {
+    /// Starts a new `Progress` for a phase made of `len` steps, drawing a bar unless `verbose`.
+    pub fn new(len: usize, verbose: bool) -> Self {
+        if verbose {
+            return Self { bar: None }
+        }
+        let style: ProgressStyle = ProgressStyle::with_template("  [{bar:30}] {pos}/{len} {wide_msg}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("=> ");
+        Self { bar: Some(ProgressBar::new(len as u64).with_style(style)) }
+    }
+
+    /// Returns if every detail is being printed, rather than drawing a bar.
+    pub fn is_verbose(&self) -> bool {
+        self.bar.is_none()
+    }
+
+    /// Reports a detail about the current step. If verbose it's printed, otherwise it's shown next
+    /// to the bar until the next detail.
+    pub fn detail(&self, message: String) {
+        match &self.bar {
+            Some(bar) => bar.set_message(message.trim().to_string()),
+            None => say!("{message}")
+        }
+    }
+
+    /// Prints `message` on it's own line above the bar, for things that should always be seen
+    /// such as warnings & errors.
+    pub fn println(&self, message: String) {
+        self.suspend(|| say!("{message}"));
+    }
+
+    /// Hides the bar while `f` runs, such as a command that prints it's own output or asks for
+    /// input, returning what `f` returns.
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        match &self.bar {
+            Some(bar) => bar.suspend(f),
+            None => f()
+        }
+    }
+
+    /// Moves on to the next step.
+    pub fn inc(&self) {
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+    }
+
+    /// Removes the bar once the phase is done, leaving anything printed above it.
+    pub fn finish(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
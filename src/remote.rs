@@ -0,0 +1,154 @@
+use std::{fs, path::{Path, PathBuf}, process::Command};
+
+use crate::{archive, error::DotulousError, format::ManifestFormat, platform, profile::DotfileProfile, source};
+
+/// A host that profiles can be installed from with a shorthand such as `github:user/repo`, rather
+/// than the repository's full URL. New hosts only need a variant here, along with it's prefix &
+/// URL in [`Shorthand::prefix`] & [`Shorthand::url`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shorthand {
+    /// `github:user/repo`
+    GitHub,
+    /// `gitlab:user/repo`
+    GitLab,
+    /// `codeberg:user/repo`
+    Codeberg,
+    /// `sourcehut:user/repo`, the `~` before the user can be left out.
+    SourceHut
+}
+impl Shorthand {
+    /// Every known shorthand, in the order they're tried.
+    pub const ALL: [Shorthand; 4] = [Shorthand::GitHub, Shorthand::GitLab, Shorthand::Codeberg, Shorthand::SourceHut];
+
+    /// Returns the prefix of the shorthand, before the `:`.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Shorthand::GitHub => "github",
+            Shorthand::GitLab => "gitlab",
+            Shorthand::Codeberg => "codeberg",
+            Shorthand::SourceHut => "sourcehut"
+        }
+    }
+
+    /// Returns the URL of the git repository at `user` & `repo` on this host.
+    pub fn url(&self, user: &str, repo: &str) -> String {
+        match self {
+            Shorthand::GitHub => format!("https://github.com/{user}/{repo}.git"),
+            Shorthand::GitLab => format!("https://gitlab.com/{user}/{repo}.git"),
+            Shorthand::Codeberg => format!("https://codeberg.org/{user}/{repo}.git"),
+            Shorthand::SourceHut => format!("https://git.sr.ht/~{}/{repo}", user.trim_start_matches('~'))
+        }
+    }
+}
+
+/// Where a profile given to `dotulous install` comes from, found from a shorthand or URL with
+/// [`Remote::resolve`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Remote {
+    /// A git repository at the given URL, cloned with `git`.
+    Git(String),
+    /// A `.tar.gz` archive at the given URL (made by [`archive::export`]), downloaded with `curl`.
+    Archive(String)
+}
+impl Remote {
+    /// Resolves `spec` into where the profile comes from. This is either one of the [`Shorthand`]s
+    /// such as `github:user/repo`, a URL ending in `.tar.gz` or `.tgz` for an archive, or anything
+    /// else `git clone` accepts.
+    ///
+    /// If `spec` starts with a shorthand's prefix but isn't followed by `user/repo`, [`Err`] with
+    /// [`DotulousError::InvalidRemote`] is returned.
+    pub fn resolve(spec: &str) -> Result<Remote, DotulousError> {
+        let spec: &str = spec.trim();
+        if spec.is_empty() {
+            return Err(DotulousError::InvalidRemote)
+        }
+        if let Some((prefix, path)) = spec.split_once(':') {
+            if let Some(shorthand) = Shorthand::ALL.iter().find(|shorthand| shorthand.prefix() == prefix) {
+                let Some((user, repo)) = path.trim_matches('/').split_once('/') else { return Err(DotulousError::InvalidRemote) };
+                let repo: &str = repo.strip_suffix(".git").unwrap_or(repo);
+                if user.is_empty() || repo.is_empty() || repo.contains('/') {
+                    return Err(DotulousError::InvalidRemote)
+                }
+                return Ok(Remote::Git(shorthand.url(user, repo)))
+            }
+        }
+
+        if spec.ends_with(".tar.gz") || spec.ends_with(".tgz") {
+            Ok(Remote::Archive(spec.to_string()))
+        } else {
+            Ok(Remote::Git(spec.to_string()))
+        }
+    }
+
+    /// Returns the URL the profile is fetched from.
+    pub fn url(&self) -> &str {
+        match self {
+            Remote::Git(url) | Remote::Archive(url) => url
+        }
+    }
+
+    /// Fetches the profile into `dotulous_path`, the user's dotulous folder, returning the
+    /// installed profile.
+    ///
+    /// A git repository is put in the sanitized `profile_name`, or the repository's name if not
+    /// given. An archive is always named after the name in it's manifest, see [`archive::import`].
+    ///
+    /// The profile is first fetched into the cache inside `dotulous_path`, and only moved into
+    /// place once it's manifest has been found & validated, so nothing is left behind if it has
+    /// none. In that case [`Err`] with [`DotulousError::NoManifestInProfile`] is returned. If the
+    /// profile's folder already exists, [`Err`] with [`DotulousError::ProfileAlreadyExists`] is
+    /// returned. The profile is **not** trusted, this is left to the user.
+    pub fn install(&self, dotulous_path: &Path, profile_name: Option<&str>) -> Result<DotfileProfile, DotulousError> {
+        let staging_path: PathBuf = dotulous_path.join("cache").join("install");
+        if staging_path.exists() {
+            fs::remove_dir_all(&staging_path).map_err(DotulousError::FailedDownloadProfile)?;
+        }
+        fs::create_dir_all(&staging_path).map_err(DotulousError::FailedDownloadProfile)?;
+
+        let result: Result<DotfileProfile, DotulousError> = match self {
+            Remote::Git(url) => install_git(dotulous_path, url, profile_name, &staging_path),
+            Remote::Archive(url) => install_archive(dotulous_path, url, &staging_path)
+        };
+        let _ = fs::remove_dir_all(&staging_path);
+        result
+    }
+}
+
+/// Clones the repository at `url` into `staging_path`, then moves it into `dotulous_path` once
+/// it's manifest has been validated. See [`Remote::install`].
+fn install_git(dotulous_path: &Path, url: &str, profile_name: Option<&str>, staging_path: &Path) -> Result<DotfileProfile, DotulousError> {
+    let Some(profile_name) = profile_name.map(str::to_string).or_else(|| source::name_from_url(url)) else {
+        return Err(DotulousError::InvalidRemote)
+    };
+    let profile_path: PathBuf = dotulous_path.join(sanitize_filename::sanitize(&profile_name));
+    if profile_path.exists() {
+        return Err(DotulousError::ProfileAlreadyExists)
+    }
+
+    let cloned_path: PathBuf = staging_path.join("profile");
+    source::git_clone(url, &cloned_path)?;
+    if ManifestFormat::find_manifest(&cloned_path).is_none() {
+        return Err(DotulousError::NoManifestInProfile)
+    }
+    DotfileProfile::from_manifest(&cloned_path)?;
+
+    fs::rename(&cloned_path, &profile_path).map_err(DotulousError::FailedDownloadProfile)?;
+    DotfileProfile::from_manifest(&profile_path)
+}
+
+/// Downloads the archive at `url` into `staging_path` using `curl`, then imports it into
+/// `dotulous_path` with [`archive::import`]. See [`Remote::install`].
+fn install_archive(dotulous_path: &Path, url: &str, staging_path: &Path) -> Result<DotfileProfile, DotulousError> {
+    let downloaded: PathBuf = staging_path.join("profile.tar.gz");
+    let status = Command::new("curl")
+        .arg("--fail")
+        .arg("--silent")
+        .arg("--show-error")
+        .arg("--location")
+        .arg("--output")
+        .arg(&downloaded)
+        .arg(url)
+        .status();
+    platform::check_status(status).map_err(DotulousError::FailedDownloadProfile)?;
+    archive::import(dotulous_path, &downloaded)
+}
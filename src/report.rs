@@ -0,0 +1,60 @@
+use std::{io, process::Stdio, sync::atomic::{AtomicBool, Ordering}};
+
+use serde::Serialize;
+
+/// Whether dotulous's output is JSON, see [`set_json`].
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Turns JSON output on or off for everything dotulous reports, set by the `--json` flag.
+///
+/// With it on, stdout only gets the results sent with [`emit`], each as one line of JSON, while
+/// everything printed with [`say!`](crate::say) (progress, warnings & questions) goes to stderr
+/// instead. This lets scripts & status bar modules read stdout as-is.
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+/// Returns if dotulous's output is JSON, see [`set_json`].
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Prints a line of output meant for the user, the same as [`println`], unless JSON output is on
+/// (see [`set_json`]) in which case it's printed to stderr so stdout stays valid JSON.
+#[macro_export]
+macro_rules! say {
+    () => {
+        $crate::say!("")
+    };
+    ($($arg:tt)*) => {
+        if $crate::report::is_json() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Returns where a program ran by dotulous, such as `git` or a package manager, should print it's
+/// output. It's printed straight to the terminal as normal, but to stderr when JSON output is on
+/// (see [`set_json`]), the same as [`say!`](crate::say).
+pub fn child_output() -> Stdio {
+    if is_json() {
+        Stdio::from(io::stderr())
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// Prints `result` to stdout as one line of JSON, if JSON output is on (see [`set_json`]).
+/// Otherwise nothing is printed, as the result has already been described with
+/// [`say!`](crate::say).
+pub fn emit(result: &impl Serialize) {
+    if !is_json() {
+        return
+    }
+    match serde_json::to_string(result) {
+        Ok(r) => println!("{r}"),
+        Err(e) => eprintln!("ERROR: Failed to serialize result: {e:#}")
+    }
+}
@@ -0,0 +1,221 @@
+use std::{fs, io::{self, ErrorKind}, path::{Path, PathBuf}, process::{Command, Output}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{entry::FileMode, error::DotulousError, platform, say};
+
+/// How a profile's encrypted files are encrypted, written as it's `secrets` in the manifest;
+/// ```json
+/// "secrets": { "backend": "age", "recipients": ["age1..."], "identity": "~/.config/age/keys.txt" }
+/// ```
+/// Files using the [`crate::entry::Strategy::Encrypted`] strategy are decrypted with this when
+/// loading, see [`SecretStore`].
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SecretConfig {
+    /// The tool used to encrypt & decrypt files.
+    pub backend: Backend,
+    /// Who files are encrypted for. For `age` these are public keys, and for `gpg` key ids or
+    /// emails. Only needed for encrypting.
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    /// The private key file used by `age` for decrypting. May start with `~`. Unused by `gpg`,
+    /// which uses the user's keyring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<PathBuf>
+}
+impl SecretConfig {
+    /// Decrypts the file at `source`, returning it's plain contents.
+    pub fn decrypt(&self, source: &Path) -> Result<Vec<u8>, DotulousError> {
+        let mut command: Command = Command::new(self.backend.program());
+        match self.backend {
+            Backend::Age => {
+                command.arg("--decrypt");
+                if let Some(identity) = &self.identity {
+                    command.arg("--identity").arg(shellexpand::tilde(&identity.to_string_lossy()).as_ref());
+                }
+            },
+            Backend::Gpg => { command.args(["--batch", "--quiet", "--decrypt"]); }
+        }
+        command.arg(source);
+        run(command, DotulousError::FailedDecryptSecret).map(|output| output.stdout)
+    }
+
+    /// Encrypts the plain file at `source` for every recipient, writing it to `destination`. If
+    /// there are no recipients, [`Err`] with [`DotulousError::NoSecretRecipients`] is returned.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn encrypt(&self, source: &Path, destination: &Path) -> Result<(), DotulousError> {
+        if self.recipients.is_empty() {
+            return Err(DotulousError::NoSecretRecipients)
+        }
+        let mut command: Command = Command::new(self.backend.program());
+        match self.backend {
+            Backend::Age => { command.arg("--encrypt"); },
+            Backend::Gpg => { command.args(["--batch", "--yes", "--encrypt"]); }
+        }
+        for recipient in &self.recipients {
+            command.arg("--recipient").arg(recipient);
+        }
+        command.arg("--output").arg(destination).arg(source);
+        run(command, DotulousError::FailedEncryptSecret).map(|_| ())
+    }
+
+    /// Encrypts the plain file at `path`, replacing it with the encrypted file. See
+    /// [`SecretConfig::encrypt`].
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn encrypt_in_place(&self, path: &Path) -> Result<(), DotulousError> {
+        let mut encrypted_name = path.as_os_str().to_os_string();
+        encrypted_name.push(".dotulous-encrypted");
+        let encrypted_path: PathBuf = PathBuf::from(encrypted_name);
+        if let Err(e) = self.encrypt(path, &encrypted_path) {
+            let _ = fs::remove_file(&encrypted_path);
+            return Err(e)
+        }
+        fs::rename(&encrypted_path, path).map_err(DotulousError::FailedEncryptSecret)?;
+        Ok(())
+    }
+}
+
+/// The tool used to encrypt & decrypt a profile's secrets.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// [age](https://age-encryption.org), using the `age` command.
+    Age,
+    /// GnuPG, using the `gpg` command.
+    Gpg
+}
+impl Backend {
+    /// Returns the name of the command used by the backend.
+    fn program(&self) -> &'static str {
+        match self {
+            Backend::Age => "age",
+            Backend::Gpg => "gpg"
+        }
+    }
+}
+
+/// Decrypts a profile's encrypted files into the profile's folder inside the secrets cache, so the
+/// plain output can then be symlinked to the system. Like [`crate::template::Renderer`], files are
+/// kept at the same path (relative to the cache) as they are inside the profile's directory.
+///
+/// Decrypted files are only readable by the user. They are removed again with
+/// [`SecretStore::clean`] when the profile is unloaded, re-encrypting any changes made to them.
+pub struct SecretStore {
+    /// The *absolute* path to the folder decrypted files are written to.
+    cache_path: PathBuf,
+    /// The *absolute* path to the profile's folder.
+    repo_path: PathBuf,
+    /// How the profile's secrets are encrypted, if set in the manifest.
+    config: Option<SecretConfig>
+}
+impl SecretStore {
+    /// Creates a new `SecretStore`, decrypting files inside `repo_path` into `cache_path` with the
+    /// given `config`.
+    pub fn new(cache_path: PathBuf, repo_path: &Path, config: Option<SecretConfig>) -> Self {
+        Self {
+            cache_path,
+            repo_path: repo_path.to_path_buf(),
+            config
+        }
+    }
+
+    /// Returns the *absolute* path the encrypted file at `source` is decrypted to.
+    pub fn decrypted_path(&self, source: &Path) -> PathBuf {
+        match source.strip_prefix(&self.repo_path) {
+            Ok(relative) => self.cache_path.join(relative),
+            Err(_) => self.cache_path.join(source.file_name().unwrap_or_default())
+        }
+    }
+
+    /// Decrypts the file at `source` into the cache, replacing any previous decryption, and returns
+    /// the *absolute* path it was decrypted to. The decrypted file is created with `mode` if it's
+    /// given, otherwise it's only readable & writable by the user.
+    pub fn decrypt_file(&self, source: &Path, mode: Option<FileMode>) -> io::Result<PathBuf> {
+        let Some(config) = &self.config else {
+            return Err(io::Error::other(DotulousError::NoSecretsConfig))
+        };
+        let contents: Vec<u8> = config.decrypt(source).map_err(io::Error::other)?;
+        let decrypted_path: PathBuf = self.decrypted_path(source);
+        if let Some(parent) = decrypted_path.parent() {
+            platform::create_private_dir_all(parent)?;
+        }
+        match mode {
+            Some(mode) => platform::write_with_mode(&decrypted_path, &contents, mode.bits())?,
+            None => platform::write_private(&decrypted_path, &contents)?
+        }
+        Ok(decrypted_path)
+    }
+
+    /// Removes the decrypted copy of the file at `source` from the cache. If it was changed since
+    /// being decrypted, the changes are first encrypted back into `source`.
+    ///
+    /// The decrypted copy is removed even if re-encrypting it fails, so the plain secret is never
+    /// left behind, in which case any changes to it are lost and the error is returned.
+    ///
+    /// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn clean(&self, source: &Path) -> Result<(), DotulousError> {
+        let decrypted_path: PathBuf = self.decrypted_path(source);
+        if decrypted_path.symlink_metadata().is_err() {
+            return Ok(())
+        }
+        let reencrypted: Result<(), DotulousError> = self.reencrypt(source, &decrypted_path);
+        let removed: Result<(), DotulousError> = fs::remove_file(&decrypted_path).map_err(DotulousError::FailedDecryptSecret);
+        reencrypted.and(removed)
+    }
+
+    /// Returns a [`Cleanup`] that cleans the decrypted copy of the file at `source` once it's
+    /// dropped (see [`SecretStore::clean`]), however unloading it's destination went.
+    pub fn clean_on_drop(&self, source: &Path) -> Cleanup<'_> {
+        Cleanup { store: self, source: source.to_path_buf() }
+    }
+
+    /// Encrypts the decrypted copy of the file at `source`, found at `decrypted_path`, back into
+    /// `source` if it was changed since being decrypted.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    fn reencrypt(&self, source: &Path, decrypted_path: &Path) -> Result<(), DotulousError> {
+        let Some(config) = &self.config else { return Err(DotulousError::NoSecretsConfig) };
+        let current: Vec<u8> = fs::read(decrypted_path).map_err(DotulousError::FailedDecryptSecret)?;
+        if config.decrypt(source)? != current {
+            say!("  Re-encrypting changes to {source:?}");
+            config.encrypt(decrypted_path, source)?;
+        }
+        Ok(())
+    }
+}
+
+/// Cleans the decrypted copy of a file from a [`SecretStore`] once dropped, see
+/// [`SecretStore::clean_on_drop`]. Failing to clean it only prints an error.
+pub struct Cleanup<'a> {
+    /// The store the file was decrypted into.
+    store: &'a SecretStore,
+    /// The *absolute* path to the encrypted file inside the profile's folder.
+    source: PathBuf
+}
+impl Drop for Cleanup<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.store.clean(&self.source) {
+            say!("  Error: Failed to clean decrypted secret for {:?}: {e:#}", self.source);
+        }
+    }
+}
+
+/// Runs `command`, returning it's output if it succeeds. If the backend isn't installed, [`Err`]
+/// with [`DotulousError::SecretBackendNotFound`] is returned, or `error` if it otherwise fails,
+/// carrying what the backend printed to stderr.
+fn run(mut command: Command, error: fn(io::Error) -> DotulousError) -> Result<Output, DotulousError> {
+    match command.output() {
+        Ok(output) if output.status.success() => Ok(output),
+        Ok(output) => Err(error(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(DotulousError::SecretBackendNotFound),
+        Err(e) => Err(error(e))
+    }
+}
@@ -0,0 +1,162 @@
+use std::{env, fs, io::{self, ErrorKind}, path::{Path, PathBuf}, process::Command};
+
+use crate::{error::DotulousError, paths::Paths, platform, report};
+
+/// The name of the systemd user unit written by [`install`].
+pub const UNIT_NAME: &str = "dotulous.service";
+
+/// The arguments the service runs dotulous with if none are given to [`install`], reapplying the
+/// loaded profile's files without running any of it's commands.
+pub const DEFAULT_ARGUMENTS: [&str; 2] = ["reload", "--no-hooks"];
+
+/// The environment variables that change where dotulous keeps it's files (see [`Paths`]), passed on
+/// to the service if they're set when it is installed and the folders weren't given with
+/// `--dotulous-dir`.
+const FORWARDED_VARS: [&str; 2] = ["XDG_DATA_HOME", "XDG_CONFIG_HOME"];
+
+/// What [`status`] found out about the service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceStatus {
+    /// The *absolute* path of the unit file.
+    pub unit_path: PathBuf,
+    /// The command the service runs, from the unit's `ExecStart`, or [`None`] if the service
+    /// isn't installed.
+    pub command: Option<String>,
+    /// Whether the service is enabled, as said by `systemctl --user is-enabled`.
+    pub enabled: String,
+    /// How the service last finished, as said by systemd's `Result` property, such as `success`
+    /// or `exit-code`.
+    pub result: String
+}
+
+/// Returns the *absolute* path the unit is written to for the user with the home folder at
+/// `home_path`, inside `$XDG_CONFIG_HOME/systemd/user` (or `~/.config/systemd/user`).
+pub fn unit_path(home_path: &Path) -> PathBuf {
+    let config_home: PathBuf = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| home_path.join(".config"));
+    config_home.join("systemd").join("user").join(UNIT_NAME)
+}
+
+/// Returns the contents of the unit, a oneshot service ran at login that runs the dotulous
+/// `executable` with `--yes` followed by `arguments`.
+///
+/// The service uses the same home folder at `home_path` & dotulous folders in `paths` as whoever
+/// installed it, given with `--home` & `--dotulous-dir`, as systemd doesn't run it with the same
+/// environment. Folders split between `$XDG_DATA_HOME` & `$XDG_CONFIG_HOME` can't be given as one
+/// `--dotulous-dir`, so those variables are passed on instead.
+pub fn render_unit(executable: &Path, home_path: &Path, paths: &Paths, arguments: &[String]) -> String {
+    let mut command: Vec<String> = vec![quote(&executable.to_string_lossy()), "--yes".to_string()];
+    command.extend(["--home".to_string(), quote(&home_path.to_string_lossy())]);
+    let in_folder: bool = paths.data == paths.config;
+    if in_folder {
+        command.extend(["--dotulous-dir".to_string(), quote(&paths.data.to_string_lossy())]);
+    }
+    command.extend(arguments.iter().map(|argument| quote(argument)));
+
+    let mut unit: String = String::new();
+    unit.push_str("# Written by `dotulous service install`, remove it with `dotulous service remove`.\n");
+    unit.push_str("[Unit]\nDescription=Reapply the loaded dotulous profile\n\n");
+    unit.push_str("[Service]\nType=oneshot\n");
+    unit.push_str(&format!("ExecStart={}\n", command.join(" ")));
+    for name in FORWARDED_VARS.iter().filter(|_| !in_folder) {
+        if let Some(value) = env::var_os(name) {
+            let value: String = value.to_string_lossy().replace('%', "%%").replace('\\', "\\\\").replace('"', "\\\"");
+            unit.push_str(&format!("Environment=\"{name}={value}\"\n"));
+        }
+    }
+    unit.push_str("\n[Install]\nWantedBy=default.target\n");
+    unit
+}
+
+/// Writes the unit (see [`render_unit`]) for the user with the home folder at `home_path` & dotulous
+/// folders in `paths`, then enables it so it runs at every login. If `arguments` is empty, [`DEFAULT_ARGUMENTS`] are used.
+/// Any unit written before is replaced. Returns the *absolute* path of the unit.
+///
+/// If `systemctl` is not installed, the unit is removed again and [`Err`] with
+/// [`DotulousError::SystemctlNotFound`] is returned.
+pub fn install(home_path: &Path, paths: &Paths, arguments: &[String]) -> Result<PathBuf, DotulousError> {
+    let executable: PathBuf = env::current_exe().map_err(DotulousError::FailedInstallService)?;
+    let arguments: Vec<String> = if arguments.is_empty() {
+        DEFAULT_ARGUMENTS.iter().map(|argument| argument.to_string()).collect()
+    } else {
+        arguments.to_vec()
+    };
+
+    let path: PathBuf = unit_path(home_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(DotulousError::FailedInstallService)?;
+    }
+    fs::write(&path, render_unit(&executable, home_path, paths, &arguments)).map_err(DotulousError::FailedInstallService)?;
+
+    let enabled: Result<(), DotulousError> = systemctl(&["daemon-reload"], DotulousError::FailedInstallService)
+        .and_then(|()| systemctl(&["enable", UNIT_NAME], DotulousError::FailedInstallService));
+    if let Err(e) = enabled {
+        if matches!(e, DotulousError::SystemctlNotFound) {
+            let _ = fs::remove_file(&path);
+        }
+        return Err(e)
+    }
+    Ok(path)
+}
+
+/// Disables & removes the unit of the user with the home folder at `home_path`. Returns `false` if
+/// it wasn't installed, in which case nothing is done.
+pub fn remove(home_path: &Path) -> Result<bool, DotulousError> {
+    let path: PathBuf = unit_path(home_path);
+    if !path.exists() {
+        return Ok(false)
+    }
+
+    systemctl(&["disable", UNIT_NAME], DotulousError::FailedRemoveService)?;
+    fs::remove_file(&path).map_err(DotulousError::FailedRemoveService)?;
+    systemctl(&["daemon-reload"], DotulousError::FailedRemoveService)?;
+    Ok(true)
+}
+
+/// Finds out whether the unit of the user with the home folder at `home_path` is installed &
+/// enabled, and how it last ran.
+///
+/// If `systemctl` is not installed, [`Err`] with [`DotulousError::SystemctlNotFound`] is returned.
+pub fn status(home_path: &Path) -> Result<ServiceStatus, DotulousError> {
+    let unit_path: PathBuf = unit_path(home_path);
+    let command: Option<String> = match fs::read_to_string(&unit_path) {
+        Ok(contents) => contents.lines().find_map(|line| line.strip_prefix("ExecStart=")).map(str::to_string),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(DotulousError::FailedReadService(e))
+    };
+    let enabled: String = systemctl_output(&["is-enabled", UNIT_NAME])?;
+    let result: String = systemctl_output(&["show", "--property=Result", "--value", UNIT_NAME])?;
+    Ok(ServiceStatus { unit_path, command, enabled, result })
+}
+
+/// Runs `systemctl --user` with `arguments`, turning a failure into an error with `error`.
+fn systemctl(arguments: &[&str], error: fn(io::Error) -> DotulousError) -> Result<(), DotulousError> {
+    let status = Command::new("systemctl").arg("--user").args(arguments).stdout(report::child_output()).status();
+    match platform::check_status(status) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(DotulousError::SystemctlNotFound),
+        Err(e) => Err(error(e))
+    }
+}
+
+/// Runs `systemctl --user` with `arguments`, returning what it printed. Unlike [`systemctl`], it
+/// exiting unsuccessfully isn't an error, as queries such as `is-enabled` do so to answer "no".
+fn systemctl_output(arguments: &[&str]) -> Result<String, DotulousError> {
+    match Command::new("systemctl").arg("--user").args(arguments).output() {
+        Ok(output) => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(DotulousError::SystemctlNotFound),
+        Err(e) => Err(DotulousError::FailedReadService(e))
+    }
+}
+
+/// Quotes `argument` for a unit file if needed, so systemd reads it back as one argument. `%` &
+/// `$` are doubled, as systemd would otherwise take them as a specifier or variable.
+fn quote(argument: &str) -> String {
+    let escaped: String = argument.replace('%', "%%").replace('$', "$$");
+    if !escaped.is_empty() && !escaped.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == '\\') {
+        return escaped
+    }
+    format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
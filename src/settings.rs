@@ -0,0 +1,163 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::{backup::Retention, entry::Shell, error::DotulousError, profile::OnConflict};
+
+/// The user's own defaults for dotulous, stored in `config.toml` inside their config folder (see
+/// [`crate::paths::Paths`]). Every setting is optional, and anything given on the command line or
+/// in a profile's manifest takes priority.
+/// ```toml
+/// on_conflict = "skip"
+/// shell = ["bash", "-euo", "pipefail", "-c"]
+/// timeout = 300
+/// ignore = ["*.bak", "node_modules/"]
+/// color = "never"
+/// backup_retention = 5
+/// backup_max_age_days = 90
+/// backup_max_size_mb = 500
+/// update_check = true
+/// ```
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// What to do when something is in the way while loading, if `--on-conflict` isn't given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_conflict: Option<OnConflict>,
+    /// The shell commands are ran with, if neither the command nor it's profile set one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<Shell>,
+    /// How many seconds commands can run for, if neither the command nor it's profile set it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Extra patterns skipped when auto-filling a profile's files, on top of the profile's
+    /// [`crate::ignores::Ignores`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    /// Whether output is colored.
+    #[serde(skip_serializing_if = "ColorChoice::is_default")]
+    pub color: ColorChoice,
+    /// How many restored backups to keep for each profile, see [`Settings::retention`]. If
+    /// [`None`], any number are kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_retention: Option<usize>,
+    /// How many days to keep restored backups for, see [`Settings::retention`]. If [`None`],
+    /// they're kept for any amount of time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_max_age_days: Option<u64>,
+    /// How many mebibytes every backup may take up together, see [`Settings::retention`]. If
+    /// [`None`], they can take up any amount of space.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_max_size_mb: Option<u64>,
+    /// Whether to look for a newer version of dotulous once a day, printing a notice if there is
+    /// one. Off unless turned on, see [`crate::update::check_daily`].
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub update_check: bool
+}
+impl Settings {
+    /// The name of every setting, in the order they're listed.
+    pub const KEYS: [&'static str; 9] = ["on_conflict", "shell", "timeout", "ignore", "color", "backup_retention", "backup_max_age_days", "backup_max_size_mb", "update_check"];
+
+    /// Returns which backups to keep according to the `backup_*` settings, for
+    /// [`crate::backup::Backup::prune`].
+    pub fn retention(&self) -> Retention {
+        Retention { keep: self.backup_retention, max_age_days: self.backup_max_age_days, max_size_mb: self.backup_max_size_mb }
+    }
+
+    /// Load the settings from `config.toml` inside the given `config_path`. If there is no
+    /// `config.toml`, the default settings are returned.
+    pub fn load(config_path: &Path) -> Result<Settings, DotulousError> {
+        let path: PathBuf = settings_path(config_path);
+        if !path.exists() {
+            return Ok(Settings::default())
+        }
+
+        let contents: String = fs::read_to_string(path).map_err(DotulousError::FailedReadSettings)?;
+        toml::from_str(&contents).map_err(DotulousError::FailedDeserializeSettings)
+    }
+
+    /// Save the settings to disk, using `config.toml` inside the given `config_path`. Any comments
+    /// in the file are lost.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn save(&self, config_path: &Path) -> Result<(), DotulousError> {
+        let serialized: String = toml::to_string_pretty(self).map_err(DotulousError::FailedSerializeSettings)?;
+        fs::create_dir_all(config_path).map_err(DotulousError::FailedSaveSettings)?;
+        fs::write(settings_path(config_path), serialized).map_err(DotulousError::FailedSaveSettings)?;
+        Ok(())
+    }
+
+    /// Returns the value of the setting `key`, or [`None`] if it isn't set.
+    ///
+    /// If `key` isn't one of [`Settings::KEYS`], [`Err`] with [`DotulousError::UnknownSetting`] is
+    /// returned.
+    pub fn get(&self, key: &str) -> Result<Option<Value>, DotulousError> {
+        if !Settings::KEYS.contains(&key) {
+            return Err(DotulousError::UnknownSetting)
+        }
+        let mut table: Table = Table::try_from(self).map_err(DotulousError::FailedSerializeSettings)?;
+        Ok(table.remove(key))
+    }
+
+    /// Changes the setting `key` to `value`, written the same as it would be in `config.toml`,
+    /// e.g. `["bash", "-c"]`. Values that aren't valid TOML are taken as strings, so quotes can be
+    /// left out.
+    ///
+    /// If `key` isn't one of [`Settings::KEYS`], [`Err`] with [`DotulousError::UnknownSetting`] is
+    /// returned. If `value` isn't valid for the setting, [`Err`] with
+    /// [`DotulousError::FailedDeserializeSettings`] is returned and nothing is changed.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), DotulousError> {
+        if !Settings::KEYS.contains(&key) {
+            return Err(DotulousError::UnknownSetting)
+        }
+        let value: Value = match format!("value = {value}").parse::<Table>() {
+            Ok(mut parsed) => parsed.remove("value").unwrap_or_else(|| Value::String(value.to_string())),
+            Err(_) => Value::String(value.to_string())
+        };
+        let mut table: Table = Table::try_from(&*self).map_err(DotulousError::FailedSerializeSettings)?;
+        table.insert(key.to_string(), value);
+        *self = table.try_into().map_err(DotulousError::FailedDeserializeSettings)?;
+        Ok(())
+    }
+}
+
+/// Returns the path of `config.toml` inside `config_path`.
+fn settings_path(config_path: &Path) -> PathBuf {
+    config_path.join("config.toml")
+}
+
+/// Whether dotulous's output is colored, see [`Settings::color`].
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorChoice {
+    /// Color output when it's going to a terminal that supports it, and `NO_COLOR` isn't set.
+    #[default]
+    Auto,
+    /// Always color output.
+    Always,
+    /// Never color output.
+    Never
+}
+impl ColorChoice {
+    /// Returns if this is the default, [`ColorChoice::Auto`].
+    pub fn is_default(&self) -> bool {
+        *self == ColorChoice::Auto
+    }
+
+    /// Applies the choice to everything dotulous prints. [`ColorChoice::Auto`] leaves it to be
+    /// detected.
+    pub fn apply(&self) {
+        let enabled: bool = match self {
+            ColorChoice::Auto => return,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false
+        };
+        console::set_colors_enabled(enabled);
+        console::set_colors_enabled_stderr(enabled);
+    }
+}
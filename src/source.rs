@@ -0,0 +1,62 @@
+use std::{io::ErrorKind, path::Path, process::Command};
+
+use crate::{entry::Shell, error::DotulousError, platform, report};
+
+/// Returns a profile name to use for the repository at `url`, being the last part of the URL with
+/// any `.git` suffix removed. For example, `https://github.com/user/dots.git` would be `dots`.
+///
+/// Returns [`None`] if no name could be found in the URL.
+pub fn name_from_url(url: &str) -> Option<String> {
+    url.rsplit(['/', ':'])
+        .map(|part| part.strip_suffix(".git").unwrap_or(part))
+        .find(|part| !part.is_empty())
+        .map(str::to_string)
+}
+
+/// Clones the git repository at `url` into `destination` using the `git` command, which must not
+/// already exist.
+///
+/// If `git` is not installed, [`Err`] with [`DotulousError::GitNotFound`] is returned. If the clone
+/// itself fails, [`Err`] with [`DotulousError::FailedGitClone`] is returned.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+pub fn git_clone(url: &str, destination: &Path) -> Result<(), DotulousError> {
+    let status = Command::new("git")
+        .arg("clone")
+        .arg("--")
+        .arg(url)
+        .arg(destination)
+        .stdout(report::child_output())
+        .status();
+    match platform::check_status(status) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(DotulousError::GitNotFound),
+        Err(e) => Err(DotulousError::FailedGitClone(e))
+    }
+}
+
+/// Updates the profile at `repo_path` from where it came from, by running `command` inside it, or
+/// `git pull` if no command is given. Commands are ran in a new `shell`, or the platform's shell if
+/// [`None`] (see [`platform::shell_command`]).
+///
+/// If `git` is needed but not installed, [`Err`] with [`DotulousError::GitNotFound`] is returned.
+/// If the update itself fails, [`Err`] with [`DotulousError::FailedUpdateProfile`] is returned.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+pub fn update(repo_path: &Path, command: Option<&str>, shell: Option<&Shell>) -> Result<(), DotulousError> {
+    let mut process: Command = match command {
+        Some(command) => shell.map_or_else(|| platform::shell_command(command), |shell| shell.command(command)),
+        None => {
+            let mut process: Command = Command::new("git");
+            process.arg("pull");
+            process
+        }
+    };
+    match platform::check_status(process.current_dir(repo_path).stdout(report::child_output()).status()) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound && command.is_none() => Err(DotulousError::GitNotFound),
+        Err(e) => Err(DotulousError::FailedUpdateProfile(e))
+    }
+}
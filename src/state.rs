@@ -0,0 +1,98 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{entry::Strategy, error::DotulousError};
+
+/// What loading the current profile actually did to the system, stored in the user's dotulous
+/// folder as `state.json`.
+///
+/// The meta only stores a copy of the profile that was loaded, which says what *should* be on the
+/// system. This records what loading really created, so that unloading only ever removes those,
+/// and never a destination that was skipped. It's made from the load's
+/// [`crate::journal::Journal`] with [`crate::journal::Journal::finish`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct LoadState {
+    /// Every destination that was put onto the system, along with how it was put there.
+    #[serde(default)]
+    pub placed: Vec<PlacedFile>,
+    /// Every directory that was created, in the order they were created.
+    #[serde(default)]
+    pub created_directories: Vec<PathBuf>,
+    /// Every destination that was skipped, as it was already in place.
+    #[serde(default)]
+    pub skipped: Vec<PathBuf>
+}
+
+/// A single destination put onto the system while loading, see [`LoadState`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlacedFile {
+    /// The *absolute* path that was created.
+    pub destination: PathBuf,
+    /// How it was put there.
+    pub strategy: Strategy
+}
+
+impl LoadState {
+    /// Load the state from disk, using `state.json` inside of the given `dotulous_path`. Returns
+    /// [`None`] if there is no state, such as when nothing is loaded or the profile was loaded by an
+    /// older version of dotulous.
+    pub fn load(dotulous_path: &Path) -> Result<Option<LoadState>, DotulousError> {
+        let path: PathBuf = dotulous_path.join(Path::new("state.json"));
+        if !path.exists() {
+            return Ok(None)
+        }
+
+        let contents: String = fs::read_to_string(path).map_err(DotulousError::FailedReadState)?;
+        let deserialized: Self = serde_json::from_str(&contents).map_err(DotulousError::FailedDeserializeState)?;
+        Ok(Some(deserialized))
+    }
+
+    /// Save the state to disk, using `state.json` inside of the given `dotulous_path`.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn save(&self, dotulous_path: &Path) -> Result<(), DotulousError> {
+        let path: PathBuf = dotulous_path.join(Path::new("state.json"));
+        let serialized: String = serde_json::to_string_pretty(self).map_err(DotulousError::FailedSerializeState)?;
+        fs::write(path, serialized).map_err(DotulousError::FailedSaveState)?;
+        Ok(())
+    }
+
+    /// Removes the state from disk inside of the given `dotulous_path`, once the profile it's for
+    /// has been unloaded. Does nothing if there is no state.
+    ///
+    /// The returned [`Result`] does not return anything on success, meaning you should only check
+    /// for [`Err`] variants.
+    pub fn remove(dotulous_path: &Path) -> Result<(), DotulousError> {
+        let path: PathBuf = dotulous_path.join(Path::new("state.json"));
+        if path.exists() {
+            fs::remove_file(path).map_err(DotulousError::FailedSaveState)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets everything about the given `destinations`, once they've been unloaded on their own.
+    /// Directories created for them are kept, as other destinations may be inside of them.
+    pub fn forget(&mut self, destinations: &[PathBuf]) {
+        self.placed.retain(|p| !destinations.contains(&p.destination));
+        self.skipped.retain(|p| !destinations.contains(p));
+    }
+
+    /// Adds everything `other` did onto this state, such as when part of the profile was loaded
+    /// again on it's own.
+    pub fn extend(&mut self, other: LoadState) {
+        self.placed.extend(other.placed);
+        for directory in other.created_directories {
+            if !self.created_directories.contains(&directory) {
+                self.created_directories.push(directory);
+            }
+        }
+        self.skipped.extend(other.skipped);
+    }
+
+    /// Returns if `destination` was put onto the system by the load.
+    pub fn was_placed(&self, destination: &Path) -> bool {
+        self.placed.iter().any(|p| p.destination == destination)
+    }
+}
@@ -0,0 +1,114 @@
+use std::{ffi::OsString, fs, io, path::{Component, Path, PathBuf}};
+
+use crate::{error::DotulousError, platform::symlink, profile::DotfileProfile};
+
+/// The prefix stow's `--dotfiles` option turns into a `.`, so `dot-bashrc` ends up at `.bashrc`.
+pub const DOTFILES_PREFIX: &str = "dot-";
+
+/// The file stow reads a package's ignore list from, which is never linked.
+pub const LOCAL_IGNORE: &str = ".stow-local-ignore";
+
+/// Returns the packages inside `stow_path`, a stow directory, sorted by name. Every folder directly
+/// inside it is a package, apart from hidden ones such as `.git`.
+pub fn packages(stow_path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut packages: Vec<PathBuf> = Vec::new();
+    for entry in fs::read_dir(stow_path)? {
+        let entry = entry?;
+        let is_hidden: bool = entry.file_name().to_string_lossy().starts_with('.');
+        if !is_hidden && entry.file_type()?.is_dir() {
+            packages.push(entry.path());
+        }
+    }
+    packages.sort();
+    Ok(packages)
+}
+
+/// Returns where stow would put `source`, a path relative to a stow directory, relative to the
+/// target folder. The package (the first component) is dropped, and any component starting with
+/// [`DOTFILES_PREFIX`] gets a `.` instead.
+///
+/// Returns [`None`] if `source` isn't inside a package, or is a package's [`LOCAL_IGNORE`] file.
+pub fn destination(source: &Path) -> Option<PathBuf> {
+    let mut components = source.components();
+    let Some(Component::Normal(_)) = components.next() else { return None };
+    let mut destination: PathBuf = PathBuf::new();
+    for component in components {
+        let Component::Normal(name) = component else { return None };
+        let name: String = name.to_string_lossy().to_string();
+        match name.strip_prefix(DOTFILES_PREFIX) {
+            Some(rest) => destination.push(OsString::from(format!(".{rest}"))),
+            None => destination.push(name),
+        }
+    }
+    if destination.as_os_str().is_empty() || source.ends_with(LOCAL_IGNORE) {
+        return None
+    }
+    Some(destination)
+}
+
+/// Imports the stow directory at `stow_path` (laid out as `<package>/.config/...`) as a new profile
+/// inside `dotulous_path`, the user's dotulous folder, returning it.
+///
+/// Every package is copied into the profile's folder, named after the sanitized `profile_name` or
+/// the stow directory's own name, with symlinks kept as they are. The profile's `files` are then
+/// filled in the stow layout (see [`DotfileProfile::fill_files`]), mapping each package's files to
+/// the same place in the user's home folder. If that folder already exists, [`Err`] with
+/// [`DotulousError::ProfileAlreadyExists`] is returned, and if the stow directory has no packages,
+/// [`Err`] with [`DotulousError::NoStowPackages`].
+///
+/// If anything fails, the half-imported profile is removed again. The profile is **not** trusted,
+/// this is left to the user.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn import(dotulous_path: &Path, stow_path: &Path, profile_name: Option<&str>) -> Result<DotfileProfile, DotulousError> {
+    let packages: Vec<PathBuf> = packages(stow_path).map_err(DotulousError::FailedImportStow)?;
+    if packages.is_empty() {
+        return Err(DotulousError::NoStowPackages)
+    }
+    let name: String = match profile_name {
+        Some(name) => name.to_string(),
+        None => stow_path.canonicalize().map_err(DotulousError::FailedImportStow)?
+            .file_name().map_or("stow".to_string(), |name| name.to_string_lossy().to_string()),
+    };
+    let profile_path: PathBuf = dotulous_path.join(sanitize_filename::sanitize(&name));
+    if profile_path.exists() {
+        return Err(DotulousError::ProfileAlreadyExists)
+    }
+
+    let result: Result<DotfileProfile, DotulousError> = fill(&name, &profile_path, &packages);
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&profile_path);
+    }
+    result
+}
+
+/// Copies `packages` into `profile_path` and creates the profile there. See [`import`].
+fn fill(name: &str, profile_path: &Path, packages: &[PathBuf]) -> Result<DotfileProfile, DotulousError> {
+    fs::create_dir_all(profile_path).map_err(DotulousError::FailedImportStow)?;
+    for package in packages {
+        let Some(package_name) = package.file_name() else { continue };
+        copy_tree(package, &profile_path.join(package_name)).map_err(DotulousError::FailedImportStow)?;
+    }
+    let mut profile: DotfileProfile = DotfileProfile::new(name, profile_path);
+    profile.fill_files(None, true, &[], false, true)?;
+    Ok(profile)
+}
+
+/// Copies `source` to `destination`, recursively if `source` is a directory. Symlinks are copied
+/// as symlinks rather than followed.
+fn copy_tree(source: &Path, destination: &Path) -> io::Result<()> {
+    let metadata: fs::Metadata = source.symlink_metadata()?;
+    if metadata.is_symlink() {
+        return symlink(fs::read_link(source)?, destination)
+    }
+    if !metadata.is_dir() {
+        return fs::copy(source, destination).map(|_| ())
+    }
+
+    fs::create_dir(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        copy_tree(&entry.path(), &destination.join(entry.file_name()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::{ffi::OsString, fs, io, path::{Path, PathBuf}};
+
+use crate::{error::DotulousError, platform, profile::{ApplyMode, DotfileProfile}, say};
+
+/// Added to the end of a system file that was in the way of one of a profile's `system_files`,
+/// such as `/etc/keyd/default.conf.dotulous-backup`. It's put back by [`remove`].
+pub const BACKUP_SUFFIX: &str = ".dotulous-backup";
+
+/// Returns where the system file at `destination` is backed up to, see [`BACKUP_SUFFIX`].
+pub fn backup_path(destination: &Path) -> PathBuf {
+    let mut path: OsString = destination.as_os_str().to_os_string();
+    path.push(BACKUP_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Copies each of `profile`'s `system_files` to their *absolute* destination, outside of the
+/// user's home folder. This is kept apart from loading, and is only done by the privileged
+/// `dotulous apply-system` sub-command, so [`Err`] with [`DotulousError::NotRoot`] is returned
+/// unless dotulous is running as root.
+///
+/// Each source must resolve to a file inside the profile's folder, so a symlink in the profile
+/// can't have root copy some other file on the system into place. [`Err`] with
+/// [`DotulousError::SystemSourceOutsideProfile`] is returned otherwise.
+///
+/// Files are copied rather than symlinked, as system services shouldn't read from a user's home
+/// folder. Anything already at a destination that differs is first moved to it's
+/// [`backup_path`], unless a backup is already there from applying before. Destinations that
+/// already match their source are left alone. If `mode` is [`ApplyMode::DryRun`], nothing is
+/// changed, only what would be is printed.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn apply(profile: &DotfileProfile, mode: ApplyMode) -> Result<(), DotulousError> {
+    if !platform::is_root() {
+        return Err(DotulousError::NotRoot)
+    }
+    say!("Applying system files for profile: {}", profile.name);
+    for (source, destination) in profile.system_files() {
+        if !destination.is_absolute() {
+            return Err(DotulousError::SystemDestinationNotAbsolute)
+        }
+        let source: PathBuf = resolve_source(profile, source)?;
+        if platform::same_contents(&source, destination) {
+            say!("  Unchanged {destination:?}");
+            continue;
+        }
+
+        let backup: PathBuf = backup_path(destination);
+        let needs_backup: bool = destination.symlink_metadata().is_ok() && backup.symlink_metadata().is_err();
+        if needs_backup {
+            say!("  Backing up {destination:?} => {backup:?}");
+        }
+        say!("  {source:?} => {destination:?}");
+        if mode == ApplyMode::DryRun {
+            continue;
+        }
+
+        if needs_backup {
+            fs::rename(destination, &backup).map_err(DotulousError::FailedApplySystemFile)?;
+        } else if destination.symlink_metadata().is_ok_and(|metadata| !metadata.is_file()) {
+            // Copying onto a symlink would write through it, so it's removed first
+            fs::remove_file(destination).map_err(DotulousError::FailedApplySystemFile)?;
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).map_err(DotulousError::FailedApplySystemFile)?;
+        }
+        fs::copy(&source, destination).map_err(DotulousError::FailedApplySystemFile)?;
+    }
+    Ok(())
+}
+
+/// Undoes [`apply`], removing each of `profile`'s `system_files` from the system and putting back
+/// any backup that was made of what was there before. Like [`apply`], this is only done as root.
+///
+/// Destinations that have changed since they were applied are left alone with a warning, so
+/// nothing is lost. If `mode` is [`ApplyMode::DryRun`], nothing is changed, only what would be is
+/// printed.
+///
+/// **Note:** This function prints to stdout, as it is normally called by the user in the CLI.
+pub fn remove(profile: &DotfileProfile, mode: ApplyMode) -> Result<(), DotulousError> {
+    if !platform::is_root() {
+        return Err(DotulousError::NotRoot)
+    }
+    say!("Removing system files for profile: {}", profile.name);
+    for (source, destination) in profile.system_files() {
+        if !destination.is_absolute() {
+            return Err(DotulousError::SystemDestinationNotAbsolute)
+        }
+        let source: PathBuf = profile.repo_path.join(source);
+        let is_applied: bool = platform::same_contents(&source, destination);
+        if !is_applied && destination.symlink_metadata().is_ok() {
+            say!("  WARNING: Skipping {destination:?}, it has changed since it was applied.");
+            continue;
+        }
+        let backup: PathBuf = backup_path(destination);
+        let has_backup: bool = backup.symlink_metadata().is_ok();
+        if is_applied {
+            say!("  Removing {destination:?}");
+        }
+        if has_backup {
+            say!("  Restoring {backup:?} => {destination:?}");
+        }
+        if mode == ApplyMode::DryRun {
+            continue;
+        }
+
+        if is_applied {
+            fs::remove_file(destination).map_err(DotulousError::FailedApplySystemFile)?;
+        }
+        if has_backup {
+            fs::rename(&backup, destination).map_err(DotulousError::FailedApplySystemFile)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the destinations of `profile`'s `system_files` that [`apply`] would overwrite, being
+/// those that already exist with different contents to their source.
+pub fn overwrites(profile: &DotfileProfile) -> Vec<PathBuf> {
+    profile.system_files().iter()
+        .filter(|(source, destination)| destination.symlink_metadata().is_ok() && !platform::same_contents(&profile.repo_path.join(source), destination))
+        .map(|(_, destination)| destination.clone())
+        .collect()
+}
+
+/// Returns the canonical path of the system file `source` within `profile`, following any
+/// symlinks. [`Err`] is returned if it isn't a file, or resolves to outside of the profile.
+fn resolve_source(profile: &DotfileProfile, source: &Path) -> Result<PathBuf, DotulousError> {
+    let joined: PathBuf = profile.repo_path.join(source);
+    let resolved: PathBuf = fs::canonicalize(&joined).map_err(DotulousError::FailedApplySystemFile)?;
+    let repo_path: PathBuf = fs::canonicalize(&profile.repo_path).map_err(DotulousError::FailedApplySystemFile)?;
+    if !resolved.starts_with(&repo_path) {
+        return Err(DotulousError::SystemSourceOutsideProfile)
+    }
+    if !resolved.is_file() {
+        return Err(DotulousError::FailedApplySystemFile(io::Error::new(io::ErrorKind::NotFound, format!("{joined:?} is not a file"))))
+    }
+    Ok(resolved)
+}
+
@@ -0,0 +1,120 @@
+use std::{collections::HashMap, fs, io, path::{Path, PathBuf}};
+
+use crate::{entry::FileMode, error::DotulousError, platform};
+
+/// Renders files using the template strategy ([`crate::entry::Strategy::Template`]) into the
+/// profile's template cache, so the rendered output can then be symlinked to the system.
+///
+/// Rendered files are kept at the same path (relative to the cache) as the template is inside the
+/// profile's directory. See [`render`] for the template syntax.
+pub struct Renderer {
+    /// The *absolute* path to the folder rendered files are written to.
+    cache_path: PathBuf,
+    /// The *absolute* path to the profile's folder.
+    repo_path: PathBuf,
+    /// The variables available to templates.
+    variables: HashMap<String, String>
+}
+impl Renderer {
+    /// Creates a new `Renderer`, rendering templates inside `repo_path` into `cache_path` with the
+    /// given `variables`.
+    pub fn new(cache_path: PathBuf, repo_path: &Path, variables: HashMap<String, String>) -> Self {
+        Self {
+            cache_path,
+            repo_path: repo_path.to_path_buf(),
+            variables
+        }
+    }
+
+    /// Returns the *absolute* path the template at `source` is rendered to.
+    pub fn rendered_path(&self, source: &Path) -> PathBuf {
+        match source.strip_prefix(&self.repo_path) {
+            Ok(relative) => self.cache_path.join(relative),
+            Err(_) => self.cache_path.join(source.file_name().unwrap_or_default())
+        }
+    }
+
+    /// Renders the template at `source` into the cache, replacing any previous render, and returns
+    /// the *absolute* path it was rendered to. The render is created with `mode` if it's given.
+    pub fn render_file(&self, source: &Path, mode: Option<FileMode>) -> io::Result<PathBuf> {
+        let rendered_path: PathBuf = self.rendered_path(source);
+        if let Some(parent) = rendered_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents: String = fs::read_to_string(source)?;
+        let rendered: String = render(&contents, &self.variables);
+        match mode {
+            Some(mode) => platform::write_with_mode(&rendered_path, rendered.as_bytes(), mode.bits())?,
+            None => fs::write(&rendered_path, rendered)?
+        }
+        Ok(rendered_path)
+    }
+}
+
+/// Reads the variables specific to this machine from `vars.json` inside `config_path`, the user's
+/// dotulous config folder, being a JSON object of variable names to values. These override any
+/// variables in a profile's manifest.
+///
+/// If the file doesn't exist, no variables are returned.
+pub fn load_host_variables(config_path: &Path) -> Result<HashMap<String, String>, DotulousError> {
+    let path: PathBuf = config_path.join("vars.json");
+    if !path.exists() {
+        return Ok(HashMap::new())
+    }
+
+    let contents: String = fs::read_to_string(&path).map_err(DotulousError::FailedReadVariables)?;
+    let deserialized: HashMap<String, String> = serde_json::from_str(&contents).map_err(DotulousError::FailedDeserializeVariables)?;
+    Ok(deserialized)
+}
+
+/// Renders the template `contents`, replacing every `{{name}}` placeholder with the value of
+/// `name` inside `variables`. Whitespace inside the braces is ignored, so `{{ name }}` also works.
+///
+/// Placeholders for variables that don't exist are left as-is.
+pub fn render(contents: &str, variables: &HashMap<String, String>) -> String {
+    let mut rendered: String = String::with_capacity(contents.len());
+    let mut rest: &str = contents;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after_open: &str = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let name: &str = after_open[..end].trim();
+        match variables.get(name) {
+            Some(value) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..start + end + 4])
+        }
+        rest = &after_open[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Renders the command `command` like [`render`], but with every value quoted for the shell (see
+/// [`shell_quote`]), so a variable can never add commands of it's own or be split up by spaces.
+pub fn render_command(command: &str, variables: &HashMap<String, String>) -> String {
+    let quoted: HashMap<String, String> = variables.iter().map(|(name, value)| (name.clone(), shell_quote(value))).collect();
+    render(command, &quoted)
+}
+
+/// Returns `value` quoted so the shell reads it as a single word exactly as it is. Values made up
+/// only of characters the shell never treats specially, such as `/home/user/.config`, are left as
+/// they are so commands stay readable.
+///
+/// `value` is wrapped in single quotes, with any inside it escaped as `'\''`, which `sh`, `bash`,
+/// `zsh` & `fish` all understand. On Windows, where `cmd` has no single quotes, it's wrapped in
+/// double quotes instead, with any inside it doubled.
+pub fn shell_quote(value: &str) -> String {
+    let is_plain = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+=,".contains(c);
+    if !value.is_empty() && value.chars().all(is_plain) {
+        return value.to_string()
+    }
+    if cfg!(windows) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
@@ -0,0 +1,361 @@
+use std::{io, path::{Path, PathBuf}};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs, Wrap},
+    DefaultTerminal, Frame
+};
+
+use crate::{
+    error::DotulousError,
+    filter::TagFilter,
+    inspect::{self, Drift, LinkHealth},
+    meta::{Meta, TrustScope},
+    operations::{self, ProfileSummary},
+    profile::{ApplyMode, DotfileProfile, OnConflict, Skip},
+    say,
+    settings::Settings
+};
+
+/// What the right side of the dashboard shows about the selected profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum View {
+    /// The profile's `files`.
+    Files,
+    /// The commands of each of the profile's hooks.
+    Hooks,
+    /// The [`LinkHealth`] of every destination, if the profile is loaded.
+    Health,
+    /// How the system differs from the profile, if it's loaded. The same as `dotulous diff`.
+    Diff
+}
+impl View {
+    /// Every view, in the order they're shown in.
+    const ALL: [View; 4] = [View::Files, View::Hooks, View::Health, View::Diff];
+
+    /// Returns the title of the view's tab.
+    fn title(&self) -> &'static str {
+        match self {
+            View::Files => "Files",
+            View::Hooks => "Hooks",
+            View::Health => "Health",
+            View::Diff => "Diff"
+        }
+    }
+
+    /// Returns the view after this one, wrapping around.
+    fn next(&self) -> View {
+        let index: usize = View::ALL.iter().position(|view| view == self).unwrap_or(0);
+        View::ALL[(index + 1) % View::ALL.len()]
+    }
+}
+
+/// An action picked in the dashboard that changes the system, ran with the terminal handed back
+/// to it so it's output (and any questions) are shown as usual. See [`Dashboard::run_action`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Action {
+    Load,
+    Unload,
+    Reload
+}
+
+/// The state of the dashboard, see [`run`].
+struct Dashboard<'a> {
+    /// The *absolute* path to the user's dotulous folder.
+    dotulous_path: &'a Path,
+    /// The *absolute* path to the user's config folder.
+    config_path: &'a Path,
+    /// The *absolute* path to the user's home folder.
+    home_path: &'a Path,
+    /// The user's settings, for running the profiles' commands.
+    settings: &'a Settings,
+    /// What to do with anything in the way when loading.
+    on_conflict: OnConflict,
+    /// Every profile, as listed by [`operations::list`].
+    profiles: Vec<ProfileSummary>,
+    /// Which profile is selected in the list.
+    selected: ListState,
+    /// The selected profile, as read from it's manifest, or the error reading it.
+    details: Result<DotfileProfile, String>,
+    /// The currently loaded profile, as stored in the meta.
+    loaded: Option<DotfileProfile>,
+    /// What's shown about the selected profile.
+    view: View,
+    /// The result of the last action, shown at the bottom.
+    message: String
+}
+impl<'a> Dashboard<'a> {
+    /// Creates the dashboard, selecting the loaded profile if there is one.
+    fn new(dotulous_path: &'a Path, config_path: &'a Path, home_path: &'a Path, settings: &'a Settings, on_conflict: OnConflict) -> Result<Self, DotulousError> {
+        let mut dashboard: Dashboard = Dashboard {
+            dotulous_path,
+            config_path,
+            home_path,
+            settings,
+            on_conflict,
+            profiles: Vec::new(),
+            selected: ListState::default(),
+            details: Err(String::new()),
+            loaded: None,
+            view: View::Files,
+            message: String::from("Press ? for help.")
+        };
+        dashboard.refresh()?;
+        let loaded: Option<usize> = dashboard.profiles.iter().position(|profile| profile.loaded);
+        dashboard.select(loaded.unwrap_or(0));
+        Ok(dashboard)
+    }
+
+    /// Reads the profiles & meta again, keeping the same profile selected where possible.
+    fn refresh(&mut self) -> Result<(), DotulousError> {
+        let selected_path: Option<PathBuf> = self.selected_profile().map(|profile| profile.path.clone());
+        self.profiles = operations::list(self.dotulous_path)?;
+        self.loaded = Meta::load_meta(self.dotulous_path)?.current_profile();
+        let index: usize = selected_path
+            .and_then(|path| self.profiles.iter().position(|profile| profile.path == path))
+            .unwrap_or(0);
+        self.select(index);
+        Ok(())
+    }
+
+    /// Selects the profile at `index` in the list, reading it's manifest.
+    fn select(&mut self, index: usize) {
+        if self.profiles.is_empty() {
+            self.selected.select(None);
+            self.details = Err(String::from("No profiles found, create one with `dotulous create`."));
+            return
+        }
+        let index: usize = index.min(self.profiles.len() - 1);
+        self.selected.select(Some(index));
+        self.details = DotfileProfile::from_manifest(&self.profiles[index].path).map_err(|e| format!("Failed to read manifest: {e:#}"));
+    }
+
+    /// Returns the summary of the selected profile.
+    fn selected_profile(&self) -> Option<&ProfileSummary> {
+        self.selected.selected().and_then(|index| self.profiles.get(index))
+    }
+
+    /// Returns the loaded profile, if it's the selected one.
+    fn selected_loaded(&self) -> Option<&DotfileProfile> {
+        let selected: &ProfileSummary = self.selected_profile()?;
+        self.loaded.as_ref().filter(|loaded| loaded.repo_path == selected.path)
+    }
+
+    /// Handles the key `code`, returning the [`Action`] to run if one was picked, and if the
+    /// dashboard should be closed.
+    fn handle_key(&mut self, code: KeyCode) -> (Option<Action>, bool) {
+        let index: usize = self.selected.selected().unwrap_or(0);
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return (None, true),
+            KeyCode::Down | KeyCode::Char('j') => self.select(index + 1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(index.saturating_sub(1)),
+            KeyCode::Tab => self.view = self.view.next(),
+            KeyCode::Char(number @ '1'..='4') => self.view = View::ALL[number as usize - '1' as usize],
+            KeyCode::Enter | KeyCode::Char('l') => return (Some(Action::Load), false),
+            KeyCode::Char('u') => return (Some(Action::Unload), false),
+            KeyCode::Char('r') => return (Some(Action::Reload), false),
+            KeyCode::Char('?') => self.message = String::from(
+                "j/k: move  enter/l: load  u: unload  r: reload  tab/1-4: switch view  q: quit"
+            ),
+            _ => {}
+        }
+        (None, false)
+    }
+
+    /// Runs `action` on the selected or loaded profile, see [`Dashboard::suspended`].
+    ///
+    /// Profiles that aren't trusted are never loaded, trusting them is left to `dotulous trust`
+    /// where their commands are shown.
+    fn run_action(&mut self, terminal: &mut DefaultTerminal, action: Action) -> Result<(), DotulousError> {
+        let Some(selected) = self.selected_profile() else { return Ok(()) };
+        match action {
+            Action::Load if !selected.trusted => {
+                let folder_name: String = selected.path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+                self.message = format!("\"{}\" isn't trusted, look over it and run `dotulous trust {folder_name}` first.", selected.name);
+                Ok(())
+            },
+            Action::Load => {
+                let profile: DotfileProfile = match &self.details {
+                    Ok(r) => r.clone(),
+                    Err(e) => {
+                        self.message = e.clone();
+                        return Ok(())
+                    }
+                };
+                self.suspended(terminal, |dashboard| {
+                    operations::load(dashboard.dotulous_path, dashboard.config_path, dashboard.home_path, dashboard.settings, &profile, false, &TagFilter::default(), Skip::default(), ApplyMode::Apply, dashboard.on_conflict, false, None)
+                        .map(|_| format!("Loaded \"{}\".", profile.name))
+                })
+            },
+            _ if self.loaded.is_none() => {
+                self.message = String::from("No profile is loaded.");
+                Ok(())
+            },
+            Action::Unload => self.suspended(terminal, |dashboard| {
+                operations::unload(dashboard.dotulous_path, dashboard.config_path, dashboard.home_path, dashboard.settings, Skip::default(), ApplyMode::Apply, false)
+                    .map(|profile| format!("Unloaded \"{}\".", profile.name))
+            }),
+            Action::Reload => self.suspended(terminal, |dashboard| {
+                operations::reload(dashboard.dotulous_path, dashboard.config_path, dashboard.home_path, dashboard.settings, Skip::default(), ApplyMode::Apply, dashboard.on_conflict, false, false, None)
+                    .map(|profile| format!("Reloaded \"{}\".", profile.name))
+            })
+        }
+    }
+
+    /// Runs `operation` with the terminal handed back to it, so it can print (and ask the user
+    /// about conflicts) as it does in the CLI. It's result is shown at the bottom of the dashboard
+    /// once the user presses enter to go back to it.
+    fn suspended(&mut self, terminal: &mut DefaultTerminal, operation: impl FnOnce(&Self) -> Result<String, DotulousError>) -> Result<(), DotulousError> {
+        ratatui::try_restore().map_err(DotulousError::FailedRunDashboard)?;
+        self.message = match operation(self) {
+            Ok(r) => r,
+            Err(e) => format!("ERROR: {e:#}")
+        };
+        say!();
+        say!("{} Press enter to go back to the dashboard.", self.message);
+        let mut line: String = String::new();
+        let _ = io::stdin().read_line(&mut line);
+        *terminal = ratatui::try_init().map_err(DotulousError::FailedRunDashboard)?;
+        self.refresh()
+    }
+
+    /// Draws the dashboard onto `frame`.
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [list_area, details_area] = Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]).areas(main);
+
+        let items: Vec<ListItem> = self.profiles.iter().map(|profile| {
+            let mut spans: Vec<Span> = vec![Span::raw(if profile.loaded { "● " } else { "  " }), Span::raw(profile.name.clone())];
+            if let Some(version) = &profile.version {
+                spans.push(Span::raw(format!(" {version}")).dark_gray());
+            }
+            match profile.trust_scope {
+                None => spans.push(Span::raw(" (untrusted)").yellow()),
+                Some(TrustScope::Files) => spans.push(Span::raw(" (files trusted)").dark_gray()),
+                Some(TrustScope::Commands) => {}
+            }
+            let item: ListItem = ListItem::new(Line::from(spans));
+            if profile.loaded { item.green().bold() } else { item }
+        }).collect();
+        let list: List = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(" Profiles "))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.selected);
+
+        self.draw_details(frame, details_area);
+        frame.render_widget(Paragraph::new(self.message.as_str()).dark_gray(), footer);
+    }
+
+    /// Draws the current [`View`] of the selected profile into `area`.
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let [tabs_area, body_area] = Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(area);
+        let selected: usize = View::ALL.iter().position(|view| *view == self.view).unwrap_or(0);
+        let tabs: Tabs = Tabs::new(View::ALL.iter().map(View::title))
+            .select(selected)
+            .highlight_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+            .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(tabs, tabs_area);
+
+        let lines: Vec<Line> = match &self.details {
+            Ok(profile) => self.view_lines(profile),
+            Err(e) => vec![Line::from(e.as_str())]
+        };
+        let title: String = self.selected_profile().map(|profile| format!(" {} ", profile.name)).unwrap_or_default();
+        let body: Paragraph = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(body, body_area);
+    }
+
+    /// Returns the lines of the current [`View`] of `profile`, the selected profile.
+    fn view_lines(&self, profile: &DotfileProfile) -> Vec<Line<'static>> {
+        match self.view {
+            View::Files => {
+                let mut lines: Vec<Line> = profile.description.iter().map(|description| Line::from(description.clone()).italic()).collect();
+                lines.extend(profile.entries().map(|(source, entry)| Line::from(format!("{source:?} => {:?} ({})", entry.destination, entry.strategy))));
+                lines.extend(profile.assets().iter().map(|asset| Line::from(format!("{} => {:?}", asset.url, asset.dest))));
+                if lines.is_empty() {
+                    lines.push(Line::from("No files."));
+                }
+                lines
+            },
+            View::Hooks => {
+                let mut lines: Vec<Line> = Vec::new();
+                for (hook, commands) in profile.command_lists().filter(|(_, commands)| !commands.is_empty()) {
+                    lines.push(Line::from(format!("{hook}:")).bold());
+                    lines.extend(commands.iter().map(|command| Line::from(format!("  {}", command.command))));
+                }
+                if lines.is_empty() {
+                    lines.push(Line::from("No commands."));
+                }
+                lines
+            },
+            View::Health => {
+                let Some(loaded) = self.selected_loaded() else { return vec![Line::from("Not loaded.")] };
+                inspect::link_health(loaded, self.dotulous_path, self.home_path).into_iter()
+                    .map(|(destination, health)| {
+                        let color: Color = match health {
+                            LinkHealth::Ok => Color::Green,
+                            LinkHealth::OptionalAbsent => Color::DarkGray,
+                            _ => Color::Red
+                        };
+                        Line::from(vec![Span::raw(format!("{health:<7} ")).fg(color), Span::raw(format!("{destination:?}"))])
+                    })
+                    .collect()
+            },
+            View::Diff => {
+                let Some(loaded) = self.selected_loaded() else { return vec![Line::from("Not loaded.")] };
+                let mut drift: Vec<Drift> = inspect::loaded_drift(loaded, self.dotulous_path, self.home_path);
+                if let Ok(unmapped) = inspect::unmapped_files(profile) {
+                    drift.extend(unmapped);
+                }
+                if drift.is_empty() {
+                    return vec![Line::from("No differences found.")]
+                }
+                drift.iter().map(|difference| Line::from(difference.to_string())).collect()
+            }
+        }
+    }
+}
+
+/// Runs the interactive dashboard in the terminal until the user quits, where `dotulous_path` is
+/// the user's dotulous folder and `home_path` is the user's home folder.
+///
+/// Profiles are shown in a list with the loaded one highlighted, alongside the selected profile's
+/// files, hooks, and if it's loaded, the health of it's links and how the system differs from it.
+/// The selected profile can be loaded, and the loaded profile unloaded or reloaded, through
+/// [`operations`] with the user's `settings`. Anything in the way while loading is dealt with
+/// according to `on_conflict`.
+///
+/// The terminal is restored before returning, even if drawing fails.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+pub fn run(dotulous_path: &Path, config_path: &Path, home_path: &Path, settings: &Settings, on_conflict: OnConflict) -> Result<(), DotulousError> {
+    let mut dashboard: Dashboard = Dashboard::new(dotulous_path, config_path, home_path, settings, on_conflict)?;
+    let mut terminal: DefaultTerminal = ratatui::try_init().map_err(DotulousError::FailedRunDashboard)?;
+    let result: Result<(), DotulousError> = event_loop(&mut dashboard, &mut terminal);
+    ratatui::try_restore().map_err(DotulousError::FailedRunDashboard)?;
+    result
+}
+
+/// Draws `dashboard` and handles key presses until the user quits. See [`run`].
+fn event_loop(dashboard: &mut Dashboard, terminal: &mut DefaultTerminal) -> Result<(), DotulousError> {
+    loop {
+        terminal.draw(|frame| dashboard.draw(frame)).map_err(DotulousError::FailedRunDashboard)?;
+        let Event::Key(key) = event::read().map_err(DotulousError::FailedRunDashboard)? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let (action, quit) = dashboard.handle_key(key.code);
+        if quit {
+            return Ok(())
+        }
+        if let Some(action) = action {
+            dashboard.run_action(terminal, action)?;
+        }
+    }
+}
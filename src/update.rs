@@ -0,0 +1,187 @@
+use std::{cmp::Ordering, env, fs, io, path::{Path, PathBuf}, process::Command};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{error::DotulousError, inspect::compare_versions, platform, profile::ApplyMode};
+
+/// The version of dotulous that's running.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Where the latest release of dotulous is looked up, using GitHub's releases API.
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/SamPertWasTaken/Dotulous/releases/latest";
+
+/// How many hours to wait between checks made by [`check_daily`].
+const CHECK_INTERVAL_HOURS: i64 = 24;
+
+/// How many seconds [`check_daily`] waits for GitHub before giving up, so a slow network never
+/// holds up the command that was ran.
+const CHECK_TIMEOUT_SECONDS: u64 = 5;
+
+/// A release of dotulous on GitHub, found with [`latest_release`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct Release {
+    /// The release's tag, such as `v0.2.0`.
+    #[serde(rename = "tag_name")]
+    pub tag: String,
+    /// Every file uploaded to the release.
+    pub assets: Vec<ReleaseAsset>
+}
+impl Release {
+    /// Returns the release's version, being it's tag without the leading `v`.
+    pub fn version(&self) -> &str {
+        self.tag.strip_prefix('v').unwrap_or(&self.tag)
+    }
+
+    /// Returns if the release is newer than the version of dotulous that's running, see
+    /// [`compare_versions`].
+    pub fn is_newer(&self) -> bool {
+        compare_versions(&self.tag, CURRENT_VERSION) == Ordering::Greater
+    }
+
+    /// Returns the file of the release built for this platform, named after [`artifact_name`], or
+    /// [`None`] if there isn't one.
+    pub fn artifact(&self) -> Option<&ReleaseAsset> {
+        let name: String = artifact_name();
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    /// Returns the SHA-256 digest `artifact` should have, read from the `<artifact>.sha256` file
+    /// uploaded alongside it. Like the output of `sha256sum`, only the first word is used.
+    ///
+    /// If the release has no checksum for `artifact`, [`Err`] with
+    /// [`DotulousError::NoReleaseArtifact`] is returned, as it can't be installed safely.
+    fn checksum(&self, artifact: &ReleaseAsset) -> Result<String, DotulousError> {
+        let name: String = format!("{}.sha256", artifact.name);
+        let checksum: &ReleaseAsset = self.assets.iter().find(|asset| asset.name == name).ok_or(DotulousError::NoReleaseArtifact)?;
+        let contents: Vec<u8> = fetch(&checksum.browser_download_url, None).map_err(DotulousError::FailedDownloadUpdate)?;
+        String::from_utf8_lossy(&contents).split_whitespace().next()
+            .map(str::to_lowercase)
+            .ok_or(DotulousError::UpdateChecksumMismatch)
+    }
+}
+
+/// A file uploaded to a [`Release`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReleaseAsset {
+    /// The name of the file, such as `dotulous-x86_64-linux`.
+    pub name: String,
+    /// Where the file can be downloaded from.
+    pub browser_download_url: String
+}
+
+/// Returns the name of the release file built for this platform, being
+/// `dotulous-<architecture>-<os>`, such as `dotulous-x86_64-linux` or
+/// `dotulous-x86_64-windows.exe`.
+pub fn artifact_name() -> String {
+    format!("dotulous-{}-{}{}", env::consts::ARCH, env::consts::OS, env::consts::EXE_SUFFIX)
+}
+
+/// Looks up the latest release of dotulous on GitHub, using `curl`.
+pub fn latest_release() -> Result<Release, DotulousError> {
+    let contents: Vec<u8> = fetch(LATEST_RELEASE_URL, None).map_err(DotulousError::FailedCheckUpdate)?;
+    serde_json::from_slice(&contents).map_err(DotulousError::FailedDeserializeRelease)
+}
+
+/// Replaces the running dotulous executable with `release`'s [`Release::artifact`], returning the
+/// path of the executable that was (or would be) replaced. If `mode` is [`ApplyMode::DryRun`],
+/// nothing is downloaded or changed.
+///
+/// The artifact is downloaded next to the executable into a temporary `.part` file, and only moved
+/// over it once it's checksum matches (see [`Release::checksum`]), so a failed or corrupted
+/// download never leaves a broken executable behind. As the checksum comes from the same release,
+/// it doesn't protect against the release itself being tampered with. If it doesn't match, it is
+/// deleted and [`Err`] with [`DotulousError::UpdateChecksumMismatch`] is returned.
+pub fn install(release: &Release, mode: ApplyMode) -> Result<PathBuf, DotulousError> {
+    let artifact: &ReleaseAsset = release.artifact().ok_or(DotulousError::NoReleaseArtifact)?;
+    // Replace the file itself rather than a symlink to it, such as one in `~/.local/bin`
+    let executable: PathBuf = env::current_exe()
+        .and_then(fs::canonicalize)
+        .map_err(DotulousError::FailedReplaceExecutable)?;
+    if mode == ApplyMode::DryRun {
+        return Ok(executable)
+    }
+
+    let expected: String = release.checksum(artifact)?;
+    let partial: PathBuf = executable.with_file_name(format!(".{}.part", artifact.name));
+    let downloaded: Result<(), DotulousError> = download(&artifact.browser_download_url, &partial)
+        .and_then(|()| verify(&partial, &expected))
+        .and_then(|()| platform::set_mode(&partial, 0o755).map_err(DotulousError::FailedReplaceExecutable));
+    if let Err(e) = downloaded {
+        let _ = fs::remove_file(&partial);
+        return Err(e)
+    }
+    if let Err(e) = platform::replace_executable(&partial, &executable) {
+        let _ = fs::remove_file(&partial);
+        return Err(DotulousError::FailedReplaceExecutable(e))
+    }
+    Ok(executable)
+}
+
+/// Looks up the latest release at most once every [`CHECK_INTERVAL_HOURS`], returning it if it's
+/// newer than the running version. When it was last checked is kept in `update-check` inside
+/// `dotulous_path`. Used for the opt-in `update_check` setting, see
+/// [`crate::settings::Settings::update_check`].
+///
+/// When it was checked is saved before looking it up, so being offline doesn't make every command
+/// wait on the network. GitHub is only given [`CHECK_TIMEOUT_SECONDS`] to answer.
+pub fn check_daily(dotulous_path: &Path) -> Result<Option<Release>, DotulousError> {
+    let path: PathBuf = dotulous_path.join("update-check");
+    let last_checked: Option<DateTime<Utc>> = fs::read_to_string(&path).ok()
+        .and_then(|contents| DateTime::parse_from_rfc3339(contents.trim()).ok())
+        .map(|timestamp| timestamp.with_timezone(&Utc));
+    let interval: TimeDelta = TimeDelta::hours(CHECK_INTERVAL_HOURS);
+    if last_checked.is_some_and(|timestamp| Utc::now() - timestamp < interval) {
+        return Ok(None)
+    }
+    fs::write(&path, Utc::now().to_rfc3339()).map_err(DotulousError::FailedCheckUpdate)?;
+
+    let contents: Vec<u8> = fetch(LATEST_RELEASE_URL, Some(CHECK_TIMEOUT_SECONDS)).map_err(DotulousError::FailedCheckUpdate)?;
+    let release: Release = serde_json::from_slice(&contents).map_err(DotulousError::FailedDeserializeRelease)?;
+    Ok(release.is_newer().then_some(release))
+}
+
+/// Returns what's at `url` using `curl`, giving up after `max_time` seconds if given.
+fn fetch(url: &str, max_time: Option<u64>) -> io::Result<Vec<u8>> {
+    let mut process: Command = Command::new("curl");
+    process.arg("--fail")
+        .arg("--silent")
+        .arg("--show-error")
+        .arg("--location");
+    if let Some(max_time) = max_time {
+        process.arg("--max-time").arg(max_time.to_string());
+    }
+    let output = process.arg(url).output()?;
+    if !output.status.success() {
+        let stderr: String = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(io::Error::other(format!("curl exited with {}: {stderr}", output.status)))
+    }
+    Ok(output.stdout)
+}
+
+/// Downloads what's at `url` to the file at `path` using `curl`.
+fn download(url: &str, path: &Path) -> Result<(), DotulousError> {
+    let status = Command::new("curl")
+        .arg("--fail")
+        .arg("--silent")
+        .arg("--show-error")
+        .arg("--location")
+        .arg("--output")
+        .arg(path)
+        .arg(url)
+        .status();
+    platform::check_status(status).map_err(DotulousError::FailedDownloadUpdate)
+}
+
+/// Verifies the file at `path` has the SHA-256 digest `expected`, as a lowercase hex string.
+///
+/// The returned [`Result`] does not return anything on success, meaning you should only check
+/// for [`Err`] variants.
+fn verify(path: &Path, expected: &str) -> Result<(), DotulousError> {
+    let contents: Vec<u8> = fs::read(path).map_err(DotulousError::FailedDownloadUpdate)?;
+    if format!("{:x}", Sha256::digest(contents)) != expected {
+        return Err(DotulousError::UpdateChecksumMismatch)
+    }
+    Ok(())
+}
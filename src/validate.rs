@@ -0,0 +1,145 @@
+use std::{error::Error, fmt::Display, path::{Component, Path}};
+
+use serde_json::Value;
+
+use crate::{asset::is_valid_sha256, condition::Condition, format::ManifestFormat, migrate::migrate_manifest, profile::DotfileProfile};
+
+/// A single problem found in a manifest by [`validate_manifest`] or [`missing_sources`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// Where in the manifest the problem is, as a JSON path (e.g. `files.bashrc.destination` or
+    /// `pre_commands[0]`). Empty if it's about the manifest as a whole.
+    pub path: String,
+    /// What the problem is.
+    pub message: String
+}
+impl Problem {
+    /// Creates a new `Problem` at `path` with the given `message`.
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { path: path.into(), message: message.into() }
+    }
+}
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Parses & checks the `contents` of a manifest in the given `format`, returning the profile if
+/// nothing is wrong with it, or every [`Problem`] found. Manifests from older versions of dotulous
+/// are upgraded first, see [`migrate_manifest`].
+///
+/// If the manifest doesn't match [`DotfileProfile`], the problem is reported at the exact path it
+/// happened at, such as a missing field or one with the wrong type. Otherwise this checks for;
+/// - Fields dotulous doesn't know about, which are otherwise silently ignored (e.g. typos).
+/// - Sources in `files` that aren't relative to the profile's folder.
+/// - Destinations that aren't relative to the profile's target folder, or that escape it with `..`,
+///   unless the profile has `allow_outside_home` set.
+/// - Destinations used by more than one file or asset, unless their `when` conditions differ.
+/// - Sources in `system_files` that aren't relative to the profile's folder, or destinations that
+///   aren't absolute.
+pub fn validate_manifest(contents: &str, format: ManifestFormat) -> Result<DotfileProfile, Vec<Problem>> {
+    let mut manifest: Value = match format.parse(contents) {
+        Ok(manifest) => manifest,
+        // Only the cause is useful here, rather than dotulous's own message
+        Err(e) => return Err(vec![Problem::new("", e.source().map_or_else(|| e.to_string(), ToString::to_string))])
+    };
+    if let Err(e) = migrate_manifest(&mut manifest) {
+        return Err(vec![Problem::new("manifest_version", e.to_string())])
+    }
+
+    let mut problems: Vec<Problem> = Vec::new();
+    let mut unknown_field = |path: serde_ignored::Path| {
+        problems.push(Problem::new(path.to_string(), "Unknown field"));
+    };
+    let ignored = serde_ignored::Deserializer::new(manifest, &mut unknown_field);
+    let parsed = serde_path_to_error::deserialize::<_, DotfileProfile>(ignored);
+    let profile: DotfileProfile = match parsed {
+        Ok(profile) => profile,
+        Err(e) => {
+            let path: String = e.path().to_string();
+            // The path is "." when the problem is with the manifest as a whole
+            let path: String = if path == "." { String::new() } else { path };
+            return Err(vec![Problem::new(path, e.into_inner().to_string())])
+        }
+    };
+
+    let mut destinations: Vec<(&Path, Option<&Condition>, String)> = Vec::new();
+    for (source, entries) in profile.files() {
+        for (index, entry) in entries.iter().enumerate() {
+            let path: String = if entries.len() == 1 {
+                format!("files.{}", source.to_string_lossy())
+            } else {
+                format!("files.{}[{index}]", source.to_string_lossy())
+            };
+            if !is_relative(source) {
+                problems.push(Problem::new(&path, "Source must be a path inside the profile's folder"));
+            }
+            if !profile.allow_outside_home && !is_relative(&entry.destination) {
+                problems.push(Problem::new(&path, format!("Destination {:?} must stay inside the target folder", entry.destination)));
+            }
+            destinations.push((&entry.destination, entry.when.as_ref(), path));
+        }
+    }
+    for (index, asset) in profile.assets().iter().enumerate() {
+        let path: String = format!("assets[{index}]");
+        if !profile.allow_outside_home && !is_relative(&asset.dest) {
+            problems.push(Problem::new(&path, format!("Destination {:?} must stay inside the target folder", asset.dest)));
+        }
+        if !is_valid_sha256(&asset.sha256) {
+            problems.push(Problem::new(format!("{path}.sha256"), "Must be a SHA-256 digest of 64 lowercase hex characters"));
+        }
+        destinations.push((&asset.dest, None, path));
+    }
+    for (index, directory) in profile.directories().iter().enumerate() {
+        if !profile.allow_outside_home && !is_relative(directory) {
+            problems.push(Problem::new(format!("directories[{index}]"), format!("Folder {directory:?} must stay inside the target folder")));
+        }
+    }
+    for (index, include) in profile.includes.iter().enumerate() {
+        if !is_relative(include) {
+            problems.push(Problem::new(format!("includes[{index}]"), format!("Folder {include:?} must be inside the profile's folder")));
+        }
+    }
+    for (source, destination) in profile.system_files() {
+        let path: String = format!("system_files.{}", source.to_string_lossy());
+        if !is_relative(source) {
+            problems.push(Problem::new(&path, "Source must be a path inside the profile's folder"));
+        }
+        if !destination.is_absolute() {
+            problems.push(Problem::new(&path, format!("Destination {destination:?} must be an absolute path")));
+        }
+    }
+    for (index, (destination, when, path)) in destinations.iter().enumerate() {
+        let duplicate = destinations[..index].iter().find(|(other, other_when, _)| other == destination && other_when == when);
+        if let Some((_, _, other)) = duplicate {
+            problems.push(Problem::new(path, format!("Destination {destination:?} is already used by {other}")));
+        }
+    }
+
+    if problems.is_empty() { Ok(profile) } else { Err(problems) }
+}
+
+/// Returns a [`Problem`] for every source in `profile`'s `files` or `system_files` that doesn't
+/// exist inside the profile's folder. Sources whose entries are all `optional` are left out.
+pub fn missing_sources(profile: &DotfileProfile) -> Vec<Problem> {
+    let files = profile.files().iter()
+        .filter(|(_, entries)| entries.iter().any(|entry| !entry.optional))
+        .map(|(source, _)| ("files", source));
+    let system_files = profile.system_files().keys().map(|source| ("system_files", source));
+    let mut missing: Vec<Problem> = files.chain(system_files)
+        .filter(|(_, source)| profile.repo_path.join(source).symlink_metadata().is_err())
+        .map(|(field, source)| Problem::new(format!("{field}.{}", source.to_string_lossy()), "Source doesn't exist in the profile's folder"))
+        .collect();
+    missing.sort_by(|a, b| a.path.cmp(&b.path));
+    missing
+}
+
+/// Returns if `path` is relative and never leaves the folder it's relative to.
+fn is_relative(path: &Path) -> bool {
+    path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
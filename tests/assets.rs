@@ -0,0 +1,61 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::TestEnv;
+use dotulous::{asset::Asset, error::DotulousError, format::ManifestFormat, validate};
+use serde_json::json;
+
+/// The SHA-256 digest of `"wallpaper"`.
+const WALLPAPER_SHA256: &str = "124962dc269749b652c6e23e063617da6c15a4061ba980fc8f0b1ba77292415c";
+
+fn asset(sha256: &str) -> Asset {
+    Asset { url: "https://example.com/wallpaper.png".to_string(), sha256: sha256.to_string(), dest: PathBuf::from("wallpaper.png") }
+}
+
+#[test]
+fn cached_assets_are_not_downloaded_again() {
+    let env = TestEnv::new();
+    let cache_path: PathBuf = env.dotulous.join("cache/assets");
+    fs::create_dir_all(&cache_path).unwrap();
+    fs::write(cache_path.join(WALLPAPER_SHA256), "wallpaper").unwrap();
+
+    assert_eq!(asset(WALLPAPER_SHA256).fetch(&cache_path).unwrap(), cache_path.join(WALLPAPER_SHA256));
+}
+
+#[test]
+fn invalid_asset_checksums_are_rejected() {
+    let env = TestEnv::new();
+    let cache_path: PathBuf = env.dotulous.join("cache/assets");
+    for sha256 in ["../../home/.bashrc", "abc123", &WALLPAPER_SHA256.to_uppercase(), &format!("{WALLPAPER_SHA256}0")] {
+        assert!(matches!(asset(sha256).cached_path(&cache_path), Err(DotulousError::InvalidAssetChecksum)), "{sha256}");
+        assert!(matches!(asset(sha256).fetch(&cache_path), Err(DotulousError::InvalidAssetChecksum)), "{sha256}");
+    }
+    assert!(!cache_path.exists());
+
+    env.write_manifest("assets", json!({"assets": [{"url": "https://example.com/a.png", "sha256": "abc123", "dest": "a.png"}]}));
+    let contents: String = fs::read_to_string(env.dotulous.join("assets/manifest.json")).unwrap();
+    let problems = validate::validate_manifest(&contents, ManifestFormat::Json).unwrap_err();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].path, "assets[0].sha256");
+}
+
+#[test]
+fn unloading_removes_assets_no_longer_used() {
+    let env = TestEnv::new();
+    let cache_path: PathBuf = env.dotulous.join("cache/assets");
+    fs::create_dir_all(&cache_path).unwrap();
+    fs::write(cache_path.join(WALLPAPER_SHA256), "wallpaper").unwrap();
+    let assets = json!([{"url": "https://example.com/wallpaper.png", "sha256": WALLPAPER_SHA256, "dest": "wallpaper.png"}]);
+    let first = env.profile("first", &[], json!({"assets": assets}));
+    let second = env.profile("second", &[], json!({"assets": assets}));
+
+    env.load(&first).unwrap();
+    env.load(&second).unwrap();
+    assert!(cache_path.join(WALLPAPER_SHA256).exists());
+    assert_eq!(fs::read_to_string(env.home_path("wallpaper.png")).unwrap(), "wallpaper");
+
+    env.unload().unwrap();
+    assert!(!cache_path.join(WALLPAPER_SHA256).exists());
+    env.assert_missing("wallpaper.png");
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use std::{path::PathBuf, thread, time::Duration};
+
+use common::TestEnv;
+use dotulous::{backup::{Backup, Retention}, operations, profile::{ApplyMode, Skip}};
+use serde_json::json;
+
+/// Returns the path of the loaded profile's backup, which is never pruned.
+fn in_use(env: &TestEnv) -> Option<PathBuf> {
+    env.meta().current_backup().map(PathBuf::from)
+}
+
+#[test]
+fn prune_deletes_backups_beyond_retention_but_not_the_one_in_use() {
+    let env = TestEnv::new();
+    let profile = env.profile("backed", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"}
+    }));
+    for i in 0..3 {
+        env.write_home_file(".bashrc", &format!("old {i}"));
+        env.load(&profile).unwrap();
+        env.unload().unwrap();
+        // Backups are named after the millisecond they're made in
+        thread::sleep(Duration::from_millis(5));
+    }
+    env.write_home_file(".bashrc", "in use");
+    env.load(&profile).unwrap();
+    let restored: Vec<Backup> = Backup::list(&env.dotulous, None).into_iter().filter(|backup| backup.restored).collect();
+    assert_eq!(restored.len(), 3);
+
+    // The backup in use counts as the one kept
+    let keep_two = Retention { keep: Some(2), ..Retention::default() };
+    let pruned = Backup::prune(&env.dotulous, &keep_two, in_use(&env).as_deref(), false, ApplyMode::DryRun).unwrap();
+    assert_eq!(pruned.iter().map(Backup::id).collect::<Vec<_>>(), restored[..2].iter().map(Backup::id).collect::<Vec<_>>());
+    assert_eq!(Backup::list(&env.dotulous, None).len(), 4);
+    Backup::prune(&env.dotulous, &keep_two, in_use(&env).as_deref(), false, ApplyMode::Apply).unwrap();
+    assert_eq!(Backup::list(&env.dotulous, None).len(), 2);
+
+    // The backup still holding "in use" is kept, however small the limit
+    let no_space = Retention { max_size_mb: Some(0), ..Retention::default() };
+    assert_eq!(Backup::prune(&env.dotulous, &no_space, in_use(&env).as_deref(), false, ApplyMode::Apply).unwrap().len(), 1);
+    let left = Backup::list(&env.dotulous, None);
+    assert!(matches!(left.as_slice(), [backup] if !backup.restored));
+    env.unload().unwrap();
+    env.assert_file(".bashrc", "in use");
+}
+
+#[test]
+fn backups_never_restored_are_only_pruned_when_asked() {
+    let env = TestEnv::new();
+    let profile = env.profile("backed", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"}
+    }));
+    env.write_home_file(".bashrc", &"x".repeat(64 * 1024));
+    env.load(&profile).unwrap();
+    // Keeping the files leaves the backup where it is, rather than restoring it
+    operations::unload(&env.dotulous, &env.config, &env.home, &env.settings, Skip { hooks: false, files: true }, ApplyMode::Apply, false).unwrap();
+    let backups: Vec<Backup> = Backup::list(&env.dotulous, None);
+    assert!(matches!(backups.as_slice(), [backup] if !backup.restored && backup.size() >= 64 * 1024));
+    assert!(in_use(&env).is_none());
+
+    let no_space = Retention { max_size_mb: Some(0), ..Retention::default() };
+    let keep_none = Retention { keep: Some(0), ..Retention::default() };
+    for retention in [no_space, keep_none] {
+        assert!(Backup::prune(&env.dotulous, &retention, None, false, ApplyMode::Apply).unwrap().is_empty());
+        assert!(backups[0].path.exists());
+    }
+    let pruned = Backup::prune(&env.dotulous, &no_space, None, true, ApplyMode::Apply).unwrap();
+    assert_eq!(pruned.len(), 1);
+    assert!(!backups[0].path.exists());
+}
+
+#[test]
+fn only_real_loads_prune_backups_on_their_own() {
+    let env = TestEnv::new();
+    env.profile("backed", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    env.write_home_file(".bashrc", "old");
+    assert!(env.run(&["load", "backed", "--yes"]).status.success());
+    assert!(env.run(&["unload", "--yes"]).status.success());
+    assert!(env.run(&["config", "set", "backup_retention", "0"]).status.success());
+
+    assert!(env.run(&["load", "backed", "--yes", "--dry-run"]).status.success());
+    assert!(env.run(&["backups", "prune", "--dry-run"]).status.success());
+    assert_eq!(Backup::list(&env.dotulous, None).len(), 1);
+
+    // Unrestored backups are left alone, only the restored one goes
+    env.write_home_file(".bashrc", "older");
+    assert!(env.run(&["load", "backed", "--yes"]).status.success());
+    let kept = env.run(&["unload", "--yes", "--keep-files"]);
+    assert!(env.run(&["trust", "backed"]).status.success());
+    let left: Vec<Backup> = Backup::list(&env.dotulous, None);
+    assert!(matches!(left.as_slice(), [backup] if !backup.restored));
+    let restore_with: String = format!("`dotulous backups restore {}`", left[0].id());
+    assert!(String::from_utf8_lossy(&kept.stdout).contains(&restore_with));
+
+    assert!(!env.run(&["backups", "prune"]).status.success());
+    assert!(env.run(&["backups", "prune", "--yes"]).status.success());
+    assert!(Backup::list(&env.dotulous, None).is_empty());
+}
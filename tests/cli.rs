@@ -0,0 +1,219 @@
+mod common;
+
+use std::{fs, process::Output};
+
+use common::TestEnv;
+use serde_json::{json, Value};
+
+/// Returns what dotulous printed to stdout as JSON, failing if anything else was printed there.
+fn stdout_json(output: &Output) -> Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|e| panic!("stdout isn't only JSON ({e}): {}", String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Returns what dotulous printed to stdout.
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn json_results_are_the_only_thing_on_stdout() {
+    let env = TestEnv::new();
+    env.profile("simple", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    assert!(env.run(&["load", "simple", "--yes"]).status.success());
+
+    let status = stdout_json(&env.run(&["status", "--json"]));
+    assert_eq!(status["current_profile"], "simple");
+    assert_eq!(status["files"][0]["health"], "ok");
+
+    let failed = env.run(&["load", "missing", "--json"]);
+    assert_eq!(failed.status.code(), Some(4));
+    assert_eq!(stdout_json(&failed)["exit_code"], 4);
+
+    assert!(env.run(&["config", "set", "timeout", "5"]).status.success());
+    assert_eq!(stdout_json(&env.run(&["config", "get", "timeout", "--json"])), json!({"timeout": 5}));
+    assert_eq!(stdout_json(&env.run(&["config", "get", "--json"]))["timeout"], 5);
+}
+
+#[cfg(unix)]
+#[test]
+fn programs_ran_by_dotulous_print_to_stderr_with_json() {
+    let env = TestEnv::new();
+    env.profile("updated", &[], json!({"update_command": "echo pulled"}));
+
+    let output = env.run(&["update", "updated", "--yes", "--json"]);
+    assert!(output.status.success());
+    assert!(!stdout(&output).contains("pulled"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pulled"));
+}
+
+#[test]
+fn exit_codes_say_what_went_wrong() {
+    let env = TestEnv::new();
+    env.profile("simple", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    env.write_manifest("untrusted", json!({"files": {}}));
+    fs::create_dir_all(env.dotulous.join("broken")).unwrap();
+    fs::write(env.dotulous.join("broken").join("manifest.json"), "{").unwrap();
+
+    assert_eq!(env.run(&["load", "missing", "--yes"]).status.code(), Some(4));
+    assert_eq!(env.run(&["unload", "--yes"]).status.code(), Some(4));
+    // Trusting the profile would need an answer, which can't be given
+    assert_eq!(env.run(&["load", "untrusted", "--yes"]).status.code(), Some(3));
+    assert_eq!(env.run(&["validate", "broken"]).status.code(), Some(7));
+    assert_eq!(env.run(&["load", "simple", "--yes"]).status.code(), Some(0));
+}
+
+#[test]
+fn changes_need_confirming_unless_told_yes() {
+    let env = TestEnv::new();
+    env.profile("simple", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+
+    let output = env.run(&["load", "simple"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--yes"));
+    env.assert_missing(".bashrc");
+
+    assert!(env.run(&["load", "simple", "--yes"]).status.success());
+    env.assert_linked(".bashrc", "simple", "bashrc");
+}
+
+#[cfg(unix)]
+#[test]
+fn files_and_hooks_can_be_left_alone_separately() {
+    let env = TestEnv::new();
+    env.profile("hooked", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": ["touch ran"],
+        "removal_commands": ["touch removed"]
+    }));
+    assert!(env.run(&["load", "hooked", "--yes", "--no-hooks"]).status.success());
+    env.assert_linked(".bashrc", "hooked", "bashrc");
+    env.assert_missing("ran");
+
+    // The commands are still ran, only the files are left alone
+    assert!(env.run(&["unload", "--yes", "--keep-files"]).status.success());
+    env.assert_linked(".bashrc", "hooked", "bashrc");
+    env.assert_file("removed", "");
+    assert!(env.meta().current_profile().is_none());
+}
+
+#[test]
+fn show_lists_everything_about_a_profile() {
+    let env = TestEnv::new();
+    env.profile("base", &[("gitconfig", "[user]")], json!({"files": {"gitconfig": ".gitconfig"}}));
+    env.profile("shown", &[("bashrc", "echo hi")], json!({
+        "extends": "base",
+        "files": {"bashrc": ".bashrc", "vimrc": {"destination": ".vimrc", "tags": ["editor"]}},
+        "post_commands": ["echo loaded"],
+        "env": {"EDITOR": "vim"}
+    }));
+
+    let shown = stdout_json(&env.run(&["show", "shown", "--json"]));
+    assert_eq!(shown["name"], "shown");
+    assert_eq!(shown["trust"], "trusted");
+    assert_eq!(shown["loaded"], false);
+    assert_eq!(shown["extends"], json!(["base"]));
+    assert_eq!(shown["env"], json!({"EDITOR": "vim"}));
+    assert_eq!(shown["hooks"]["post_commands"], json!(["echo loaded"]));
+    let files: Vec<(&str, bool)> = shown["files"].as_array().unwrap().iter()
+        .map(|file| (file["destination"].as_str().unwrap(), file["exists"].as_bool().unwrap()))
+        .collect();
+    assert_eq!(files, [(".gitconfig", true), (".bashrc", true), (".vimrc", false)]);
+
+    assert_eq!(env.run(&["show", "missing"]).status.code(), Some(4));
+}
+
+#[test]
+fn settings_can_be_changed_and_read_back() {
+    let env = TestEnv::new();
+    assert!(env.run(&["config", "set", "timeout", "5"]).status.success());
+    assert_eq!(stdout(&env.run(&["config", "get", "timeout"])).trim(), "5");
+
+    // Nothing is changed by a setting that doesn't exist or a value of the wrong type
+    assert!(!env.run(&["config", "set", "timout", "5"]).status.success());
+    assert!(!env.run(&["config", "set", "timeout", "soon"]).status.success());
+    assert_eq!(stdout(&env.run(&["config", "get", "timeout"])).trim(), "5");
+}
+
+#[cfg(unix)]
+#[test]
+fn named_hooks_are_ran_on_their_own() {
+    let env = TestEnv::new();
+    env.profile("hooked", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": ["touch post"],
+        "hooks": {"refresh": ["touch refreshed"]}
+    }));
+
+    assert!(env.run(&["run", "hooked", "refresh"]).status.success());
+    env.assert_file("refreshed", "");
+    env.assert_missing("post");
+    env.assert_missing(".bashrc");
+    assert!(!env.run(&["run", "hooked", "missing"]).status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn dangling_links_into_the_dotulous_folder_are_cleaned() {
+    let env = TestEnv::new();
+    let outside = env.home_path("elsewhere");
+    std::os::unix::fs::symlink(env.dotulous.join("deleted/bashrc"), env.home_path(".bashrc")).unwrap();
+    std::os::unix::fs::symlink(&outside, env.home_path(".vimrc")).unwrap();
+
+    assert!(env.run(&["clean", "--yes"]).status.success());
+    env.assert_missing(".bashrc");
+    // Not dotulous's to remove
+    assert_eq!(fs::read_link(env.home_path(".vimrc")).unwrap(), outside);
+}
+
+#[cfg(unix)]
+#[test]
+fn home_files_are_adopted_into_profiles() {
+    let env = TestEnv::new();
+    env.write_home_file(".bashrc", "my own bashrc");
+    env.write_home_file(".config/nvim/init.lua", "-- my own nvim");
+    assert!(env.run(&["create", "adopted", "--from-home", ".bashrc", "--yes"]).status.success());
+    env.assert_linked(".bashrc", "adopted", ".bashrc");
+    assert_eq!(fs::read_to_string(env.dotulous.join("adopted/.bashrc")).unwrap(), "my own bashrc");
+
+    let nvim = env.home_path(".config/nvim");
+    assert!(env.run(&["adopt", nvim.to_str().unwrap(), "--yes"]).status.success());
+    env.assert_linked(".config/nvim", "adopted", ".config/nvim");
+    let manifest: Value = serde_json::from_str(&fs::read_to_string(env.dotulous.join("adopted/manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["files"], json!({".bashrc": ".bashrc", ".config/nvim": ".config/nvim"}));
+
+    // Adopting something that doesn't exist changes nothing
+    let missing = env.home_path(".missing");
+    assert!(!env.run(&["adopt", missing.to_str().unwrap(), "--yes"]).status.success());
+    env.unload().unwrap();
+    env.assert_missing(".bashrc");
+}
+
+#[test]
+fn the_dashboard_needs_a_terminal() {
+    let env = TestEnv::new();
+    let output = env.run(&["tui"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("interactive terminal"));
+}
+
+#[test]
+fn auto_fill_skips_ignored_files() {
+    let env = TestEnv::new();
+    env.profile("filled", &[("bashrc", "echo hi"), ("README.md", "# dotfiles"), (".git/config", "[core]"), ("notes.txt", "todo"), (".dotulousignore", "*.txt")], json!({}));
+
+    assert!(env.run(&["auto-fill", "filled"]).status.success());
+    let manifest: Value = serde_json::from_str(&fs::read_to_string(env.dotulous.join("filled/manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["files"], json!({"bashrc": "bashrc"}));
+}
+
+#[cfg(unix)]
+#[test]
+fn profiles_are_updated_with_their_own_command() {
+    let env = TestEnv::new();
+    env.profile("updated", &[], json!({"update_command": "touch pulled"}));
+    env.profile("stale", &[], json!({"update_command": "exit 1"}));
+
+    assert!(env.run(&["update", "updated", "--yes"]).status.success());
+    assert!(env.dotulous.join("updated/pulled").exists());
+    assert!(!env.run(&["update", "stale", "--yes"]).status.success());
+}
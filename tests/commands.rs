@@ -0,0 +1,200 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, thread, time::Duration};
+
+use common::TestEnv;
+use dotulous::{audit, error::DotulousError, logs};
+use serde_json::json;
+
+#[test]
+fn timed_out_commands_are_killed_with_everything_they_started() {
+    let env = TestEnv::new();
+    let profile = env.profile("slow", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": [{"command": "(sleep 2; touch {{home}}/late) & sleep 2", "timeout": 1}]
+    }));
+
+    assert!(matches!(env.load(&profile), Err(DotulousError::FailedRunCommand(_))));
+    thread::sleep(Duration::from_secs(3));
+    env.assert_missing("late");
+}
+
+#[test]
+fn commands_use_the_timeout_from_the_settings_in_use() {
+    let env = TestEnv::new();
+    env.profile("slow", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "pre_commands": ["sleep 3"]
+    }));
+
+    assert!(env.run(&["config", "set", "timeout", "1"]).status.success());
+    let output = env.run(&["load", "slow", "--yes"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("timed out after 1s"));
+}
+
+#[test]
+fn failed_post_command_rolls_back() {
+    let env = TestEnv::new();
+    let profile = env.profile("failing", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": ["exit 1"]
+    }));
+
+    assert!(matches!(env.load(&profile), Err(DotulousError::FailedRunCommand(_))));
+    env.assert_missing(".bashrc");
+    assert!(env.meta().current_profile().is_none());
+}
+
+#[test]
+fn commands_are_recorded_in_the_audit_log() {
+    let env = TestEnv::new();
+    let profile = env.profile("audited", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "pre_commands": ["true"],
+        "removal_commands": ["exit 3"]
+    }));
+    env.load(&profile).unwrap();
+    env.unload().unwrap();
+
+    let entries = audit::read(&env.dotulous).unwrap();
+    let recorded: Vec<(&str, Option<i32>)> = entries.iter().map(|entry| (entry.command.as_str(), entry.exit_code)).collect();
+    assert_eq!(recorded, [("true", Some(0)), ("exit 3", Some(3))]);
+    assert!(entries.iter().all(|entry| entry.profile_name == "audited" && entry.manifest_hash == profile.manifest_hash().unwrap()));
+    let output = env.run(&["audit", "--failed"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("exit 3"));
+}
+
+#[test]
+fn command_output_is_kept_in_logs() {
+    let env = TestEnv::new();
+    let profile = env.profile("logged", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "pre_commands": ["echo first; echo second >&2"],
+        "removal_commands": ["echo failing; exit 3"]
+    }));
+    env.load(&profile).unwrap();
+    env.unload().unwrap();
+
+    let logs = logs::list(&env.dotulous, Some("logged")).unwrap();
+    let logged: Vec<(&str, Option<i32>)> = logs.iter().map(|log| (log.command.as_str(), log.exit_code)).collect();
+    assert_eq!(logged, [("echo failing; exit 3", Some(3)), ("echo first; echo second >&2", Some(0))]);
+    assert_eq!(logs[0].tail(10).unwrap(), ["failing"]);
+    let mut output = logs[1].tail(10).unwrap();
+    output.sort();
+    assert_eq!(output, ["first", "second"]);
+    let output = env.run(&["logs", "logged"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("echo failing; exit 3"));
+}
+
+#[test]
+fn commands_have_variables_filled_in() {
+    let env = TestEnv::new();
+    let profile = env.profile("vars", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "variables": {"greeting": "it's; touch pwned"},
+        "post_commands": ["echo {{ greeting }} from {{profile_name}} > {{home}}/greeting"],
+        "removal_commands": ["cp {{profile_dir}}/bashrc {{home}}/copied"]
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_file("greeting", "it's; touch pwned from vars\n");
+    env.assert_missing("pwned");
+    env.unload().unwrap();
+    env.assert_file("copied", "echo hi");
+}
+
+#[test]
+fn host_variables_come_from_the_config_folder_in_use() {
+    let env = TestEnv::new();
+    let profile = env.profile("host", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "variables": {"greeting": "hello"},
+        "post_commands": ["echo {{greeting}} > {{home}}/greeting"]
+    }));
+    fs::write(env.config.join("vars.json"), r#"{"greeting": "howdy"}"#).unwrap();
+
+    env.load(&profile).unwrap();
+    env.assert_file("greeting", "howdy\n");
+}
+
+#[test]
+fn commands_get_the_profiles_environment() {
+    let env = TestEnv::new();
+    let profile = env.profile("env", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "env": {"GREETING": "hello"},
+        "post_commands": ["echo $GREETING > greeting"]
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_file("greeting", "hello\n");
+}
+
+#[test]
+fn commands_only_run_where_their_condition_matches() {
+    let env = TestEnv::new();
+    let profile = env.profile("conditional", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": [
+            {"command": "touch has-sh", "when": {"command_exists": "sh"}},
+            {"command": "touch has-nothing", "when": {"command_exists": "dotulous-command-that-does-not-exist"}},
+            {"command": "touch elsewhere", "when": {"hostname": "dotulous-host-that-does-not-exist"}}
+        ]
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_file("has-sh", "");
+    env.assert_missing("has-nothing");
+    env.assert_missing("elsewhere");
+}
+
+#[test]
+fn failing_commands_can_be_carried_on_past() {
+    let env = TestEnv::new();
+    let profile = env.profile("forgiving", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": [{"command": "exit 1", "on_error": "continue"}, "touch after"]
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "forgiving", "bashrc");
+    env.assert_file("after", "");
+}
+
+#[test]
+fn commands_can_use_another_shell() {
+    let env = TestEnv::new();
+    let profile = env.profile("shelled", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "shell": ["env", "SHELLED=profile", "sh", "-c"],
+        "post_commands": [
+            "echo $SHELLED > profile",
+            {"command": "echo $SHELLED > command", "shell": ["env", "SHELLED=command", "sh", "-c"]}
+        ]
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_file("profile", "profile\n");
+    env.assert_file("command", "command\n");
+}
+
+#[test]
+fn interrupted_loads_stop_before_the_next_command_and_roll_back() {
+    let env = TestEnv::new();
+    env.profile("slow", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": ["sleep 1", "touch after"]
+    }));
+    let mut child = env.command(&["load", "slow", "--yes"]).spawn().unwrap();
+    thread::sleep(Duration::from_millis(500));
+    let killed = std::process::Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    assert!(killed.success());
+
+    assert_eq!(child.wait().unwrap().code(), Some(130));
+    env.assert_missing("after");
+    env.assert_missing(".bashrc");
+    assert!(env.meta().current_profile().is_none());
+}
@@ -0,0 +1,182 @@
+//! Support for the integration tests, giving each test it's own fake home & dotulous folders to
+//! load profiles into.
+
+// Each test file only uses some of the helpers
+#![allow(dead_code)]
+
+use std::{env, fs, path::{Path, PathBuf}, process::{self, Command, Output}, sync::atomic::{AtomicUsize, Ordering}};
+
+use dotulous::{error::DotulousError, filter::TagFilter, meta::{Meta, TrustScope}, operations, profile::{ApplyMode, DotfileProfile, OnConflict, Skip}, settings::Settings};
+use serde_json::{json, Value};
+
+/// How many [`TestEnv`]s have been made by this test binary, so each gets it's own folder.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A fake home folder & dotulous folder inside the system's temporary folder, removed once
+/// dropped.
+pub struct TestEnv {
+    /// The *absolute* path to the folder holding everything else.
+    root: PathBuf,
+    /// The *absolute* path to the fake home folder.
+    pub home: PathBuf,
+    /// The *absolute* path to the fake dotulous folder.
+    pub dotulous: PathBuf,
+    /// The *absolute* path to the fake config folder, being the dotulous folder like with
+    /// `--dotulous-dir`.
+    pub config: PathBuf,
+    /// The user's settings profiles are loaded with, left as the defaults.
+    pub settings: Settings
+}
+impl TestEnv {
+    /// Creates a new, empty home folder & dotulous folder with a fresh meta.
+    pub fn new() -> Self {
+        let id: usize = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let root: PathBuf = env::temp_dir().join(format!("dotulous-test-{}-{id}", process::id()));
+        if root.exists() {
+            fs::remove_dir_all(&root).expect("failed to clear old test folder");
+        }
+        let home: PathBuf = root.join("home");
+        let dotulous: PathBuf = root.join("dotulous");
+        fs::create_dir_all(&home).expect("failed to create test home folder");
+        fs::create_dir_all(&dotulous).expect("failed to create test dotulous folder");
+        Meta::new().save_meta(&dotulous).expect("failed to save test meta");
+        Self { root, home, config: dotulous.clone(), dotulous, settings: Settings::default() }
+    }
+
+    /// Creates a profile called `name` inside the dotulous folder, with a file for each of
+    /// `files` (the path inside the profile & it's contents) and the given `manifest`. The
+    /// manifest only needs the fields the test cares about, such as `files`, everything else is
+    /// filled in.
+    ///
+    /// The profile is trusted, see [`TestEnv::trust`].
+    pub fn profile(&self, name: &str, files: &[(&str, &str)], manifest: Value) -> DotfileProfile {
+        let repo_path: PathBuf = self.dotulous.join(name);
+        for (path, contents) in files {
+            write_file(&repo_path.join(path), contents);
+        }
+        self.write_manifest(name, manifest);
+        let profile: DotfileProfile = DotfileProfile::find_profile(&self.dotulous, name).expect("failed to read test profile");
+        self.trust(&profile);
+        profile
+    }
+
+    /// Writes the manifest of the profile called `name`, filling in any fields `manifest` leaves
+    /// out. Unlike [`TestEnv::profile`], the profile isn't trusted again.
+    pub fn write_manifest(&self, name: &str, manifest: Value) {
+        let repo_path: PathBuf = self.dotulous.join(name);
+        let mut full: Value = json!({
+            "name": name,
+            "manifest_path": repo_path.join("manifest.json"),
+            "repo_path": repo_path,
+            "files": {},
+            "pre_commands": [],
+            "post_commands": [],
+            "removal_commands": [],
+            "assets": [],
+            "packages": {}
+        });
+        if let (Some(full), Value::Object(fields)) = (full.as_object_mut(), manifest) {
+            full.extend(fields);
+        }
+        write_file(&repo_path.join("manifest.json"), &full.to_string());
+    }
+
+    /// Trusts `profile` with it's current manifest, to link it's files & run it's commands.
+    pub fn trust(&self, profile: &DotfileProfile) {
+        self.trust_as(profile, TrustScope::Commands);
+    }
+
+    /// Trusts `profile` with it's current manifest as far as `scope`.
+    pub fn trust_as(&self, profile: &DotfileProfile, scope: TrustScope) {
+        let mut meta: Meta = self.meta();
+        meta.trust_profile(profile.repo_path.clone(), profile.manifest_hash().expect("failed to hash test profile"), profile.version.clone(), scope);
+        meta.save_meta(&self.dotulous).expect("failed to save test meta");
+    }
+
+    /// Returns the meta inside the dotulous folder.
+    pub fn meta(&self) -> Meta {
+        Meta::load_meta(&self.dotulous).expect("failed to load test meta")
+    }
+
+    /// Loads `profile` with [`operations::load`], backing up anything in the way.
+    pub fn load(&self, profile: &DotfileProfile) -> Result<(), DotulousError> {
+        operations::load(&self.dotulous, &self.config, &self.home, &self.settings, profile, false, &TagFilter::default(), Skip::default(), ApplyMode::Apply, OnConflict::Backup, false, None)
+    }
+
+    /// Unloads the loaded profile with [`operations::unload`].
+    pub fn unload(&self) -> Result<DotfileProfile, DotulousError> {
+        operations::unload(&self.dotulous, &self.config, &self.home, &self.settings, Skip::default(), ApplyMode::Apply, false)
+    }
+
+    /// Reloads the loaded profile with [`operations::reload`], backing up anything in the way.
+    pub fn reload(&self) -> Result<DotfileProfile, DotulousError> {
+        operations::reload(&self.dotulous, &self.config, &self.home, &self.settings, Skip::default(), ApplyMode::Apply, OnConflict::Backup, false, false, None)
+    }
+
+    /// Runs the dotulous binary with `args`, using the fake home & dotulous folders and never
+    /// asking anything.
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().expect("failed to run dotulous")
+    }
+
+    /// Returns a [`Command`] for running the dotulous binary like [`TestEnv::run`], for tests
+    /// that need to change it's environment first. `$DOTULOUS_HOME`, `$XDG_DATA_HOME` &
+    /// `$XDG_CONFIG_HOME` are cleared, so nothing of the real user's is used.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command: Command = Command::new(env!("CARGO_BIN_EXE_dotulous"));
+        command.arg("--home").arg(&self.home)
+            .arg("--dotulous-dir").arg(&self.dotulous)
+            .arg("--non-interactive")
+            .args(args)
+            .env_remove("DOTULOUS_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("XDG_CONFIG_HOME");
+        command
+    }
+
+    /// Returns the *absolute* path of `path` inside the home folder.
+    pub fn home_path(&self, path: &str) -> PathBuf {
+        self.home.join(path)
+    }
+
+    /// Writes `contents` to `path` inside the home folder, creating any folders it's in.
+    pub fn write_home_file(&self, path: &str, contents: &str) {
+        write_file(&self.home_path(path), contents);
+    }
+
+    /// Asserts that `path` inside the home folder is a symlink to `source` inside the profile
+    /// called `profile_name`.
+    pub fn assert_linked(&self, path: &str, profile_name: &str, source: &str) {
+        let destination: PathBuf = self.home_path(path);
+        let target: PathBuf = fs::read_link(&destination).unwrap_or_else(|e| panic!("{destination:?} isn't a symlink: {e}"));
+        assert_eq!(target, self.dotulous.join(profile_name).join(source), "{destination:?} links to the wrong file");
+    }
+
+    /// Asserts that nothing exists at `path` inside the home folder, not even a broken symlink.
+    pub fn assert_missing(&self, path: &str) {
+        let destination: PathBuf = self.home_path(path);
+        assert!(destination.symlink_metadata().is_err(), "{destination:?} exists");
+    }
+
+    /// Asserts that `path` inside the home folder is a real file (not a symlink) containing
+    /// `contents`.
+    pub fn assert_file(&self, path: &str, contents: &str) {
+        let destination: PathBuf = self.home_path(path);
+        assert!(!destination.is_symlink(), "{destination:?} is a symlink");
+        let actual: String = fs::read_to_string(&destination).unwrap_or_else(|e| panic!("failed to read {destination:?}: {e}"));
+        assert_eq!(actual, contents, "{destination:?} has the wrong contents");
+    }
+}
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Writes `contents` to the file at `path`, creating any folders it's in.
+fn write_file(path: &Path, contents: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("failed to create test folder");
+    }
+    fs::write(path, contents).expect("failed to write test file");
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, inspect::{self, DestinationIndex}, preflight};
+use serde_json::json;
+
+#[cfg(unix)]
+#[test]
+fn destinations_inside_the_dotulous_folder_are_refused() {
+    let env = TestEnv::new();
+    let profile = env.profile("reckless", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc", ".": "../dotulous/reckless"},
+        "allow_outside_home": true,
+        "pre_commands": ["touch ran"]
+    }));
+
+    assert!(matches!(env.load(&profile), Err(DotulousError::DestinationInsideDotulous)));
+    env.assert_missing("ran");
+    env.assert_missing(".bashrc");
+    assert!(env.dotulous.join("reckless").join("manifest.json").exists());
+}
+
+#[test]
+fn a_file_is_linked_to_every_destination() {
+    let env = TestEnv::new();
+    let profile = env.profile("targets", &[("starship.toml", "format = '$all'")], json!({
+        "files": {"starship.toml": [".config/starship.toml", {"destination": ".config/other/starship.toml", "strategy": "copy"}]}
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".config/starship.toml", "targets", "starship.toml");
+    env.assert_file(".config/other/starship.toml", "format = '$all'");
+    let loaded = env.meta().current_profile().unwrap();
+    assert!(inspect::loaded_drift(&loaded, &env.dotulous, &env.home).is_empty());
+    assert_eq!(inspect::link_health(&loaded, &env.dotulous, &env.home).len(), 2);
+
+    env.unload().unwrap();
+    env.assert_missing(".config/starship.toml");
+    env.assert_missing(".config/other/starship.toml");
+}
+
+#[test]
+fn destinations_shared_with_trusted_profiles_are_found() {
+    let env = TestEnv::new();
+    let home = env.profile("home", &[("bashrc", "echo home"), ("nvim/init.lua", "-- home")], json!({
+        "files": {"bashrc": ".bashrc", "nvim": ".config/nvim"}
+    }));
+    env.profile("work", &[("bashrc", "echo work"), ("init.lua", "-- work")], json!({
+        "files": {"bashrc": ".bashrc", "init.lua": ".config/nvim/init.lua"}
+    }));
+    // Never trusted, so it's left out
+    env.write_manifest("stranger", json!({"files": {"bashrc": ".bashrc"}}));
+
+    let index = DestinationIndex::build(&env.dotulous, &env.home, &env.meta()).unwrap();
+    let shared: Vec<(String, PathBuf)> = index.shared_with(&home, &env.home).into_iter()
+        .map(|shared| (shared.profile, shared.other_destination))
+        .collect();
+    assert_eq!(shared, [
+        ("work".to_string(), env.home_path(".bashrc")),
+        ("work".to_string(), env.home_path(".config/nvim/init.lua"))
+    ]);
+}
+
+#[test]
+fn destinations_can_start_from_the_home_folder() {
+    let env = TestEnv::new();
+    let profile = env.profile("expanded", &[("bashrc", "echo hi"), ("init.lua", "-- nvim"), ("gitconfig", "[user]")], json!({
+        "target_root": "target",
+        "files": {"bashrc": "~/.bashrc", "init.lua": "$XDG_CONFIG_HOME/nvim/init.lua", "gitconfig": "${HOME}/.gitconfig"}
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "expanded", "bashrc");
+    env.assert_linked(".config/nvim/init.lua", "expanded", "init.lua");
+    env.assert_linked(".gitconfig", "expanded", "gitconfig");
+    let loaded = env.meta().current_profile().unwrap();
+    assert!(inspect::loaded_drift(&loaded, &env.dotulous, &env.home).is_empty());
+
+    env.unload().unwrap();
+    env.assert_missing(".bashrc");
+    env.assert_missing(".config/nvim/init.lua");
+    env.assert_missing(".gitconfig");
+}
+
+#[test]
+fn every_preflight_problem_is_found_before_loading() {
+    let env = TestEnv::new();
+    env.write_home_file(".config", "not a folder");
+    let profile = env.profile("broken", &[("bashrc", "echo hi"), ("init.lua", "-- nvim")], json!({
+        "files": {"bashrc": ".bashrc", "init.lua": ".config/nvim/init.lua", "gone": ".gone", "also-gone": ".also-gone"}
+    }));
+
+    let problems = preflight::check(&profile, &env.home);
+    assert_eq!(problems, [
+        preflight::Problem::MissingSource { source: profile.repo_path.join("also-gone") },
+        preflight::Problem::MissingSource { source: profile.repo_path.join("gone") },
+        preflight::Problem::NotAFolder { path: env.home_path(".config") }
+    ]);
+    assert!(matches!(env.load(&profile), Err(DotulousError::PreflightFailed)));
+    env.assert_missing(".bashrc");
+    assert!(env.meta().current_profile().is_none());
+}
+
+#[test]
+fn system_files_are_never_loaded() {
+    let env = TestEnv::new();
+    let system_path = env.home_path("etc/keyd/default.conf");
+    let profile = env.profile("system", &[("bashrc", "echo hi"), ("keyd.conf", "[main]")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "system_files": {"keyd.conf": system_path}
+    }));
+    assert_eq!(profile.system_files().get(Path::new("keyd.conf")), Some(&system_path));
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "system", "bashrc");
+    env.assert_missing("etc/keyd/default.conf");
+    env.unload().unwrap();
+    env.assert_missing("etc");
+}
+
+#[test]
+fn destinations_cant_escape_the_home_folder() {
+    let env = TestEnv::new();
+    for (name, manifest) in [
+        ("relative", json!({"files": {"bashrc": "../escaped"}})),
+        ("home", json!({"files": {"bashrc": "~/../escaped"}})),
+        ("target", json!({"files": {"bashrc": "escaped"}, "target_root": ".."})),
+        ("folder", json!({"files": {"bashrc": ".bashrc"}, "directories": ["../escaped"]}))
+    ] {
+        let profile = env.profile(name, &[("bashrc", "echo hi")], manifest);
+        assert!(matches!(env.load(&profile), Err(DotulousError::DestinationOutsideHome)), "{name}");
+        assert!(env.home.parent().unwrap().join("escaped").symlink_metadata().is_err(), "{name}");
+        env.assert_missing(".bashrc");
+    }
+}
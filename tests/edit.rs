@@ -0,0 +1,35 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, os::unix::fs::{symlink, PermissionsExt}, path::PathBuf};
+
+use common::TestEnv;
+use serde_json::json;
+
+#[test]
+fn manifests_are_edited_in_a_new_private_file() {
+    let env = TestEnv::new();
+    env.profile("edited", &[], json!({}));
+    let runtime_dir: PathBuf = env.home_path("runtime");
+    fs::create_dir_all(&runtime_dir).unwrap();
+    // Something already sitting where the first copy would go must never be written through
+    env.write_home_file("victim", "untouched");
+    symlink(env.home_path("victim"), runtime_dir.join(".dotulous-edit-edited-1-manifest.json")).unwrap();
+
+    let editor: PathBuf = env.home_path("editor.sh");
+    let record: PathBuf = env.home_path("edited-path");
+    fs::write(&editor, format!("#!/bin/sh\nprintf '%s' \"$1\" > {record:?}\n[ ! -L \"$1\" ] && [ \"$(stat -c %a \"$1\")\" = 600 ]\n")).unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = env.command(&["edit", "edited"])
+        .env("VISUAL", &editor)
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let edited: PathBuf = PathBuf::from(fs::read_to_string(&record).unwrap());
+    assert_eq!(edited, runtime_dir.join(".dotulous-edit-edited-2-manifest.json"));
+    assert!(!edited.exists());
+    env.assert_file("victim", "untouched");
+}
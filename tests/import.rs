@@ -0,0 +1,83 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::TestEnv;
+use dotulous::{archive, error::DotulousError, remote::Remote, stow};
+use serde_json::json;
+
+#[test]
+fn stow_directories_are_imported_to_the_same_places() {
+    let env = TestEnv::new();
+    let stow_path = env.home.join("dotfiles");
+    for (path, contents) in [("nvim/.config/nvim/init.lua", "-- nvim"), ("bash/dot-bashrc", "echo hi"), ("bash/.stow-local-ignore", "README"), ("README.md", "# dotfiles")] {
+        let path = stow_path.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    let profile = stow::import(&env.dotulous, &stow_path, None).unwrap();
+    assert_eq!(profile.name, "dotfiles");
+    let destinations: Vec<(&Path, &Path)> = profile.entries().map(|(source, entry)| (source.as_path(), entry.destination.as_path())).collect();
+    assert_eq!(destinations, [
+        (Path::new("bash/dot-bashrc"), Path::new(".bashrc")),
+        (Path::new("nvim/.config/nvim/init.lua"), Path::new(".config/nvim/init.lua"))
+    ]);
+    assert!(matches!(stow::import(&env.dotulous, &stow_path, None), Err(DotulousError::ProfileAlreadyExists)));
+
+    env.trust(&profile);
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "dotfiles", "bash/dot-bashrc");
+    env.assert_linked(".config/nvim/init.lua", "dotfiles", "nvim/.config/nvim/init.lua");
+}
+
+#[test]
+fn exported_profiles_are_imported_untrusted() {
+    let env = TestEnv::new();
+    let profile = env.profile("shared", &[("bashrc", "echo hi"), ("nvim/init.lua", "-- nvim")], json!({
+        "files": {"bashrc": ".bashrc", "nvim": ".config/nvim"}
+    }));
+    let archive = env.home_path("shared.tar.gz");
+    archive::export(&profile, &archive).unwrap();
+
+    let other = TestEnv::new();
+    let imported = archive::import(&other.dotulous, &archive).unwrap();
+    assert_eq!(imported.name, "shared");
+    assert_eq!(imported.manifest_hash().unwrap(), profile.manifest_hash().unwrap());
+    assert_eq!(fs::read_to_string(other.dotulous.join("shared/nvim/init.lua")).unwrap(), "-- nvim");
+    assert!(!other.meta().is_path_trusted(&imported.repo_path));
+    assert!(matches!(archive::import(&other.dotulous, &archive), Err(DotulousError::ProfileAlreadyExists)));
+}
+
+#[test]
+fn archives_without_a_profile_are_refused() {
+    let env = TestEnv::new();
+    let empty = env.profile("empty", &[("bashrc", "echo hi")], json!({}));
+    fs::remove_file(&empty.manifest_path).unwrap();
+    let archive = env.home_path("empty.tar.gz");
+    archive::export(&empty, &archive).unwrap();
+    fs::write(env.home_path("broken.tar.gz"), "not an archive").unwrap();
+
+    let other = TestEnv::new();
+    assert!(matches!(archive::import(&other.dotulous, &archive), Err(DotulousError::NoManifestInProfile)));
+    assert!(matches!(archive::import(&other.dotulous, &env.home_path("broken.tar.gz")), Err(DotulousError::FailedReadArchive(_))));
+    assert!(!other.dotulous.join("empty").exists());
+}
+
+#[test]
+fn remotes_are_found_from_shorthands() {
+    let remotes = [
+        ("github:user/repo", Remote::Git("https://github.com/user/repo.git".to_string())),
+        ("gitlab:user/repo.git", Remote::Git("https://gitlab.com/user/repo.git".to_string())),
+        ("codeberg:user/repo/", Remote::Git("https://codeberg.org/user/repo.git".to_string())),
+        ("sourcehut:~user/repo", Remote::Git("https://git.sr.ht/~user/repo".to_string())),
+        ("https://example.com/profile.tar.gz", Remote::Archive("https://example.com/profile.tar.gz".to_string())),
+        ("git@example.com:user/repo.git", Remote::Git("git@example.com:user/repo.git".to_string()))
+    ];
+    for (spec, remote) in remotes {
+        assert_eq!(Remote::resolve(spec).unwrap(), remote, "{spec}");
+    }
+    for spec in ["", "github:user", "github:/repo", "gitlab:user/group/repo"] {
+        assert!(matches!(Remote::resolve(spec), Err(DotulousError::InvalidRemote)), "{spec}");
+    }
+}
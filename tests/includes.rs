@@ -0,0 +1,128 @@
+mod common;
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, profile::DotfileProfile};
+use serde_json::json;
+
+#[test]
+fn included_modules_are_merged_when_loading() {
+    let env = TestEnv::new();
+    env.write_manifest("mono/shell", json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": ["cp {{profile_dir}}/bashrc {{home}}/copied"]
+    }));
+    env.write_manifest("mono/editor", json!({"files": {"vimrc": ".vimrc"}}));
+    let profile = env.profile("mono", &[("shell/bashrc", "echo hi"), ("editor/vimrc", "set number"), ("gitconfig", "[user]")], json!({
+        "files": {"gitconfig": ".gitconfig"},
+        "includes": ["shell", "editor"]
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "mono", "shell/bashrc");
+    env.assert_linked(".vimrc", "mono", "editor/vimrc");
+    env.assert_linked(".gitconfig", "mono", "gitconfig");
+    env.assert_file("copied", "echo hi");
+    assert_eq!(env.meta().current_profile().unwrap().included, ["shell", "editor"]);
+
+    env.unload().unwrap();
+    env.assert_missing(".bashrc");
+    env.assert_missing(".vimrc");
+}
+
+#[test]
+fn included_modules_cant_set_the_same_things() {
+    let env = TestEnv::new();
+    env.write_manifest("mono/shell", json!({
+        "files": {"bashrc": "~/.bashrc"},
+        "hooks": {"refresh": ["echo shell"]},
+        "variables": {"theme": "dark"},
+        "env": {"EDITOR": "vim"},
+        "target_root": "shell"
+    }));
+    env.write_manifest("mono/work", json!({
+        "files": {"bashrc": "./.bashrc"},
+        "hooks": {"refresh": ["echo work"]},
+        "variables": {"theme": "light"},
+        "env": {"EDITOR": "nano"},
+        "target_root": "work"
+    }));
+    let profile = env.profile("mono", &[("shell/bashrc", "echo hi"), ("work/bashrc", "echo work")], json!({
+        "includes": ["shell", "work"]
+    }));
+
+    let Err(DotulousError::IncludeConflict(conflicts)) = env.load(&profile) else { panic!("conflicting includes were loaded") };
+    let items: Vec<&str> = conflicts.0.iter().map(|(item, _)| item.as_str()).collect();
+    assert_eq!(items, ["env \"EDITOR\"", "hook \"refresh\"", "target_root", "variable \"theme\"", "~/.bashrc"]);
+    env.assert_missing(".bashrc");
+}
+
+#[test]
+fn profile_dir_in_modules_is_the_modules_own_folder() {
+    let env = TestEnv::new();
+    env.write_manifest("mono/shell", json!({"post_commands": ["cp {{profile_dir}}/bashrc ~/module-dir"]}));
+    env.profile("base", &[("bashrc", "echo base")], json!({
+        "post_commands": ["cp {{profile_dir}}/bashrc ~/base-dir"]
+    }));
+    let profile = env.profile("mono", &[("shell/bashrc", "echo shell"), ("bashrc", "echo mono")], json!({
+        "includes": ["shell"],
+        "extends": "base"
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_file("module-dir", "echo shell");
+    // Extended profiles' commands are ran from the profile extending them, as before includes
+    env.assert_file("base-dir", "echo mono");
+}
+
+#[test]
+fn included_modules_cant_share_destinations() {
+    let env = TestEnv::new();
+    env.write_manifest("mono/shell", json!({"files": {"bashrc": ".bashrc"}}));
+    env.write_manifest("mono/work", json!({"files": {"bashrc": ".bashrc"}}));
+    let profile = env.profile("mono", &[("shell/bashrc", "echo hi"), ("work/bashrc", "echo work")], json!({
+        "includes": ["shell", "work"]
+    }));
+
+    let Err(DotulousError::IncludeConflict(conflicts)) = env.load(&profile) else { panic!("conflicting includes were loaded") };
+    assert_eq!(conflicts.to_string(), "~/.bashrc is set by shell & work");
+    env.assert_missing(".bashrc");
+}
+
+#[test]
+fn extended_profiles_are_merged_underneath() {
+    let env = TestEnv::new();
+    env.profile("base", &[("bashrc", "echo base"), ("gitconfig", "[user]")], json!({
+        "files": {"bashrc": ".bashrc", "gitconfig": ".gitconfig"},
+        "env": {"EDITOR": "vim", "PAGER": "less"},
+        "post_commands": ["echo $EDITOR >> order"]
+    }));
+    let profile = env.profile("child", &[("bashrc", "echo child")], json!({
+        "extends": "base",
+        "files": {"bashrc": ".bashrc"},
+        "env": {"EDITOR": "nano"},
+        "post_commands": ["echo $PAGER >> order"]
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "child", "bashrc");
+    env.assert_linked(".gitconfig", "base", "gitconfig");
+    env.assert_file("order", "nano\nless\n");
+    assert_eq!(env.meta().current_profile().unwrap().extends_chain, ["base"]);
+}
+
+#[test]
+fn extending_a_missing_or_circular_profile_fails() {
+    let env = TestEnv::new();
+    env.write_manifest("orphan", json!({"extends": "missing"}));
+    env.write_manifest("loop", json!({"extends": "circle"}));
+    env.write_manifest("circle", json!({"extends": "loop"}));
+    let mut orphan = DotfileProfile::find_profile(&env.dotulous, "orphan").unwrap();
+    let mut circle = DotfileProfile::find_profile(&env.dotulous, "circle").unwrap();
+
+    assert!(matches!(orphan.resolve_extends(), Err(DotulousError::ExtendedProfileNotFound)));
+    assert!(matches!(circle.resolve_extends(), Err(DotulousError::ExtendsCycle)));
+    // Neither can be trusted, as their manifest hash includes what they extend
+    assert!(matches!(orphan.manifest_hash(), Err(DotulousError::ExtendedProfileNotFound)));
+    assert_eq!(env.run(&["load", "circle", "--yes", "--trust"]).status.code(), Some(7));
+    assert!(env.meta().current_profile().is_none());
+}
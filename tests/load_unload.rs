@@ -0,0 +1,359 @@
+mod common;
+
+use std::fs;
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, filter::TagFilter, inspect, operations, preflight, profile::{ApplyMode, DotfileProfile, OnConflict, Skip}, state::LoadState};
+use serde_json::json;
+
+#[test]
+fn load_links_files_and_unload_removes_them() {
+    let env = TestEnv::new();
+    let profile = env.profile("simple", &[("bashrc", "echo hi"), ("nvim/init.lua", "-- nvim")], json!({
+        "files": {"bashrc": ".bashrc", "nvim": ".config/nvim"}
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "simple", "bashrc");
+    env.assert_linked(".config/nvim", "simple", "nvim");
+    assert_eq!(env.meta().current_profile().map(|p| p.name), Some("simple".to_string()));
+
+    env.unload().unwrap();
+    env.assert_missing(".bashrc");
+    env.assert_missing(".config/nvim");
+    assert!(env.meta().current_profile().is_none());
+}
+
+#[test]
+fn unload_without_a_profile_fails() {
+    let env = TestEnv::new();
+    assert!(matches!(env.unload(), Err(DotulousError::NoProfileLoaded)));
+}
+
+#[test]
+fn conflicts_are_backed_up_and_restored() {
+    let env = TestEnv::new();
+    env.write_home_file(".bashrc", "my own bashrc");
+    let profile = env.profile("conflict", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "conflict", "bashrc");
+
+    env.unload().unwrap();
+    env.assert_file(".bashrc", "my own bashrc");
+}
+
+#[test]
+fn conflicts_can_be_skipped() {
+    let env = TestEnv::new();
+    env.write_home_file(".bashrc", "my own bashrc");
+    let profile = env.profile("skip", &[("bashrc", "echo hi"), ("vimrc", "set nu")], json!({
+        "files": {"bashrc": ".bashrc", "vimrc": ".vimrc"}
+    }));
+
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &TagFilter::default(), Skip::default(), ApplyMode::Apply, OnConflict::Skip, false, None).unwrap();
+    env.assert_file(".bashrc", "my own bashrc");
+    env.assert_linked(".vimrc", "skip", "vimrc");
+
+    // Only what loading created is removed
+    env.unload().unwrap();
+    env.assert_file(".bashrc", "my own bashrc");
+    env.assert_missing(".vimrc");
+}
+
+#[test]
+fn copies_are_real_files() {
+    let env = TestEnv::new();
+    let profile = env.profile("copy", &[("gitconfig", "[user]")], json!({
+        "files": {"gitconfig": {"destination": ".gitconfig", "strategy": "copy"}}
+    }));
+
+    env.load(&profile).unwrap();
+    env.assert_file(".gitconfig", "[user]");
+
+    env.unload().unwrap();
+    env.assert_missing(".gitconfig");
+}
+
+#[test]
+fn changed_copies_are_only_removed_with_force() {
+    let env = TestEnv::new();
+    let profile = env.profile("copy", &[("gitconfig", "[user]"), ("ssh/config", "Host *")], json!({
+        "files": {
+            "gitconfig": {"destination": ".gitconfig", "strategy": "copy"},
+            "ssh": {"destination": ".ssh", "strategy": "copy"}
+        }
+    }));
+    env.load(&profile).unwrap();
+    env.write_home_file(".gitconfig", "[user] mine");
+
+    let loaded = env.meta().current_profile().unwrap();
+    let mut health = inspect::link_health(&loaded, &env.dotulous, &env.home);
+    health.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(health, [
+        (env.home_path(".gitconfig"), inspect::LinkHealth::Foreign),
+        (env.home_path(".ssh"), inspect::LinkHealth::Ok)
+    ]);
+    env.unload().unwrap();
+    env.assert_file(".gitconfig", "[user] mine");
+    env.assert_missing(".ssh");
+
+    fs::remove_file(env.home_path(".gitconfig")).unwrap();
+    env.load(&profile).unwrap();
+    env.write_home_file(".gitconfig", "[user] mine");
+    operations::unload(&env.dotulous, &env.config, &env.home, &env.settings, Skip::default(), ApplyMode::Apply, true).unwrap();
+    env.assert_missing(".gitconfig");
+}
+
+#[cfg(unix)]
+#[test]
+fn files_are_given_their_mode() {
+    use std::os::unix::fs::PermissionsExt;
+    let env = TestEnv::new();
+    let profile = env.profile("modes", &[("gitconfig", "[user]"), ("ssh/config", "Host *"), ("netrc", "machine example"), ("bashrc", "echo hi")], json!({
+        "files": {
+            "gitconfig": {"destination": ".gitconfig", "strategy": "copy", "mode": "0600"},
+            "ssh": {"destination": ".ssh", "strategy": "copy", "mode": "0700"},
+            "netrc": {"destination": ".netrc", "strategy": "template", "mode": "0640"},
+            "bashrc": {"destination": ".bashrc", "mode": "0666"}
+        }
+    }));
+    let mode = |path: &str| fs::metadata(env.home_path(path)).unwrap().permissions().mode() & 0o7777;
+
+    env.load(&profile).unwrap();
+    env.assert_file(".gitconfig", "[user]");
+    assert_eq!(mode(".gitconfig"), 0o600);
+    assert_eq!(mode(".ssh"), 0o700);
+    env.assert_file(".ssh/config", "Host *");
+    assert_eq!(mode(".netrc"), 0o640);
+    // Wider than the umask would allow, and set on the profile's own file
+    env.assert_linked(".bashrc", "modes", "bashrc");
+    assert_eq!(mode(".bashrc"), 0o666);
+}
+
+#[test]
+fn folders_can_be_linked_file_by_file() {
+    let env = TestEnv::new();
+    env.profile("base", &[("vim/vimrc", "set nu"), ("vim/notes.txt", "todo"), (".dotulousignore", "*.txt")], json!({
+        "files": {"vim": {"destination": ".vim", "link_mode": "contents"}}
+    }));
+    let profile = env.profile("unfolded", &[("nvim/init.lua", "-- init"), ("nvim/lua/plugins.lua", "return {}"), ("nvim/init.lua~", "-- old"), ("nvim/cache.log", ""), (".dotulousignore", "*.log")], json!({
+        "extends": "base",
+        "files": {
+            "nvim": {"destination": ".config/nvim", "link_mode": "contents"}
+        }
+    }));
+
+    env.load(&profile).unwrap();
+    assert!(!env.home_path(".config/nvim").is_symlink());
+    env.assert_linked(".config/nvim/init.lua", "unfolded", "nvim/init.lua");
+    env.assert_linked(".config/nvim/lua/plugins.lua", "unfolded", "nvim/lua/plugins.lua");
+    env.assert_missing(".config/nvim/init.lua~");
+    env.assert_missing(".config/nvim/cache.log");
+    // Extended profiles are ignored by their own `.dotulousignore`
+    env.assert_linked(".vim/vimrc", "base", "vim/vimrc");
+    env.assert_missing(".vim/notes.txt");
+
+    env.unload().unwrap();
+    env.assert_missing(".config/nvim");
+    env.assert_missing(".vim");
+}
+
+#[test]
+fn loading_another_profile_unloads_the_first() {
+    let env = TestEnv::new();
+    let first = env.profile("first", &[("bashrc", "first"), ("only_first", "first")], json!({
+        "files": {"bashrc": ".bashrc", "only_first": ".first"}
+    }));
+    let second = env.profile("second", &[("bashrc", "second")], json!({"files": {"bashrc": ".bashrc"}}));
+
+    env.load(&first).unwrap();
+    env.load(&second).unwrap();
+    env.assert_linked(".bashrc", "second", "bashrc");
+    env.assert_missing(".first");
+}
+
+#[test]
+fn reload_picks_up_manifest_changes() {
+    let env = TestEnv::new();
+    let profile = env.profile("changing", &[("bashrc", "echo hi"), ("vimrc", "set nu")], json!({
+        "files": {"bashrc": ".bashrc"}
+    }));
+    env.load(&profile).unwrap();
+
+    env.write_manifest("changing", json!({"files": {"vimrc": ".vimrc"}}));
+    let changed = DotfileProfile::find_profile(&env.dotulous, "changing").unwrap();
+    env.trust(&changed);
+    env.reload().unwrap();
+
+    env.assert_missing(".bashrc");
+    env.assert_linked(".vimrc", "changing", "vimrc");
+}
+
+#[test]
+fn undo_restores_the_previous_profile() {
+    let env = TestEnv::new();
+    let first = env.profile("first", &[("bashrc", "first")], json!({"files": {"bashrc": ".bashrc"}}));
+    let second = env.profile("second", &[("vimrc", "second")], json!({"files": {"vimrc": ".vimrc"}}));
+    env.load(&first).unwrap();
+    env.load(&second).unwrap();
+
+    let restored = operations::undo(&env.dotulous, &env.config, &env.home, &env.settings, OnConflict::Backup, false).unwrap();
+    assert_eq!(restored.map(|p| p.name), Some("first".to_string()));
+    env.assert_linked(".bashrc", "first", "bashrc");
+    env.assert_missing(".vimrc");
+}
+
+#[cfg(unix)]
+#[test]
+fn reload_only_relinks_what_changed() {
+    use std::os::unix::fs::MetadataExt;
+
+    let env = TestEnv::new();
+    let profile = env.profile("steady", &[("bashrc", "echo hi"), ("vimrc", "set nu"), ("zshrc", "echo zsh")], json!({
+        "files": {"bashrc": ".bashrc", "vimrc": ".vimrc"},
+        "post_commands": ["echo ran >> ran"]
+    }));
+    env.load(&profile).unwrap();
+    let inode = |path: &str| env.home_path(path).symlink_metadata().unwrap().ino();
+    let bashrc = inode(".bashrc");
+
+    env.write_manifest("steady", json!({
+        "files": {"bashrc": ".bashrc", "zshrc": ".zshrc"},
+        "post_commands": ["echo ran >> ran"]
+    }));
+    env.trust(&DotfileProfile::find_profile(&env.dotulous, "steady").unwrap());
+    env.reload().unwrap();
+    assert_eq!(inode(".bashrc"), bashrc);
+    env.assert_missing(".vimrc");
+    env.assert_linked(".zshrc", "steady", "zshrc");
+    env.assert_file("ran", "ran\n");
+
+    let full = operations::reload(&env.dotulous, &env.config, &env.home, &env.settings, Skip::default(), ApplyMode::Apply, OnConflict::Backup, true, false, None);
+    full.unwrap();
+    env.assert_file("ran", "ran\nran\n");
+    env.unload().unwrap();
+    env.assert_missing(".bashrc");
+    env.assert_missing(".zshrc");
+}
+
+#[cfg(unix)]
+#[test]
+fn unload_never_removes_what_dotulous_does_not_own() {
+    let env = TestEnv::new();
+    let profile = env.profile("owned", &[("bashrc", "echo hi"), ("nvim/init.lua", "-- nvim"), ("vimrc", "set nu")], json!({
+        "files": {"bashrc": ".bashrc", "nvim": ".config/nvim", "vimrc": ".vimrc"}
+    }));
+    env.load(&profile).unwrap();
+
+    // Replace the links with things dotulous didn't make, which even --force mustn't remove
+    std::fs::remove_file(env.home_path(".config/nvim")).unwrap();
+    env.write_home_file(".config/nvim/init.lua", "-- my own nvim");
+    std::fs::remove_file(env.home_path(".bashrc")).unwrap();
+    let elsewhere = env.home_path("elsewhere");
+    env.write_home_file("elsewhere", "not dotulous");
+    std::os::unix::fs::symlink(&elsewhere, env.home_path(".bashrc")).unwrap();
+
+    operations::unload(&env.dotulous, &env.config, &env.home, &env.settings, Skip::default(), ApplyMode::Apply, true).unwrap();
+    env.assert_file(".config/nvim/init.lua", "-- my own nvim");
+    assert_eq!(std::fs::read_link(env.home_path(".bashrc")).unwrap(), elsewhere);
+    env.assert_missing(".vimrc");
+}
+
+#[test]
+fn optional_files_are_skipped_when_missing() {
+    let env = TestEnv::new();
+    let profile = env.profile("optional", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc", "local.conf": {"destination": ".config/local.conf", "optional": true}}
+    }));
+    assert!(preflight::check(&profile, &env.home).is_empty());
+
+    env.load(&profile).unwrap();
+    env.assert_linked(".bashrc", "optional", "bashrc");
+    env.assert_missing(".config/local.conf");
+    let loaded = env.meta().current_profile().unwrap();
+    let drift = inspect::loaded_drift(&loaded, &env.dotulous, &env.home);
+    assert!(matches!(drift.as_slice(), [inspect::Drift::OptionalAbsent { destination }] if *destination == env.home_path(".config/local.conf")));
+    let health = inspect::link_health(&loaded, &env.dotulous, &env.home);
+    assert!(health.contains(&(env.home_path(".config/local.conf"), inspect::LinkHealth::OptionalAbsent)));
+
+    env.unload().unwrap();
+    env.assert_missing(".bashrc");
+}
+
+#[test]
+fn directories_are_created_and_only_removed_when_empty() {
+    let env = TestEnv::new();
+    fs::create_dir_all(env.home_path(".local/share/kept")).unwrap();
+    let profile = env.profile("folders", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "directories": [".local/share/foo/cache", ".cache/bar", ".local/share/kept"]
+    }));
+
+    env.load(&profile).unwrap();
+    assert!(env.home_path(".local/share/foo/cache").is_dir());
+    assert!(env.home_path(".cache/bar").is_dir());
+    env.write_home_file(".cache/bar/in-use", "data");
+
+    env.unload().unwrap();
+    env.assert_missing(".local/share/foo");
+    env.assert_file(".cache/bar/in-use", "data");
+    // It was already there before loading
+    assert!(env.home_path(".local/share/kept").is_dir());
+}
+
+#[test]
+fn what_was_placed_is_recorded_until_unloaded() {
+    let env = TestEnv::new();
+    env.write_home_file(".vimrc", "my own vimrc");
+    let profile = env.profile("recorded", &[("bashrc", "echo hi"), ("vimrc", "set nu")], json!({
+        "files": {"bashrc": ".bashrc", "vimrc": ".vimrc"}
+    }));
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &TagFilter::default(), Skip::default(), ApplyMode::Apply, OnConflict::Skip, false, None).unwrap();
+
+    let state = LoadState::load(&env.dotulous).unwrap().unwrap();
+    assert!(state.was_placed(&env.home_path(".bashrc")));
+    assert!(!state.was_placed(&env.home_path(".vimrc")));
+
+    env.unload().unwrap();
+    assert!(LoadState::load(&env.dotulous).unwrap().is_none());
+}
+
+#[test]
+fn many_files_are_all_linked() {
+    let env = TestEnv::new();
+    let names: Vec<String> = (0..64).map(|i| format!("file{i}")).collect();
+    let files: Vec<(&str, &str)> = names.iter().map(|name| (name.as_str(), name.as_str())).collect();
+    let destinations: serde_json::Map<String, serde_json::Value> = names.iter().map(|name| (name.clone(), json!(format!(".many/{name}")))).collect();
+    let profile = env.profile("many", &files, json!({"files": destinations}));
+
+    env.load(&profile).unwrap();
+    for name in &names {
+        env.assert_linked(&format!(".many/{name}"), "many", name);
+    }
+    env.unload().unwrap();
+    env.assert_missing(".many");
+}
+
+#[test]
+fn link_health_finds_what_went_wrong_since_loading() {
+    let env = TestEnv::new();
+    let profile = env.profile("health", &[("bashrc", "echo hi"), ("vimrc", "set nu"), ("zshrc", "echo zsh"), ("gitconfig", "[user]")], json!({
+        "files": {"bashrc": ".bashrc", "vimrc": ".vimrc", "zshrc": ".zshrc", "gitconfig": ".gitconfig"}
+    }));
+    env.load(&profile).unwrap();
+
+    fs::remove_file(profile.repo_path.join("vimrc")).unwrap();
+    fs::remove_file(env.home_path(".zshrc")).unwrap();
+    fs::remove_file(env.home_path(".gitconfig")).unwrap();
+    env.write_home_file(".gitconfig", "[user] mine");
+    let loaded = env.meta().current_profile().unwrap();
+    let mut health = inspect::link_health(&loaded, &env.dotulous, &env.home);
+    health.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(health, [
+        (env.home_path(".bashrc"), inspect::LinkHealth::Ok),
+        (env.home_path(".gitconfig"), inspect::LinkHealth::Foreign),
+        (env.home_path(".vimrc"), inspect::LinkHealth::Broken),
+        (env.home_path(".zshrc"), inspect::LinkHealth::Missing)
+    ]);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::process::Output;
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, lock::Lock};
+use serde_json::json;
+
+/// Returns if `output` is dotulous refusing to run as the dotulous folder is locked.
+fn was_locked_out(output: &Output) -> bool {
+    !output.status.success() && String::from_utf8_lossy(&output.stderr).contains("Another dotulous instance is running")
+}
+
+#[test]
+fn actions_that_write_wait_for_the_lock() {
+    let env = TestEnv::new();
+    env.profile("simple", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    let _lock = Lock::acquire(&env.dotulous).unwrap();
+
+    assert!(was_locked_out(&env.run(&["load", "simple", "--yes"])));
+    assert!(was_locked_out(&env.run(&["config", "set", "timeout", "5"])));
+    assert!(was_locked_out(&env.run(&["exec", "simple", "--", "true"])));
+    assert!(was_locked_out(&env.run(&["service", "remove"])));
+    env.assert_missing(".bashrc");
+}
+
+#[test]
+fn read_only_actions_ignore_the_lock() {
+    let env = TestEnv::new();
+    env.profile("simple", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    let _lock = Lock::acquire(&env.dotulous).unwrap();
+
+    assert!(env.run(&["status"]).status.success());
+    assert!(env.run(&["list"]).status.success());
+    assert!(env.run(&["config", "get"]).status.success());
+    assert!(!was_locked_out(&env.run(&["apply-system", "simple", "--dry-run"])));
+}
+
+#[test]
+fn exec_lets_go_of_the_lock_while_the_command_runs() {
+    let env = TestEnv::new();
+    env.profile("simple", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    let nested: String = format!("{:?} --home {:?} --dotulous-dir {:?} config set timeout 5", env!("CARGO_BIN_EXE_dotulous"), env.home, env.dotulous);
+
+    assert!(env.run(&["exec", "simple", "--", "sh", "-c", &nested]).status.success());
+    let output = env.run(&["config", "get", "timeout"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "5");
+}
+
+#[test]
+fn the_lock_is_only_held_once_and_let_go_when_dropped() {
+    let env = TestEnv::new();
+    let lock = Lock::acquire(&env.dotulous).unwrap();
+    assert!(matches!(Lock::acquire(&env.dotulous), Err(DotulousError::AlreadyRunning)));
+
+    // The lock file left behind doesn't keep it locked
+    drop(lock);
+    assert!(env.dotulous.join("dotulous.lock").exists());
+    let _lock = Lock::acquire(&env.dotulous).unwrap();
+    assert!(matches!(Lock::acquire(&env.dotulous.join("missing")), Err(DotulousError::FailedLockDotulousDirectory(_))));
+}
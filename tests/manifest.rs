@@ -0,0 +1,83 @@
+mod common;
+
+use std::fs;
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, format::ManifestFormat, migrate::MANIFEST_VERSION, profile::DotfileProfile, validate};
+use serde_json::{json, Value};
+
+/// Writes the manifest of the profile called `name` with [`TestEnv::write_manifest`] and returns
+/// what was written.
+fn manifest_contents(env: &TestEnv, name: &str, manifest: Value) -> String {
+    env.write_manifest(name, manifest);
+    fs::read_to_string(env.dotulous.join(name).join("manifest.json")).unwrap()
+}
+
+#[test]
+fn problems_are_reported_where_they_are() {
+    let env = TestEnv::new();
+    let contents = manifest_contents(&env, "broken", json!({
+        "files": {"bashrc": ".bashrc", "zshrc": "../.zshrc", "vimrc": ".bashrc"},
+        "directories": ["/etc/broken"],
+        "fiels": {}
+    }));
+
+    let problems = validate::validate_manifest(&contents, ManifestFormat::Json).unwrap_err();
+    let paths: Vec<&str> = problems.iter().map(|problem| problem.path.as_str()).collect();
+    assert_eq!(paths, ["fiels", "files.zshrc", "directories[0]", "files.vimrc"]);
+
+    let contents = manifest_contents(&env, "typed", json!({"pre_commands": [1]}));
+    let problems = validate::validate_manifest(&contents, ManifestFormat::Json).unwrap_err();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].path, "pre_commands[0]");
+}
+
+#[test]
+fn destinations_may_leave_the_home_folder_only_when_allowed() {
+    let env = TestEnv::new();
+    let contents = manifest_contents(&env, "outside", json!({
+        "files": {"bashrc": "../.bashrc"},
+        "allow_outside_home": true
+    }));
+    assert!(validate::validate_manifest(&contents, ManifestFormat::Json).is_ok());
+}
+
+#[test]
+fn manifests_from_newer_versions_are_refused() {
+    let env = TestEnv::new();
+    let contents = manifest_contents(&env, "future", json!({"manifest_version": MANIFEST_VERSION + 1}));
+
+    let problems = validate::validate_manifest(&contents, ManifestFormat::Json).unwrap_err();
+    assert_eq!(problems[0].path, "manifest_version");
+    assert!(matches!(DotfileProfile::find_profile(&env.dotulous, "future"), Err(DotulousError::ManifestTooNew)));
+}
+
+#[test]
+fn old_manifests_are_upgraded_when_migrated() {
+    let env = TestEnv::new();
+    env.profile("old", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    assert_eq!(DotfileProfile::find_profile(&env.dotulous, "old").unwrap().manifest_version, MANIFEST_VERSION);
+
+    assert!(env.run(&["migrate", "old"]).status.success());
+    let manifest: Value = serde_json::from_str(&fs::read_to_string(env.dotulous.join("old/manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["manifest_version"], MANIFEST_VERSION);
+    assert_eq!(manifest["files"], json!({"bashrc": ".bashrc"}));
+}
+
+#[test]
+fn yaml_manifests_can_end_in_yml() {
+    let env = TestEnv::new();
+    let contents = manifest_contents(&env, "yml", json!({"files": {"bashrc": ".bashrc"}}));
+    let yaml: String = ManifestFormat::Yaml.serialize(&ManifestFormat::Json.parse(&contents).unwrap()).unwrap();
+    fs::remove_file(env.dotulous.join("yml/manifest.json")).unwrap();
+    fs::write(env.dotulous.join("yml/manifest.yml"), yaml).unwrap();
+
+    let profile: DotfileProfile = DotfileProfile::find_profile(&env.dotulous, "yml").unwrap();
+    assert_eq!(profile.manifest_path, env.dotulous.join("yml/manifest.yml"));
+    assert_eq!(ManifestFormat::from_path(&profile.manifest_path), ManifestFormat::Yaml);
+    assert_eq!(profile.files().len(), 1);
+
+    assert!(env.run(&["convert", "yml", "--to", "json"]).status.success());
+    assert!(!env.dotulous.join("yml/manifest.yml").exists());
+    assert_eq!(DotfileProfile::find_profile(&env.dotulous, "yml").unwrap().files().len(), 1);
+}
@@ -0,0 +1,11 @@
+use dotulous::packages::PackageManager;
+
+#[test]
+fn packages_are_missing_when_they_cant_be_queried() {
+    let managers = [PackageManager::Pacman, PackageManager::Apt, PackageManager::Dnf, PackageManager::Zypper, PackageManager::Xbps, PackageManager::Apk];
+    let packages: Vec<String> = vec!["dotulous-package-that-does-not-exist".to_string()];
+    for manager in managers {
+        // Whether or not the package manager is installed here, this never panics
+        assert_eq!(manager.missing(&packages), packages, "{manager:?}");
+    }
+}
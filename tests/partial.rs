@@ -0,0 +1,100 @@
+mod common;
+
+use std::path::Path;
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, filter::{EntryFilter, TagFilter}, operations, profile::{ApplyMode, DotfileProfile, OnConflict, Skip}};
+use serde_json::json;
+
+#[cfg(unix)]
+#[test]
+fn partial_reload_leaves_the_rest_alone() {
+    let env = TestEnv::new();
+    let profile = env.profile("partial", &[("bashrc", "echo hi"), ("nvim/init.lua", "-- nvim")], json!({
+        "files": {"bashrc": ".bashrc", "nvim": ".config/nvim"},
+        "post_commands": ["touch ran"]
+    }));
+    env.load(&profile).unwrap();
+    std::fs::remove_file(env.home_path("ran")).unwrap();
+
+    env.write_manifest("partial", json!({
+        "files": {"bashrc": ".bashrc", "nvim": ".config/nvim", "vimrc": ".vimrc"},
+        "post_commands": ["touch ran"]
+    }));
+    let changed = DotfileProfile::find_profile(&env.dotulous, "partial").unwrap();
+    env.trust(&changed);
+    let filter = EntryFilter::new(&[".config/nvim*".to_string()], &[]).unwrap();
+    operations::reload_partial(&env.dotulous, &env.config, &env.home, &env.settings, &filter, ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+
+    env.assert_linked(".config/nvim", "partial", "nvim");
+    env.assert_linked(".bashrc", "partial", "bashrc");
+    // Only the picked entries are loaded, and no commands are ran
+    env.assert_missing(".vimrc");
+    env.assert_missing("ran");
+}
+
+#[test]
+fn partial_unload_restores_only_the_picked_backups() {
+    let env = TestEnv::new();
+    env.write_home_file(".bashrc", "my own bashrc");
+    env.write_home_file(".vimrc", "my own vimrc");
+    let profile = env.profile("partial", &[("bashrc", "echo hi"), ("vimrc", "set nu")], json!({
+        "files": {"bashrc": ".bashrc", "vimrc": ".vimrc"}
+    }));
+    env.load(&profile).unwrap();
+
+    let filter = EntryFilter::new(&[], &[".bashrc".to_string()]).unwrap();
+    operations::unload_partial(&env.dotulous, &env.config, &env.home, &env.settings, &filter, ApplyMode::Apply, false).unwrap();
+    env.assert_file(".vimrc", "my own vimrc");
+    env.assert_linked(".bashrc", "partial", "bashrc");
+    assert_eq!(env.meta().current_profile().map(|p| p.destinations().len()), Some(1));
+
+    env.unload().unwrap();
+    env.assert_file(".bashrc", "my own bashrc");
+    env.assert_file(".vimrc", "my own vimrc");
+}
+
+#[cfg(unix)]
+#[test]
+fn skipped_tags_stay_skipped_on_reload() {
+    let env = TestEnv::new();
+    let profile = env.profile("tagged", &[("bashrc", "echo hi"), ("sway", "bindsym")], json!({
+        "files": {"bashrc": ".bashrc", "sway": {"destination": ".config/sway", "tags": ["gui"]}},
+        "post_commands": [{"command": "touch gui-ran", "tags": ["gui"]}]
+    }));
+    let tags = TagFilter { tags: Vec::new(), skip_tags: vec!["gui".to_string()] };
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &tags, Skip::default(), ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+    env.assert_linked(".bashrc", "tagged", "bashrc");
+    env.assert_missing(".config/sway");
+    env.assert_missing("gui-ran");
+
+    env.reload().unwrap();
+    env.assert_linked(".bashrc", "tagged", "bashrc");
+    env.assert_missing(".config/sway");
+    env.assert_missing("gui-ran");
+}
+
+#[cfg(unix)]
+#[test]
+fn added_files_can_be_linked_and_removed_while_loaded() {
+    let env = TestEnv::new();
+    let profile = env.profile("editable", &[("bashrc", "echo hi"), ("vimrc", "set nu")], json!({
+        "files": {"bashrc": ".bashrc"}
+    }));
+    env.load(&profile).unwrap();
+    env.write_home_file(".vimrc", "old");
+
+    let profile = operations::add_file(&env.dotulous, &env.home, "editable", Path::new("vimrc"), Path::new(".vimrc")).unwrap();
+    assert!(matches!(operations::add_file(&env.dotulous, &env.home, "editable", Path::new("missing"), Path::new(".missing")), Err(DotulousError::SourceNotFound)));
+    assert!(matches!(operations::add_file(&env.dotulous, &env.home, "editable", Path::new("bashrc"), Path::new(".vimrc")), Err(DotulousError::DestinationAlreadyMapped)));
+    operations::load_partial(&env.dotulous, &env.config, &env.home, &env.settings, &profile, &EntryFilter::path(Path::new(".vimrc")), ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+    env.assert_linked(".vimrc", "editable", "vimrc");
+    env.assert_linked(".bashrc", "editable", "bashrc");
+
+    operations::remove_file(&env.dotulous, &env.config, &env.home, &env.settings, "editable", Path::new("vimrc")).unwrap();
+    env.assert_file(".vimrc", "old");
+    env.assert_linked(".bashrc", "editable", "bashrc");
+    assert!(!DotfileProfile::find_profile(&env.dotulous, "editable").unwrap().files().contains_key(Path::new("vimrc")));
+    assert!(matches!(operations::remove_file(&env.dotulous, &env.config, &env.home, &env.settings, "editable", Path::new("vimrc")), Err(DotulousError::FileNotMapped)));
+    env.reload().unwrap();
+}
@@ -0,0 +1,38 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::TestEnv;
+use dotulous::{meta::Meta, paths::Paths};
+
+#[test]
+fn the_legacy_folder_is_moved_to_the_data_folder() {
+    let env = TestEnv::new();
+    let legacy: PathBuf = env.home_path(".dotulous");
+    fs::create_dir_all(&legacy).unwrap();
+    Meta::new().save_meta(&legacy).unwrap();
+    env.write_home_file(".dotulous/vars.json", "{}");
+    let paths = Paths { data: env.home_path(".local/share/dotulous"), config: env.home_path(".config/dotulous") };
+
+    assert_eq!(paths.migrate_legacy(&env.home).unwrap(), Some(legacy.clone()));
+    assert_eq!(fs::read_link(&legacy).unwrap(), paths.data);
+    assert!(paths.data.join("meta.json").exists());
+    assert!(!paths.data.join("vars.json").exists());
+    assert!(paths.config.join("vars.json").exists());
+
+    // Once moved, it's left alone
+    assert_eq!(paths.migrate_legacy(&env.home).unwrap(), None);
+}
+
+#[test]
+fn the_legacy_folder_never_replaces_an_existing_data_folder() {
+    let env = TestEnv::new();
+    env.write_home_file(".dotulous/meta.json", "{}");
+    env.write_home_file(".local/share/dotulous/meta.json", "{}");
+    let paths = Paths { data: env.home_path(".local/share/dotulous"), config: env.home_path(".config/dotulous") };
+
+    assert_eq!(paths.migrate_legacy(&env.home).unwrap(), None);
+    assert!(env.home_path(".dotulous").is_dir());
+}
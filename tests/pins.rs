@@ -0,0 +1,42 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, pin::Pins};
+use serde_json::json;
+
+#[test]
+fn pinned_repos_are_checked_against_the_lockfile() {
+    let env = TestEnv::new();
+    let profile = env.profile("pinned", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "pinned_repos": ["~/plugin"]
+    }));
+    assert!(matches!(Pins::check(&profile, &env.home), Err(DotulousError::PinsNotFound)));
+
+    Pins::record(&profile, &env.home).unwrap();
+    assert!(Pins::check(&profile, &env.home).unwrap().is_empty());
+
+    let plugin = env.home_path("plugin");
+    std::fs::create_dir_all(&plugin).unwrap();
+    let status = std::process::Command::new("git")
+        .arg("-C").arg(&plugin)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(["init", "--quiet"])
+        .status();
+    if !status.is_ok_and(|status| status.success()) {
+        return
+    }
+    std::process::Command::new("git")
+        .arg("-C").arg(&plugin)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "--quiet", "--allow-empty", "-m", "plugin"])
+        .status()
+        .unwrap();
+    let differences = Pins::check(&profile, &env.home).unwrap();
+    assert_eq!(differences.len(), 1);
+    assert!(differences[0].starts_with("~/plugin: (none) -> "));
+
+    Pins::record(&profile, &env.home).unwrap();
+    assert!(Pins::check(&profile, &env.home).unwrap().is_empty());
+}
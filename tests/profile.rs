@@ -0,0 +1,164 @@
+use std::{cmp::Ordering, env, fs, path::{Path, PathBuf}, process};
+
+use dotulous::{entry::{CommandEntry, FileEntry}, error::DotulousError, inspect::compare_versions, meta::{Meta, TrustOnce, TrustScope, TrustStatus}, paths::expand_path, profile::{DotfileProfile, Hook}, update::{self, Release, ReleaseAsset}};
+
+#[test]
+fn files_can_be_added_and_removed() {
+    let mut profile = DotfileProfile::new("built", Path::new("/profiles/built"));
+    profile
+        .add_file(PathBuf::from("bashrc"), FileEntry::new(PathBuf::from(".bashrc"))).unwrap()
+        .add_file(PathBuf::from("nvim"), FileEntry::new(PathBuf::from(".config/nvim"))).unwrap();
+    assert_eq!(profile.files().len(), 2);
+
+    assert!(matches!(profile.add_file(PathBuf::from("bashrc"), FileEntry::new(PathBuf::from(".bashrc2"))), Err(DotulousError::FileAlreadyMapped)));
+    assert!(matches!(profile.add_file(PathBuf::from("zshrc"), FileEntry::new(PathBuf::from(".bashrc"))), Err(DotulousError::DestinationAlreadyMapped)));
+
+    let removed = profile.remove_file(Path::new("bashrc")).unwrap();
+    assert_eq!(removed, [FileEntry::new(PathBuf::from(".bashrc"))]);
+    assert!(profile.remove_file(Path::new("bashrc")).is_none());
+    assert_eq!(profile.files().keys().collect::<Vec<_>>(), [Path::new("nvim")]);
+}
+
+#[test]
+fn commands_can_be_set_per_hook() {
+    let mut profile = DotfileProfile::new("built", Path::new("/profiles/built"));
+    profile
+        .set_commands(&Hook::Pre, vec![CommandEntry::new("echo pre")])
+        .set_commands(&Hook::Custom("theme-dark".to_string()), vec![CommandEntry::new("echo dark")]);
+
+    assert_eq!(profile.commands(&Hook::Pre).unwrap()[0].command, "echo pre");
+    assert!(profile.commands(&Hook::Post).unwrap().is_empty());
+    assert!(profile.commands(&Hook::Custom("theme-light".to_string())).is_none());
+    let hooks: Vec<String> = profile.command_lists().map(|(hook, _)| hook.to_string()).collect();
+    assert_eq!(hooks, ["pre", "post", "removal", "theme-dark"]);
+
+    profile.set_commands(&Hook::Custom("theme-dark".to_string()), Vec::new());
+    assert!(profile.commands(&Hook::Custom("theme-dark".to_string())).is_none());
+}
+
+#[test]
+fn built_profiles_round_trip_through_the_manifest() {
+    let path: PathBuf = env::temp_dir().join(format!("dotulous-profile-test-{}", process::id()));
+    fs::create_dir_all(&path).unwrap();
+    let mut profile = DotfileProfile::new("built", &path);
+    profile.add_file(PathBuf::from("bashrc"), FileEntry::new(PathBuf::from(".bashrc"))).unwrap();
+    profile.set_commands(&Hook::Post, vec![CommandEntry::new("echo post")]);
+    profile.save_manifest().unwrap();
+
+    let read = DotfileProfile::from_manifest(&path).unwrap();
+    fs::remove_dir_all(&path).unwrap();
+    assert_eq!(read.files().get(Path::new("bashrc")).unwrap(), &[FileEntry::new(PathBuf::from(".bashrc"))]);
+    assert_eq!(read.commands(&Hook::Post).unwrap(), [CommandEntry::new("echo post")]);
+}
+
+#[test]
+fn versions_compare_part_by_part() {
+    assert_eq!(compare_versions("1.2", "1.3"), Ordering::Less);
+    assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+    assert_eq!(compare_versions("v2.0", "2.0"), Ordering::Equal);
+    assert_eq!(compare_versions("1.2", "1.2.1"), Ordering::Less);
+    assert_eq!(compare_versions("1.0-beta", "1.0-alpha"), Ordering::Greater);
+}
+
+#[test]
+fn trusted_version_is_remembered() {
+    let path: PathBuf = env::temp_dir().join(format!("dotulous-meta-test-{}", process::id()));
+    fs::create_dir_all(&path).unwrap();
+    let mut meta: Meta = Meta::new();
+    meta.trust_profile(PathBuf::from("/profiles/versioned"), "hash".to_string(), Some("1.2".to_string()), TrustScope::Commands);
+    meta.trust_profile(PathBuf::from("/profiles/plain"), "hash".to_string(), None, TrustScope::Commands);
+    meta.save_meta(&path).unwrap();
+
+    let read: Meta = Meta::load_meta(&path).unwrap();
+    fs::remove_dir_all(&path).unwrap();
+    assert_eq!(read.trusted_version(Path::new("/profiles/versioned")), Some("1.2"));
+    assert_eq!(read.trusted_version(Path::new("/profiles/plain")), None);
+    assert!(read.is_trusted(Path::new("/profiles/versioned"), "hash", TrustScope::Commands));
+}
+
+#[test]
+fn trusted_profiles_can_be_listed_and_revoked() {
+    let mut meta: Meta = Meta::new();
+    meta.trust_profile(PathBuf::from("/profiles/first"), "hash".to_string(), None, TrustScope::Commands);
+    meta.trust_profile(PathBuf::from("/profiles/second"), "hash".to_string(), None, TrustScope::Commands);
+    assert!(meta.set_trust_note(Path::new("/profiles/first"), Some("Reviewed".to_string())));
+    assert!(!meta.set_trust_note(Path::new("/profiles/stranger"), Some("Never trusted".to_string())));
+
+    // Trusting again with a new manifest keeps the note
+    meta.trust_profile(PathBuf::from("/profiles/first"), "new hash".to_string(), None, TrustScope::Commands);
+    let paths: Vec<&Path> = meta.trusted_profiles().iter().map(|trusted| trusted.path.as_path()).collect();
+    assert_eq!(paths, [Path::new("/profiles/second"), Path::new("/profiles/first")]);
+    let first = meta.trusted_profile(Path::new("/profiles/first")).unwrap();
+    assert_eq!(first.manifest_hash.as_deref(), Some("new hash"));
+    assert_eq!(first.note.as_deref(), Some("Reviewed"));
+    assert!(first.trusted_at.is_some());
+    assert_eq!(first.status(), TrustStatus::Missing);
+
+    let revoked = meta.untrust_profile(Path::new("/profiles/second")).unwrap();
+    assert_eq!(revoked.path, Path::new("/profiles/second"));
+    assert!(meta.untrust_profile(Path::new("/profiles/second")).is_none());
+    assert_eq!(meta.trusted_profiles().len(), 1);
+}
+
+#[test]
+fn trusting_once_is_never_saved() {
+    let mut meta: Meta = Meta::new();
+    meta.trust_once(TrustOnce { path: PathBuf::from("/profiles/once"), manifest_hash: "hash".to_string(), scope: TrustScope::Commands });
+    assert!(meta.is_trusted(Path::new("/profiles/once"), "hash", TrustScope::Commands));
+    assert!(!meta.is_trusted(Path::new("/profiles/once"), "changed hash", TrustScope::Commands));
+    assert!(!meta.is_path_trusted(Path::new("/profiles/once")));
+    assert!(meta.trusted_profiles().is_empty());
+    // Only the meta it was given to trusts it
+    assert!(!Meta::new().is_trusted(Path::new("/profiles/once"), "hash", TrustScope::Commands));
+}
+
+#[test]
+fn a_file_can_go_to_several_destinations() {
+    let path: PathBuf = env::temp_dir().join(format!("dotulous-targets-test-{}", process::id()));
+    fs::create_dir_all(&path).unwrap();
+    let mut profile = DotfileProfile::new("targets", &path);
+    profile
+        .add_file(PathBuf::from("starship.toml"), FileEntry::new(PathBuf::from(".config/starship.toml"))).unwrap()
+        .add_file(PathBuf::from("bashrc"), FileEntry::new(PathBuf::from(".bashrc"))).unwrap()
+        .add_destination(Path::new("starship.toml"), FileEntry::new(PathBuf::from(".config/other/starship.toml"))).unwrap();
+    assert!(matches!(profile.add_destination(Path::new("starship.toml"), FileEntry::new(PathBuf::from(".bashrc"))), Err(DotulousError::DestinationAlreadyMapped)));
+    assert!(matches!(profile.add_destination(Path::new("zshrc"), FileEntry::new(PathBuf::from(".zshrc"))), Err(DotulousError::FileNotMapped)));
+    profile.save_manifest().unwrap();
+
+    let manifest: String = fs::read_to_string(path.join("manifest.json")).unwrap();
+    let read = DotfileProfile::from_manifest(&path).unwrap();
+    fs::remove_dir_all(&path).unwrap();
+    assert!(manifest.contains("\"bashrc\": \".bashrc\""), "single entries should be saved without a list");
+    let destinations: Vec<&Path> = read.entries().map(|(_, entry)| entry.destination.as_path()).collect();
+    assert_eq!(destinations, [Path::new(".bashrc"), Path::new(".config/starship.toml"), Path::new(".config/other/starship.toml")]);
+    assert_eq!(read.links().len(), 3);
+}
+
+#[test]
+fn home_and_config_folders_are_expanded() {
+    let home = Path::new("/home/user");
+    assert_eq!(expand_path(Path::new(".bashrc"), home), Path::new(".bashrc"));
+    assert_eq!(expand_path(Path::new("~/.bashrc"), home), Path::new("/home/user/.bashrc"));
+    assert_eq!(expand_path(Path::new("~"), home), Path::new("/home/user"));
+    assert_eq!(expand_path(Path::new("$HOME/.bashrc"), home), Path::new("/home/user/.bashrc"));
+    assert_eq!(expand_path(Path::new("${HOME}/.bashrc"), home), Path::new("/home/user/.bashrc"));
+    if env::var_os("XDG_CONFIG_HOME").is_none() {
+        assert_eq!(expand_path(Path::new("$XDG_CONFIG_HOME/nvim"), home), Path::new("/home/user/.config/nvim"));
+    }
+    // Only the home & config folders are expanded
+    assert_eq!(expand_path(Path::new("$EDITOR/config"), home), Path::new("$EDITOR/config"));
+    assert_eq!(expand_path(Path::new("notes/~draft"), home), Path::new("notes/~draft"));
+}
+
+#[test]
+fn releases_are_matched_to_this_platform() {
+    let asset = |name: &str| ReleaseAsset { name: name.to_string(), browser_download_url: format!("https://example.com/{name}") };
+    let release = Release { tag: "v999.0.0".to_string(), assets: vec![asset("dotulous-sparc-plan9"), asset(&update::artifact_name())] };
+    assert!(release.is_newer());
+    assert_eq!(release.version(), "999.0.0");
+    assert_eq!(release.artifact().map(|artifact| artifact.name.clone()), Some(update::artifact_name()));
+
+    let current = Release { tag: format!("v{}", update::CURRENT_VERSION), assets: vec![asset("dotulous-sparc-plan9")] };
+    assert!(!current.is_newer());
+    assert!(current.artifact().is_none());
+}
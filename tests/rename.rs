@@ -0,0 +1,42 @@
+mod common;
+
+use std::fs;
+
+use common::TestEnv;
+use dotulous::{operations, platform};
+use serde_json::json;
+
+#[test]
+fn renaming_a_loaded_profile_moves_its_links() {
+    let env = TestEnv::new();
+    let profile = env.profile("old", &[("bashrc", "echo hi"), ("nvim/init.lua", "-- nvim")], json!({
+        "files": {"bashrc": ".bashrc", "nvim": ".config/nvim"}
+    }));
+    env.load(&profile).unwrap();
+
+    let renamed = operations::rename(&env.dotulous, &env.home, "old", "new").unwrap();
+    assert_eq!(renamed.name, "new");
+    env.assert_linked(".bashrc", "new", "bashrc");
+    env.assert_linked(".config/nvim", "new", "nvim");
+    // Nothing is left behind from swapping the links
+    let mut names: Vec<String> = fs::read_dir(&env.home).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    assert_eq!(names, [".bashrc", ".config"]);
+
+    env.unload().unwrap();
+    env.assert_missing(".bashrc");
+    env.assert_missing(".config/nvim");
+}
+
+#[cfg(unix)]
+#[test]
+fn links_are_left_alone_if_they_cant_be_replaced() {
+    let env = TestEnv::new();
+    env.write_home_file("target", "");
+    env.write_home_file(".config/nvim/init.lua", "-- my own nvim");
+
+    platform::replace_symlink(&env.home_path("target"), &env.home_path(".config/nvim")).unwrap_err();
+    env.assert_file(".config/nvim/init.lua", "-- my own nvim");
+    let names: Vec<_> = fs::read_dir(env.home_path(".config")).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(names, ["nvim"]);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, meta::Meta, operations, profile::{ApplyMode, DotfileProfile, Skip}, secret::{Backend, SecretConfig, SecretStore}};
+use serde_json::{json, Value};
+
+/// Marks `profile` as loaded without loading it, as decrypting needs a real key, and puts a
+/// decrypted copy of `secret` into it's secrets cache. Returns the path of the decrypted copy.
+fn fake_loaded_secret(env: &TestEnv, profile: &DotfileProfile, secret: &str) -> PathBuf {
+    let decrypted: PathBuf = profile.secret_cache_path(&env.dotulous).join(secret);
+    fs::create_dir_all(decrypted.parent().unwrap()).unwrap();
+    fs::write(&decrypted, "hunter2").unwrap();
+    let mut meta: Meta = env.meta();
+    meta.set_current_profile(profile, &env.home);
+    meta.save_meta(&env.dotulous).unwrap();
+    decrypted
+}
+
+/// Returns the manifest of a profile with an encrypted `token` file going to `destination`.
+fn encrypted_manifest(destination: &str) -> Value {
+    json!({
+        "files": {"token": {"destination": destination, "strategy": "encrypted"}},
+        "secrets": {"backend": "gpg"}
+    })
+}
+
+#[test]
+fn decrypted_secrets_are_removed_when_their_destination_is_missing() {
+    let env = TestEnv::new();
+    let profile = env.profile("secret", &[("token", "not really encrypted")], encrypted_manifest(".token"));
+    let decrypted = fake_loaded_secret(&env, &profile, "token");
+
+    env.unload().unwrap();
+    assert!(!decrypted.exists());
+}
+
+#[test]
+fn decrypted_secrets_are_removed_when_their_destination_is_skipped() {
+    let env = TestEnv::new();
+    let profile = env.profile("secret", &[("token", "not really encrypted")], encrypted_manifest(".token"));
+    env.write_home_file(".token", "a real file");
+    let decrypted = fake_loaded_secret(&env, &profile, "token");
+
+    env.unload().unwrap();
+    env.assert_file(".token", "a real file");
+    assert!(!decrypted.exists());
+}
+
+#[test]
+fn decrypted_secrets_are_removed_even_if_reencrypting_fails() {
+    let env = TestEnv::new();
+    let repo = env.dotulous.join("secret");
+    fs::create_dir_all(&repo).unwrap();
+    fs::write(repo.join("token"), "not really encrypted").unwrap();
+    let store = SecretStore::new(env.dotulous.join("cache"), &repo, Some(SecretConfig { backend: Backend::Gpg, recipients: Vec::new(), identity: None }));
+    let decrypted = store.decrypted_path(&repo.join("token"));
+    fs::create_dir_all(decrypted.parent().unwrap()).unwrap();
+    fs::write(&decrypted, "hunter2").unwrap();
+
+    assert!(store.clean(&repo.join("token")).is_err());
+    assert!(!decrypted.exists());
+}
+
+#[test]
+fn decrypted_secrets_are_kept_by_a_dry_run() {
+    let env = TestEnv::new();
+    let profile = env.profile("secret", &[("token", "not really encrypted")], encrypted_manifest(".token"));
+    let decrypted = fake_loaded_secret(&env, &profile, "token");
+
+    operations::unload(&env.dotulous, &env.config, &env.home, &env.settings, Skip::default(), ApplyMode::DryRun, false).unwrap();
+    assert!(decrypted.exists());
+}
+
+#[test]
+fn secrets_that_cant_be_decrypted_arent_loaded() {
+    let env = TestEnv::new();
+    let profile = env.profile("secret", &[("bashrc", "echo hi"), ("token", "not really encrypted")], json!({
+        "files": {"bashrc": ".bashrc", "token": {"destination": ".token", "strategy": "encrypted"}},
+        "secrets": {"backend": "gpg"}
+    }));
+
+    assert!(matches!(env.load(&profile), Err(DotulousError::FailedPlaceFile)));
+    env.assert_missing(".token");
+    env.assert_missing(".bashrc");
+    assert!(!profile.secret_cache_path(&env.dotulous).join("token").exists());
+    assert!(env.meta().current_profile().is_none());
+}
+
+#[test]
+fn encrypted_files_need_the_profile_to_set_how() {
+    let env = TestEnv::new();
+    let profile = env.profile("secret", &[("token", "not really encrypted")], json!({
+        "files": {"token": {"destination": ".token", "strategy": "encrypted"}}
+    }));
+
+    assert!(matches!(env.load(&profile), Err(DotulousError::FailedPlaceFile)));
+    env.assert_missing(".token");
+    let config = SecretConfig { backend: Backend::Gpg, recipients: Vec::new(), identity: None };
+    let source = profile.repo_path.join("token");
+    assert!(matches!(config.encrypt(&source, &source), Err(DotulousError::NoSecretRecipients)));
+    assert_eq!(fs::read_to_string(&source).unwrap(), "not really encrypted");
+}
@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use dotulous::{paths::Paths, service};
+
+/// Returns the `ExecStart` line of `unit`.
+fn exec_start(unit: &str) -> &str {
+    unit.lines().find_map(|line| line.strip_prefix("ExecStart=")).unwrap()
+}
+
+#[test]
+fn the_service_uses_the_same_folders_it_was_installed_with() {
+    let arguments: Vec<String> = vec!["reload".to_string(), "--no-hooks".to_string()];
+    let paths: Paths = Paths::in_folder("/srv/my dotfiles".into());
+    let unit: String = service::render_unit(Path::new("/usr/bin/dotulous"), Path::new("/home/sam"), &paths, &arguments);
+    assert_eq!(exec_start(&unit), "/usr/bin/dotulous --yes --home /home/sam --dotulous-dir \"/srv/my dotfiles\" reload --no-hooks");
+
+    // Split folders can't be one `--dotulous-dir`
+    let paths: Paths = Paths { data: "/home/sam/.local/share/dotulous".into(), config: "/home/sam/.config/dotulous".into() };
+    let unit: String = service::render_unit(Path::new("/usr/bin/dotulous"), Path::new("/home/sam"), &paths, &arguments);
+    assert_eq!(exec_start(&unit), "/usr/bin/dotulous --yes --home /home/sam reload --no-hooks");
+}
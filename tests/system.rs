@@ -0,0 +1,54 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, os::unix::fs::symlink};
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, platform, profile::ApplyMode, system};
+use serde_json::json;
+
+// System files are only ever applied as root, so these tests are skipped otherwise.
+
+#[test]
+fn system_sources_must_stay_inside_the_profile() {
+    if !platform::is_root() {
+        return
+    }
+    let env = TestEnv::new();
+    env.write_home_file("secret", "hunter2");
+    fs::create_dir_all(env.dotulous.join("system")).unwrap();
+    symlink(env.home_path("secret"), env.dotulous.join("system/keyd.conf")).unwrap();
+    let destination = env.home_path("etc/keyd/default.conf");
+    let profile = env.profile("system", &[], json!({
+        "system_files": {"keyd.conf": destination}
+    }));
+
+    let result = system::apply(&profile, ApplyMode::Apply);
+    assert!(matches!(result, Err(DotulousError::SystemSourceOutsideProfile)), "{result:?}");
+    env.assert_missing("etc/keyd/default.conf");
+}
+
+#[test]
+fn overwriting_system_files_needs_confirmation() {
+    if !platform::is_root() {
+        return
+    }
+    let env = TestEnv::new();
+    env.write_home_file("etc/keyd/default.conf", "[old]");
+    let destination = env.home_path("etc/keyd/default.conf");
+    let profile = env.profile("system", &[("keyd.conf", "[main]")], json!({
+        "system_files": {"keyd.conf": destination}
+    }));
+    assert_eq!(system::overwrites(&profile), vec![destination.clone()]);
+
+    let output = env.run(&["apply-system", "system"]);
+    assert!(!output.status.success());
+    env.assert_file("etc/keyd/default.conf", "[old]");
+
+    let output = env.run(&["--yes", "apply-system", "system"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    env.assert_file("etc/keyd/default.conf", "[main]");
+    assert_eq!(fs::read_to_string(system::backup_path(&destination)).unwrap(), "[old]");
+    assert!(system::overwrites(&profile).is_empty());
+}
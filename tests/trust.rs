@@ -0,0 +1,198 @@
+mod common;
+
+use std::fs;
+
+use common::TestEnv;
+use dotulous::{error::DotulousError, filter::TagFilter, meta::{Meta, TrustOnce, TrustScope}, operations, profile::{ApplyMode, DotfileProfile, OnConflict, Skip}};
+use serde_json::json;
+
+/// Leaves out the profile's commands, as with `--no-hooks`.
+const NO_HOOKS: Skip = Skip { hooks: true, files: false };
+
+#[test]
+fn files_only_trust_loads_without_hooks() {
+    let env = TestEnv::new();
+    let profile = env.profile("hooks", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": ["touch {{home}}/ran"]
+    }));
+    env.trust_as(&profile, TrustScope::Files);
+
+    assert!(env.load(&profile).is_err());
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &TagFilter::default(), NO_HOOKS, ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+    env.assert_linked(".bashrc", "hooks", "bashrc");
+    env.assert_missing("ran");
+}
+
+#[test]
+fn unload_skips_removal_commands_without_command_trust() {
+    let env = TestEnv::new();
+    let profile = env.profile("hooks", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "removal_commands": ["touch {{home}}/ran"]
+    }));
+    env.trust_as(&profile, TrustScope::Files);
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &TagFilter::default(), NO_HOOKS, ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+
+    env.unload().unwrap();
+    env.assert_missing(".bashrc");
+    env.assert_missing("ran");
+}
+
+#[test]
+fn loading_another_profile_skips_untrusted_removal_commands() {
+    let env = TestEnv::new();
+    let first = env.profile("first", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "removal_commands": ["touch {{home}}/ran"]
+    }));
+    env.trust_as(&first, TrustScope::Files);
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &first, false, &TagFilter::default(), NO_HOOKS, ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+    let second = env.profile("second", &[("vimrc", "set number")], json!({"files": {"vimrc": ".vimrc"}}));
+
+    env.load(&second).unwrap();
+    env.assert_missing(".bashrc");
+    env.assert_missing("ran");
+}
+
+#[test]
+fn removal_commands_run_once_trusted() {
+    let env = TestEnv::new();
+    let profile = env.profile("hooks", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "removal_commands": ["touch {{home}}/ran"]
+    }));
+    env.trust_as(&profile, TrustScope::Files);
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &TagFilter::default(), NO_HOOKS, ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+    env.trust(&profile);
+
+    env.unload().unwrap();
+    assert!(env.home_path("ran").exists());
+}
+
+#[test]
+fn run_refuses_the_loaded_profile_without_command_trust() {
+    let env = TestEnv::new();
+    let profile = env.profile("hooks", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": ["touch {{home}}/ran"],
+        "hooks": {"theme": ["touch {{home}}/themed"]}
+    }));
+    env.trust_as(&profile, TrustScope::Files);
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &TagFilter::default(), NO_HOOKS, ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+
+    for hook in ["post", "theme"] {
+        let output = env.run(&["run", hook]);
+        assert_eq!(output.status.code(), Some(5), "running {hook} wasn't refused");
+    }
+    env.assert_missing("ran");
+    env.assert_missing("themed");
+
+    env.trust(&profile);
+    assert!(env.run(&["run", "post"]).status.success());
+    assert!(env.home_path("ran").exists());
+}
+
+#[test]
+fn custom_hooks_and_packages_need_command_trust() {
+    let env = TestEnv::new();
+    let files = env.profile("files", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    let hooks = env.profile("hooks", &[], json!({"hooks": {"theme": ["echo dark"]}}));
+    let packages = env.profile("packages", &[], json!({"packages": {"arch": ["neovim"]}}));
+
+    assert_eq!(files.required_trust(Skip::default()), TrustScope::Files);
+    assert_eq!(hooks.required_trust(Skip::default()), TrustScope::Commands);
+    assert_eq!(packages.required_trust(Skip::default()), TrustScope::Commands);
+    assert_eq!(hooks.required_trust(NO_HOOKS), TrustScope::Files);
+    assert_eq!(packages.required_trust(NO_HOOKS), TrustScope::Files);
+}
+
+#[test]
+fn trusting_once_only_lasts_for_the_operation_given_it() {
+    let env = TestEnv::new();
+    let profile = env.profile("once", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "post_commands": ["touch {{home}}/ran"]
+    }));
+    let mut meta = env.meta();
+    meta.untrust_profile(&profile.repo_path);
+    meta.save_meta(&env.dotulous).unwrap();
+    assert!(matches!(env.load(&profile), Err(DotulousError::ProfileNotTrusted)));
+
+    let once = TrustOnce { path: profile.repo_path.clone(), manifest_hash: profile.manifest_hash().unwrap(), scope: TrustScope::Commands };
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &TagFilter::default(), Skip::default(), ApplyMode::Apply, OnConflict::Backup, false, Some(&once)).unwrap();
+    env.assert_linked(".bashrc", "once", "bashrc");
+    env.assert_file("ran", "");
+    assert!(!env.meta().is_path_trusted(&profile.repo_path));
+    assert!(matches!(env.reload(), Err(DotulousError::ProfileNotTrusted)));
+}
+
+#[test]
+fn unreadable_meta_is_an_error() {
+    let env = TestEnv::new();
+    fs::remove_file(env.dotulous.join("meta.json")).unwrap();
+    fs::create_dir(env.dotulous.join("meta.json")).unwrap();
+
+    let Err(e) = Meta::load_meta(&env.dotulous) else { panic!("a folder was read as the meta") };
+    assert!(matches!(e, DotulousError::FailedReadMeta(_)));
+    // The cause is kept for printing
+    assert!(std::error::Error::source(&e).is_some());
+}
+
+#[test]
+fn untrusted_profile_is_not_loaded() {
+    let env = TestEnv::new();
+    env.profile("untrusted", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    // Changing the manifest after trusting it means it has to be trusted again
+    env.write_manifest("untrusted", json!({"files": {"bashrc": ".bashrc"}, "pre_commands": ["echo changed"]}));
+    let changed = DotfileProfile::find_profile(&env.dotulous, "untrusted").unwrap();
+
+    assert!(matches!(env.load(&changed), Err(DotulousError::ProfileNotTrusted)));
+    env.assert_missing(".bashrc");
+}
+
+#[cfg(unix)]
+#[test]
+fn trusting_only_files_never_runs_commands() {
+    let env = TestEnv::new();
+    let marker = env.home_path("ran");
+    let profile = env.profile("hooked", &[("bashrc", "echo hi")], json!({
+        "files": {"bashrc": ".bashrc"},
+        "pre_commands": [format!("touch {}", marker.display())]
+    }));
+    env.trust_as(&profile, TrustScope::Files);
+    assert!(matches!(env.load(&profile), Err(DotulousError::ProfileNotTrusted)));
+
+    let skip = Skip { hooks: true, files: false };
+    operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &profile, false, &TagFilter::default(), skip, ApplyMode::Apply, OnConflict::Backup, false, None).unwrap();
+    env.assert_linked(".bashrc", "hooked", "bashrc");
+    assert!(!marker.exists());
+    assert!(matches!(env.reload(), Err(DotulousError::ProfileNotTrusted)));
+    operations::unload(&env.dotulous, &env.config, &env.home, &env.settings, skip, ApplyMode::Apply, false).unwrap();
+    env.assert_missing(".bashrc");
+}
+
+#[test]
+fn trusting_once_only_covers_the_manifest_that_was_shown() {
+    let env = TestEnv::new();
+    let profile = env.profile("once", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    let once = TrustOnce { path: profile.repo_path.clone(), manifest_hash: profile.manifest_hash().unwrap(), scope: TrustScope::Commands };
+    env.write_manifest("once", json!({"files": {"bashrc": ".bashrc"}, "post_commands": ["touch {{home}}/ran"]}));
+    let changed = DotfileProfile::find_profile(&env.dotulous, "once").unwrap();
+
+    let loaded = operations::load(&env.dotulous, &env.config, &env.home, &env.settings, &changed, false, &TagFilter::default(), Skip::default(), ApplyMode::Apply, OnConflict::Backup, false, Some(&once));
+    assert!(matches!(loaded, Err(DotulousError::ProfileNotTrusted)));
+    env.assert_missing(".bashrc");
+    env.assert_missing("ran");
+}
+
+#[test]
+fn untrusted_profiles_have_to_be_trusted_again() {
+    let env = TestEnv::new();
+    env.profile("revoked", &[("bashrc", "echo hi")], json!({"files": {"bashrc": ".bashrc"}}));
+    assert!(env.run(&["untrust", "revoked"]).status.success());
+
+    assert_eq!(env.run(&["load", "revoked", "--yes"]).status.code(), Some(3));
+    env.assert_missing(".bashrc");
+    assert!(!env.run(&["untrust", "missing"]).status.success());
+}